@@ -1,3 +1,3 @@
 package java.lang;
 
-public class ArrayIndexOutOfBoundsException extends Throwable {}
+public class ArrayIndexOutOfBoundsException extends IndexOutOfBoundsException {}
@@ -1,7 +1,16 @@
 package java.lang;
 
+import java.lang.reflect.RecordComponent;
+
 public class Class {
     public boolean desiredAssertionStatus() {
         return true;
     }
+
+    public native boolean isRecord();
+
+    /**
+     * Returns null if the class is not a record.
+     */
+    public native RecordComponent[] getRecordComponents();
 }
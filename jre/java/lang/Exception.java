@@ -1,4 +1,11 @@
 package java.lang;
 
 public class Exception extends Throwable {
+
+    public Exception() {
+    }
+
+    public Exception(String message) {
+        super(message);
+    }
 }
@@ -1,4 +1,14 @@
 package java.lang;
 
 public class Object {
+
+    public native String toString();
+
+    public final native void wait() throws InterruptedException;
+
+    public final native void wait(long timeout) throws InterruptedException;
+
+    public final native void notify();
+
+    public final native void notifyAll();
 }
@@ -0,0 +1,62 @@
+package java.lang;
+
+public final class String {
+
+    /**
+     * The characters as UTF-16, high byte first. Strings are created by the VM.
+     */
+    private final byte[] value;
+
+    private String(byte[] value) {
+        this.value = value;
+    }
+
+    public int length() {
+        return value.length >> 1;
+    }
+
+    public boolean equals(Object other) {
+        if (this == other) {
+            return true;
+        }
+
+        if (!(other instanceof String)) {
+            return false;
+        }
+
+        byte[] otherValue = ((String) other).value;
+        if (otherValue.length != value.length) {
+            return false;
+        }
+
+        for (int i = 0; i != value.length; i++) {
+            if (otherValue[i] != value[i]) {
+                return false;
+            }
+        }
+
+        return true;
+    }
+
+    public String toString() {
+        return this;
+    }
+
+    public native String concat(String str);
+
+    public static String valueOf(Object obj) {
+        return obj == null ? "null" : obj.toString();
+    }
+
+    public static native String valueOf(boolean b);
+
+    public static native String valueOf(char c);
+
+    public static native String valueOf(int i);
+
+    public static native String valueOf(long l);
+
+    public static native String valueOf(float f);
+
+    public static native String valueOf(double d);
+}
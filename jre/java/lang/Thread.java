@@ -0,0 +1,33 @@
+package java.lang;
+
+public class Thread implements Runnable {
+
+    private Runnable target;
+
+    public Thread() {
+    }
+
+    public Thread(Runnable target) {
+        this.target = target;
+    }
+
+    public void run() {
+        if (target != null) {
+            target.run();
+        }
+    }
+
+    public native void start();
+
+    public final native void join() throws InterruptedException;
+
+    public final native boolean isAlive();
+
+    public static native Thread currentThread();
+
+    public static native void sleep(long millis) throws InterruptedException;
+
+    public static native void yield();
+
+    public static native boolean holdsLock(Object obj);
+}
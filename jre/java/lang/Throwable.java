@@ -1,3 +1,32 @@
 package java.lang;
 
-class Throwable {}
+public class Throwable {
+
+    private String detailMessage;
+
+    /**
+     * The frames where the throwable was created, filled in by the VM.
+     */
+    private StackTraceElement[] stackTrace;
+
+    public Throwable() {
+        fillInStackTrace();
+    }
+
+    public Throwable(String message) {
+        fillInStackTrace();
+        detailMessage = message;
+    }
+
+    public String getMessage() {
+        return detailMessage;
+    }
+
+    public native Throwable fillInStackTrace();
+
+    public StackTraceElement[] getStackTrace() {
+        return stackTrace;
+    }
+
+    public native void printStackTrace();
+}
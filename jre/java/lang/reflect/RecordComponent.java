@@ -0,0 +1,31 @@
+package java.lang.reflect;
+
+public final class RecordComponent {
+
+    /**
+     * Record components are created by the VM, the signature is null unless the type is generic.
+     */
+    private Class clazz;
+    private String name;
+    private Class type;
+    private String signature;
+
+    private RecordComponent() {
+    }
+
+    public String getName() {
+        return name;
+    }
+
+    public Class getType() {
+        return type;
+    }
+
+    public String getGenericSignature() {
+        return signature;
+    }
+
+    public Class getDeclaringRecord() {
+        return clazz;
+    }
+}
@@ -0,0 +1,14 @@
+package java.util;
+
+public final class Objects {
+
+    private Objects() {
+    }
+
+    public static <T> T requireNonNull(T obj) {
+        if (obj == null) {
+            throw new NullPointerException();
+        }
+        return obj;
+    }
+}
@@ -0,0 +1,1334 @@
+//! A Jasmin-like assembler, building classes and code from text.
+//!
+//! A class is a list of directives, each method holding its instructions:
+//!
+//! ```text
+//! .bytecode 49.0              ; Optional, classes default to version 49.0
+//! .source Counter.java        ; Optional SourceFile attribute
+//! .class public example/Counter
+//! .super java/lang/Object     ; Optional, defaults to java/lang/Object
+//! .implements java/lang/Runnable
+//! .field private static count I = 0
+//!
+//! .method public static count(I)I
+//!     .limit stack 2          ; Defaults to 0
+//!     .limit locals 2         ; Defaults to the size of the arguments
+//!     .throws java/lang/Exception
+//!     .catch java/lang/RuntimeException from Start to End using Handler
+//!     .line 10
+//! Start:
+//!     getstatic example/Counter/count I
+//!     invokestatic example/Counter/increment(I)I
+//! End:
+//!     ireturn
+//! Handler:
+//!     ...
+//! .end method
+//! ```
+//!
+//! Operands are written symbolically: branches take labels, member references are written as
+//! `class/name` followed by the descriptor, and `ldc` takes a number, a quoted string or a class
+//! name. `wide` and `ldc_w` are used automatically when an index does not fit a single byte.
+//! Switches list their targets on the following lines and end with the default target:
+//!
+//! ```text
+//!     tableswitch 0           ; Low key
+//!         Zero
+//!         One
+//!         default: Other
+//!     lookupswitch
+//!         1: One
+//!         10: Ten
+//!         default: Other
+//! ```
+//!
+//! Comments start with `;`. Stack maps are not computed, so classes with branches must use a
+//! version below 50 to pass verification. `invokedynamic` is not supported.
+
+use crate::class::attribute::AttributeData::{
+    CodeInfo, ConstantValue, Exceptions, LineNumberTable, SourceFile,
+};
+use crate::class::attribute::{Attribute, Code, ExceptionHandler};
+use crate::class::code::Opcode::*;
+use crate::class::code::{Instruction, Opcode};
+use crate::class::constant::Constant;
+use crate::class::constant::ConstantPool;
+use crate::class::{
+    Class, ClassAccessFlags, FieldAccessFlags, FieldInfo, MethodAccessFlags, MethodInfo, Version,
+};
+use crate::error::ErrorKind::ParseError;
+use crate::error::{Error, Result};
+use crate::io::code::{CodeReader, CodeWriter};
+use crate::vm::data_type::{FieldType, MethodDescriptor};
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::io::Cursor;
+use std::rc::Rc;
+use std::str::FromStr;
+
+const CLASS_FLAGS: &[(&str, u16)] = &[
+    ("public", 0x0001),
+    ("final", 0x0010),
+    ("super", 0x0020),
+    ("interface", 0x0200),
+    ("abstract", 0x0400),
+    ("synthetic", 0x1000),
+    ("annotation", 0x2000),
+    ("enum", 0x4000),
+];
+
+const FIELD_FLAGS: &[(&str, u16)] = &[
+    ("public", 0x0001),
+    ("private", 0x0002),
+    ("protected", 0x0004),
+    ("static", 0x0008),
+    ("final", 0x0010),
+    ("volatile", 0x0040),
+    ("transient", 0x0080),
+    ("synthetic", 0x1000),
+    ("enum", 0x4000),
+];
+
+const METHOD_FLAGS: &[(&str, u16)] = &[
+    ("public", 0x0001),
+    ("private", 0x0002),
+    ("protected", 0x0004),
+    ("static", 0x0008),
+    ("final", 0x0010),
+    ("synchronized", 0x0020),
+    ("bridge", 0x0040),
+    ("varargs", 0x0080),
+    ("native", 0x0100),
+    ("abstract", 0x0400),
+    ("strict", 0x0800),
+    ("synthetic", 0x1000),
+];
+
+/// Assembles a class. Classes that are not interfaces get the `super` flag, like classes
+/// compiled by `javac`.
+///
+/// ```
+///# use rjvm::assembler::assemble;
+///# use rjvm::error::Result;
+///# fn main() -> Result<()> {
+/// let class = assemble(
+///     "
+///     .class public Example
+///     .method public static answer()I
+///         .limit stack 1
+///         bipush 42
+///         ireturn
+///     .end method
+///     ",
+/// )?;
+///
+/// assert_eq!(class.this_class, "Example");
+/// assert_eq!(class.methods[0].get_code().unwrap().max_stack, 1);
+///# Ok(())
+///# }
+/// ```
+pub fn assemble(source: &str) -> Result<Class> {
+    let lines = tokenize(source)?;
+
+    let mut class = Class {
+        version: Version {
+            minor: 0,
+            major: 49,
+        },
+        constants: ConstantPool::new(0),
+        access_flags: ClassAccessFlags::empty(),
+        this_class: String::new(),
+        super_class: "java/lang/Object".to_owned(),
+        interfaces: vec![],
+        fields: vec![],
+        methods: vec![],
+        attributes: vec![],
+    };
+
+    let mut i = 0;
+    while i < lines.len() {
+        let line = &lines[i];
+        let operands = &line.tokens[1..];
+        match line.tokens[0].text.as_str() {
+            ".bytecode" => {
+                let [version] = line.operands::<1>()?;
+                let (major, minor) = version.split_once('.').unwrap_or((version, "0"));
+                class.version = Version {
+                    major: line.parse(major)?,
+                    minor: line.parse(minor)?,
+                };
+            }
+            ".source" => {
+                let [source_file] = line.operands::<1>()?;
+                class.attributes.push(Attribute {
+                    name: "SourceFile".to_owned(),
+                    data: SourceFile(source_file.to_owned()),
+                });
+            }
+            ".class" => {
+                let (name, flags) = operands
+                    .split_last()
+                    .ok_or_else(|| line.error("Expected a class name.".to_owned()))?;
+                let mut bits = line.flags(flags, CLASS_FLAGS)?;
+                if bits & 0x0200 == 0 {
+                    bits |= 0x0020;
+                } else {
+                    bits |= 0x0400;
+                }
+                class.access_flags = ClassAccessFlags::from_bits_truncate(bits);
+                class.this_class = name.text.clone();
+            }
+            ".super" => {
+                let [super_class] = line.operands::<1>()?;
+                class.super_class = super_class.to_owned();
+            }
+            ".implements" => {
+                let [interface] = line.operands::<1>()?;
+                class.interfaces.push(interface.to_owned());
+            }
+            ".field" => {
+                let field = assemble_field(line, &mut class.constants)?;
+                class.fields.push(field);
+            }
+            ".method" => {
+                let end = lines[i..]
+                    .iter()
+                    .position(|l| l.tokens[0].text == ".end")
+                    .map(|end| i + end)
+                    .ok_or_else(|| line.error("Missing .end method.".to_owned()))?;
+                let method = assemble_method(line, &lines[i + 1..end], &mut class.constants)?;
+                class.methods.push(Rc::new(method));
+                i = end;
+            }
+            directive => return Err(line.error(format!("Unexpected '{}'.", directive))),
+        }
+        i += 1;
+    }
+
+    if class.this_class.is_empty() {
+        return Err(Error::new(
+            ParseError,
+            Some("Missing .class directive.".to_owned()),
+        ));
+    }
+    Ok(class)
+}
+
+/// Assembles the body of a method: its instructions, labels and the `.limit`, `.catch` and
+/// `.line` directives. Constants referred to by the instructions are added to `constants`.
+pub fn assemble_code(source: &str, constants: &mut ConstantPool) -> Result<Code> {
+    let lines = tokenize(source)?;
+    let mut assembler = CodeAssembler::new(constants, 0);
+    assembler.assemble(&lines)
+}
+
+fn assemble_field(line: &Line, constants: &mut ConstantPool) -> Result<FieldInfo> {
+    let operands = &line.tokens[1..];
+    let (declaration, value) = match operands.iter().position(|t| t.text == "=") {
+        Some(i) => (&operands[..i], operands.get(i + 1)),
+        None => (operands, None),
+    };
+    if declaration.len() < 2 {
+        return Err(line.error("Expected a field name and descriptor.".to_owned()));
+    }
+
+    let (name, descriptor) = (
+        &declaration[declaration.len() - 2],
+        &declaration[declaration.len() - 1],
+    );
+    let flags = line.flags(&declaration[..declaration.len() - 2], FIELD_FLAGS)?;
+    let field_type = field_type(line, &descriptor.text)?;
+
+    let mut attributes = vec![];
+    if let Some(value) = value {
+        let constant = match field_type {
+            FieldType::Long => Constant::Long(line.parse(&value.text)?),
+            FieldType::Float => Constant::Float(line.parse(&value.text)?),
+            FieldType::Double => Constant::Double(line.parse(&value.text)?),
+            FieldType::Object(ref class) if class == "java/lang/String" && value.quoted => {
+                Constant::StringRef(constants.find_or_add_utf8(&value.text)?)
+            }
+            FieldType::Object(_) | FieldType::Array(_) => {
+                return Err(line.error(format!("Invalid constant value {}.", value.text)))
+            }
+            _ => Constant::Integer(line.parse(&value.text)?),
+        };
+        constants.find_or_add(constant.clone())?;
+        attributes.push(Attribute {
+            name: "ConstantValue".to_owned(),
+            data: ConstantValue(constant),
+        });
+    }
+
+    Ok(FieldInfo {
+        access_flags: FieldAccessFlags::from_bits_truncate(flags),
+        name: name.text.clone(),
+        descriptor: descriptor.text.clone(),
+        attributes,
+    })
+}
+
+fn assemble_method(line: &Line, body: &[Line], constants: &mut ConstantPool) -> Result<MethodInfo> {
+    let (signature, flags) = line.tokens[1..]
+        .split_last()
+        .ok_or_else(|| line.error("Expected a method name and descriptor.".to_owned()))?;
+    let flags = MethodAccessFlags::from_bits_truncate(line.flags(flags, METHOD_FLAGS)?);
+
+    let split = signature
+        .text
+        .find('(')
+        .ok_or_else(|| line.error(format!("Invalid method '{}'.", signature.text)))?;
+    let (name, descriptor) = signature.text.split_at(split);
+    let descriptor = method_descriptor(line, descriptor)?;
+
+    let (throws, body): (Vec<&Line>, Vec<&Line>) =
+        body.iter().partition(|l| l.tokens[0].text == ".throws");
+
+    let mut attributes = vec![];
+    if !body.is_empty()
+        || !flags.intersects(MethodAccessFlags::ACC_ABSTRACT | MethodAccessFlags::ACC_NATIVE)
+    {
+        let mut locals = argument_slots(&descriptor);
+        if !flags.contains(MethodAccessFlags::ACC_STATIC) {
+            locals += 1;
+        }
+        let body: Vec<Line> = body.into_iter().cloned().collect();
+        let code = CodeAssembler::new(constants, locals).assemble(&body)?;
+        attributes.push(Attribute {
+            name: "Code".to_owned(),
+            data: CodeInfo(code),
+        });
+    }
+
+    if !throws.is_empty() {
+        let mut exceptions = Vec::with_capacity(throws.len());
+        for line in throws {
+            let [exception] = line.operands::<1>()?;
+            exceptions.push(exception.to_owned());
+        }
+        attributes.push(Attribute {
+            name: "Exceptions".to_owned(),
+            data: Exceptions(exceptions),
+        });
+    }
+
+    Ok(MethodInfo {
+        access_flags: flags,
+        name: name.to_owned(),
+        descriptor,
+        attributes,
+    })
+}
+
+/// The operands of an instruction, with labels not yet resolved to offsets.
+enum Operands {
+    Bytes(Vec<u8>),
+    /// The opcode modified by `wide` and its operands.
+    Wide(Opcode, Vec<u8>),
+    Branch(String),
+    WideBranch(String),
+    TableSwitch {
+        low: i32,
+        targets: Vec<String>,
+        default: String,
+    },
+    LookupSwitch {
+        pairs: Vec<(i32, String)>,
+        default: String,
+    },
+}
+
+struct Item {
+    line: usize,
+    offset: u32,
+    opcode: Opcode,
+    operands: Operands,
+}
+
+struct Catch {
+    line: usize,
+    catch_type: Option<String>,
+    start: String,
+    end: String,
+    handler: String,
+}
+
+struct CodeAssembler<'c> {
+    constants: &'c mut ConstantPool,
+    max_stack: u16,
+    max_locals: u16,
+    items: Vec<Item>,
+    labels: HashMap<String, u32>,
+    offset: u32,
+    catches: Vec<Catch>,
+    line_numbers: Vec<(u16, u16)>,
+}
+
+impl<'c> CodeAssembler<'c> {
+    fn new(constants: &'c mut ConstantPool, max_locals: u16) -> Self {
+        CodeAssembler {
+            constants,
+            max_stack: 0,
+            max_locals,
+            items: vec![],
+            labels: HashMap::new(),
+            offset: 0,
+            catches: vec![],
+            line_numbers: vec![],
+        }
+    }
+
+    fn assemble(&mut self, lines: &[Line]) -> Result<Code> {
+        let mut i = 0;
+        while i < lines.len() {
+            let mut line = lines[i].clone();
+
+            let first = &line.tokens[0];
+            if !first.quoted && first.text.ends_with(':') {
+                let label = first.text[..first.text.len() - 1].to_owned();
+                if self.labels.insert(label, self.offset).is_some() {
+                    return Err(line.error(format!("Duplicate label '{}'.", first.text)));
+                }
+                line.tokens.remove(0);
+            }
+
+            if let Some(first) = line.tokens.first() {
+                if first.text.starts_with('.') {
+                    self.directive(&line)?;
+                } else {
+                    let opcode = Opcode::from_mnemonic(&first.text).ok_or_else(|| {
+                        line.error(format!("Unknown instruction '{}'.", first.text))
+                    })?;
+                    let operands = &line.tokens[1..];
+                    let (opcode, operands) = match opcode {
+                        TableSwitch | LookupSwitch => {
+                            let (operands, consumed) =
+                                switch(&opcode, &line, operands, &lines[i + 1..])?;
+                            i += consumed;
+                            (opcode, operands)
+                        }
+                        _ => self.instruction(opcode, &line, operands)?,
+                    };
+                    self.push(line.number, opcode, operands);
+                }
+            }
+            i += 1;
+        }
+
+        if self.items.is_empty() {
+            return Err(Error::new(
+                ParseError,
+                Some("Code without instructions.".to_owned()),
+            ));
+        }
+        self.code()
+    }
+
+    fn directive(&mut self, line: &Line) -> Result<()> {
+        let operands = &line.tokens[1..];
+        match line.tokens[0].text.as_str() {
+            ".limit" => {
+                let [kind, value] = line.operands::<2>()?;
+                match kind {
+                    "stack" => self.max_stack = line.parse(value)?,
+                    "locals" => self.max_locals = line.parse(value)?,
+                    _ => return Err(line.error(format!("Unknown limit '{}'.", kind))),
+                }
+            }
+            ".catch" => {
+                let texts: Vec<&str> = operands.iter().map(|t| t.text.as_str()).collect();
+                match texts[..] {
+                    [catch_type, "from", start, "to", end, "using", handler] => {
+                        self.catches.push(Catch {
+                            line: line.number,
+                            catch_type: match catch_type {
+                                "all" => None,
+                                class => Some(class.to_owned()),
+                            },
+                            start: start.to_owned(),
+                            end: end.to_owned(),
+                            handler: handler.to_owned(),
+                        })
+                    }
+                    _ => {
+                        return Err(line.error(
+                            "Expected .catch <class> from <label> to <label> using <label>."
+                                .to_owned(),
+                        ))
+                    }
+                }
+            }
+            ".line" => {
+                let [number] = line.operands::<1>()?;
+                self.line_numbers
+                    .push((self.offset as u16, line.parse(number)?));
+            }
+            directive => return Err(line.error(format!("Unexpected '{}'.", directive))),
+        }
+        Ok(())
+    }
+
+    fn push(&mut self, line: usize, opcode: Opcode, operands: Operands) {
+        let size = match &operands {
+            Operands::Bytes(bytes) => 1 + bytes.len() as u32,
+            Operands::Wide(_, bytes) => 2 + bytes.len() as u32,
+            Operands::Branch(_) => 3,
+            Operands::WideBranch(_) => 5,
+            Operands::TableSwitch { targets, .. } => {
+                1 + switch_pad(self.offset) + 12 + 4 * targets.len() as u32
+            }
+            Operands::LookupSwitch { pairs, .. } => {
+                1 + switch_pad(self.offset) + 8 + 8 * pairs.len() as u32
+            }
+        };
+
+        self.items.push(Item {
+            line,
+            offset: self.offset,
+            opcode,
+            operands,
+        });
+        self.offset += size;
+    }
+
+    fn instruction(
+        &mut self,
+        opcode: Opcode,
+        line: &Line,
+        operands: &[Token],
+    ) -> Result<(Opcode, Operands)> {
+        let expect = |count: usize| -> Result<()> {
+            if operands.len() == count {
+                Ok(())
+            } else {
+                Err(line.error(format!(
+                    "Expected {} operands for {}, found {}.",
+                    count,
+                    opcode.mnemonic(),
+                    operands.len()
+                )))
+            }
+        };
+
+        let bytes = match opcode {
+            BiPush => {
+                expect(1)?;
+                vec![line.parse::<i8>(&operands[0].text)? as u8]
+            }
+            SiPush => {
+                expect(1)?;
+                line.parse::<i16>(&operands[0].text)?.to_be_bytes().to_vec()
+            }
+            ILoad | LLoad | FLoad | DLoad | ALoad | IStore | LStore | FStore | DStore | AStore
+            | Ret => {
+                expect(1)?;
+                let index: u16 = line.parse(&operands[0].text)?;
+                if index <= u8::MAX as u16 {
+                    vec![index as u8]
+                } else {
+                    let bytes = index.to_be_bytes().to_vec();
+                    return Ok((Wide, Operands::Wide(opcode, bytes)));
+                }
+            }
+            IInc => {
+                expect(2)?;
+                let index: u16 = line.parse(&operands[0].text)?;
+                let constant: i16 = line.parse(&operands[1].text)?;
+                if index <= u8::MAX as u16 && (-128..=127).contains(&constant) {
+                    vec![index as u8, constant as u8]
+                } else {
+                    let mut bytes = index.to_be_bytes().to_vec();
+                    bytes.extend_from_slice(&constant.to_be_bytes());
+                    return Ok((Wide, Operands::Wide(opcode, bytes)));
+                }
+            }
+            IfEq | IfNe | IfLt | IfGe | IfGt | IfLe | IfIcmpEq | IfIcmpNe | IfIcmpLt | IfIcmpGe
+            | IfIcmpGt | IfIcmpLe | IfAcmpEq | IfAcmpNe | IfNull | IfNonNull | Goto | Jsr => {
+                expect(1)?;
+                return Ok((opcode, Operands::Branch(operands[0].text.clone())));
+            }
+            GotoW | JsrW => {
+                expect(1)?;
+                return Ok((opcode, Operands::WideBranch(operands[0].text.clone())));
+            }
+            GetField | GetStatic | PutField | PutStatic => {
+                expect(2)?;
+                let (class, name) = member(line, &operands[0].text)?;
+                let descriptor = &operands[1].text;
+                field_type(line, descriptor)?;
+                let index = self.member_ref(class, name, descriptor, Constant::FieldRef)?;
+                index.to_be_bytes().to_vec()
+            }
+            InvokeVirtual | InvokeSpecial | InvokeStatic => {
+                expect(1)?;
+                let (class, name, descriptor) = method(line, &operands[0].text)?;
+                let index = self.member_ref(class, name, descriptor, Constant::MethodRef)?;
+                index.to_be_bytes().to_vec()
+            }
+            InvokeInterface => {
+                expect(1)?;
+                let (class, name, descriptor) = method(line, &operands[0].text)?;
+                let count = argument_slots(&method_descriptor(line, descriptor)?) + 1;
+                let index =
+                    self.member_ref(class, name, descriptor, Constant::InterfaceMethodRef)?;
+                let [high, low] = index.to_be_bytes();
+                vec![high, low, count as u8, 0]
+            }
+            New | ANewArray | CheckCast | Instanceof => {
+                expect(1)?;
+                let index = self.constants.find_or_add_class(&operands[0].text)?;
+                index.to_be_bytes().to_vec()
+            }
+            MultiANewArray => {
+                expect(2)?;
+                let index = self.constants.find_or_add_class(&operands[0].text)?;
+                let [high, low] = index.to_be_bytes();
+                vec![high, low, line.parse(&operands[1].text)?]
+            }
+            NewArray => {
+                expect(1)?;
+                vec![array_type(line, &operands[0].text)?]
+            }
+            Ldc | LdcW => {
+                expect(1)?;
+                let index = self.loadable(line, &operands[0])?;
+                if opcode == Ldc && index <= u8::MAX as u16 {
+                    vec![index as u8]
+                } else {
+                    return Ok((LdcW, Operands::Bytes(index.to_be_bytes().to_vec())));
+                }
+            }
+            Ldc2W => {
+                expect(1)?;
+                let text = &operands[0].text;
+                let constant = match text.parse::<i64>() {
+                    Ok(value) => Constant::Long(value),
+                    Err(_) => Constant::Double(line.parse(text)?),
+                };
+                self.constants.find_or_add(constant)?.to_be_bytes().to_vec()
+            }
+            InvokeDynamic => {
+                return Err(line.error("invokedynamic is not supported.".to_owned()));
+            }
+            Wide => {
+                return Err(line.error(
+                    "wide is used automatically for large local variable indexes.".to_owned(),
+                ));
+            }
+            _ => {
+                expect(0)?;
+                vec![]
+            }
+        };
+
+        Ok((opcode, Operands::Bytes(bytes)))
+    }
+
+    fn member_ref(
+        &mut self,
+        class: &str,
+        name: &str,
+        descriptor: &str,
+        constant: fn(u16, u16) -> Constant,
+    ) -> Result<u16> {
+        let class_index = self.constants.find_or_add_class(class)?;
+        let name_and_type_index = self.constants.find_or_add_name_and_type(name, descriptor)?;
+        self.constants
+            .find_or_add(constant(class_index, name_and_type_index))
+    }
+
+    /// Adds the constant loaded by `ldc`: a quoted string, an int, a float or a class name.
+    fn loadable(&mut self, line: &Line, token: &Token) -> Result<u16> {
+        let text = &token.text;
+        let constant = if token.quoted {
+            Constant::StringRef(self.constants.find_or_add_utf8(text)?)
+        } else if let Ok(value) = text.parse::<i32>() {
+            Constant::Integer(value)
+        } else if let Ok(value) = text.parse::<f32>() {
+            Constant::Float(value)
+        } else if text.starts_with(|c: char| c.is_ascii_digit() || c == '-') {
+            return Err(line.error(format!("Invalid number '{}'.", text)));
+        } else {
+            Constant::ClassRef(self.constants.find_or_add_utf8(text)?)
+        };
+        self.constants.find_or_add(constant)
+    }
+
+    /// Resolves labels and encodes the instructions. They are written and read back, so that the
+    /// code has the layout of code read from a class file.
+    fn code(&mut self) -> Result<Code> {
+        let mut instructions = Vec::with_capacity(self.items.len());
+        for item in &self.items {
+            let operands = match &item.operands {
+                Operands::Bytes(bytes) => bytes.clone(),
+                Operands::Wide(opcode, bytes) => {
+                    instructions.push(Instruction::wide(opcode.clone(), bytes.clone()));
+                    continue;
+                }
+                Operands::Branch(label) => {
+                    let offset = self.relative(item, label)?;
+                    let offset: i16 = offset.try_into().map_err(|_| {
+                        item_error(item, format!("Label '{}' is too far away.", label))
+                    })?;
+                    offset.to_be_bytes().to_vec()
+                }
+                Operands::WideBranch(label) => self.relative(item, label)?.to_be_bytes().to_vec(),
+                Operands::TableSwitch {
+                    low,
+                    targets,
+                    default,
+                } => {
+                    let high = low + targets.len() as i32 - 1;
+                    let mut bytes = self.relative(item, default)?.to_be_bytes().to_vec();
+                    bytes.extend_from_slice(&low.to_be_bytes());
+                    bytes.extend_from_slice(&high.to_be_bytes());
+                    for target in targets {
+                        bytes.extend_from_slice(&self.relative(item, target)?.to_be_bytes());
+                    }
+                    bytes
+                }
+                Operands::LookupSwitch { pairs, default } => {
+                    let mut bytes = self.relative(item, default)?.to_be_bytes().to_vec();
+                    bytes.extend_from_slice(&(pairs.len() as i32).to_be_bytes());
+                    for (key, target) in pairs {
+                        bytes.extend_from_slice(&key.to_be_bytes());
+                        bytes.extend_from_slice(&self.relative(item, target)?.to_be_bytes());
+                    }
+                    bytes
+                }
+            };
+            instructions.push(Instruction::new(item.opcode.clone(), operands));
+        }
+
+        let mut bytes = Vec::new();
+        CodeWriter::new(&mut bytes).write_code(&instructions)?;
+        let instructions = CodeReader::new(&mut Cursor::new(bytes)).read_code()?;
+
+        let mut exception_handlers = Vec::with_capacity(self.catches.len());
+        for catch in &self.catches {
+            let label = |name: &str| {
+                self.labels
+                    .get(name)
+                    .map(|offset| *offset as u16)
+                    .ok_or_else(|| {
+                        Error::new(
+                            ParseError,
+                            Some(format!("Line {}: Unknown label '{}'.", catch.line, name)),
+                        )
+                    })
+            };
+            exception_handlers.push(ExceptionHandler {
+                start_pc: label(&catch.start)?,
+                end_pc: label(&catch.end)?,
+                handler_pc: label(&catch.handler)?,
+                catch_type: catch.catch_type.clone(),
+            });
+        }
+
+        let mut attributes = vec![];
+        if !self.line_numbers.is_empty() {
+            attributes.push(Attribute {
+                name: "LineNumberTable".to_owned(),
+                data: LineNumberTable(self.line_numbers.clone()),
+            });
+        }
+
+        Ok(Code::new(
+            self.max_stack,
+            self.max_locals,
+            exception_handlers,
+            attributes,
+            instructions,
+        ))
+    }
+
+    /// Returns the offset of the label relative to the instruction.
+    fn relative(&self, item: &Item, label: &str) -> Result<i32> {
+        match self.labels.get(label) {
+            Some(offset) => Ok(*offset as i32 - item.offset as i32),
+            None => Err(item_error(item, format!("Unknown label '{}'.", label))),
+        }
+    }
+}
+
+/// Parses the targets of a switch from the lines following it. Returns the operands and the number
+/// of lines used.
+fn switch(
+    opcode: &Opcode,
+    line: &Line,
+    operands: &[Token],
+    following: &[Line],
+) -> Result<(Operands, usize)> {
+    let mut targets = vec![];
+    let mut pairs = vec![];
+
+    for (i, target_line) in following.iter().enumerate() {
+        let tokens: Vec<&str> = target_line.tokens.iter().map(|t| t.text.as_str()).collect();
+        match (opcode, &tokens[..]) {
+            (_, ["default:", default]) => {
+                let default = default.to_string();
+                let operands = if *opcode == TableSwitch {
+                    let (low, high) = match operands {
+                        [low] => (line.parse(&low.text)?, None),
+                        [low, high] => (line.parse(&low.text)?, Some(line.parse(&high.text)?)),
+                        _ => {
+                            return Err(
+                                line.error("Expected tableswitch <low> [<high>].".to_owned())
+                            )
+                        }
+                    };
+                    if targets.is_empty()
+                        || high.is_some_and(|high: i32| high - low + 1 != targets.len() as i32)
+                    {
+                        return Err(line.error("Wrong number of tableswitch targets.".to_owned()));
+                    }
+                    Operands::TableSwitch {
+                        low,
+                        targets,
+                        default,
+                    }
+                } else {
+                    if !operands.is_empty() {
+                        return Err(
+                            line.error("Expected lookupswitch without operands.".to_owned())
+                        );
+                    }
+                    pairs.sort_by_key(|(key, _)| *key);
+                    if pairs.windows(2).any(|w: &[(i32, String)]| w[0].0 == w[1].0) {
+                        return Err(line.error("Duplicate lookupswitch key.".to_owned()));
+                    }
+                    Operands::LookupSwitch { pairs, default }
+                };
+                return Ok((operands, i + 1));
+            }
+            (TableSwitch, [target]) => targets.push(target.to_string()),
+            (LookupSwitch, [key, target]) if key.ends_with(':') => {
+                let key = target_line.parse(&key[..key.len() - 1])?;
+                pairs.push((key, target.to_string()));
+            }
+            _ => return Err(target_line.error("Invalid switch target.".to_owned())),
+        }
+    }
+
+    Err(line.error("Missing default target.".to_owned()))
+}
+
+#[derive(Clone)]
+struct Token {
+    text: String,
+    quoted: bool,
+}
+
+#[derive(Clone)]
+struct Line {
+    number: usize,
+    tokens: Vec<Token>,
+}
+
+impl Line {
+    fn error(&self, message: String) -> Error {
+        Error::new(
+            ParseError,
+            Some(format!("Line {}: {}", self.number, message)),
+        )
+    }
+
+    fn parse<T: FromStr>(&self, text: &str) -> Result<T> {
+        text.parse()
+            .map_err(|_| self.error(format!("Invalid number '{}'.", text)))
+    }
+
+    /// Returns the operands of a directive, which must be exactly `N`.
+    fn operands<const N: usize>(&self) -> Result<[&str; N]> {
+        let texts: Vec<&str> = self.tokens[1..].iter().map(|t| t.text.as_str()).collect();
+        texts.try_into().map_err(|_| {
+            self.error(format!(
+                "Expected {} operands for {}.",
+                N, self.tokens[0].text
+            ))
+        })
+    }
+
+    fn flags(&self, words: &[Token], names: &[(&str, u16)]) -> Result<u16> {
+        let mut bits = 0;
+        for word in words {
+            match names.iter().find(|(name, _)| *name == word.text) {
+                Some((_, flag)) => bits |= flag,
+                None => return Err(self.error(format!("Unknown flag '{}'.", word.text))),
+            }
+        }
+        Ok(bits)
+    }
+}
+
+fn item_error(item: &Item, message: String) -> Error {
+    Error::new(ParseError, Some(format!("Line {}: {}", item.line, message)))
+}
+
+fn tokenize(source: &str) -> Result<Vec<Line>> {
+    let mut lines = vec![];
+    for (i, text) in source.lines().enumerate() {
+        let mut line = Line {
+            number: i + 1,
+            tokens: vec![],
+        };
+        let mut chars = text.chars().peekable();
+
+        loop {
+            while chars.next_if(|c| c.is_whitespace()).is_some() {}
+            match chars.peek() {
+                None | Some(';') => break,
+                Some('"') => {
+                    chars.next();
+                    let text = string(&mut chars).map_err(|m| line.error(m))?;
+                    line.tokens.push(Token { text, quoted: true });
+                }
+                Some(_) => {
+                    let mut text = String::new();
+                    while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                        text.push(c);
+                    }
+                    line.tokens.push(Token {
+                        text,
+                        quoted: false,
+                    });
+                }
+            }
+        }
+
+        if !line.tokens.is_empty() {
+            lines.push(line);
+        }
+    }
+    Ok(lines)
+}
+
+/// Reads a quoted string up to the closing quote, with Java escape sequences.
+fn string<I: Iterator<Item = char>>(chars: &mut I) -> std::result::Result<String, String> {
+    let mut units: Vec<u16> = vec![];
+    let mut buffer = [0u16; 2];
+
+    loop {
+        let c = match chars.next() {
+            None => return Err("Unterminated string.".to_owned()),
+            Some('"') => return Ok(String::from_utf16_lossy(&units)),
+            Some('\\') => match chars.next() {
+                Some('n') => '\n',
+                Some('t') => '\t',
+                Some('r') => '\r',
+                Some('"') => '"',
+                Some('\\') => '\\',
+                Some('u') => {
+                    let hex: String = chars.take(4).collect();
+                    let unit = u16::from_str_radix(&hex, 16)
+                        .map_err(|_| format!("Invalid escape '\\u{}'.", hex))?;
+                    units.push(unit);
+                    continue;
+                }
+                c => return Err(format!("Invalid escape '\\{}'.", c.unwrap_or(' '))),
+            },
+            Some(c) => c,
+        };
+        units.extend_from_slice(c.encode_utf16(&mut buffer));
+    }
+}
+
+/// Splits a member reference like `java/lang/System/out` into class and name.
+fn member<'t>(line: &Line, text: &'t str) -> Result<(&'t str, &'t str)> {
+    match text.rfind('/') {
+        Some(i) if i > 0 && i < text.len() - 1 => Ok((&text[..i], &text[i + 1..])),
+        _ => Err(line.error(format!("Invalid member '{}'.", text))),
+    }
+}
+
+/// Splits a method reference like `java/io/PrintStream/println(I)V` into class, name and
+/// descriptor.
+fn method<'t>(line: &Line, text: &'t str) -> Result<(&'t str, &'t str, &'t str)> {
+    let split = text
+        .find('(')
+        .ok_or_else(|| line.error(format!("Invalid method '{}'.", text)))?;
+    let (member_text, descriptor) = text.split_at(split);
+    let (class, name) = member(line, member_text)?;
+    method_descriptor(line, descriptor)?;
+    Ok((class, name, descriptor))
+}
+
+fn field_type(line: &Line, descriptor: &str) -> Result<FieldType> {
+    match field_descriptor_len(descriptor) {
+        Some(len) if len == descriptor.len() => descriptor.try_into(),
+        _ => Err(line.error(format!("Invalid descriptor '{}'.", descriptor))),
+    }
+}
+
+fn method_descriptor(line: &Line, descriptor: &str) -> Result<MethodDescriptor> {
+    let invalid = || line.error(format!("Invalid descriptor '{}'.", descriptor));
+
+    let mut rest = descriptor.strip_prefix('(').ok_or_else(invalid)?;
+    while !rest.starts_with(')') {
+        let len = field_descriptor_len(rest).ok_or_else(invalid)?;
+        rest = &rest[len..];
+    }
+    let return_type = &rest[1..];
+    if return_type != "V" && field_descriptor_len(return_type) != Some(return_type.len()) {
+        return Err(invalid());
+    }
+
+    descriptor.try_into()
+}
+
+/// Returns the length of the field descriptor at the start of `descriptor`.
+fn field_descriptor_len(descriptor: &str) -> Option<usize> {
+    match descriptor.chars().next()? {
+        'B' | 'C' | 'D' | 'F' | 'I' | 'J' | 'S' | 'Z' => Some(1),
+        'L' => descriptor
+            .find(';')
+            .filter(|end| *end > 1)
+            .map(|end| end + 1),
+        '[' => field_descriptor_len(&descriptor[1..]).map(|len| len + 1),
+        _ => None,
+    }
+}
+
+fn argument_slots(descriptor: &MethodDescriptor) -> u16 {
+    descriptor
+        .argument_types
+        .iter()
+        .map(|t| match t {
+            FieldType::Long | FieldType::Double => 2,
+            _ => 1,
+        })
+        .sum()
+}
+
+fn switch_pad(offset: u32) -> u32 {
+    (4 - (offset + 1) % 4) % 4
+}
+
+fn array_type(line: &Line, name: &str) -> Result<u8> {
+    Ok(match name {
+        "boolean" => 4,
+        "char" => 5,
+        "float" => 6,
+        "double" => 7,
+        "byte" => 8,
+        "short" => 9,
+        "int" => 10,
+        "long" => 11,
+        _ => return Err(line.error(format!("Invalid array type '{}'.", name))),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::assembler::{assemble, assemble_code};
+    use crate::class::attribute::AttributeData::{ConstantValue, Exceptions, LineNumberTable};
+    use crate::class::attribute::ExceptionHandler;
+    use crate::class::code::Instruction;
+    use crate::class::code::Opcode::*;
+    use crate::class::constant::{Constant, ConstantPool};
+    use crate::class::{ClassAccessFlags, MethodAccessFlags};
+
+    #[test]
+    fn assemble_class() {
+        let class = assemble(
+            r#"
+            .bytecode 52.0
+            .source Example.java
+            .class public final example/Example
+            .super example/Base
+            .implements java/lang/Runnable
+            .field private static final NAME Ljava/lang/String; = "name"
+            .field public count J
+
+            .method public abstract run()V
+                .throws java/lang/Exception
+            .end method
+
+            .method public static add(JI)J ; Takes three slots
+                lload_0
+                iload_2
+                i2l
+                ladd
+                lreturn
+            .end method
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(class.version.major, 52);
+        assert_eq!(class.this_class, "example/Example");
+        assert_eq!(class.super_class, "example/Base");
+        assert_eq!(class.interfaces, vec!["java/lang/Runnable"]);
+        assert_eq!(
+            class.access_flags,
+            ClassAccessFlags::PUBLIC | ClassAccessFlags::FINAL | ClassAccessFlags::SUPER
+        );
+
+        let name = &class.fields[0];
+        assert_eq!(name.descriptor, "Ljava/lang/String;");
+        match &name.attributes[0].data {
+            ConstantValue(Constant::StringRef(index)) => {
+                assert_eq!(class.constants.get_utf8(*index).unwrap(), "name")
+            }
+            data => panic!("Unexpected constant value {:?}", data),
+        }
+        assert!(class.fields[1].attributes.is_empty());
+
+        let run = &class.methods[0];
+        assert!(run.get_code().is_none());
+        assert_eq!(
+            run.attributes[0].data,
+            Exceptions(vec!["java/lang/Exception".to_owned()])
+        );
+
+        let add = &class.methods[1];
+        assert_eq!(
+            add.access_flags,
+            MethodAccessFlags::ACC_PUBLIC | MethodAccessFlags::ACC_STATIC
+        );
+        let code = add.get_code().unwrap();
+        assert_eq!(code.max_stack, 0);
+        assert_eq!(code.max_locals, 3);
+        assert_eq!(code.instructions.len(), 5);
+    }
+
+    #[test]
+    fn assemble_labels() {
+        let mut constants = ConstantPool::new(0);
+        let code = assemble_code(
+            "
+                .limit stack 1
+            Loop:
+                iinc 0 1
+                goto Loop
+                jsr_w Loop
+            ",
+            &mut constants,
+        )
+        .unwrap();
+
+        assert_eq!(code.max_stack, 1);
+        assert_eq!(
+            code.instructions,
+            vec![
+                Instruction::new(IInc, vec![0, 1]),
+                Instruction::operation_spacer(),
+                Instruction::operation_spacer(),
+                Instruction::new(Goto, vec![0xff, 0xfd]),
+                Instruction::operation_spacer(),
+                Instruction::operation_spacer(),
+                Instruction::new(JsrW, vec![0xff, 0xff, 0xff, 0xfa]),
+                Instruction::operation_spacer(),
+                Instruction::operation_spacer(),
+                Instruction::operation_spacer(),
+                Instruction::operation_spacer(),
+            ]
+        );
+    }
+
+    #[test]
+    fn assemble_wide() {
+        let mut constants = ConstantPool::new(0);
+        let code = assemble_code(
+            "
+                iload 300
+                iinc 1 -200
+                ret 2
+            ",
+            &mut constants,
+        )
+        .unwrap();
+
+        let instructions: Vec<&Instruction> = code
+            .instructions
+            .iter()
+            .filter(|i| i.opcode != OperationSpacer)
+            .collect();
+        assert_eq!(
+            instructions,
+            vec![
+                &Instruction::wide(ILoad, vec![0x01, 0x2c]),
+                &Instruction::wide(IInc, vec![0x00, 0x01, 0xff, 0x38]),
+                &Instruction::new(Ret, vec![2]),
+            ]
+        );
+    }
+
+    #[test]
+    fn assemble_constants() {
+        let mut constants = ConstantPool::new(0);
+        for i in 0..300 {
+            constants.add(Constant::Integer(i));
+        }
+        let code = assemble_code(
+            r#"
+                ldc 1
+                ldc 1000
+                ldc "text"
+                ldc2_w 5
+                ldc2_w 0.5
+                getstatic java/lang/System/out Ljava/io/PrintStream;
+                invokeinterface java/util/List/add(ILjava/lang/Object;)V
+            "#,
+            &mut constants,
+        )
+        .unwrap();
+
+        let instructions: Vec<&Instruction> = code
+            .instructions
+            .iter()
+            .filter(|i| i.opcode != OperationSpacer)
+            .collect();
+
+        assert_eq!(instructions[0], &Instruction::new(Ldc, vec![2]));
+        assert_eq!(instructions[1].opcode, LdcW);
+        assert_eq!(instructions[2].opcode, LdcW);
+        assert_eq!(instructions[4].opcode, Ldc2W);
+
+        let index = |i: &Instruction| u16::from_be_bytes([i.operands[0], i.operands[1]]);
+        assert_eq!(
+            constants.get(index(instructions[1])).unwrap(),
+            &Constant::Integer(1000)
+        );
+        assert_eq!(
+            constants.get_string(index(instructions[2])).unwrap(),
+            "text"
+        );
+        assert_eq!(
+            constants.get(index(instructions[3])).unwrap(),
+            &Constant::Long(5)
+        );
+        assert_eq!(
+            constants.get(index(instructions[4])).unwrap(),
+            &Constant::Double(0.5)
+        );
+        assert_eq!(
+            constants
+                .get_interface_method_ref(index(instructions[6]))
+                .unwrap(),
+            ("java/util/List", "add", "(ILjava/lang/Object;)V")
+        );
+        assert_eq!(instructions[6].operands[2..], [3, 0]);
+    }
+
+    #[test]
+    fn assemble_switches() {
+        let mut constants = ConstantPool::new(0);
+        let code = assemble_code(
+            "
+                iload_0
+                tableswitch 1
+                    One
+                    default: Other
+            One:
+                lookupswitch
+                    10: Other
+                    -1: One
+                    default: Other
+            Other:
+                return
+            ",
+            &mut constants,
+        )
+        .unwrap();
+
+        assert_eq!(
+            code.instructions[1],
+            Instruction::new_with_pad(
+                TableSwitch,
+                vec![0, 0, 0, 0x2f, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 0x13],
+                2
+            )
+        );
+        assert_eq!(
+            code.instructions[0x14],
+            Instruction::new_with_pad(
+                LookupSwitch,
+                vec![
+                    0, 0, 0, 0x1c, 0, 0, 0, 2, 0xff, 0xff, 0xff, 0xff, 0, 0, 0, 0, 0, 0, 0, 10, 0,
+                    0, 0, 0x1c,
+                ],
+                3
+            )
+        );
+        assert_eq!(code.instructions[0x30], Instruction::new(Return, vec![]));
+    }
+
+    #[test]
+    fn assemble_exception_handlers() {
+        let mut constants = ConstantPool::new(0);
+        let code = assemble_code(
+            "
+                .catch java/lang/Exception from Start to End using Handler
+                .catch all from Start to End using Handler
+                .line 3
+            Start:
+                aconst_null
+                athrow
+            End:
+            Handler: .line 4
+                areturn
+            ",
+            &mut constants,
+        )
+        .unwrap();
+
+        assert_eq!(
+            code.exception_handlers,
+            vec![
+                ExceptionHandler {
+                    start_pc: 0,
+                    end_pc: 2,
+                    handler_pc: 2,
+                    catch_type: Some("java/lang/Exception".to_owned()),
+                },
+                ExceptionHandler {
+                    start_pc: 0,
+                    end_pc: 2,
+                    handler_pc: 2,
+                    catch_type: None,
+                },
+            ]
+        );
+        assert_eq!(
+            code.attributes[0].data,
+            LineNumberTable(vec![(0, 3), (2, 4)])
+        );
+    }
+
+    #[test]
+    fn assemble_errors() {
+        let error = |source: &str| {
+            let mut constants = ConstantPool::new(0);
+            assemble_code(source, &mut constants)
+                .unwrap_err()
+                .to_string()
+        };
+
+        assert_eq!(
+            error("goto\n  goto Missing"),
+            "Line 1: Expected 1 operands for goto, found 0."
+        );
+        assert_eq!(
+            error("nop\ngoto Missing"),
+            "Line 2: Unknown label 'Missing'."
+        );
+        assert_eq!(error("iconst_9"), "Line 1: Unknown instruction 'iconst_9'.");
+        assert_eq!(error("bipush 128"), "Line 1: Invalid number '128'.");
+        assert_eq!(
+            error("getstatic A/b Lc"),
+            "Line 1: Invalid descriptor 'Lc'."
+        );
+        assert_eq!(error("ldc \"open"), "Line 1: Unterminated string.");
+        assert_eq!(
+            error("iload_0\ntableswitch 0\n  L"),
+            "Line 2: Missing default target."
+        );
+        assert_eq!(
+            assemble(".method static f()V\nreturn")
+                .unwrap_err()
+                .to_string(),
+            "Line 1: Missing .end method."
+        );
+    }
+}
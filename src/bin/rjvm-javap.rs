@@ -0,0 +1,34 @@
+use rjvm::javap;
+use rjvm::vm::class_loader::ClassLoader;
+use std::env;
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+
+    if args.len() < 3 {
+        println!("Usage: ./rjvm-javap class_path class_name...");
+        std::process::exit(1);
+    }
+
+    let class_path: Vec<&str> = args[1].split(':').collect();
+    let mut class_loader = ClassLoader::new();
+    class_loader.set_paths(class_path);
+
+    for (i, class_name) in args[2..].iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+
+        let result = class_loader
+            .read_class(&class_name.replace('.', "/"))
+            .and_then(|class| javap::disassemble(&class));
+
+        match result {
+            Ok(disassembly) => print!("{}", disassembly),
+            Err(error) => {
+                eprintln!("{}", error);
+                std::process::exit(1);
+            }
+        }
+    }
+}
@@ -1,6 +1,15 @@
-use crate::class::attribute::AttributeData::CodeInfo;
-use crate::class::attribute::{Attribute, Code};
+use crate::class::attribute::AttributeData::{
+    AnnotationDefault, BootstrapMethods, CodeInfo, EnclosingMethod, InnerClasses, Module,
+    ModuleMainClass, ModulePackages, NestHost, NestMembers, PermittedSubclasses, Record,
+    RuntimeVisibleParameterAnnotations, SourceFile,
+};
+use crate::class::attribute::{
+    Annotation, Attribute, BootstrapMethod, Code, ElementValue, InnerClass, ModuleDescriptor,
+    RecordComponent,
+};
 use crate::class::constant::ConstantPool;
+use crate::class::signature::{ClassSignature, MethodSignature, TypeSignature};
+use crate::error::Result;
 use crate::vm::data_type::MethodDescriptor;
 use std::convert::TryInto;
 use std::rc::Rc;
@@ -8,6 +17,7 @@ use std::rc::Rc;
 pub mod attribute;
 pub mod code;
 pub mod constant;
+pub mod signature;
 
 bitflags! {
     pub struct ClassAccessFlags: u16 {
@@ -23,6 +33,46 @@ bitflags! {
     }
 }
 
+bitflags! {
+    pub struct InnerClassAccessFlags: u16 {
+        const PUBLIC     = 0x0001;
+        const PRIVATE    = 0x0002;
+        const PROTECTED  = 0x0004;
+        const STATIC     = 0x0008;
+        const FINAL      = 0x0010;
+        const INTERFACE  = 0x0200;
+        const ABSTRACT   = 0x0400;
+        const SYNTHETIC  = 0x1000;
+        const ANNOTATION = 0x2000;
+        const ENUM       = 0x4000;
+    }
+}
+
+bitflags! {
+    pub struct ModuleFlags: u16 {
+        const OPEN      = 0x0020;
+        const SYNTHETIC = 0x1000;
+        const MANDATED  = 0x8000;
+    }
+}
+
+bitflags! {
+    pub struct RequiresFlags: u16 {
+        const TRANSITIVE   = 0x0020;
+        const STATIC_PHASE = 0x0040;
+        const SYNTHETIC    = 0x1000;
+        const MANDATED     = 0x8000;
+    }
+}
+
+bitflags! {
+    /// The flags of both exported and opened packages.
+    pub struct ExportsFlags: u16 {
+        const SYNTHETIC = 0x1000;
+        const MANDATED  = 0x8000;
+    }
+}
+
 bitflags! {
     pub struct FieldAccessFlags: u16 {
         const ACC_PUBLIC    = 0x0001;
@@ -84,6 +134,125 @@ impl Class {
         }
     }
 
+    /// The name of the source file from the `SourceFile` attribute.
+    pub fn source_file(&self) -> Option<&str> {
+        self.attributes.iter().find_map(|a| match &a.data {
+            SourceFile(file) => Some(file.as_str()),
+            _ => None,
+        })
+    }
+
+    /// The bootstrap method at `index` in the `BootstrapMethods` attribute.
+    pub fn bootstrap_method(&self, index: u16) -> Option<&BootstrapMethod> {
+        self.attributes
+            .iter()
+            .find_map(|a| match &a.data {
+                BootstrapMethods(methods) => Some(methods),
+                _ => None,
+            })
+            .and_then(|methods| methods.get(index as usize))
+    }
+
+    /// The entries of the `InnerClasses` attribute, both the classes nested in this class and
+    /// the classes this class is nested in.
+    pub fn inner_classes(&self) -> &[InnerClass] {
+        self.attributes
+            .iter()
+            .find_map(|a| match &a.data {
+                InnerClasses(classes) => Some(classes.as_slice()),
+                _ => None,
+            })
+            .unwrap_or(&[])
+    }
+
+    pub fn enclosing_method(&self) -> Option<&attribute::EnclosingMethod> {
+        self.attributes.iter().find_map(|a| match &a.data {
+            EnclosingMethod(method) => Some(method),
+            _ => None,
+        })
+    }
+
+    /// The host of the nest this class belongs to, a class without a `NestHost` attribute is its
+    /// own host.
+    pub fn nest_host(&self) -> &str {
+        self.attributes
+            .iter()
+            .find_map(|a| match &a.data {
+                NestHost(host) => Some(host.as_str()),
+                _ => None,
+            })
+            .unwrap_or(&self.this_class)
+    }
+
+    /// The members of the nest hosted by this class, from the `NestMembers` attribute.
+    pub fn nest_members(&self) -> &[String] {
+        self.attributes
+            .iter()
+            .find_map(|a| match &a.data {
+                NestMembers(members) => Some(members.as_slice()),
+                _ => None,
+            })
+            .unwrap_or(&[])
+    }
+
+    /// The annotations retained at runtime, from the `RuntimeVisibleAnnotations` attribute.
+    pub fn annotations(&self) -> &[Annotation] {
+        attribute::runtime_annotations(&self.attributes)
+    }
+
+    /// The generic signature, classes that are not generic and do not extend or implement generic
+    /// types have none.
+    pub fn signature(&self) -> Result<Option<ClassSignature>> {
+        attribute::signature(&self.attributes)
+            .map(TryInto::try_into)
+            .transpose()
+    }
+
+    /// The components of a record class, `None` if the class is not a record.
+    pub fn record_components(&self) -> Option<&[RecordComponent]> {
+        self.attributes.iter().find_map(|a| match &a.data {
+            Record(components) => Some(components.as_slice()),
+            _ => None,
+        })
+    }
+
+    /// The classes allowed to directly extend or implement a sealed class, `None` if the class is
+    /// not sealed.
+    pub fn permitted_subclasses(&self) -> Option<&[String]> {
+        self.attributes.iter().find_map(|a| match &a.data {
+            PermittedSubclasses(classes) => Some(classes.as_slice()),
+            _ => None,
+        })
+    }
+
+    /// The module declared by a `module-info` class.
+    pub fn module(&self) -> Option<&ModuleDescriptor> {
+        self.attributes.iter().find_map(|a| match &a.data {
+            Module(module) => Some(module),
+            _ => None,
+        })
+    }
+
+    /// All packages of the module, from the `ModulePackages` attribute added by tools like
+    /// `jar`. Without it only the exported and opened packages are known.
+    pub fn module_packages(&self) -> &[String] {
+        self.attributes
+            .iter()
+            .find_map(|a| match &a.data {
+                ModulePackages(packages) => Some(packages.as_slice()),
+                _ => None,
+            })
+            .unwrap_or(&[])
+    }
+
+    /// The main class of the module, from the `ModuleMainClass` attribute.
+    pub fn module_main_class(&self) -> Option<&str> {
+        self.attributes.iter().find_map(|a| match &a.data {
+            ModuleMainClass(class) => Some(class.as_str()),
+            _ => None,
+        })
+    }
+
     pub fn resolve_method(&self, name: &str, descriptor: &str) -> Option<Rc<MethodInfo>> {
         self.methods
             .iter()
@@ -167,6 +336,39 @@ impl MethodInfo {
             None
         }
     }
+
+    /// The annotations retained at runtime, from the `RuntimeVisibleAnnotations` attribute.
+    pub fn annotations(&self) -> &[Annotation] {
+        attribute::runtime_annotations(&self.attributes)
+    }
+
+    /// The runtime annotations of each parameter. Parameters without annotations may be left out
+    /// at the end, and synthetic parameters at the start, so the list can be shorter than the
+    /// descriptor.
+    pub fn parameter_annotations(&self) -> &[Vec<Annotation>] {
+        self.attributes
+            .iter()
+            .find_map(|a| match &a.data {
+                RuntimeVisibleParameterAnnotations(parameters) => Some(parameters.as_slice()),
+                _ => None,
+            })
+            .unwrap_or(&[])
+    }
+
+    /// The generic signature, methods without generic types have none.
+    pub fn signature(&self) -> Result<Option<MethodSignature>> {
+        attribute::signature(&self.attributes)
+            .map(TryInto::try_into)
+            .transpose()
+    }
+
+    /// The default value of an annotation element, declared by a method of an annotation type.
+    pub fn annotation_default(&self) -> Option<&ElementValue> {
+        self.attributes.iter().find_map(|a| match &a.data {
+            AnnotationDefault(value) => Some(value),
+            _ => None,
+        })
+    }
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -177,6 +379,20 @@ pub struct FieldInfo {
     pub attributes: Vec<Attribute>,
 }
 
+impl FieldInfo {
+    /// The annotations retained at runtime, from the `RuntimeVisibleAnnotations` attribute.
+    pub fn annotations(&self) -> &[Annotation] {
+        attribute::runtime_annotations(&self.attributes)
+    }
+
+    /// The generic signature, fields without generic types have none.
+    pub fn signature(&self) -> Result<Option<TypeSignature>> {
+        attribute::signature(&self.attributes)
+            .map(TryInto::try_into)
+            .transpose()
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::class::constant::ConstantPool;
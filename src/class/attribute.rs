@@ -1,5 +1,9 @@
 use crate::class::code::Instruction;
 use crate::class::constant::Constant;
+use crate::class::signature::TypeSignature;
+use crate::class::{ExportsFlags, InnerClassAccessFlags, ModuleFlags, RequiresFlags};
+use crate::error::Result;
+use std::convert::TryInto;
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct Attribute {
@@ -14,9 +18,314 @@ pub enum AttributeData {
     CodeInfo(Code),
     ConstantValue(Constant),
     Exceptions(Vec<String>),
+    BootstrapMethods(Vec<BootstrapMethod>),
+    InnerClasses(Vec<InnerClass>),
+    EnclosingMethod(EnclosingMethod),
+    NestHost(String),
+    NestMembers(Vec<String>),
+    StackMapTable(Vec<StackMapFrame>),
+    LocalVariableTable(Vec<LocalVariable>),
+    LocalVariableTypeTable(Vec<LocalVariable>),
+    /// A generic signature, parsed with `ClassSignature`, `MethodSignature` or `TypeSignature`
+    /// depending on what the attribute belongs to.
+    Signature(String),
+    RuntimeVisibleAnnotations(Vec<Annotation>),
+    RuntimeInvisibleAnnotations(Vec<Annotation>),
+    RuntimeVisibleParameterAnnotations(Vec<Vec<Annotation>>),
+    RuntimeInvisibleParameterAnnotations(Vec<Vec<Annotation>>),
+    RuntimeVisibleTypeAnnotations(Vec<TypeAnnotation>),
+    RuntimeInvisibleTypeAnnotations(Vec<TypeAnnotation>),
+    AnnotationDefault(ElementValue),
+    Module(ModuleDescriptor),
+    /// The packages of the module, in internal form like `java/lang`.
+    ModulePackages(Vec<String>),
+    ModuleMainClass(String),
+    Record(Vec<RecordComponent>),
+    PermittedSubclasses(Vec<String>),
     Unknown(Vec<u8>),
 }
 
+/// A bootstrap method used by `invokedynamic`. Both the method handle and the static arguments
+/// are indexes in the constant pool of the class.
+#[derive(Debug, PartialEq, Clone)]
+pub struct BootstrapMethod {
+    pub method_ref: u16,
+    pub arguments: Vec<u16>,
+}
+
+/// An entry of the `InnerClasses` attribute. The outer class is missing for local and anonymous
+/// classes, the name is missing for anonymous classes.
+#[derive(Debug, PartialEq, Clone)]
+pub struct InnerClass {
+    pub inner_class: String,
+    pub outer_class: Option<String>,
+    pub inner_name: Option<String>,
+    pub access_flags: InnerClassAccessFlags,
+}
+
+/// The innermost method enclosing a local or anonymous class, as the name and descriptor. There
+/// is no method when the class is declared in an initializer.
+#[derive(Debug, PartialEq, Clone)]
+pub struct EnclosingMethod {
+    pub class: String,
+    pub method: Option<(String, String)>,
+}
+
+/// The module declared by a `module-info` class, from the `Module` attribute. Packages are in
+/// internal form, like `java/lang`, while modules use their dotted names.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ModuleDescriptor {
+    pub name: String,
+    pub flags: ModuleFlags,
+    pub version: Option<String>,
+    pub requires: Vec<Requires>,
+    pub exports: Vec<Exports>,
+    pub opens: Vec<Exports>,
+    /// The services the module consumes.
+    pub uses: Vec<String>,
+    pub provides: Vec<Provides>,
+}
+
+/// A dependence on another module. The version is the one the module was compiled against.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Requires {
+    pub module: String,
+    pub flags: RequiresFlags,
+    pub version: Option<String>,
+}
+
+/// A package exported or opened to all modules, or only to the modules in `to` when it is not
+/// empty.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Exports {
+    pub package: String,
+    pub flags: ExportsFlags,
+    pub to: Vec<String>,
+}
+
+/// The classes implementing a service for `ServiceLoader`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Provides {
+    pub service: String,
+    pub with: Vec<String>,
+}
+
+/// A component of a record class, from the `Record` attribute. The component has a private field
+/// and an accessor method with the same name.
+#[derive(Debug, PartialEq, Clone)]
+pub struct RecordComponent {
+    pub name: String,
+    pub descriptor: String,
+    pub attributes: Vec<Attribute>,
+}
+
+impl RecordComponent {
+    /// The annotations retained at runtime, from the `RuntimeVisibleAnnotations` attribute.
+    pub fn annotations(&self) -> &[Annotation] {
+        runtime_annotations(&self.attributes)
+    }
+
+    /// The generic signature, components without generic types have none.
+    pub fn signature(&self) -> Result<Option<TypeSignature>> {
+        signature(&self.attributes)
+            .map(TryInto::try_into)
+            .transpose()
+    }
+}
+
+/// A frame of the `StackMapTable` attribute, the types of the locals and operands at the start of
+/// a basic block. The offset of the first frame is `offset_delta`, the offset of each following
+/// frame is `offset_delta + 1` past the previous one.
+///
+/// The extended forms of `Same` and `SameLocals1StackItem` are used when `offset_delta` does not
+/// fit in the frame type.
+#[derive(Debug, PartialEq, Clone)]
+pub enum StackMapFrame {
+    /// Same locals as the previous frame and an empty stack.
+    Same { offset_delta: u16 },
+
+    /// Same locals as the previous frame and a single operand.
+    SameLocals1StackItem {
+        offset_delta: u16,
+        stack: VerificationType,
+    },
+
+    /// The last `count` locals of the previous frame are removed and the stack is empty.
+    Chop { offset_delta: u16, count: u8 },
+
+    /// Locals added to those of the previous frame, the stack is empty.
+    Append {
+        offset_delta: u16,
+        locals: Vec<VerificationType>,
+    },
+
+    Full {
+        offset_delta: u16,
+        locals: Vec<VerificationType>,
+        stack: Vec<VerificationType>,
+    },
+}
+
+impl StackMapFrame {
+    pub fn offset_delta(&self) -> u16 {
+        match self {
+            StackMapFrame::Same { offset_delta }
+            | StackMapFrame::SameLocals1StackItem { offset_delta, .. }
+            | StackMapFrame::Chop { offset_delta, .. }
+            | StackMapFrame::Append { offset_delta, .. }
+            | StackMapFrame::Full { offset_delta, .. } => *offset_delta,
+        }
+    }
+}
+
+/// The type of a local or operand in a stack map frame. Longs and doubles take up a single entry.
+#[derive(Debug, PartialEq, Clone)]
+pub enum VerificationType {
+    Top,
+    Integer,
+    Float,
+    Double,
+    Long,
+    Null,
+    UninitializedThis,
+    Object(String),
+
+    /// An object created by the `new` instruction at the offset, not yet initialized.
+    Uninitialized(u16),
+}
+
+/// An entry of the `LocalVariableTable` or `LocalVariableTypeTable` attribute. The variable in
+/// slot `index` is named `name` from `start_pc` up to, but not including, `start_pc + length`.
+/// In a `LocalVariableTypeTable` the descriptor is a generic signature.
+#[derive(Debug, PartialEq, Clone)]
+pub struct LocalVariable {
+    pub start_pc: u16,
+    pub length: u16,
+    pub name: String,
+    pub descriptor: String,
+    pub index: u16,
+}
+
+/// An annotation of type `type_descriptor`, a field descriptor like `Ljava/lang/Deprecated;`.
+/// Elements left at their default value are not included.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Annotation {
+    pub type_descriptor: String,
+    pub elements: Vec<(String, ElementValue)>,
+}
+
+impl Annotation {
+    pub fn element(&self, name: &str) -> Option<&ElementValue> {
+        self.elements
+            .iter()
+            .find(|(element, _)| element == name)
+            .map(|(_, value)| value)
+    }
+}
+
+/// The value of an annotation element. Bytes, chars, shorts and booleans are stored as ints in
+/// the constant pool.
+#[derive(Debug, PartialEq, Clone)]
+pub enum ElementValue {
+    Byte(i8),
+    Char(u16),
+    Short(i16),
+    Boolean(bool),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    String(String),
+
+    /// An enum constant, the type is a field descriptor.
+    Enum {
+        type_descriptor: String,
+        name: String,
+    },
+
+    /// A class literal as a return descriptor, like `Ljava/lang/String;` or `V` for `void.class`.
+    Class(String),
+
+    Annotation(Annotation),
+    Array(Vec<ElementValue>),
+}
+
+/// An annotation on a use of a type. The `target_type` is the kind of use, like `0x13` for the
+/// type of a field, and the type path locates the annotated part of a compound type, like the
+/// component of an array or a type argument.
+#[derive(Debug, PartialEq, Clone)]
+pub struct TypeAnnotation {
+    pub target_type: u8,
+    pub target: TypeAnnotationTarget,
+    pub type_path: Vec<TypePathEntry>,
+    pub annotation: Annotation,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum TypeAnnotationTarget {
+    /// A type parameter of a generic class or method.
+    TypeParameter(u8),
+
+    /// The super class when `u16::MAX`, otherwise the interface at the index of `interfaces`.
+    Supertype(u16),
+
+    TypeParameterBound {
+        type_parameter: u8,
+        bound: u8,
+    },
+
+    /// The type of a field, the return type of a method or the type of its receiver.
+    Empty,
+
+    FormalParameter(u8),
+
+    /// The exception at the index of the `Exceptions` attribute.
+    Throws(u16),
+
+    /// The ranges where a local variable is live, as start pc, length and slot.
+    LocalVariable(Vec<(u16, u16, u16)>),
+
+    /// The handler at the index of the exception table.
+    Catch(u16),
+
+    /// The `instanceof`, `new` or method reference at the offset.
+    Offset(u16),
+
+    /// A type argument of a cast or generic invocation at the offset.
+    TypeArgument {
+        offset: u16,
+        type_argument: u8,
+    },
+}
+
+/// A step of a type path, `kind` is 0 for an array component, 1 for a nested type, 2 for the
+/// bound of a wildcard and 3 for the type argument at `type_argument_index`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct TypePathEntry {
+    pub kind: u8,
+    pub type_argument_index: u8,
+}
+
+/// The `Signature` attribute, if any.
+pub(crate) fn signature(attributes: &[Attribute]) -> Option<&str> {
+    attributes.iter().find_map(|a| match &a.data {
+        AttributeData::Signature(signature) => Some(signature.as_str()),
+        _ => None,
+    })
+}
+
+/// The annotations of the `RuntimeVisibleAnnotations` attribute, those available through
+/// reflection.
+pub(crate) fn runtime_annotations(attributes: &[Attribute]) -> &[Annotation] {
+    attributes
+        .iter()
+        .find_map(|a| match &a.data {
+            AttributeData::RuntimeVisibleAnnotations(annotations) => Some(annotations.as_slice()),
+            _ => None,
+        })
+        .unwrap_or(&[])
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct ExceptionHandler {
     pub start_pc: u16,
@@ -50,4 +359,91 @@ impl Code {
             instructions,
         }
     }
+
+    /// Returns the source line of the instruction at `pc`, if the code has a line number table.
+    pub fn line_number(&self, pc: u16) -> Option<u16> {
+        self.attributes
+            .iter()
+            .filter_map(|a| match &a.data {
+                AttributeData::LineNumberTable(table) => Some(table),
+                _ => None,
+            })
+            .flatten()
+            .filter(|(start_pc, _)| *start_pc <= pc)
+            .max_by_key(|(start_pc, _)| *start_pc)
+            .map(|(_, line_number)| *line_number)
+    }
+
+    /// Returns the local variable in `slot` at `pc`, if the code has a local variable table.
+    pub fn local_variable(&self, slot: u16, pc: u16) -> Option<&LocalVariable> {
+        self.attributes
+            .iter()
+            .filter_map(|a| match &a.data {
+                AttributeData::LocalVariableTable(table) => Some(table),
+                _ => None,
+            })
+            .flatten()
+            .find(|v| v.index == slot && v.start_pc <= pc && pc - v.start_pc < v.length)
+    }
+
+    pub fn stack_map_table(&self) -> &[StackMapFrame] {
+        self.attributes
+            .iter()
+            .find_map(|a| match &a.data {
+                AttributeData::StackMapTable(frames) => Some(frames.as_slice()),
+                _ => None,
+            })
+            .unwrap_or(&[])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::class::attribute::AttributeData::{LineNumberTable, LocalVariableTable};
+    use crate::class::attribute::{Attribute, Code, LocalVariable};
+
+    #[test]
+    fn line_number() {
+        let attribute = Attribute {
+            name: "LineNumberTable".to_owned(),
+            data: LineNumberTable(vec![(0, 5), (8, 7), (4, 6)]),
+        };
+        let code = Code::new(0, 0, vec![], vec![attribute], vec![]);
+
+        assert_eq!(code.line_number(0), Some(5));
+        assert_eq!(code.line_number(5), Some(6));
+        assert_eq!(code.line_number(10), Some(7));
+    }
+
+    #[test]
+    fn line_number_without_table() {
+        let code = Code::new(0, 0, vec![], vec![], vec![]);
+        assert_eq!(code.line_number(0), None);
+    }
+
+    #[test]
+    fn local_variable() {
+        let variable = |start_pc, length, name: &str, index| LocalVariable {
+            start_pc,
+            length,
+            name: name.to_owned(),
+            descriptor: "I".to_owned(),
+            index,
+        };
+        let attribute = Attribute {
+            name: "LocalVariableTable".to_owned(),
+            data: LocalVariableTable(vec![
+                variable(0, 10, "a", 0),
+                variable(2, 3, "b", 1),
+                variable(6, 4, "c", 1),
+            ]),
+        };
+        let code = Code::new(0, 2, vec![], vec![attribute], vec![]);
+
+        assert_eq!(code.local_variable(0, 9).unwrap().name, "a");
+        assert_eq!(code.local_variable(1, 4).unwrap().name, "b");
+        assert_eq!(code.local_variable(1, 6).unwrap().name, "c");
+        assert_eq!(code.local_variable(1, 5), None);
+        assert_eq!(code.local_variable(0, 10), None);
+    }
 }
@@ -4,6 +4,8 @@ use core::fmt;
 #[derive(Debug, Eq, PartialEq, Clone)]
 pub struct Instruction {
     pub opcode: Opcode,
+    /// The opcode modified by a `wide` instruction, its operands follow in `operands`.
+    pub modified: Option<Opcode>,
     pub operands: Vec<u8>,
     pad: u8,
 }
@@ -12,11 +14,19 @@ impl Instruction {
     pub fn new(opcode: Opcode, operands: Vec<u8>) -> Self {
         Instruction {
             opcode,
+            modified: None,
             operands,
             pad: 0,
         }
     }
 
+    /// A `wide` instruction modifying `opcode`, like `wide iload` with a two byte index.
+    pub fn wide(opcode: Opcode, operands: Vec<u8>) -> Self {
+        let mut instruction = Self::new(Opcode::Wide, operands);
+        instruction.modified = Some(opcode);
+        instruction
+    }
+
     pub fn new_with_pad(opcode: Opcode, operands: Vec<u8>, pad: u8) -> Self {
         let mut instruction = Self::new(opcode, operands);
         instruction.pad = pad;
@@ -28,221 +38,296 @@ impl Instruction {
     }
 
     pub fn size(&self) -> u16 {
-        1 + self.operands.len() as u16 + self.pad as u16
+        let modified = self.modified.is_some() as u16;
+        1 + modified + self.operands.len() as u16 + self.pad as u16
     }
 }
 
 impl fmt::Display for Instruction {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "{:<15}{:?}", format!("{:?}", self.opcode), self.operands)
+        let opcode = match &self.modified {
+            Some(modified) => format!("{:?} {:?}", self.opcode, modified),
+            None => format!("{:?}", self.opcode),
+        };
+        write!(f, "{:<15}{:?}", opcode, self.operands)
     }
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
-pub enum Opcode {
-    AaLoad,
-    AaStore,
-    AConstNull,
-    ALoad,
-    ALoad0,
-    ALoad1,
-    ALoad2,
-    ALoad3,
-    ANewArray,
-    AReturn,
-    ArrayLength,
-    AStore,
-    AStore0,
-    AStore1,
-    AStore2,
-    AStore3,
-    AThrow,
-    BaLoad,
-    BaStore,
-    BiPush,
-    CaLoad,
-    CaStore,
-    CheckCast,
-    D2f,
-    D2i,
-    D2l,
-    DAdd,
-    DaLoad,
-    DaStore,
-    DCmpg,
-    DCmpl,
-    DConst0,
-    DConst1,
-    DDiv,
-    DLoad,
-    DLoad0,
-    DLoad1,
-    DLoad2,
-    DLoad3,
-    DMul,
-    DNeg,
-    DRem,
-    DReturn,
-    DStore,
-    DStore0,
-    DStore1,
-    DStore2,
-    DStore3,
-    DSub,
-    Dup,
-    DupX1,
-    DupX2,
-    Dup2,
-    Dup2X1,
-    Dup2X2,
-    F2d,
-    F2i,
-    F2l,
-    FAdd,
-    FaLoad,
-    FaStore,
-    FCmpg,
-    FCmpl,
-    FConst0,
-    FConst1,
-    FConst2,
-    FDiv,
-    FLoad,
-    FLoad0,
-    FLoad1,
-    FLoad2,
-    FLoad3,
-    FMul,
-    FNeg,
-    FRem,
-    FReturn,
-    FStore,
-    FStore0,
-    FStore1,
-    FStore2,
-    FStore3,
-    FSub,
-    GetField,
-    GetStatic,
-    Goto,
-    GotoW,
-    I2b,
-    I2c,
-    I2d,
-    I2f,
-    I2l,
-    I2s,
-    IAdd,
-    IaLoad,
-    IAnd,
-    IaStore,
-    IConstM1,
-    IConst0,
-    IConst1,
-    IConst2,
-    IConst3,
-    IConst4,
-    IConst5,
-    IDiv,
-    IfAcmpEq,
-    IfAcmpNe,
-    IfIcmpEq,
-    IfIcmpNe,
-    IfIcmpLt,
-    IfIcmpGe,
-    IfIcmpGt,
-    IfIcmpLe,
-    IfEq,
-    IfNe,
-    IfLt,
-    IfGe,
-    IfGt,
-    IfLe,
-    IfNonNull,
-    IfNull,
-    IInc,
-    ILoad,
-    ILoad0,
-    ILoad1,
-    ILoad2,
-    ILoad3,
-    IMul,
-    INeg,
-    Instanceof,
-    InvokeDynamic,
-    InvokeInterface,
-    InvokeSpecial,
-    InvokeStatic,
-    InvokeVirtual,
-    IOr,
-    IRem,
-    IReturn,
-    IShl,
-    IShr,
-    IStore,
-    IStore0,
-    IStore1,
-    IStore2,
-    IStore3,
-    ISub,
-    IUshr,
-    IXor,
-    Jsr,
-    JsrW,
-    L2d,
-    L2f,
-    L2i,
-    LAdd,
-    LaLoad,
-    LAnd,
-    LaStore,
-    LCmp,
-    LConst0,
-    LConst1,
-    Ldc,
-    LdcW,
-    Ldc2W,
-    LDiv,
-    LLoad,
-    LLoad0,
-    LLoad1,
-    LLoad2,
-    LLoad3,
-    LMul,
-    LNeg,
-    LookupSwitch,
-    LOr,
-    LRem,
-    LReturn,
-    LShl,
-    LShr,
-    LStore,
-    LStore0,
-    LStore1,
-    LStore2,
-    LStore3,
-    LSub,
-    LUshr,
-    LXor,
-    MonitorEnter,
-    MonitorExit,
-    MultiANewArray,
-    New,
-    NewArray,
-    Nop,
-    Pop,
-    Pop2,
-    PutField,
-    PutStatic,
-    Ret,
-    Return,
-    SaLoad,
-    SaStore,
-    SiPush,
-    Swap,
-    TableSwitch,
-    Wide,
-    ImpDep2,
-    BreakPoint,
-    OperationSpacer, // Used to mark operations in code array
+/// Declares `Opcode` from the opcodes of the JVM specification, each with its byte in a class
+/// file, its mnemonic and the number of operand bytes. Variable width instructions list none.
+macro_rules! opcodes {
+    ($(($opcode:ident, $byte:literal, $mnemonic:literal, $argc:literal),)*) => {
+        #[derive(Debug, Eq, PartialEq, Clone)]
+        pub enum Opcode {
+            $($opcode,)*
+            OperationSpacer, // Used to mark operations in code array
+        }
+
+        impl Opcode {
+            /// Returns the name of the instruction in the JVM specification, like `iconst_m1`.
+            pub fn mnemonic(&self) -> &'static str {
+                match self {
+                    $(Opcode::$opcode => $mnemonic,)*
+                    Opcode::OperationSpacer => "<spacer>",
+                }
+            }
+
+            /// Returns the opcode with the name `mnemonic`, the inverse of `Opcode::mnemonic`.
+            pub fn from_mnemonic(mnemonic: &str) -> Option<Opcode> {
+                match mnemonic {
+                    $($mnemonic => Some(Opcode::$opcode),)*
+                    _ => None,
+                }
+            }
+
+            /// Returns the byte of the opcode in a class file, operation spacers have none.
+            pub fn byte(&self) -> Option<u8> {
+                match self {
+                    $(Opcode::$opcode => Some($byte),)*
+                    Opcode::OperationSpacer => None,
+                }
+            }
+
+            /// Returns the opcode of `byte` in a class file and the number of operand bytes
+            /// following it.
+            pub fn from_byte(byte: u8) -> Option<(Opcode, u8)> {
+                match byte {
+                    $($byte => Some((Opcode::$opcode, $argc)),)*
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+opcodes! {
+    (AaLoad, 0x32, "aaload", 0),
+    (AaStore, 0x53, "aastore", 0),
+    (AConstNull, 0x01, "aconst_null", 0),
+    (ALoad, 0x19, "aload", 1),
+    (ALoad0, 0x2a, "aload_0", 0),
+    (ALoad1, 0x2b, "aload_1", 0),
+    (ALoad2, 0x2c, "aload_2", 0),
+    (ALoad3, 0x2d, "aload_3", 0),
+    (ANewArray, 0xbd, "anewarray", 2),
+    (AReturn, 0xb0, "areturn", 0),
+    (ArrayLength, 0xbe, "arraylength", 0),
+    (AStore, 0x3a, "astore", 1),
+    (AStore0, 0x4b, "astore_0", 0),
+    (AStore1, 0x4c, "astore_1", 0),
+    (AStore2, 0x4d, "astore_2", 0),
+    (AStore3, 0x4e, "astore_3", 0),
+    (AThrow, 0xbf, "athrow", 0),
+    (BaLoad, 0x33, "baload", 0),
+    (BaStore, 0x54, "bastore", 0),
+    (BiPush, 0x10, "bipush", 1),
+    (CaLoad, 0x34, "caload", 0),
+    (CaStore, 0x55, "castore", 0),
+    (CheckCast, 0xc0, "checkcast", 2),
+    (D2f, 0x90, "d2f", 0),
+    (D2i, 0x8e, "d2i", 0),
+    (D2l, 0x8f, "d2l", 0),
+    (DAdd, 0x63, "dadd", 0),
+    (DaLoad, 0x31, "daload", 0),
+    (DaStore, 0x52, "dastore", 0),
+    (DCmpg, 0x98, "dcmpg", 0),
+    (DCmpl, 0x97, "dcmpl", 0),
+    (DConst0, 0x0e, "dconst_0", 0),
+    (DConst1, 0x0f, "dconst_1", 0),
+    (DDiv, 0x6f, "ddiv", 0),
+    (DLoad, 0x18, "dload", 1),
+    (DLoad0, 0x26, "dload_0", 0),
+    (DLoad1, 0x27, "dload_1", 0),
+    (DLoad2, 0x28, "dload_2", 0),
+    (DLoad3, 0x29, "dload_3", 0),
+    (DMul, 0x6b, "dmul", 0),
+    (DNeg, 0x77, "dneg", 0),
+    (DRem, 0x73, "drem", 0),
+    (DReturn, 0xaf, "dreturn", 0),
+    (DStore, 0x39, "dstore", 1),
+    (DStore0, 0x47, "dstore_0", 0),
+    (DStore1, 0x48, "dstore_1", 0),
+    (DStore2, 0x49, "dstore_2", 0),
+    (DStore3, 0x4a, "dstore_3", 0),
+    (DSub, 0x67, "dsub", 0),
+    (Dup, 0x59, "dup", 0),
+    (DupX1, 0x5a, "dup_x1", 0),
+    (DupX2, 0x5b, "dup_x2", 0),
+    (Dup2, 0x5c, "dup2", 0),
+    (Dup2X1, 0x5d, "dup2_x1", 0),
+    (Dup2X2, 0x5e, "dup2_x2", 0),
+    (F2d, 0x8d, "f2d", 0),
+    (F2i, 0x8b, "f2i", 0),
+    (F2l, 0x8c, "f2l", 0),
+    (FAdd, 0x62, "fadd", 0),
+    (FaLoad, 0x30, "faload", 0),
+    (FaStore, 0x51, "fastore", 0),
+    (FCmpg, 0x96, "fcmpg", 0),
+    (FCmpl, 0x95, "fcmpl", 0),
+    (FConst0, 0x0b, "fconst_0", 0),
+    (FConst1, 0x0c, "fconst_1", 0),
+    (FConst2, 0x0d, "fconst_2", 0),
+    (FDiv, 0x6e, "fdiv", 0),
+    (FLoad, 0x17, "fload", 1),
+    (FLoad0, 0x22, "fload_0", 0),
+    (FLoad1, 0x23, "fload_1", 0),
+    (FLoad2, 0x24, "fload_2", 0),
+    (FLoad3, 0x25, "fload_3", 0),
+    (FMul, 0x6a, "fmul", 0),
+    (FNeg, 0x76, "fneg", 0),
+    (FRem, 0x72, "frem", 0),
+    (FReturn, 0xae, "freturn", 0),
+    (FStore, 0x38, "fstore", 1),
+    (FStore0, 0x43, "fstore_0", 0),
+    (FStore1, 0x44, "fstore_1", 0),
+    (FStore2, 0x45, "fstore_2", 0),
+    (FStore3, 0x46, "fstore_3", 0),
+    (FSub, 0x66, "fsub", 0),
+    (GetField, 0xb4, "getfield", 2),
+    (GetStatic, 0xb2, "getstatic", 2),
+    (Goto, 0xa7, "goto", 2),
+    (GotoW, 0xc8, "goto_w", 4),
+    (I2b, 0x91, "i2b", 0),
+    (I2c, 0x92, "i2c", 0),
+    (I2d, 0x87, "i2d", 0),
+    (I2f, 0x86, "i2f", 0),
+    (I2l, 0x85, "i2l", 0),
+    (I2s, 0x93, "i2s", 0),
+    (IAdd, 0x60, "iadd", 0),
+    (IaLoad, 0x2e, "iaload", 0),
+    (IAnd, 0x7e, "iand", 0),
+    (IaStore, 0x4f, "iastore", 0),
+    (IConstM1, 0x02, "iconst_m1", 0),
+    (IConst0, 0x03, "iconst_0", 0),
+    (IConst1, 0x04, "iconst_1", 0),
+    (IConst2, 0x05, "iconst_2", 0),
+    (IConst3, 0x06, "iconst_3", 0),
+    (IConst4, 0x07, "iconst_4", 0),
+    (IConst5, 0x08, "iconst_5", 0),
+    (IDiv, 0x6c, "idiv", 0),
+    (IfAcmpEq, 0xa5, "if_acmpeq", 2),
+    (IfAcmpNe, 0xa6, "if_acmpne", 2),
+    (IfIcmpEq, 0x9f, "if_icmpeq", 2),
+    (IfIcmpNe, 0xa0, "if_icmpne", 2),
+    (IfIcmpLt, 0xa1, "if_icmplt", 2),
+    (IfIcmpGe, 0xa2, "if_icmpge", 2),
+    (IfIcmpGt, 0xa3, "if_icmpgt", 2),
+    (IfIcmpLe, 0xa4, "if_icmple", 2),
+    (IfEq, 0x99, "ifeq", 2),
+    (IfNe, 0x9a, "ifne", 2),
+    (IfLt, 0x9b, "iflt", 2),
+    (IfGe, 0x9c, "ifge", 2),
+    (IfGt, 0x9d, "ifgt", 2),
+    (IfLe, 0x9e, "ifle", 2),
+    (IfNonNull, 0xc7, "ifnonnull", 2),
+    (IfNull, 0xc6, "ifnull", 2),
+    (IInc, 0x84, "iinc", 2),
+    (ILoad, 0x15, "iload", 1),
+    (ILoad0, 0x1a, "iload_0", 0),
+    (ILoad1, 0x1b, "iload_1", 0),
+    (ILoad2, 0x1c, "iload_2", 0),
+    (ILoad3, 0x1d, "iload_3", 0),
+    (IMul, 0x68, "imul", 0),
+    (INeg, 0x74, "ineg", 0),
+    (Instanceof, 0xc1, "instanceof", 2),
+    (InvokeDynamic, 0xba, "invokedynamic", 4),
+    (InvokeInterface, 0xb9, "invokeinterface", 4),
+    (InvokeSpecial, 0xb7, "invokespecial", 2),
+    (InvokeStatic, 0xb8, "invokestatic", 2),
+    (InvokeVirtual, 0xb6, "invokevirtual", 2),
+    (IOr, 0x80, "ior", 0),
+    (IRem, 0x70, "irem", 0),
+    (IReturn, 0xac, "ireturn", 0),
+    (IShl, 0x78, "ishl", 0),
+    (IShr, 0x7a, "ishr", 0),
+    (IStore, 0x36, "istore", 1),
+    (IStore0, 0x3b, "istore_0", 0),
+    (IStore1, 0x3c, "istore_1", 0),
+    (IStore2, 0x3d, "istore_2", 0),
+    (IStore3, 0x3e, "istore_3", 0),
+    (ISub, 0x64, "isub", 0),
+    (IUshr, 0x7c, "iushr", 0),
+    (IXor, 0x82, "ixor", 0),
+    (Jsr, 0xa8, "jsr", 2),
+    (JsrW, 0xc9, "jsr_w", 4),
+    (L2d, 0x8a, "l2d", 0),
+    (L2f, 0x89, "l2f", 0),
+    (L2i, 0x88, "l2i", 0),
+    (LAdd, 0x61, "ladd", 0),
+    (LaLoad, 0x2f, "laload", 0),
+    (LAnd, 0x7f, "land", 0),
+    (LaStore, 0x50, "lastore", 0),
+    (LCmp, 0x94, "lcmp", 0),
+    (LConst0, 0x09, "lconst_0", 0),
+    (LConst1, 0x0a, "lconst_1", 0),
+    (Ldc, 0x12, "ldc", 1),
+    (LdcW, 0x13, "ldc_w", 2),
+    (Ldc2W, 0x14, "ldc2_w", 2),
+    (LDiv, 0x6d, "ldiv", 0),
+    (LLoad, 0x16, "lload", 1),
+    (LLoad0, 0x1e, "lload_0", 0),
+    (LLoad1, 0x1f, "lload_1", 0),
+    (LLoad2, 0x20, "lload_2", 0),
+    (LLoad3, 0x21, "lload_3", 0),
+    (LMul, 0x69, "lmul", 0),
+    (LNeg, 0x75, "lneg", 0),
+    (LookupSwitch, 0xab, "lookupswitch", 0), // Variable width
+    (LOr, 0x81, "lor", 0),
+    (LRem, 0x71, "lrem", 0),
+    (LReturn, 0xad, "lreturn", 0),
+    (LShl, 0x79, "lshl", 0),
+    (LShr, 0x7b, "lshr", 0),
+    (LStore, 0x37, "lstore", 1),
+    (LStore0, 0x3f, "lstore_0", 0),
+    (LStore1, 0x40, "lstore_1", 0),
+    (LStore2, 0x41, "lstore_2", 0),
+    (LStore3, 0x42, "lstore_3", 0),
+    (LSub, 0x65, "lsub", 0),
+    (LUshr, 0x7d, "lushr", 0),
+    (LXor, 0x83, "lxor", 0),
+    (MonitorEnter, 0xc2, "monitorenter", 0),
+    (MonitorExit, 0xc3, "monitorexit", 0),
+    (MultiANewArray, 0xc5, "multianewarray", 3),
+    (New, 0xbb, "new", 2),
+    (NewArray, 0xbc, "newarray", 1),
+    (Nop, 0x00, "nop", 0),
+    (Pop, 0x57, "pop", 0),
+    (Pop2, 0x58, "pop2", 0),
+    (PutField, 0xb5, "putfield", 2),
+    (PutStatic, 0xb3, "putstatic", 2),
+    (Ret, 0xa9, "ret", 1),
+    (Return, 0xb1, "return", 0),
+    (SaLoad, 0x35, "saload", 0),
+    (SaStore, 0x56, "sastore", 0),
+    (SiPush, 0x11, "sipush", 2),
+    (Swap, 0x5f, "swap", 0),
+    (TableSwitch, 0xaa, "tableswitch", 0), // Variable width
+    (Wide, 0xc4, "wide", 0), // Variable width
+    (ImpDep2, 0xff, "impdep2", 0),
+    (BreakPoint, 0xca, "breakpoint", 0),
+}
+
+#[cfg(test)]
+mod test {
+    use crate::class::code::Opcode;
+
+    #[test]
+    fn opcode_tables() {
+        let opcodes: Vec<Opcode> = (0..=u8::MAX)
+            .filter_map(Opcode::from_byte)
+            .map(|(opcode, _)| opcode)
+            .collect();
+        assert_eq!(opcodes.len(), 204);
+
+        for opcode in opcodes {
+            let byte = opcode.byte().unwrap();
+            assert_eq!(
+                Opcode::from_byte(byte).map(|(opcode, _)| opcode),
+                Some(opcode.clone())
+            );
+            assert_eq!(Opcode::from_mnemonic(opcode.mnemonic()), Some(opcode));
+        }
+        assert_eq!(Opcode::OperationSpacer.byte(), None);
+        assert_eq!(Opcode::from_mnemonic("<spacer>"), None);
+    }
 }
@@ -1,11 +1,15 @@
 use crate::class::constant::Constant::{
-    ClassRef, Double, InterfaceMethodRef, Long, MethodRef, NameAndType, Utf8, NOOP,
+    ClassRef, Double, Float, InterfaceMethodRef, InvokeDynamic, Long, MethodHandle, MethodRef,
+    MethodType, Module, NameAndType, Package, StringRef, Utf8, NOOP,
 };
 use crate::error::{Error, ErrorKind, Result};
 use crate::vm::data_type::FieldRef;
 
 type Index = u16;
 
+/// The most slots a pool can have, the constant pool count written to a class file is one more.
+pub const MAX_LEN: usize = u16::MAX as usize - 1;
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum MethodHandleKind {
     GetField,
@@ -22,6 +26,11 @@ pub enum MethodHandleKind {
 #[derive(Debug, PartialEq, Clone)]
 pub enum Constant {
     Utf8(String),
+
+    /// A `Utf8` constant holding unpaired surrogates, which a Rust string can not hold. The
+    /// string is kept as UTF-16 code units, like the characters of a Java string.
+    Utf16(Vec<u16>),
+
     Integer(i32),
     Float(f32),
     Long(i64),
@@ -64,12 +73,101 @@ impl ConstantPool {
         }
     }
 
-    pub fn get(&self, index: u16) -> &Constant {
-        &self.constants[(index - 1) as usize]
+    /// Returns the number of slots in the pool. Longs and doubles take up two slots.
+    pub fn len(&self) -> usize {
+        self.constants.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.constants.is_empty()
+    }
+
+    /// Iterates over the constants in index order, skipping the unusable slots after longs and
+    /// doubles.
+    pub fn iter(&self) -> impl Iterator<Item = &Constant> {
+        self.constants.iter().filter(|c| !matches!(c, NOOP))
+    }
+
+    /// Returns the index of the first constant equal to `constant`. Floats and doubles are
+    /// compared by their bits, so `NaN` and `-0.0` are found as well.
+    pub fn find(&self, constant: &Constant) -> Option<u16> {
+        self.constants
+            .iter()
+            .position(|c| match (c, constant) {
+                (Float(a), Float(b)) => a.to_bits() == b.to_bits(),
+                (Double(a), Double(b)) => a.to_bits() == b.to_bits(),
+                (NOOP, _) => false,
+                (a, b) => a == b,
+            })
+            .map(|i| i as u16 + 1)
+    }
+
+    /// Returns the index of `constant`, adding it to the end of the pool if it is missing. Fails
+    /// if the pool is full, the constant pool count of a class file must fit in two bytes.
+    pub fn find_or_add(&mut self, constant: Constant) -> Result<u16> {
+        if let Some(index) = self.find(&constant) {
+            return Ok(index);
+        }
+
+        let size = if matches!(constant, Long(_) | Double(_)) {
+            2
+        } else {
+            1
+        };
+        if self.len() + size > MAX_LEN {
+            return Err(Error::new(
+                ErrorKind::ParseError,
+                Some(format!("Too many constants, can not add {:?}.", constant)),
+            ));
+        }
+
+        let index = self.len() as u16 + 1;
+        self.add(constant);
+        Ok(index)
+    }
+
+    pub fn find_or_add_utf8(&mut self, string: &str) -> Result<u16> {
+        self.find_or_add(Utf8(string.to_owned()))
+    }
+
+    pub fn find_or_add_class(&mut self, name: &str) -> Result<u16> {
+        let name_index = self.find_or_add_utf8(name)?;
+        self.find_or_add(ClassRef(name_index))
+    }
+
+    pub fn find_or_add_module(&mut self, name: &str) -> Result<u16> {
+        let name_index = self.find_or_add_utf8(name)?;
+        self.find_or_add(Module(name_index))
+    }
+
+    pub fn find_or_add_package(&mut self, name: &str) -> Result<u16> {
+        let name_index = self.find_or_add_utf8(name)?;
+        self.find_or_add(Package(name_index))
+    }
+
+    pub fn find_or_add_name_and_type(&mut self, name: &str, descriptor: &str) -> Result<u16> {
+        let name_index = self.find_or_add_utf8(name)?;
+        let descriptor_index = self.find_or_add_utf8(descriptor)?;
+        self.find_or_add(NameAndType(name_index, descriptor_index))
+    }
+
+    /// Returns the constant at `index`. Indexes start at 1, the slot after a long or double is
+    /// not a valid index either.
+    pub fn get(&self, index: u16) -> Result<&Constant> {
+        match index
+            .checked_sub(1)
+            .and_then(|i| self.constants.get(i as usize))
+        {
+            Some(NOOP) | None => Err(Error::new(
+                ErrorKind::RuntimeError,
+                Some(format!("Invalid constant pool index {}.", index)),
+            )),
+            Some(constant) => Ok(constant),
+        }
     }
 
     pub fn get_utf8(&self, index: u16) -> Result<&str> {
-        let entry = self.get(index);
+        let entry = self.get(index)?;
         if let Utf8(s) = entry {
             Ok(s.as_ref())
         } else {
@@ -81,7 +179,7 @@ impl ConstantPool {
     }
 
     pub fn get_class_info_name(&self, index: u16) -> Result<&str> {
-        let entry = self.get(index);
+        let entry = self.get(index)?;
         if let ClassRef(name_index) = entry {
             self.get_utf8(*name_index)
         } else {
@@ -92,8 +190,33 @@ impl ConstantPool {
         }
     }
 
+    pub fn get_module_name(&self, index: u16) -> Result<&str> {
+        let entry = self.get(index)?;
+        if let Module(name_index) = entry {
+            self.get_utf8(*name_index)
+        } else {
+            Err(Error::new(
+                ErrorKind::RuntimeError,
+                Some(format!("Tried to get {:?} as a module", entry)),
+            ))
+        }
+    }
+
+    /// Returns the name of a package in internal form, like `java/lang`.
+    pub fn get_package_name(&self, index: u16) -> Result<&str> {
+        let entry = self.get(index)?;
+        if let Package(name_index) = entry {
+            self.get_utf8(*name_index)
+        } else {
+            Err(Error::new(
+                ErrorKind::RuntimeError,
+                Some(format!("Tried to get {:?} as a package", entry)),
+            ))
+        }
+    }
+
     pub fn get_name_and_type(&self, index: u16) -> Result<(&str, &str)> {
-        let entry = self.get(index);
+        let entry = self.get(index)?;
         if let NameAndType(name_index, descriptor_index) = entry {
             Ok((
                 self.get_utf8(*name_index)?,
@@ -108,7 +231,7 @@ impl ConstantPool {
     }
 
     pub fn get_method_ref(&self, index: u16) -> Result<(&str, &str, &str)> {
-        let entry = self.get(index);
+        let entry = self.get(index)?;
         if let MethodRef(class_index, name_type_index) = entry {
             let class_name = self.get_class_info_name(*class_index)?;
             let (method_name, descriptor_string) = self.get_name_and_type(*name_type_index)?;
@@ -119,7 +242,7 @@ impl ConstantPool {
     }
 
     pub fn get_interface_method_ref(&self, index: u16) -> Result<(&str, &str, &str)> {
-        let entry = self.get(index);
+        let entry = self.get(index)?;
         if let InterfaceMethodRef(class_index, name_type_index) = entry {
             let class_name = self.get_class_info_name(*class_index)?;
             let (method_name, descriptor_string) = self.get_name_and_type(*name_type_index)?;
@@ -130,7 +253,7 @@ impl ConstantPool {
     }
 
     pub fn get_field_ref(&self, index: u16) -> Result<FieldRef> {
-        let entry = self.get(index);
+        let entry = self.get(index)?;
         if let Constant::FieldRef(class_index, name_type_index) = entry {
             let class_name = self.get_class_info_name(*class_index)?;
             let (field_name, field_type) = self.get_name_and_type(*name_type_index)?;
@@ -140,4 +263,81 @@ impl ConstantPool {
             panic!("Tried to get {:?} as a field reference", entry)
         }
     }
+
+    pub fn get_string(&self, index: u16) -> Result<&str> {
+        let entry = self.get(index)?;
+        if let StringRef(string_index) = entry {
+            self.get_utf8(*string_index)
+        } else {
+            runtime_error!("Tried to get {:?} as a string", entry)
+        }
+    }
+
+    /// Returns the kind of the method handle and the index of the field or method it refers to.
+    pub fn get_method_handle(&self, index: u16) -> Result<(&MethodHandleKind, u16)> {
+        let entry = self.get(index)?;
+        if let MethodHandle(kind, reference_index) = entry {
+            Ok((kind, *reference_index))
+        } else {
+            runtime_error!("Tried to get {:?} as a method handle", entry)
+        }
+    }
+
+    pub fn get_method_type(&self, index: u16) -> Result<&str> {
+        let entry = self.get(index)?;
+        if let MethodType(descriptor_index) = entry {
+            self.get_utf8(*descriptor_index)
+        } else {
+            runtime_error!("Tried to get {:?} as a method type", entry)
+        }
+    }
+
+    /// Returns the class, name and descriptor of a field, method or interface method reference.
+    pub fn get_member_ref(&self, index: u16) -> Result<(&str, &str, &str)> {
+        match self.get(index)? {
+            Constant::FieldRef(class_index, name_type_index)
+            | MethodRef(class_index, name_type_index)
+            | InterfaceMethodRef(class_index, name_type_index) => {
+                let class_name = self.get_class_info_name(*class_index)?;
+                let (name, descriptor) = self.get_name_and_type(*name_type_index)?;
+                Ok((class_name, name, descriptor))
+            }
+            entry => runtime_error!("Tried to get {:?} as a member reference", entry),
+        }
+    }
+
+    /// Returns the index of the bootstrap method and the name and descriptor of the call site.
+    pub fn get_invoke_dynamic(&self, index: u16) -> Result<(u16, &str, &str)> {
+        let entry = self.get(index)?;
+        if let InvokeDynamic(bootstrap_index, name_type_index) = entry {
+            let (name, descriptor) = self.get_name_and_type(*name_type_index)?;
+            Ok((*bootstrap_index, name, descriptor))
+        } else {
+            runtime_error!("Tried to get {:?} as an invoke dynamic", entry)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::class::constant::Constant::{Long, Utf8};
+    use crate::class::constant::{ConstantPool, MAX_LEN};
+
+    #[test]
+    fn find_or_add_full_pool() {
+        let mut constants = ConstantPool::new(0);
+        for i in 1..MAX_LEN {
+            constants.add(Utf8(i.to_string()));
+        }
+
+        // A long needs two slots, but only one is left.
+        assert!(constants.find_or_add(Long(1)).is_err());
+        assert_eq!(constants.find_or_add_utf8("last").unwrap(), MAX_LEN as u16);
+        assert_eq!(constants.find_or_add_utf8("1").unwrap(), 1);
+        assert_eq!(
+            constants.find_or_add_utf8("more").unwrap_err().to_string(),
+            "Too many constants, can not add Utf8(\"more\")."
+        );
+        assert_eq!(constants.len(), MAX_LEN);
+    }
 }
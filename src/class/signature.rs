@@ -0,0 +1,578 @@
+//! Generic signatures, from the `Signature` attribute of classes, methods and fields.
+//!
+//! Signatures are parsed with the `TryInto` trait and formatted in Java syntax with qualified
+//! names, or with simple names using the alternate flag:
+//! ```
+//!# use rjvm::class::signature::TypeSignature;
+//!# use std::convert::TryInto;
+//!# use rjvm::error::Result;
+//!# fn main() -> Result<()> {
+//! let signature: TypeSignature =
+//!     "Ljava/util/Map<Ljava/lang/String;Ljava/util/List<+TT;>;>;".try_into()?;
+//!
+//! assert_eq!(
+//!     signature.to_string(),
+//!     "java.util.Map<java.lang.String, java.util.List<? extends T>>"
+//! );
+//! assert_eq!(
+//!     format!("{:#}", signature),
+//!     "Map<String, List<? extends T>>"
+//! );
+//!# Ok(())
+//!# }
+//! ```
+//!
+//! Reference: https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-4.html#jvms-4.7.9.1
+
+use crate::error::ErrorKind::ParseError;
+use crate::error::{Error, Result};
+use std::convert::TryFrom;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+/// The signature of a generic class, or of a class extending or implementing generic types.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ClassSignature {
+    pub type_parameters: Vec<TypeParameter>,
+    pub super_class: ClassTypeSignature,
+    pub interfaces: Vec<ClassTypeSignature>,
+}
+
+/// The signature of a method with generic parameters, return type or exceptions. The return type
+/// is `None` on void methods.
+#[derive(Debug, PartialEq, Clone)]
+pub struct MethodSignature {
+    pub type_parameters: Vec<TypeParameter>,
+    pub parameters: Vec<TypeSignature>,
+    pub return_type: Option<TypeSignature>,
+    pub throws: Vec<TypeSignature>,
+}
+
+/// A type parameter like `T extends Number & Comparable<T>`. The class bound is missing when the
+/// parameter is only bound by interfaces.
+#[derive(Debug, PartialEq, Clone)]
+pub struct TypeParameter {
+    pub name: String,
+    pub class_bound: Option<TypeSignature>,
+    pub interface_bounds: Vec<TypeSignature>,
+}
+
+/// A type in a signature, the signature of a field is always a class, array or type variable.
+#[derive(Debug, PartialEq, Clone)]
+pub enum TypeSignature {
+    Byte,
+    Char,
+    Double,
+    Float,
+    Int,
+    Long,
+    Short,
+    Boolean,
+    Class(ClassTypeSignature),
+    TypeVariable(String),
+    Array(Box<TypeSignature>),
+}
+
+/// A class with its type arguments. A class nested in a parameterized class is the `inner` of its
+/// outer class, like `Entry` in `Outer<T>.Entry`, otherwise the name is the binary name.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ClassTypeSignature {
+    pub name: String,
+    pub type_arguments: Vec<TypeArgument>,
+    pub inner: Option<Box<ClassTypeSignature>>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum TypeArgument {
+    /// The unbounded wildcard `?`.
+    Any,
+    Exact(TypeSignature),
+    Extends(TypeSignature),
+    Super(TypeSignature),
+}
+
+impl TryFrom<&str> for ClassSignature {
+    type Error = Error;
+
+    fn try_from(s: &str) -> Result<ClassSignature> {
+        let mut parser = Parser::new(s);
+        let type_parameters = parser.type_parameters()?;
+        let super_class = parser.class_type()?;
+        let mut interfaces = vec![];
+        while !parser.at_end() {
+            interfaces.push(parser.class_type()?);
+        }
+
+        Ok(ClassSignature {
+            type_parameters,
+            super_class,
+            interfaces,
+        })
+    }
+}
+
+impl TryFrom<&str> for MethodSignature {
+    type Error = Error;
+
+    fn try_from(s: &str) -> Result<MethodSignature> {
+        let mut parser = Parser::new(s);
+        let type_parameters = parser.type_parameters()?;
+
+        parser.expect('(')?;
+        let mut parameters = vec![];
+        while parser.peek() != Some(')') {
+            parameters.push(parser.java_type()?);
+        }
+        parser.expect(')')?;
+
+        let return_type = if parser.peek() == Some('V') {
+            parser.next();
+            None
+        } else {
+            Some(parser.java_type()?)
+        };
+
+        let mut throws = vec![];
+        while parser.peek() == Some('^') {
+            parser.next();
+            throws.push(parser.reference_type()?);
+        }
+        parser.end()?;
+
+        Ok(MethodSignature {
+            type_parameters,
+            parameters,
+            return_type,
+            throws,
+        })
+    }
+}
+
+impl TryFrom<&str> for TypeSignature {
+    type Error = Error;
+
+    fn try_from(s: &str) -> Result<TypeSignature> {
+        let mut parser = Parser::new(s);
+        let signature = parser.java_type()?;
+        parser.end()?;
+        Ok(signature)
+    }
+}
+
+struct Parser<'s> {
+    signature: &'s str,
+    position: usize,
+}
+
+impl<'s> Parser<'s> {
+    fn new(signature: &'s str) -> Self {
+        Parser {
+            signature,
+            position: 0,
+        }
+    }
+
+    fn error(&self) -> Error {
+        Error::new(
+            ParseError,
+            Some(format!("Invalid signature '{}'.", self.signature)),
+        )
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.signature[self.position..].chars().next()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += c.len_utf8();
+        Some(c)
+    }
+
+    fn expect(&mut self, expected: char) -> Result<()> {
+        match self.next() {
+            Some(c) if c == expected => Ok(()),
+            _ => Err(self.error()),
+        }
+    }
+
+    fn at_end(&self) -> bool {
+        self.position == self.signature.len()
+    }
+
+    fn end(&self) -> Result<()> {
+        if self.at_end() {
+            Ok(())
+        } else {
+            Err(self.error())
+        }
+    }
+
+    /// Reads up to the next character with a meaning in signatures, `/` is allowed in binary
+    /// names.
+    fn identifier(&mut self, allow_slash: bool) -> Result<String> {
+        let start = self.position;
+        while let Some(c) = self.peek() {
+            if ".;[<>:".contains(c) || (c == '/' && !allow_slash) {
+                break;
+            }
+            self.next();
+        }
+
+        if self.position == start {
+            Err(self.error())
+        } else {
+            Ok(self.signature[start..self.position].to_owned())
+        }
+    }
+
+    fn type_parameters(&mut self) -> Result<Vec<TypeParameter>> {
+        let mut parameters = vec![];
+        if self.peek() != Some('<') {
+            return Ok(parameters);
+        }
+
+        self.next();
+        while self.peek() != Some('>') {
+            let name = self.identifier(false)?;
+            self.expect(':')?;
+            let class_bound = match self.peek() {
+                Some(':') => None,
+                _ => Some(self.reference_type()?),
+            };
+            let mut interface_bounds = vec![];
+            while self.peek() == Some(':') {
+                self.next();
+                interface_bounds.push(self.reference_type()?);
+            }
+            parameters.push(TypeParameter {
+                name,
+                class_bound,
+                interface_bounds,
+            });
+        }
+        self.next();
+
+        if parameters.is_empty() {
+            return Err(self.error());
+        }
+        Ok(parameters)
+    }
+
+    fn java_type(&mut self) -> Result<TypeSignature> {
+        let base = match self.peek() {
+            Some('B') => TypeSignature::Byte,
+            Some('C') => TypeSignature::Char,
+            Some('D') => TypeSignature::Double,
+            Some('F') => TypeSignature::Float,
+            Some('I') => TypeSignature::Int,
+            Some('J') => TypeSignature::Long,
+            Some('S') => TypeSignature::Short,
+            Some('Z') => TypeSignature::Boolean,
+            _ => return self.reference_type(),
+        };
+        self.next();
+        Ok(base)
+    }
+
+    fn reference_type(&mut self) -> Result<TypeSignature> {
+        match self.peek() {
+            Some('L') => Ok(TypeSignature::Class(self.class_type()?)),
+            Some('T') => {
+                self.next();
+                let name = self.identifier(false)?;
+                self.expect(';')?;
+                Ok(TypeSignature::TypeVariable(name))
+            }
+            Some('[') => {
+                self.next();
+                Ok(TypeSignature::Array(Box::new(self.java_type()?)))
+            }
+            _ => Err(self.error()),
+        }
+    }
+
+    fn class_type(&mut self) -> Result<ClassTypeSignature> {
+        self.expect('L')?;
+        let mut classes = vec![(self.identifier(true)?, self.type_arguments()?)];
+        while self.peek() == Some('.') {
+            self.next();
+            classes.push((self.identifier(false)?, self.type_arguments()?));
+        }
+        self.expect(';')?;
+
+        let class = classes
+            .into_iter()
+            .rev()
+            .fold(None, |inner, (name, type_arguments)| {
+                Some(ClassTypeSignature {
+                    name,
+                    type_arguments,
+                    inner: inner.map(Box::new),
+                })
+            });
+        Ok(class.unwrap())
+    }
+
+    fn type_arguments(&mut self) -> Result<Vec<TypeArgument>> {
+        let mut arguments = vec![];
+        if self.peek() != Some('<') {
+            return Ok(arguments);
+        }
+
+        self.next();
+        while self.peek() != Some('>') {
+            let argument = match self.peek() {
+                Some('*') => {
+                    self.next();
+                    TypeArgument::Any
+                }
+                Some('+') => {
+                    self.next();
+                    TypeArgument::Extends(self.reference_type()?)
+                }
+                Some('-') => {
+                    self.next();
+                    TypeArgument::Super(self.reference_type()?)
+                }
+                _ => TypeArgument::Exact(self.reference_type()?),
+            };
+            arguments.push(argument);
+        }
+        self.next();
+
+        if arguments.is_empty() {
+            return Err(self.error());
+        }
+        Ok(arguments)
+    }
+}
+
+/// Writes the items separated by `separator`, passing on the alternate flag.
+fn write_list<T: Display>(f: &mut Formatter<'_>, items: &[T], separator: &str) -> fmt::Result {
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            f.write_str(separator)?;
+        }
+        if f.alternate() {
+            write!(f, "{:#}", item)?;
+        } else {
+            write!(f, "{}", item)?;
+        }
+    }
+    Ok(())
+}
+
+impl Display for TypeParameter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)?;
+
+        let bounds: Vec<&TypeSignature> = self
+            .class_bound
+            .iter()
+            .chain(self.interface_bounds.iter())
+            .collect();
+        if !bounds.is_empty() {
+            f.write_str(" extends ")?;
+            write_list(f, &bounds, " & ")?;
+        }
+        Ok(())
+    }
+}
+
+/// Formats the type in Java syntax, like `java.util.List<? extends T>[]`.
+impl Display for TypeSignature {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            TypeSignature::Byte => f.write_str("byte"),
+            TypeSignature::Char => f.write_str("char"),
+            TypeSignature::Double => f.write_str("double"),
+            TypeSignature::Float => f.write_str("float"),
+            TypeSignature::Int => f.write_str("int"),
+            TypeSignature::Long => f.write_str("long"),
+            TypeSignature::Short => f.write_str("short"),
+            TypeSignature::Boolean => f.write_str("boolean"),
+            TypeSignature::Class(class) if f.alternate() => write!(f, "{:#}", class),
+            TypeSignature::Class(class) => write!(f, "{}", class),
+            TypeSignature::TypeVariable(name) => f.write_str(name),
+            TypeSignature::Array(component) if f.alternate() => write!(f, "{:#}[]", component),
+            TypeSignature::Array(component) => write!(f, "{}[]", component),
+        }
+    }
+}
+
+impl Display for ClassTypeSignature {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            let simple_name = self.name.rsplit('/').next().unwrap_or(&self.name);
+            f.write_str(&simple_name.replace('$', "."))?;
+        } else {
+            f.write_str(&self.name.replace('/', "."))?;
+        }
+
+        if !self.type_arguments.is_empty() {
+            f.write_str("<")?;
+            write_list(f, &self.type_arguments, ", ")?;
+            f.write_str(">")?;
+        }
+
+        match &self.inner {
+            Some(inner) if f.alternate() => write!(f, ".{:#}", inner),
+            Some(inner) => write!(f, ".{}", inner),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Display for TypeArgument {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let (prefix, bound) = match self {
+            TypeArgument::Any => return f.write_str("?"),
+            TypeArgument::Exact(bound) => ("", bound),
+            TypeArgument::Extends(bound) => ("? extends ", bound),
+            TypeArgument::Super(bound) => ("? super ", bound),
+        };
+
+        f.write_str(prefix)?;
+        if f.alternate() {
+            write!(f, "{:#}", bound)
+        } else {
+            write!(f, "{}", bound)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::class::signature::{
+        ClassSignature, ClassTypeSignature, MethodSignature, TypeArgument, TypeParameter,
+        TypeSignature,
+    };
+    use std::convert::TryInto;
+
+    fn class(name: &str, type_arguments: Vec<TypeArgument>) -> ClassTypeSignature {
+        ClassTypeSignature {
+            name: name.to_owned(),
+            type_arguments,
+            inner: None,
+        }
+    }
+
+    fn variable(name: &str) -> TypeSignature {
+        TypeSignature::TypeVariable(name.to_owned())
+    }
+
+    #[test]
+    fn parse_class_signature() {
+        let signature: ClassSignature =
+            "<K:Ljava/lang/Object;V::Ljava/lang/Comparable<TV;>;>Ljava/util/AbstractMap<TK;TV;>;Ljava/io/Serializable;"
+                .try_into()
+                .unwrap();
+
+        assert_eq!(
+            signature,
+            ClassSignature {
+                type_parameters: vec![
+                    TypeParameter {
+                        name: "K".to_owned(),
+                        class_bound: Some(TypeSignature::Class(class("java/lang/Object", vec![]))),
+                        interface_bounds: vec![],
+                    },
+                    TypeParameter {
+                        name: "V".to_owned(),
+                        class_bound: None,
+                        interface_bounds: vec![TypeSignature::Class(class(
+                            "java/lang/Comparable",
+                            vec![TypeArgument::Exact(variable("V"))]
+                        ))],
+                    },
+                ],
+                super_class: class(
+                    "java/util/AbstractMap",
+                    vec![
+                        TypeArgument::Exact(variable("K")),
+                        TypeArgument::Exact(variable("V"))
+                    ]
+                ),
+                interfaces: vec![class("java/io/Serializable", vec![])],
+            }
+        );
+        assert_eq!(
+            signature.type_parameters[1].to_string(),
+            "V extends java.lang.Comparable<V>"
+        );
+    }
+
+    #[test]
+    fn parse_method_signature() {
+        let signature: MethodSignature =
+            "<T:Ljava/lang/Exception;>([TT;I)V^TT;^Ljava/io/IOException;"
+                .try_into()
+                .unwrap();
+
+        assert_eq!(signature.type_parameters[0].name, "T");
+        assert_eq!(
+            signature.parameters,
+            vec![
+                TypeSignature::Array(Box::new(variable("T"))),
+                TypeSignature::Int
+            ]
+        );
+        assert_eq!(signature.return_type, None);
+        assert_eq!(
+            signature.throws,
+            vec![
+                variable("T"),
+                TypeSignature::Class(class("java/io/IOException", vec![]))
+            ]
+        );
+    }
+
+    #[test]
+    fn display_wildcards() {
+        let signature: TypeSignature = "Ljava/util/Map<*Ljava/util/List<-Ljava/lang/Integer;>;>;"
+            .try_into()
+            .unwrap();
+
+        assert_eq!(
+            signature.to_string(),
+            "java.util.Map<?, java.util.List<? super java.lang.Integer>>"
+        );
+        assert_eq!(format!("{:#}", signature), "Map<?, List<? super Integer>>");
+    }
+
+    #[test]
+    fn display_inner_class() {
+        let signature: TypeSignature = "Ltest/Outer<TT;>.Inner<Ljava/lang/String;>.Deepest;"
+            .try_into()
+            .unwrap();
+
+        assert_eq!(
+            signature.to_string(),
+            "test.Outer<T>.Inner<java.lang.String>.Deepest"
+        );
+
+        let signature: TypeSignature = "[Ljava/util/Map$Entry<TK;TV;>;".try_into().unwrap();
+        assert_eq!(format!("{:#}", signature), "Map.Entry<K, V>[]");
+    }
+
+    #[test]
+    fn parse_invalid_signatures() {
+        for signature in &[
+            "",
+            "I",
+            "Ljava/lang/Object",
+            "Ljava/util/List<>;",
+            "TT",
+            "Ljava/lang/Object;I",
+        ] {
+            let result: crate::error::Result<ClassSignature> = (*signature).try_into();
+            assert!(result.is_err(), "{}", signature);
+        }
+
+        let result: crate::error::Result<MethodSignature> = "(I)".try_into();
+        assert!(result.is_err());
+        let result: crate::error::Result<TypeSignature> = "Ljava/lang/Object;;".try_into();
+        assert!(result.is_err());
+    }
+}
@@ -1,5 +1,6 @@
-use crate::error::ErrorKind::{RuntimeError, IO};
+use crate::error::ErrorKind::{LinkageError, RuntimeError, UncaughtException, IO};
 use crate::error::Repr::Simple;
+use crate::vm::exception::JavaException;
 use std::fmt::{Display, Formatter};
 use std::io;
 
@@ -37,6 +38,13 @@ pub enum ErrorKind {
 
     /// Error during runtime.
     RuntimeError,
+
+    /// A Java exception that was thrown but never caught.
+    UncaughtException(JavaException),
+
+    /// A class failed to link, like `java/lang/VerifyError`. Thrown in Java as the named error
+    /// class.
+    LinkageError(String),
 }
 
 impl Error {
@@ -54,6 +62,36 @@ impl Error {
         }
     }
 
+    pub fn uncaught_exception(exception: JavaException) -> Self {
+        Error {
+            repr: Simple(UncaughtException(exception)),
+            message: None,
+        }
+    }
+
+    pub fn linkage_error(class_name: &str, message: String) -> Self {
+        Error {
+            repr: Simple(LinkageError(class_name.to_owned())),
+            message: Some(message),
+        }
+    }
+
+    /// Returns the class of the Java error to throw if this is a linkage error.
+    pub fn linkage_error_class(&self) -> Option<&str> {
+        match self.kind() {
+            LinkageError(class_name) => Some(class_name),
+            _ => None,
+        }
+    }
+
+    /// Returns the Java exception if this error is caused by an uncaught exception.
+    pub fn exception(&self) -> Option<&JavaException> {
+        match self.kind() {
+            UncaughtException(exception) => Some(exception),
+            _ => None,
+        }
+    }
+
     pub fn message(&self) -> Option<&String> {
         self.message.as_ref()
     }
@@ -88,6 +126,8 @@ impl Display for ErrorKind {
                 _ => write!(f, "IO error: {}", io_error)?,
             },
             ErrorKind::RuntimeError => write!(f, "Runtime error.")?,
+            UncaughtException(exception) => write!(f, "Uncaught exception {}", exception)?,
+            LinkageError(class_name) => write!(f, "{}", class_name.replace('/', "."))?,
         };
         Ok(())
     }
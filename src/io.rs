@@ -1,17 +1,23 @@
 mod attribute;
 pub mod class;
-mod code;
+pub(crate) mod code;
+pub mod mutf8;
 
-use crate::error::Result;
+use crate::error::ErrorKind::{ParseError, IO};
+use crate::error::{Error, Result};
+use std::fmt::Display;
+use std::io::{BufRead, Cursor, ErrorKind, Read};
 
 trait ReadBytesExt: std::io::Read {
+    /// Reads exactly `length` bytes. The buffer grows as the bytes are read, so a corrupt length
+    /// fails at the end of the input instead of allocating it up front.
     #[inline]
     fn read_bytes(&mut self, length: usize) -> Result<Vec<u8>> {
-        let mut buf = Vec::with_capacity(length);
-        unsafe {
-            buf.set_len(length);
+        let mut buf = Vec::new();
+        self.take(length as u64).read_to_end(&mut buf)?;
+        if buf.len() < length {
+            return Err(std::io::Error::from(ErrorKind::UnexpectedEof).into());
         }
-        self.read_exact(&mut buf)?;
         Ok(buf)
     }
 
@@ -38,3 +44,94 @@ trait ReadBytesExt: std::io::Read {
 }
 
 impl<R: std::io::Read + ?Sized> ReadBytesExt for R {}
+
+trait WriteBytesExt: std::io::Write {
+    #[inline]
+    fn write_u1(&mut self, value: u8) -> Result<()> {
+        self.write_all(&[value])?;
+        Ok(())
+    }
+
+    #[inline]
+    fn write_u2(&mut self, value: u16) -> Result<()> {
+        self.write_all(&value.to_be_bytes())?;
+        Ok(())
+    }
+
+    #[inline]
+    fn write_u4(&mut self, value: u32) -> Result<()> {
+        self.write_all(&value.to_be_bytes())?;
+        Ok(())
+    }
+}
+
+impl<W: std::io::Write + ?Sized> WriteBytesExt for W {}
+
+/// A reader that knows how far into the input it is, used to report where a class file is
+/// malformed.
+pub(crate) trait Offset {
+    fn offset(&self) -> usize;
+}
+
+impl<T: AsRef<[u8]>> Offset for Cursor<T> {
+    fn offset(&self) -> usize {
+        self.position() as usize
+    }
+}
+
+/// Counts the bytes read from the inner reader.
+pub(crate) struct OffsetReader<R> {
+    inner: R,
+    offset: usize,
+}
+
+impl<R> OffsetReader<R> {
+    pub fn new(inner: R) -> Self {
+        OffsetReader { inner, offset: 0 }
+    }
+}
+
+impl<R> Offset for OffsetReader<R> {
+    fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl<R: Read> Read for OffsetReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let len = self.inner.read(buf)?;
+        self.offset += len;
+        Ok(len)
+    }
+}
+
+impl<R: BufRead> BufRead for OffsetReader<R> {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.inner.consume(amt);
+        self.offset += amt;
+    }
+}
+
+/// A malformed class file, `offset` is where the offending bytes start.
+pub(crate) fn format_error(offset: usize, description: impl Display) -> Error {
+    Error::new(
+        ParseError,
+        Some(format!("{} at byte {}.", description, offset)),
+    )
+}
+
+/// Reports errors from interpreting the bytes at `offset`, like a constant pool index of the
+/// wrong type, as format errors at that offset. Input errors are passed on as they are.
+pub(crate) fn at_offset<T>(result: Result<T>, offset: usize) -> Result<T> {
+    result.map_err(|error| match error.kind() {
+        IO(_) => error,
+        _ => {
+            let message = error.message().map_or("Invalid data", |m| m.as_str());
+            format_error(offset, message.trim_end_matches('.'))
+        }
+    })
+}
@@ -1,21 +1,33 @@
 use crate::class::attribute::AttributeData::{
-    CodeInfo, ConstantValue, Exceptions, LineNumberTable, SourceFile, Unknown,
+    AnnotationDefault, BootstrapMethods, CodeInfo, ConstantValue, EnclosingMethod, Exceptions,
+    InnerClasses, LineNumberTable, LocalVariableTable, LocalVariableTypeTable, Module,
+    ModuleMainClass, ModulePackages, NestHost, NestMembers, PermittedSubclasses, Record,
+    RuntimeInvisibleAnnotations, RuntimeInvisibleParameterAnnotations,
+    RuntimeInvisibleTypeAnnotations, RuntimeVisibleAnnotations, RuntimeVisibleParameterAnnotations,
+    RuntimeVisibleTypeAnnotations, Signature, SourceFile, StackMapTable, Unknown,
 };
-use crate::class::attribute::{Attribute, AttributeData, Code, ExceptionHandler};
-use crate::class::constant::ConstantPool;
-use crate::error::Result;
-use crate::io::code::CodeReader;
-use crate::io::ReadBytesExt;
-use std::io::BufRead;
+use crate::class::attribute::{
+    self, Annotation, Attribute, AttributeData, BootstrapMethod, Code, ElementValue,
+    ExceptionHandler, Exports, InnerClass, LocalVariable, ModuleDescriptor, Provides,
+    RecordComponent, Requires, StackMapFrame, TypeAnnotation, TypeAnnotationTarget, TypePathEntry,
+    VerificationType,
+};
+use crate::class::constant::{Constant, ConstantPool};
+use crate::class::{ExportsFlags, InnerClassAccessFlags, ModuleFlags, RequiresFlags};
+use crate::error::ErrorKind::ParseError;
+use crate::error::{Error, Result};
+use crate::io::code::{CodeReader, CodeWriter};
+use crate::io::{at_offset, format_error, Offset, ReadBytesExt, WriteBytesExt};
+use std::io::{BufRead, Write};
 
 pub trait AttributeRead {}
 
-pub struct AttributeReader<'r, 'c, R: BufRead> {
+pub struct AttributeReader<'r, 'c, R: BufRead + Offset> {
     reader: &'r mut R,
     constants: &'c ConstantPool,
 }
 
-impl<'r, 'c, R: BufRead> AttributeReader<'r, 'c, R> {
+impl<'r, 'c, R: BufRead + Offset> AttributeReader<'r, 'c, R> {
     pub fn new(reader: &'r mut R, constants: &'c ConstantPool) -> AttributeReader<'r, 'c, R> {
         AttributeReader { reader, constants }
     }
@@ -30,28 +42,69 @@ impl<'r, 'c, R: BufRead> AttributeReader<'r, 'c, R> {
     }
 
     fn read_attribute_info(&mut self) -> Result<Attribute> {
-        let name = self.constants.get_utf8(self.reader.read_u2()?)?.to_owned();
+        let start = self.reader.offset();
+        let name = self.read_utf8()?;
         let len = self.reader.read_u4()? as usize;
+        let data_start = self.reader.offset();
         let data = match &name[..] {
             "SourceFile" => self.read_source_file_attribute()?,
             "LineNumberTable" => self.read_line_number_table_attribute()?,
             "Code" => self.read_code_attribute()?,
             "ConstantValue" => self.read_constant_value_attribute()?,
             "Exceptions" => self.read_exceptions_attribute()?,
+            "BootstrapMethods" => self.read_bootstrap_methods_attribute()?,
+            "InnerClasses" => self.read_inner_classes_attribute()?,
+            "EnclosingMethod" => self.read_enclosing_method_attribute()?,
+            "NestHost" => self.read_nest_host_attribute()?,
+            "NestMembers" => self.read_nest_members_attribute()?,
+            "StackMapTable" => self.read_stack_map_table_attribute()?,
+            "LocalVariableTable" => LocalVariableTable(self.read_local_variables()?),
+            "LocalVariableTypeTable" => LocalVariableTypeTable(self.read_local_variables()?),
+            "Signature" => Signature(self.read_utf8()?),
+            "RuntimeVisibleAnnotations" => RuntimeVisibleAnnotations(self.read_annotations()?),
+            "RuntimeInvisibleAnnotations" => RuntimeInvisibleAnnotations(self.read_annotations()?),
+            "RuntimeVisibleParameterAnnotations" => {
+                RuntimeVisibleParameterAnnotations(self.read_parameter_annotations()?)
+            }
+            "RuntimeInvisibleParameterAnnotations" => {
+                RuntimeInvisibleParameterAnnotations(self.read_parameter_annotations()?)
+            }
+            "RuntimeVisibleTypeAnnotations" => {
+                RuntimeVisibleTypeAnnotations(self.read_type_annotations()?)
+            }
+            "RuntimeInvisibleTypeAnnotations" => {
+                RuntimeInvisibleTypeAnnotations(self.read_type_annotations()?)
+            }
+            "AnnotationDefault" => AnnotationDefault(self.read_element_value()?),
+            "Module" => Module(self.read_module()?),
+            "ModulePackages" => self.read_module_packages_attribute()?,
+            "ModuleMainClass" => ModuleMainClass(self.read_class_name()?),
+            "Record" => self.read_record_attribute()?,
+            "PermittedSubclasses" => PermittedSubclasses(self.read_class_names()?),
             _ => self.read_unknown_attribute(len)?,
         };
 
+        let read = self.reader.offset() - data_start;
+        if read != len {
+            return Err(format_error(
+                start,
+                format!(
+                    "Attribute {} has length {} but its data is {} bytes",
+                    name, len, read
+                ),
+            ));
+        }
+
         Ok(Attribute { name, data })
     }
 
     fn read_source_file_attribute(&mut self) -> Result<AttributeData> {
-        let name_index = self.reader.read_u2()?;
-        Ok(SourceFile(self.constants.get_utf8(name_index)?.to_owned()))
+        Ok(SourceFile(self.read_utf8()?))
     }
 
     fn read_constant_value_attribute(&mut self) -> Result<AttributeData> {
-        let value_index = self.reader.read_u2()?;
-        Ok(ConstantValue((*self.constants.get(value_index)).clone()))
+        let value = self.lookup(|constants, index| constants.get(index).cloned())?;
+        Ok(ConstantValue(value))
     }
 
     fn read_exceptions_attribute(&mut self) -> Result<AttributeData> {
@@ -59,11 +112,7 @@ impl<'r, 'c, R: BufRead> AttributeReader<'r, 'c, R> {
         let mut exceptions = Vec::with_capacity(exception_count as usize);
 
         for _ in 0..exception_count {
-            exceptions.push(
-                self.constants
-                    .get_class_info_name(self.reader.read_u2()?)?
-                    .to_owned(),
-            );
+            exceptions.push(self.read_class_name()?);
         }
 
         Ok(Exceptions(exceptions))
@@ -73,7 +122,7 @@ impl<'r, 'c, R: BufRead> AttributeReader<'r, 'c, R> {
         let max_stack = self.reader.read_u2()?;
         let max_locals = self.reader.read_u2()?;
 
-        let mut code_reader = CodeReader::new(&mut self.reader);
+        let mut code_reader = CodeReader::new(&mut *self.reader);
         let instructions = code_reader.read_code()?;
 
         let exception_table_length = self.reader.read_u2()?;
@@ -102,16 +151,7 @@ impl<'r, 'c, R: BufRead> AttributeReader<'r, 'c, R> {
             let end_pc = self.reader.read_u2()?;
             let handler_pc = self.reader.read_u2()?;
 
-            let catch_type_index = self.reader.read_u2()?;
-            let catch_type = if catch_type_index == 0 {
-                None
-            } else {
-                Some(
-                    self.constants
-                        .get_class_info_name(catch_type_index)?
-                        .to_owned(),
-                )
-            };
+            let catch_type = self.read_optional_class_name()?;
 
             result.push(ExceptionHandler {
                 start_pc,
@@ -135,28 +175,931 @@ impl<'r, 'c, R: BufRead> AttributeReader<'r, 'c, R> {
         Ok(LineNumberTable(table))
     }
 
+    fn read_bootstrap_methods_attribute(&mut self) -> Result<AttributeData> {
+        let length = self.reader.read_u2()?;
+        let mut methods = Vec::with_capacity(length as usize);
+        for _ in 0..length {
+            let method_ref = self.reader.read_u2()?;
+            let argument_count = self.reader.read_u2()?;
+            let mut arguments = Vec::with_capacity(argument_count as usize);
+            for _ in 0..argument_count {
+                arguments.push(self.reader.read_u2()?);
+            }
+            methods.push(BootstrapMethod {
+                method_ref,
+                arguments,
+            });
+        }
+        Ok(BootstrapMethods(methods))
+    }
+
+    fn read_inner_classes_attribute(&mut self) -> Result<AttributeData> {
+        let length = self.reader.read_u2()?;
+        let mut classes = Vec::with_capacity(length as usize);
+        for _ in 0..length {
+            let inner_class = self.read_class_name()?;
+            let outer_class = self.read_optional_class_name()?;
+            let inner_name = self.read_optional_utf8()?;
+            let access_flags = InnerClassAccessFlags::from_bits_truncate(self.reader.read_u2()?);
+            classes.push(InnerClass {
+                inner_class,
+                outer_class,
+                inner_name,
+                access_flags,
+            });
+        }
+        Ok(InnerClasses(classes))
+    }
+
+    fn read_enclosing_method_attribute(&mut self) -> Result<AttributeData> {
+        let class = self.read_class_name()?;
+        let method = self.lookup(|constants, index| match index {
+            0 => Ok(None),
+            index => {
+                let (name, descriptor) = constants.get_name_and_type(index)?;
+                Ok(Some((name.to_owned(), descriptor.to_owned())))
+            }
+        })?;
+        Ok(EnclosingMethod(attribute::EnclosingMethod {
+            class,
+            method,
+        }))
+    }
+
+    fn read_nest_host_attribute(&mut self) -> Result<AttributeData> {
+        Ok(NestHost(self.read_class_name()?))
+    }
+
+    fn read_nest_members_attribute(&mut self) -> Result<AttributeData> {
+        let length = self.reader.read_u2()?;
+        let mut members = Vec::with_capacity(length as usize);
+        for _ in 0..length {
+            members.push(self.read_class_name()?);
+        }
+        Ok(NestMembers(members))
+    }
+
+    fn read_stack_map_table_attribute(&mut self) -> Result<AttributeData> {
+        let length = self.reader.read_u2()?;
+        let mut frames = Vec::with_capacity(length as usize);
+        for _ in 0..length {
+            frames.push(self.read_stack_map_frame()?);
+        }
+        Ok(StackMapTable(frames))
+    }
+
+    fn read_stack_map_frame(&mut self) -> Result<StackMapFrame> {
+        let frame_type = self.reader.read_u1()?;
+        let frame = match frame_type {
+            0..=63 => StackMapFrame::Same {
+                offset_delta: frame_type as u16,
+            },
+            64..=127 => StackMapFrame::SameLocals1StackItem {
+                offset_delta: frame_type as u16 - 64,
+                stack: self.read_verification_type()?,
+            },
+            247 => StackMapFrame::SameLocals1StackItem {
+                offset_delta: self.reader.read_u2()?,
+                stack: self.read_verification_type()?,
+            },
+            248..=250 => StackMapFrame::Chop {
+                offset_delta: self.reader.read_u2()?,
+                count: 251 - frame_type,
+            },
+            251 => StackMapFrame::Same {
+                offset_delta: self.reader.read_u2()?,
+            },
+            252..=254 => StackMapFrame::Append {
+                offset_delta: self.reader.read_u2()?,
+                locals: self.read_verification_types((frame_type - 251) as u16)?,
+            },
+            255 => {
+                let offset_delta = self.reader.read_u2()?;
+                let locals_count = self.reader.read_u2()?;
+                let locals = self.read_verification_types(locals_count)?;
+                let stack_count = self.reader.read_u2()?;
+                let stack = self.read_verification_types(stack_count)?;
+                StackMapFrame::Full {
+                    offset_delta,
+                    locals,
+                    stack,
+                }
+            }
+            _ => {
+                return Err(format_error(
+                    self.reader.offset() - 1,
+                    format!("Invalid stack map frame type {}", frame_type),
+                ))
+            }
+        };
+        Ok(frame)
+    }
+
+    fn read_verification_types(&mut self, count: u16) -> Result<Vec<VerificationType>> {
+        let mut types = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            types.push(self.read_verification_type()?);
+        }
+        Ok(types)
+    }
+
+    fn read_verification_type(&mut self) -> Result<VerificationType> {
+        let tag = self.reader.read_u1()?;
+        let verification_type = match tag {
+            0 => VerificationType::Top,
+            1 => VerificationType::Integer,
+            2 => VerificationType::Float,
+            3 => VerificationType::Double,
+            4 => VerificationType::Long,
+            5 => VerificationType::Null,
+            6 => VerificationType::UninitializedThis,
+            7 => VerificationType::Object(self.read_class_name()?),
+            8 => VerificationType::Uninitialized(self.reader.read_u2()?),
+            _ => {
+                return Err(format_error(
+                    self.reader.offset() - 1,
+                    format!("Invalid verification type {}", tag),
+                ))
+            }
+        };
+        Ok(verification_type)
+    }
+
+    fn read_local_variables(&mut self) -> Result<Vec<LocalVariable>> {
+        let length = self.reader.read_u2()?;
+        let mut variables = Vec::with_capacity(length as usize);
+        for _ in 0..length {
+            let start_pc = self.reader.read_u2()?;
+            let length = self.reader.read_u2()?;
+            let name = self.read_utf8()?;
+            let descriptor = self.read_utf8()?;
+            let index = self.reader.read_u2()?;
+            variables.push(LocalVariable {
+                start_pc,
+                length,
+                name,
+                descriptor,
+                index,
+            });
+        }
+        Ok(variables)
+    }
+
+    fn read_annotations(&mut self) -> Result<Vec<Annotation>> {
+        let length = self.reader.read_u2()?;
+        let mut annotations = Vec::with_capacity(length as usize);
+        for _ in 0..length {
+            annotations.push(self.read_annotation()?);
+        }
+        Ok(annotations)
+    }
+
+    fn read_parameter_annotations(&mut self) -> Result<Vec<Vec<Annotation>>> {
+        let length = self.reader.read_u1()?;
+        let mut parameters = Vec::with_capacity(length as usize);
+        for _ in 0..length {
+            parameters.push(self.read_annotations()?);
+        }
+        Ok(parameters)
+    }
+
+    fn read_annotation(&mut self) -> Result<Annotation> {
+        let type_descriptor = self.read_utf8()?;
+        let length = self.reader.read_u2()?;
+        let mut elements = Vec::with_capacity(length as usize);
+        for _ in 0..length {
+            let name = self.read_utf8()?;
+            elements.push((name, self.read_element_value()?));
+        }
+        Ok(Annotation {
+            type_descriptor,
+            elements,
+        })
+    }
+
+    fn read_element_value(&mut self) -> Result<ElementValue> {
+        let tag = self.reader.read_u1()?;
+        let value = match tag {
+            b'B' => ElementValue::Byte(self.read_int_constant()? as i8),
+            b'C' => ElementValue::Char(self.read_int_constant()? as u16),
+            b'S' => ElementValue::Short(self.read_int_constant()? as i16),
+            b'Z' => ElementValue::Boolean(self.read_int_constant()? != 0),
+            b'I' => ElementValue::Int(self.read_int_constant()?),
+            b'J' | b'F' | b'D' => {
+                let offset = self.reader.offset();
+                let index = self.reader.read_u2()?;
+                match (tag, self.constants.get(index)) {
+                    (b'J', Ok(Constant::Long(value))) => ElementValue::Long(*value),
+                    (b'F', Ok(Constant::Float(value))) => ElementValue::Float(*value),
+                    (b'D', Ok(Constant::Double(value))) => ElementValue::Double(*value),
+                    _ => return Err(invalid_element_constant(offset, index)),
+                }
+            }
+            b's' => ElementValue::String(self.read_utf8()?),
+            b'e' => ElementValue::Enum {
+                type_descriptor: self.read_utf8()?,
+                name: self.read_utf8()?,
+            },
+            b'c' => ElementValue::Class(self.read_utf8()?),
+            b'@' => ElementValue::Annotation(self.read_annotation()?),
+            b'[' => {
+                let length = self.reader.read_u2()?;
+                let mut values = Vec::with_capacity(length as usize);
+                for _ in 0..length {
+                    values.push(self.read_element_value()?);
+                }
+                ElementValue::Array(values)
+            }
+            _ => {
+                return Err(format_error(
+                    self.reader.offset() - 1,
+                    format!("Invalid element value tag {}", tag),
+                ))
+            }
+        };
+        Ok(value)
+    }
+
+    fn read_int_constant(&mut self) -> Result<i32> {
+        let offset = self.reader.offset();
+        let index = self.reader.read_u2()?;
+        match self.constants.get(index) {
+            Ok(Constant::Integer(value)) => Ok(*value),
+            _ => Err(invalid_element_constant(offset, index)),
+        }
+    }
+
+    fn read_type_annotations(&mut self) -> Result<Vec<TypeAnnotation>> {
+        let length = self.reader.read_u2()?;
+        let mut annotations = Vec::with_capacity(length as usize);
+        for _ in 0..length {
+            let target_type = self.reader.read_u1()?;
+            let target = self.read_type_annotation_target(target_type)?;
+
+            let path_length = self.reader.read_u1()?;
+            let mut type_path = Vec::with_capacity(path_length as usize);
+            for _ in 0..path_length {
+                type_path.push(TypePathEntry {
+                    kind: self.reader.read_u1()?,
+                    type_argument_index: self.reader.read_u1()?,
+                });
+            }
+
+            annotations.push(TypeAnnotation {
+                target_type,
+                target,
+                type_path,
+                annotation: self.read_annotation()?,
+            });
+        }
+        Ok(annotations)
+    }
+
+    fn read_type_annotation_target(&mut self, target_type: u8) -> Result<TypeAnnotationTarget> {
+        let target = match target_type {
+            0x00 | 0x01 => TypeAnnotationTarget::TypeParameter(self.reader.read_u1()?),
+            0x10 => TypeAnnotationTarget::Supertype(self.reader.read_u2()?),
+            0x11 | 0x12 => TypeAnnotationTarget::TypeParameterBound {
+                type_parameter: self.reader.read_u1()?,
+                bound: self.reader.read_u1()?,
+            },
+            0x13..=0x15 => TypeAnnotationTarget::Empty,
+            0x16 => TypeAnnotationTarget::FormalParameter(self.reader.read_u1()?),
+            0x17 => TypeAnnotationTarget::Throws(self.reader.read_u2()?),
+            0x40 | 0x41 => {
+                let length = self.reader.read_u2()?;
+                let mut ranges = Vec::with_capacity(length as usize);
+                for _ in 0..length {
+                    let start_pc = self.reader.read_u2()?;
+                    let length = self.reader.read_u2()?;
+                    let index = self.reader.read_u2()?;
+                    ranges.push((start_pc, length, index));
+                }
+                TypeAnnotationTarget::LocalVariable(ranges)
+            }
+            0x42 => TypeAnnotationTarget::Catch(self.reader.read_u2()?),
+            0x43..=0x46 => TypeAnnotationTarget::Offset(self.reader.read_u2()?),
+            0x47..=0x4b => TypeAnnotationTarget::TypeArgument {
+                offset: self.reader.read_u2()?,
+                type_argument: self.reader.read_u1()?,
+            },
+            _ => {
+                return Err(format_error(
+                    self.reader.offset() - 1,
+                    format!("Invalid type annotation target {}", target_type),
+                ))
+            }
+        };
+        Ok(target)
+    }
+
+    fn read_record_attribute(&mut self) -> Result<AttributeData> {
+        let length = self.reader.read_u2()?;
+        let mut components = Vec::with_capacity(length as usize);
+        for _ in 0..length {
+            components.push(RecordComponent {
+                name: self.read_utf8()?,
+                descriptor: self.read_utf8()?,
+                attributes: self.read_attributes()?,
+            });
+        }
+        Ok(Record(components))
+    }
+
+    fn read_module(&mut self) -> Result<ModuleDescriptor> {
+        let name = self.read_module_name()?;
+        let flags = ModuleFlags::from_bits_truncate(self.reader.read_u2()?);
+        let version = self.read_optional_utf8()?;
+
+        let length = self.reader.read_u2()?;
+        let mut requires = Vec::with_capacity(length as usize);
+        for _ in 0..length {
+            requires.push(Requires {
+                module: self.read_module_name()?,
+                flags: RequiresFlags::from_bits_truncate(self.reader.read_u2()?),
+                version: self.read_optional_utf8()?,
+            });
+        }
+
+        let exports = self.read_exports()?;
+        let opens = self.read_exports()?;
+        let uses = self.read_class_names()?;
+
+        let length = self.reader.read_u2()?;
+        let mut provides = Vec::with_capacity(length as usize);
+        for _ in 0..length {
+            provides.push(Provides {
+                service: self.read_class_name()?,
+                with: self.read_class_names()?,
+            });
+        }
+
+        Ok(ModuleDescriptor {
+            name,
+            flags,
+            version,
+            requires,
+            exports,
+            opens,
+            uses,
+            provides,
+        })
+    }
+
+    /// Reads the `exports` or `opens` table of the `Module` attribute, they share a layout.
+    fn read_exports(&mut self) -> Result<Vec<Exports>> {
+        let length = self.reader.read_u2()?;
+        let mut exports = Vec::with_capacity(length as usize);
+        for _ in 0..length {
+            let package = self.read_package_name()?;
+            let flags = ExportsFlags::from_bits_truncate(self.reader.read_u2()?);
+            let to_length = self.reader.read_u2()?;
+            let mut to = Vec::with_capacity(to_length as usize);
+            for _ in 0..to_length {
+                to.push(self.read_module_name()?);
+            }
+            exports.push(Exports { package, flags, to });
+        }
+        Ok(exports)
+    }
+
+    fn read_module_packages_attribute(&mut self) -> Result<AttributeData> {
+        let length = self.reader.read_u2()?;
+        let mut packages = Vec::with_capacity(length as usize);
+        for _ in 0..length {
+            packages.push(self.read_package_name()?);
+        }
+        Ok(ModulePackages(packages))
+    }
+
+    fn read_module_name(&mut self) -> Result<String> {
+        self.lookup(|constants, index| constants.get_module_name(index).map(str::to_owned))
+    }
+
+    fn read_package_name(&mut self) -> Result<String> {
+        self.lookup(|constants, index| constants.get_package_name(index).map(str::to_owned))
+    }
+
+    fn read_optional_utf8(&mut self) -> Result<Option<String>> {
+        self.lookup(|constants, index| match index {
+            0 => Ok(None),
+            index => Ok(Some(constants.get_utf8(index)?.to_owned())),
+        })
+    }
+
+    fn read_class_names(&mut self) -> Result<Vec<String>> {
+        let length = self.reader.read_u2()?;
+        let mut names = Vec::with_capacity(length as usize);
+        for _ in 0..length {
+            names.push(self.read_class_name()?);
+        }
+        Ok(names)
+    }
+
+    fn read_utf8(&mut self) -> Result<String> {
+        self.lookup(|constants, index| constants.get_utf8(index).map(str::to_owned))
+    }
+
+    fn read_class_name(&mut self) -> Result<String> {
+        self.lookup(|constants, index| constants.get_class_info_name(index).map(str::to_owned))
+    }
+
+    fn read_optional_class_name(&mut self) -> Result<Option<String>> {
+        self.lookup(|constants, index| match index {
+            0 => Ok(None),
+            index => Ok(Some(constants.get_class_info_name(index)?.to_owned())),
+        })
+    }
+
+    /// Reads a constant pool index and looks it up with `get`, reporting a bad index at the
+    /// offset it was read from.
+    fn lookup<T>(&mut self, get: impl FnOnce(&'c ConstantPool, u16) -> Result<T>) -> Result<T> {
+        let offset = self.reader.offset();
+        let index = self.reader.read_u2()?;
+        at_offset(get(self.constants, index), offset)
+    }
+
     fn read_unknown_attribute(&mut self, len: usize) -> Result<AttributeData> {
-        let mut info = Vec::with_capacity(len);
-        unsafe {
-            info.set_len(len);
+        Ok(Unknown(self.reader.read_bytes(len)?))
+    }
+}
+
+fn invalid_element_constant(offset: usize, index: u16) -> crate::error::Error {
+    format_error(
+        offset,
+        format!("Invalid element value constant at index {}", index),
+    )
+}
+
+pub struct AttributeWriter<'w, 'c, W: Write> {
+    writer: &'w mut W,
+    constants: &'c mut ConstantPool,
+}
+
+impl<'w, 'c, W: Write> AttributeWriter<'w, 'c, W> {
+    /// Creates a writer resolving names against `constants`. Entries missing from the pool are
+    /// added to its end.
+    pub fn new(writer: &'w mut W, constants: &'c mut ConstantPool) -> AttributeWriter<'w, 'c, W> {
+        AttributeWriter { writer, constants }
+    }
+
+    pub fn write_attributes(&mut self, attributes: &[Attribute]) -> Result<()> {
+        self.writer.write_u2(attributes.len() as u16)?;
+        for attribute in attributes {
+            self.write_attribute_info(attribute)?;
+        }
+        Ok(())
+    }
+
+    fn write_attribute_info(&mut self, attribute: &Attribute) -> Result<()> {
+        let name_index = self.constants.find_or_add_utf8(&attribute.name)?;
+
+        // The length comes before the data, so the data is written to a buffer first.
+        let mut info = Vec::new();
+        let mut info_writer = AttributeWriter::new(&mut info, self.constants);
+        match &attribute.data {
+            SourceFile(name) | Signature(name) => info_writer.write_utf8_index(name)?,
+            LineNumberTable(table) => info_writer.write_line_number_table(table)?,
+            CodeInfo(code) => info_writer.write_code(code)?,
+            ConstantValue(constant) => {
+                let index = info_writer.constants.find_or_add(constant.clone())?;
+                info_writer.writer.write_u2(index)?
+            }
+            Exceptions(exceptions) => info_writer.write_class_names(exceptions)?,
+            BootstrapMethods(methods) => info_writer.write_bootstrap_methods(methods)?,
+            InnerClasses(classes) => info_writer.write_inner_classes(classes)?,
+            EnclosingMethod(method) => info_writer.write_enclosing_method(method)?,
+            NestHost(host) => info_writer.write_class_name(host)?,
+            NestMembers(members) => info_writer.write_class_names(members)?,
+            StackMapTable(frames) => info_writer.write_stack_map_table(frames)?,
+            LocalVariableTable(variables) | LocalVariableTypeTable(variables) => {
+                info_writer.write_local_variables(variables)?
+            }
+            RuntimeVisibleAnnotations(annotations) | RuntimeInvisibleAnnotations(annotations) => {
+                info_writer.write_annotations(annotations)?
+            }
+            RuntimeVisibleParameterAnnotations(parameters)
+            | RuntimeInvisibleParameterAnnotations(parameters) => {
+                info_writer.writer.write_u1(parameters.len() as u8)?;
+                for annotations in parameters {
+                    info_writer.write_annotations(annotations)?;
+                }
+            }
+            RuntimeVisibleTypeAnnotations(annotations)
+            | RuntimeInvisibleTypeAnnotations(annotations) => {
+                info_writer.write_type_annotations(annotations)?
+            }
+            AnnotationDefault(value) => info_writer.write_element_value(value)?,
+            Module(module) => info_writer.write_module(module)?,
+            ModulePackages(packages) => {
+                info_writer.writer.write_u2(packages.len() as u16)?;
+                for package in packages {
+                    info_writer.write_package_name(package)?;
+                }
+            }
+            ModuleMainClass(class) => info_writer.write_class_name(class)?,
+            Record(components) => {
+                info_writer.writer.write_u2(components.len() as u16)?;
+                for component in components {
+                    info_writer.write_utf8_index(&component.name)?;
+                    info_writer.write_utf8_index(&component.descriptor)?;
+                    info_writer.write_attributes(&component.attributes)?;
+                }
+            }
+            PermittedSubclasses(classes) => info_writer.write_class_names(classes)?,
+            Unknown(bytes) => info.extend_from_slice(bytes),
+        }
+
+        self.writer.write_u2(name_index)?;
+        self.writer.write_u4(info.len() as u32)?;
+        self.writer.write_all(&info)?;
+        Ok(())
+    }
+
+    fn write_code(&mut self, code: &Code) -> Result<()> {
+        self.writer.write_u2(code.max_stack)?;
+        self.writer.write_u2(code.max_locals)?;
+        CodeWriter::new(self.writer).write_code(&code.instructions)?;
+
+        self.writer.write_u2(code.exception_handlers.len() as u16)?;
+        for handler in &code.exception_handlers {
+            self.writer.write_u2(handler.start_pc)?;
+            self.writer.write_u2(handler.end_pc)?;
+            self.writer.write_u2(handler.handler_pc)?;
+            self.write_optional_class_name(handler.catch_type.as_deref())?;
+        }
+
+        self.write_attributes(&code.attributes)
+    }
+
+    fn write_line_number_table(&mut self, table: &[(u16, u16)]) -> Result<()> {
+        self.writer.write_u2(table.len() as u16)?;
+        for (start_pc, line_number) in table {
+            self.writer.write_u2(*start_pc)?;
+            self.writer.write_u2(*line_number)?;
+        }
+        Ok(())
+    }
+
+    fn write_bootstrap_methods(&mut self, methods: &[BootstrapMethod]) -> Result<()> {
+        self.writer.write_u2(methods.len() as u16)?;
+        for method in methods {
+            self.writer.write_u2(method.method_ref)?;
+            self.writer.write_u2(method.arguments.len() as u16)?;
+            for argument in &method.arguments {
+                self.writer.write_u2(*argument)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_inner_classes(&mut self, classes: &[InnerClass]) -> Result<()> {
+        self.writer.write_u2(classes.len() as u16)?;
+        for class in classes {
+            self.write_class_name(&class.inner_class)?;
+            self.write_optional_class_name(class.outer_class.as_deref())?;
+            match &class.inner_name {
+                Some(name) => self.write_utf8_index(name)?,
+                None => self.writer.write_u2(0)?,
+            }
+            self.writer.write_u2(class.access_flags.bits())?;
+        }
+        Ok(())
+    }
+
+    fn write_enclosing_method(&mut self, method: &attribute::EnclosingMethod) -> Result<()> {
+        self.write_class_name(&method.class)?;
+        let index = match &method.method {
+            Some((name, descriptor)) => {
+                self.constants.find_or_add_name_and_type(name, descriptor)?
+            }
+            None => 0,
+        };
+        self.writer.write_u2(index)
+    }
+
+    fn write_stack_map_table(&mut self, frames: &[StackMapFrame]) -> Result<()> {
+        self.writer.write_u2(frames.len() as u16)?;
+        for frame in frames {
+            self.write_stack_map_frame(frame)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the frame in its shortest form, the one picked by `javac`.
+    fn write_stack_map_frame(&mut self, frame: &StackMapFrame) -> Result<()> {
+        match frame {
+            StackMapFrame::Same { offset_delta } if *offset_delta <= 63 => {
+                self.writer.write_u1(*offset_delta as u8)
+            }
+            StackMapFrame::Same { offset_delta } => {
+                self.writer.write_u1(251)?;
+                self.writer.write_u2(*offset_delta)
+            }
+            StackMapFrame::SameLocals1StackItem {
+                offset_delta,
+                stack,
+            } => {
+                if *offset_delta <= 63 {
+                    self.writer.write_u1(64 + *offset_delta as u8)?;
+                } else {
+                    self.writer.write_u1(247)?;
+                    self.writer.write_u2(*offset_delta)?;
+                }
+                self.write_verification_type(stack)
+            }
+            StackMapFrame::Chop {
+                offset_delta,
+                count,
+            } => {
+                if !(1..=3).contains(count) {
+                    return Err(Error::new(
+                        ParseError,
+                        Some(format!(
+                            "Can not chop {} locals in a stack map frame.",
+                            count
+                        )),
+                    ));
+                }
+                self.writer.write_u1(251 - count)?;
+                self.writer.write_u2(*offset_delta)
+            }
+            StackMapFrame::Append {
+                offset_delta,
+                locals,
+            } => {
+                if !(1..=3).contains(&locals.len()) {
+                    return Err(Error::new(
+                        ParseError,
+                        Some(format!(
+                            "Can not append {} locals in a stack map frame.",
+                            locals.len()
+                        )),
+                    ));
+                }
+                self.writer.write_u1(251 + locals.len() as u8)?;
+                self.writer.write_u2(*offset_delta)?;
+                self.write_verification_types(locals, false)
+            }
+            StackMapFrame::Full {
+                offset_delta,
+                locals,
+                stack,
+            } => {
+                self.writer.write_u1(255)?;
+                self.writer.write_u2(*offset_delta)?;
+                self.write_verification_types(locals, true)?;
+                self.write_verification_types(stack, true)
+            }
+        }
+    }
+
+    fn write_verification_types(
+        &mut self,
+        types: &[VerificationType],
+        with_count: bool,
+    ) -> Result<()> {
+        if with_count {
+            self.writer.write_u2(types.len() as u16)?;
         }
-        self.reader.read_exact(&mut info)?;
+        for verification_type in types {
+            self.write_verification_type(verification_type)?;
+        }
+        Ok(())
+    }
+
+    fn write_verification_type(&mut self, verification_type: &VerificationType) -> Result<()> {
+        match verification_type {
+            VerificationType::Top => self.writer.write_u1(0),
+            VerificationType::Integer => self.writer.write_u1(1),
+            VerificationType::Float => self.writer.write_u1(2),
+            VerificationType::Double => self.writer.write_u1(3),
+            VerificationType::Long => self.writer.write_u1(4),
+            VerificationType::Null => self.writer.write_u1(5),
+            VerificationType::UninitializedThis => self.writer.write_u1(6),
+            VerificationType::Object(class) => {
+                self.writer.write_u1(7)?;
+                self.write_class_name(class)
+            }
+            VerificationType::Uninitialized(offset) => {
+                self.writer.write_u1(8)?;
+                self.writer.write_u2(*offset)
+            }
+        }
+    }
 
-        Ok(Unknown(info))
+    fn write_local_variables(&mut self, variables: &[LocalVariable]) -> Result<()> {
+        self.writer.write_u2(variables.len() as u16)?;
+        for variable in variables {
+            self.writer.write_u2(variable.start_pc)?;
+            self.writer.write_u2(variable.length)?;
+            self.write_utf8_index(&variable.name)?;
+            self.write_utf8_index(&variable.descriptor)?;
+            self.writer.write_u2(variable.index)?;
+        }
+        Ok(())
+    }
+
+    fn write_annotations(&mut self, annotations: &[Annotation]) -> Result<()> {
+        self.writer.write_u2(annotations.len() as u16)?;
+        for annotation in annotations {
+            self.write_annotation(annotation)?;
+        }
+        Ok(())
+    }
+
+    fn write_annotation(&mut self, annotation: &Annotation) -> Result<()> {
+        self.write_utf8_index(&annotation.type_descriptor)?;
+        self.writer.write_u2(annotation.elements.len() as u16)?;
+        for (name, value) in &annotation.elements {
+            self.write_utf8_index(name)?;
+            self.write_element_value(value)?;
+        }
+        Ok(())
+    }
+
+    fn write_element_value(&mut self, value: &ElementValue) -> Result<()> {
+        let (tag, constant) = match value {
+            ElementValue::Byte(value) => (b'B', Constant::Integer(*value as i32)),
+            ElementValue::Char(value) => (b'C', Constant::Integer(*value as i32)),
+            ElementValue::Short(value) => (b'S', Constant::Integer(*value as i32)),
+            ElementValue::Boolean(value) => (b'Z', Constant::Integer(*value as i32)),
+            ElementValue::Int(value) => (b'I', Constant::Integer(*value)),
+            ElementValue::Long(value) => (b'J', Constant::Long(*value)),
+            ElementValue::Float(value) => (b'F', Constant::Float(*value)),
+            ElementValue::Double(value) => (b'D', Constant::Double(*value)),
+            ElementValue::String(value) => (b's', Constant::Utf8(value.clone())),
+            ElementValue::Enum {
+                type_descriptor,
+                name,
+            } => {
+                self.writer.write_u1(b'e')?;
+                self.write_utf8_index(type_descriptor)?;
+                return self.write_utf8_index(name);
+            }
+            ElementValue::Class(descriptor) => (b'c', Constant::Utf8(descriptor.clone())),
+            ElementValue::Annotation(annotation) => {
+                self.writer.write_u1(b'@')?;
+                return self.write_annotation(annotation);
+            }
+            ElementValue::Array(values) => {
+                self.writer.write_u1(b'[')?;
+                self.writer.write_u2(values.len() as u16)?;
+                for value in values {
+                    self.write_element_value(value)?;
+                }
+                return Ok(());
+            }
+        };
+
+        self.writer.write_u1(tag)?;
+        let index = self.constants.find_or_add(constant)?;
+        self.writer.write_u2(index)
+    }
+
+    fn write_type_annotations(&mut self, annotations: &[TypeAnnotation]) -> Result<()> {
+        self.writer.write_u2(annotations.len() as u16)?;
+        for annotation in annotations {
+            self.writer.write_u1(annotation.target_type)?;
+            match &annotation.target {
+                TypeAnnotationTarget::TypeParameter(index)
+                | TypeAnnotationTarget::FormalParameter(index) => self.writer.write_u1(*index)?,
+                TypeAnnotationTarget::Supertype(index)
+                | TypeAnnotationTarget::Throws(index)
+                | TypeAnnotationTarget::Catch(index)
+                | TypeAnnotationTarget::Offset(index) => self.writer.write_u2(*index)?,
+                TypeAnnotationTarget::TypeParameterBound {
+                    type_parameter,
+                    bound,
+                } => {
+                    self.writer.write_u1(*type_parameter)?;
+                    self.writer.write_u1(*bound)?;
+                }
+                TypeAnnotationTarget::Empty => {}
+                TypeAnnotationTarget::LocalVariable(ranges) => {
+                    self.writer.write_u2(ranges.len() as u16)?;
+                    for (start_pc, length, index) in ranges {
+                        self.writer.write_u2(*start_pc)?;
+                        self.writer.write_u2(*length)?;
+                        self.writer.write_u2(*index)?;
+                    }
+                }
+                TypeAnnotationTarget::TypeArgument {
+                    offset,
+                    type_argument,
+                } => {
+                    self.writer.write_u2(*offset)?;
+                    self.writer.write_u1(*type_argument)?;
+                }
+            }
+
+            self.writer.write_u1(annotation.type_path.len() as u8)?;
+            for entry in &annotation.type_path {
+                self.writer.write_u1(entry.kind)?;
+                self.writer.write_u1(entry.type_argument_index)?;
+            }
+            self.write_annotation(&annotation.annotation)?;
+        }
+        Ok(())
+    }
+
+    fn write_module(&mut self, module: &ModuleDescriptor) -> Result<()> {
+        self.write_module_name(&module.name)?;
+        self.writer.write_u2(module.flags.bits())?;
+        self.write_optional_utf8_index(module.version.as_deref())?;
+
+        self.writer.write_u2(module.requires.len() as u16)?;
+        for requires in &module.requires {
+            self.write_module_name(&requires.module)?;
+            self.writer.write_u2(requires.flags.bits())?;
+            self.write_optional_utf8_index(requires.version.as_deref())?;
+        }
+
+        self.write_exports(&module.exports)?;
+        self.write_exports(&module.opens)?;
+        self.write_class_names(&module.uses)?;
+
+        self.writer.write_u2(module.provides.len() as u16)?;
+        for provides in &module.provides {
+            self.write_class_name(&provides.service)?;
+            self.write_class_names(&provides.with)?;
+        }
+        Ok(())
+    }
+
+    fn write_exports(&mut self, exports: &[Exports]) -> Result<()> {
+        self.writer.write_u2(exports.len() as u16)?;
+        for export in exports {
+            self.write_package_name(&export.package)?;
+            self.writer.write_u2(export.flags.bits())?;
+            self.writer.write_u2(export.to.len() as u16)?;
+            for module in &export.to {
+                self.write_module_name(module)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_module_name(&mut self, name: &str) -> Result<()> {
+        let index = self.constants.find_or_add_module(name)?;
+        self.writer.write_u2(index)
+    }
+
+    fn write_package_name(&mut self, name: &str) -> Result<()> {
+        let index = self.constants.find_or_add_package(name)?;
+        self.writer.write_u2(index)
+    }
+
+    fn write_optional_utf8_index(&mut self, string: Option<&str>) -> Result<()> {
+        match string {
+            Some(string) => self.write_utf8_index(string),
+            None => self.writer.write_u2(0),
+        }
+    }
+
+    fn write_utf8_index(&mut self, string: &str) -> Result<()> {
+        let index = self.constants.find_or_add_utf8(string)?;
+        self.writer.write_u2(index)
+    }
+
+    fn write_class_names(&mut self, names: &[String]) -> Result<()> {
+        self.writer.write_u2(names.len() as u16)?;
+        for name in names {
+            self.write_class_name(name)?;
+        }
+        Ok(())
+    }
+
+    fn write_class_name(&mut self, name: &str) -> Result<()> {
+        let index = self.constants.find_or_add_class(name)?;
+        self.writer.write_u2(index)
+    }
+
+    fn write_optional_class_name(&mut self, name: Option<&str>) -> Result<()> {
+        match name {
+            Some(name) => self.write_class_name(name),
+            None => self.writer.write_u2(0),
+        }
     }
 }
 
 #[cfg(test)]
 mod test {
     use crate::class::attribute::AttributeData::{
-        CodeInfo, ConstantValue, Exceptions, LineNumberTable, SourceFile, Unknown,
+        BootstrapMethods, CodeInfo, ConstantValue, EnclosingMethod, Exceptions, InnerClasses,
+        LineNumberTable, LocalVariableTable, NestHost, NestMembers, RuntimeVisibleAnnotations,
+        SourceFile, StackMapTable, Unknown,
     };
-    use crate::class::attribute::{Attribute, Code, ExceptionHandler};
+    use crate::class::attribute::{self, Attribute, BootstrapMethod, Code, ExceptionHandler};
+    use crate::class::attribute::{Annotation, ElementValue};
+    use crate::class::attribute::{InnerClass, LocalVariable, StackMapFrame, VerificationType};
     use crate::class::code::Instruction;
     use crate::class::code::Opcode::Nop;
     use crate::class::constant::Constant::*;
     use crate::class::constant::ConstantPool;
-    use crate::io::attribute::AttributeReader;
+    use crate::class::InnerClassAccessFlags;
+    use crate::io::attribute::{AttributeReader, AttributeWriter};
+    use crate::io::Offset;
     use std::io::{BufRead, Cursor};
 
     #[test]
@@ -273,6 +1216,285 @@ mod test {
         );
     }
 
+    #[test]
+    fn read_bootstrap_methods_attribute() {
+        let mut constants = ConstantPool::new(1);
+        constants.add(Utf8("BootstrapMethods".to_owned()));
+
+        let mut data = Cursor::new(vec![
+            0x00, 0x01, // Count
+            0x00, 0x01, // Name index
+            0x00, 0x00, 0x00, 0x0e, // Info length
+            0x00, 0x02, // Number of bootstrap methods
+            0x00, 0x05, // 1. Method handle index
+            0x00, 0x02, // 1. Number of arguments
+            0x00, 0x06, // 1. Argument
+            0x00, 0x07, // 1. Argument
+            0x00, 0x08, // 2. Method handle index
+            0x00, 0x00, // 2. Number of arguments
+        ]);
+
+        assert_eq!(
+            read_attributes(&mut data, &constants),
+            vec![Attribute {
+                name: "BootstrapMethods".to_owned(),
+                data: BootstrapMethods(vec![
+                    BootstrapMethod {
+                        method_ref: 5,
+                        arguments: vec![6, 7],
+                    },
+                    BootstrapMethod {
+                        method_ref: 8,
+                        arguments: vec![],
+                    }
+                ]),
+            }]
+        );
+    }
+
+    #[test]
+    fn read_inner_classes_attribute() {
+        let mut constants = ConstantPool::new(1);
+        constants.add(Utf8("InnerClasses".to_owned()));
+        constants.add(ClassRef(3));
+        constants.add(Utf8("Outer$Inner".to_owned()));
+        constants.add(ClassRef(5));
+        constants.add(Utf8("Outer".to_owned()));
+        constants.add(Utf8("Inner".to_owned()));
+        constants.add(ClassRef(8));
+        constants.add(Utf8("Outer$1".to_owned()));
+
+        let mut data = Cursor::new(vec![
+            0x00, 0x01, // Count
+            0x00, 0x01, // Name index
+            0x00, 0x00, 0x00, 0x12, // Info length
+            0x00, 0x02, // Number of classes
+            0x00, 0x02, // 1. Inner class
+            0x00, 0x04, // 1. Outer class
+            0x00, 0x06, // 1. Inner name
+            0x00, 0x0a, // 1. Access flags, private static
+            0x00, 0x07, // 2. Inner class
+            0x00, 0x00, // 2. No outer class
+            0x00, 0x00, // 2. No inner name
+            0x00, 0x00, // 2. Access flags
+        ]);
+
+        assert_eq!(
+            read_attributes(&mut data, &constants),
+            vec![Attribute {
+                name: "InnerClasses".to_owned(),
+                data: InnerClasses(vec![
+                    InnerClass {
+                        inner_class: "Outer$Inner".to_owned(),
+                        outer_class: Some("Outer".to_owned()),
+                        inner_name: Some("Inner".to_owned()),
+                        access_flags: InnerClassAccessFlags::PRIVATE
+                            | InnerClassAccessFlags::STATIC,
+                    },
+                    InnerClass {
+                        inner_class: "Outer$1".to_owned(),
+                        outer_class: None,
+                        inner_name: None,
+                        access_flags: InnerClassAccessFlags::empty(),
+                    }
+                ]),
+            }]
+        );
+    }
+
+    #[test]
+    fn read_enclosing_method_attribute() {
+        let mut constants = ConstantPool::new(1);
+        constants.add(Utf8("EnclosingMethod".to_owned()));
+        constants.add(ClassRef(3));
+        constants.add(Utf8("Outer".to_owned()));
+        constants.add(NameAndType(5, 6));
+        constants.add(Utf8("run".to_owned()));
+        constants.add(Utf8("()V".to_owned()));
+
+        let mut data = Cursor::new(vec![
+            0x00, 0x02, // Count
+            0x00, 0x01, // 1. Name index
+            0x00, 0x00, 0x00, 0x04, // 1. Info length
+            0x00, 0x02, // 1. Class
+            0x00, 0x04, // 1. Method
+            0x00, 0x01, // 2. Name index
+            0x00, 0x00, 0x00, 0x04, // 2. Info length
+            0x00, 0x02, // 2. Class
+            0x00, 0x00, // 2. No method
+        ]);
+
+        assert_eq!(
+            read_attributes(&mut data, &constants),
+            vec![
+                Attribute {
+                    name: "EnclosingMethod".to_owned(),
+                    data: EnclosingMethod(attribute::EnclosingMethod {
+                        class: "Outer".to_owned(),
+                        method: Some(("run".to_owned(), "()V".to_owned())),
+                    }),
+                },
+                Attribute {
+                    name: "EnclosingMethod".to_owned(),
+                    data: EnclosingMethod(attribute::EnclosingMethod {
+                        class: "Outer".to_owned(),
+                        method: None,
+                    }),
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn read_nest_attributes() {
+        let mut constants = ConstantPool::new(1);
+        constants.add(Utf8("NestHost".to_owned()));
+        constants.add(Utf8("NestMembers".to_owned()));
+        constants.add(ClassRef(4));
+        constants.add(Utf8("Outer".to_owned()));
+        constants.add(ClassRef(6));
+        constants.add(Utf8("Outer$Inner".to_owned()));
+
+        let mut data = Cursor::new(vec![
+            0x00, 0x02, // Count
+            0x00, 0x01, // 1. Name index
+            0x00, 0x00, 0x00, 0x02, // 1. Info length
+            0x00, 0x03, // 1. Host class
+            0x00, 0x02, // 2. Name index
+            0x00, 0x00, 0x00, 0x06, // 2. Info length
+            0x00, 0x02, // 2. Number of classes
+            0x00, 0x03, // 2. Class
+            0x00, 0x05, // 2. Class
+        ]);
+
+        assert_eq!(
+            read_attributes(&mut data, &constants),
+            vec![
+                Attribute {
+                    name: "NestHost".to_owned(),
+                    data: NestHost("Outer".to_owned()),
+                },
+                Attribute {
+                    name: "NestMembers".to_owned(),
+                    data: NestMembers(vec!["Outer".to_owned(), "Outer$Inner".to_owned()]),
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn read_stack_map_table_attribute() {
+        let mut constants = ConstantPool::new(1);
+        constants.add(Utf8("StackMapTable".to_owned()));
+        constants.add(ClassRef(3));
+        constants.add(Utf8("java/lang/String".to_owned()));
+
+        let mut data = Cursor::new(vec![
+            0x00, 0x01, // Count
+            0x00, 0x01, // Name index
+            0x00, 0x00, 0x00, 0x22, // Info length
+            0x00, 0x08, // Number of frames
+            0x05, // Same
+            0xfb, 0x01, 0x00, // Same extended
+            0x41, 0x01, // Same locals 1 stack item, integer
+            0xf7, 0x01, 0x00, 0x05, // Same locals 1 stack item extended, null
+            0xf9, 0x00, 0x02, // Chop 2
+            0xfc, 0x00, 0x03, 0x07, 0x00, 0x02, // Append string
+            0xff, 0x00, 0x04, // Full
+            0x00, 0x02, 0x06, 0x04, // Locals, uninitialized this and long
+            0x00, 0x01, 0x08, 0x00, 0x07, // Stack, uninitialized at 7
+            0x06, // Same
+        ]);
+
+        assert_eq!(
+            read_attributes(&mut data, &constants),
+            vec![Attribute {
+                name: "StackMapTable".to_owned(),
+                data: StackMapTable(vec![
+                    StackMapFrame::Same { offset_delta: 5 },
+                    StackMapFrame::Same { offset_delta: 256 },
+                    StackMapFrame::SameLocals1StackItem {
+                        offset_delta: 1,
+                        stack: VerificationType::Integer,
+                    },
+                    StackMapFrame::SameLocals1StackItem {
+                        offset_delta: 256,
+                        stack: VerificationType::Null,
+                    },
+                    StackMapFrame::Chop {
+                        offset_delta: 2,
+                        count: 2,
+                    },
+                    StackMapFrame::Append {
+                        offset_delta: 3,
+                        locals: vec![VerificationType::Object("java/lang/String".to_owned())],
+                    },
+                    StackMapFrame::Full {
+                        offset_delta: 4,
+                        locals: vec![VerificationType::UninitializedThis, VerificationType::Long],
+                        stack: vec![VerificationType::Uninitialized(7)],
+                    },
+                    StackMapFrame::Same { offset_delta: 6 },
+                ]),
+            }]
+        );
+    }
+
+    #[test]
+    fn read_invalid_stack_map_frame() {
+        let mut constants = ConstantPool::new(1);
+        constants.add(Utf8("StackMapTable".to_owned()));
+
+        let mut data = Cursor::new(vec![
+            0x00, 0x01, // Count
+            0x00, 0x01, // Name index
+            0x00, 0x00, 0x00, 0x03, // Info length
+            0x00, 0x01, // Number of frames
+            0x80, // Reserved frame type
+        ]);
+
+        let mut reader = AttributeReader::new(&mut data, &constants);
+        let error = reader.read_attributes().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid stack map frame type 128 at byte 10."
+        );
+    }
+
+    #[test]
+    fn read_local_variable_table_attribute() {
+        let mut constants = ConstantPool::new(1);
+        constants.add(Utf8("LocalVariableTable".to_owned()));
+        constants.add(Utf8("this".to_owned()));
+        constants.add(Utf8("LMain;".to_owned()));
+
+        let mut data = Cursor::new(vec![
+            0x00, 0x01, // Count
+            0x00, 0x01, // Name index
+            0x00, 0x00, 0x00, 0x0c, // Info length
+            0x00, 0x01, // Table length
+            0x00, 0x00, // Start PC
+            0x00, 0x05, // Length
+            0x00, 0x02, // Name index
+            0x00, 0x03, // Descriptor index
+            0x00, 0x00, // Slot
+        ]);
+
+        assert_eq!(
+            read_attributes(&mut data, &constants),
+            vec![Attribute {
+                name: "LocalVariableTable".to_owned(),
+                data: LocalVariableTable(vec![LocalVariable {
+                    start_pc: 0,
+                    length: 5,
+                    name: "this".to_owned(),
+                    descriptor: "LMain;".to_owned(),
+                    index: 0,
+                }]),
+            }]
+        );
+    }
+
     #[test]
     fn read_code_attribute() {
         let mut constants = ConstantPool::new(2);
@@ -284,7 +1506,7 @@ mod test {
         let mut data = Cursor::new(vec![
             0x00, 0x01, // Attribute count
             0x00, 0x01, // Name index (Code)
-            0x00, 0x00, 0x00, 0x28, // Length
+            0x00, 0x00, 0x00, 0x2d, // Length
             0x00, 0x03, // Max stack
             0x00, 0x01, // Max locals
             0x00, 0x00, 0x00, 0x01, // Code length
@@ -339,7 +1561,170 @@ mod test {
         );
     }
 
-    fn read_attributes<R: BufRead>(r: &mut R, constants: &ConstantPool) -> Vec<Attribute> {
+    #[test]
+    fn read_annotations_attribute() {
+        let mut constants = ConstantPool::new(9);
+        constants.add(Utf8("RuntimeVisibleAnnotations".to_owned()));
+        constants.add(Utf8("Ltest/Info;".to_owned()));
+        constants.add(Utf8("kind".to_owned()));
+        constants.add(Utf8("Ljava/lang/annotation/ElementType;".to_owned()));
+        constants.add(Utf8("TYPE".to_owned()));
+        constants.add(Utf8("values".to_owned()));
+        constants.add(Integer(1));
+        constants.add(Utf8("nested".to_owned()));
+        constants.add(Utf8("Ltest/Tag;".to_owned()));
+
+        let bytes = vec![
+            0x00, 0x01, // Count
+            0x00, 0x01, // Name index
+            0x00, 0x00, 0x00, 0x1f, // Info length
+            0x00, 0x01, // Number of annotations
+            0x00, 0x02, // Type index
+            0x00, 0x03, // Number of elements
+            0x00, 0x03, b'e', 0x00, 0x04, 0x00, 0x05, // Enum constant
+            0x00, 0x06, b'[', 0x00, 0x02, b'Z', 0x00, 0x07, b'I', 0x00, 0x07, // Array
+            0x00, 0x08, b'@', 0x00, 0x09, 0x00, 0x00, // Nested annotation
+        ];
+        let attributes = read_attributes(&mut Cursor::new(bytes.clone()), &constants);
+
+        assert_eq!(
+            attributes,
+            vec![Attribute {
+                name: "RuntimeVisibleAnnotations".to_owned(),
+                data: RuntimeVisibleAnnotations(vec![Annotation {
+                    type_descriptor: "Ltest/Info;".to_owned(),
+                    elements: vec![
+                        (
+                            "kind".to_owned(),
+                            ElementValue::Enum {
+                                type_descriptor: "Ljava/lang/annotation/ElementType;".to_owned(),
+                                name: "TYPE".to_owned(),
+                            }
+                        ),
+                        (
+                            "values".to_owned(),
+                            ElementValue::Array(vec![
+                                ElementValue::Boolean(true),
+                                ElementValue::Int(1)
+                            ])
+                        ),
+                        (
+                            "nested".to_owned(),
+                            ElementValue::Annotation(Annotation {
+                                type_descriptor: "Ltest/Tag;".to_owned(),
+                                elements: vec![],
+                            })
+                        ),
+                    ],
+                }]),
+            }]
+        );
+
+        let mut written = Vec::new();
+        AttributeWriter::new(&mut written, &mut constants)
+            .write_attributes(&attributes)
+            .unwrap();
+        assert_eq!(written, bytes);
+    }
+
+    #[test]
+    fn write_attributes() {
+        let mut constants = ConstantPool::new(1);
+        constants.add(Utf8("StackMapTable".to_owned()));
+        constants.add(ClassRef(3));
+        constants.add(Utf8("java/lang/String".to_owned()));
+
+        let bytes = vec![
+            0x00, 0x01, // Count
+            0x00, 0x01, // Name index
+            0x00, 0x00, 0x00, 0x22, // Info length
+            0x00, 0x08, // Number of frames
+            0x05, // Same
+            0xfb, 0x01, 0x00, // Same extended
+            0x41, 0x01, // Same locals 1 stack item, integer
+            0xf7, 0x01, 0x00, 0x05, // Same locals 1 stack item extended, null
+            0xf9, 0x00, 0x02, // Chop 2
+            0xfc, 0x00, 0x03, 0x07, 0x00, 0x02, // Append string
+            0xff, 0x00, 0x04, // Full
+            0x00, 0x02, 0x06, 0x04, // Locals, uninitialized this and long
+            0x00, 0x01, 0x08, 0x00, 0x07, // Stack, uninitialized at 7
+            0x06, // Same
+        ];
+        let attributes = read_attributes(&mut Cursor::new(bytes.clone()), &constants);
+
+        let mut written = Vec::new();
+        AttributeWriter::new(&mut written, &mut constants)
+            .write_attributes(&attributes)
+            .unwrap();
+        assert_eq!(written, bytes);
+        assert_eq!(constants.len(), 3);
+    }
+
+    #[test]
+    fn write_invalid_stack_map_frames() {
+        let write_error = |frame: StackMapFrame| {
+            let attributes = vec![Attribute {
+                name: "StackMapTable".to_owned(),
+                data: StackMapTable(vec![frame]),
+            }];
+            let mut constants = ConstantPool::new(0);
+            AttributeWriter::new(&mut Vec::new(), &mut constants)
+                .write_attributes(&attributes)
+                .unwrap_err()
+                .to_string()
+        };
+
+        assert_eq!(
+            write_error(StackMapFrame::Chop {
+                offset_delta: 1,
+                count: 0,
+            }),
+            "Can not chop 0 locals in a stack map frame."
+        );
+        assert_eq!(
+            write_error(StackMapFrame::Chop {
+                offset_delta: 1,
+                count: 4,
+            }),
+            "Can not chop 4 locals in a stack map frame."
+        );
+        assert_eq!(
+            write_error(StackMapFrame::Append {
+                offset_delta: 1,
+                locals: vec![VerificationType::Integer; 4],
+            }),
+            "Can not append 4 locals in a stack map frame."
+        );
+    }
+
+    #[test]
+    fn write_attributes_adds_constants() {
+        let mut constants = ConstantPool::new(0);
+        let attributes = vec![Attribute {
+            name: "NestHost".to_owned(),
+            data: NestHost("Host".to_owned()),
+        }];
+
+        let mut written = Vec::new();
+        AttributeWriter::new(&mut written, &mut constants)
+            .write_attributes(&attributes)
+            .unwrap();
+
+        assert_eq!(
+            written,
+            vec![
+                0x00, 0x01, // Count
+                0x00, 0x01, // Name index
+                0x00, 0x00, 0x00, 0x02, // Info length
+                0x00, 0x03, // Host class index
+            ]
+        );
+        assert_eq!(constants.get(1).unwrap(), &Utf8("NestHost".to_owned()));
+        assert_eq!(constants.get(2).unwrap(), &Utf8("Host".to_owned()));
+        assert_eq!(constants.get(3).unwrap(), &ClassRef(2));
+    }
+
+    fn read_attributes<R: BufRead + Offset>(r: &mut R, constants: &ConstantPool) -> Vec<Attribute> {
         let mut reader = AttributeReader::new(r, &constants);
         reader.read_attributes().unwrap()
     }
@@ -1,25 +1,34 @@
 use crate::class::attribute::Attribute;
 use crate::class::constant::Constant::*;
 use crate::class::constant::MethodHandleKind::*;
-use crate::class::constant::{Constant, ConstantPool};
+use crate::class::constant::{Constant, ConstantPool, MAX_LEN};
 use crate::class::ClassAccessFlags;
 use crate::class::FieldAccessFlags;
 use crate::class::MethodAccessFlags;
 use crate::class::{Class, FieldInfo, MethodInfo, Version};
-use crate::error::ErrorKind::ParseError;
+use crate::error::ErrorKind::{ParseError, IO};
 use crate::error::{Error, Result};
-use crate::io::attribute::AttributeReader;
-use crate::io::ReadBytesExt;
-use std::convert::TryInto;
+use crate::io::attribute::{AttributeReader, AttributeWriter};
+use crate::io::mutf8;
+use crate::io::{at_offset, format_error, Offset, OffsetReader, ReadBytesExt, WriteBytesExt};
+use crate::vm::data_type::FieldType;
+use std::convert::{TryFrom, TryInto};
 use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
 use std::path::Path;
 use std::rc::Rc;
 
 const SIGNATURE: &[u8] = &[0xCA, 0xFE, 0xBA, 0xBE];
 
+/// The class file versions that can be read, from Java 1.1 to Java 17.
+const MIN_MAJOR_VERSION: u16 = 45;
+const MAX_MAJOR_VERSION: u16 = 61;
+
+/// Reads a class file, checking the format as described in
+/// https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-4.html#jvms-4.8. A malformed class
+/// file is reported as a parse error with the offset of the offending bytes.
 pub struct ClassReader<R: BufRead> {
-    reader: R,
+    reader: OffsetReader<R>,
 }
 
 impl ClassReader<BufReader<File>> {
@@ -36,32 +45,72 @@ impl ClassReader<BufReader<File>> {
 
 impl<R: BufRead> ClassReader<R> {
     pub fn new(reader: R) -> Self {
-        ClassReader { reader }
+        ClassReader {
+            reader: OffsetReader::new(reader),
+        }
     }
 
     pub fn read_class(mut self) -> Result<Class> {
+        self.read_class_file().map_err(|error| match error.kind() {
+            IO(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                format_error(self.reader.offset(), "Unexpected end of class file")
+            }
+            _ => error,
+        })
+    }
+
+    fn read_class_file(&mut self) -> Result<Class> {
         self.read_signature()?;
         let version = self.read_version()?;
-        let constants = self.read_constants()?;
+        let (constants, offsets) = self.read_constants()?;
+        check_constants(&constants, &offsets)?;
+
+        let flags_offset = self.reader.offset();
         let access_flags = self.read_access_flags()?;
-        let this_class = constants
-            .get_class_info_name(self.reader.read_u2()?)?
-            .to_owned();
+        if let Err(reason) = check_class_flags(access_flags) {
+            return Err(invalid_flags(
+                flags_offset,
+                access_flags.bits(),
+                "class",
+                reason,
+            ));
+        }
+
+        let this_class = self.lookup(|index| constants.get_class_info_name(index))?;
 
+        let super_offset = self.reader.offset();
         let super_class_index = self.reader.read_u2()?;
 
-        // Object class have no super class.
+        // Object class have no super class, neither do modules.
         let super_class = if super_class_index != 0 {
-            constants.get_class_info_name(super_class_index)?.to_owned()
-        } else {
+            at_offset(
+                constants.get_class_info_name(super_class_index),
+                super_offset,
+            )?
+            .to_owned()
+        } else if this_class == "java/lang/Object"
+            || access_flags.contains(ClassAccessFlags::MODULE)
+        {
             "".to_owned()
+        } else {
+            return Err(format_error(
+                super_offset,
+                format!("Class {} has no super class", this_class),
+            ));
         };
 
         let interfaces = self.read_interfaces(&constants)?;
-        let fields = self.read_fields(&constants)?;
-        let methods = self.read_methods(&constants)?;
+        let fields = self.read_fields(&constants, access_flags)?;
+        let methods = self.read_methods(&constants, access_flags, &version)?;
         let attributes = self.read_attributes(&constants)?;
 
+        if !self.reader.fill_buf()?.is_empty() {
+            return Err(format_error(
+                self.reader.offset(),
+                "Extra bytes after the end of the class file",
+            ));
+        }
+
         Ok(Class {
             version,
             constants,
@@ -75,15 +124,21 @@ impl<R: BufRead> ClassReader<R> {
         })
     }
 
+    /// Reads the constant pool on its own. Unlike `read_class` the references between the
+    /// constants are not checked.
     pub fn read_constant_pool(&mut self) -> Result<ConstantPool> {
-        Ok(self.read_constants()?)
+        Ok(self.read_constants()?.0)
     }
 
-    fn read_fields(&mut self, constants: &ConstantPool) -> Result<Vec<FieldInfo>> {
+    fn read_fields(
+        &mut self,
+        constants: &ConstantPool,
+        class_flags: ClassAccessFlags,
+    ) -> Result<Vec<FieldInfo>> {
         let len = self.reader.read_u2()?;
         let mut fields = Vec::with_capacity(len as usize);
         for _ in 0..len {
-            fields.push(self.read_field(constants)?);
+            fields.push(self.read_field(constants, class_flags)?);
         }
         Ok(fields)
     }
@@ -93,10 +148,26 @@ impl<R: BufRead> ClassReader<R> {
         Ok(attribute_reader.read_attributes()?)
     }
 
-    fn read_field(&mut self, constants: &ConstantPool) -> Result<FieldInfo> {
-        let access_flags = FieldAccessFlags::from_bits(self.reader.read_u2()?).unwrap();
-        let name = constants.get_utf8(self.reader.read_u2()?)?.to_owned();
-        let descriptor = constants.get_utf8(self.reader.read_u2()?)?.to_owned();
+    fn read_field(
+        &mut self,
+        constants: &ConstantPool,
+        class_flags: ClassAccessFlags,
+    ) -> Result<FieldInfo> {
+        let flags_offset = self.reader.offset();
+        let access_flags = FieldAccessFlags::from_bits_truncate(self.reader.read_u2()?);
+        let name = self.lookup(|index| constants.get_utf8(index))?;
+        let descriptor_offset = self.reader.offset();
+        let descriptor = self.lookup(|index| constants.get_utf8(index))?;
+        at_offset(FieldType::try_from(descriptor.as_str()), descriptor_offset)?;
+        if let Err(reason) = check_field_flags(access_flags, class_flags) {
+            let member = format!("field {}", name);
+            return Err(invalid_flags(
+                flags_offset,
+                access_flags.bits(),
+                &member,
+                reason,
+            ));
+        }
         let attributes = self.read_attributes(constants)?;
 
         Ok(FieldInfo {
@@ -107,19 +178,45 @@ impl<R: BufRead> ClassReader<R> {
         })
     }
 
-    fn read_methods(&mut self, constants: &ConstantPool) -> Result<Vec<Rc<MethodInfo>>> {
+    fn read_methods(
+        &mut self,
+        constants: &ConstantPool,
+        class_flags: ClassAccessFlags,
+        version: &Version,
+    ) -> Result<Vec<Rc<MethodInfo>>> {
         let len = self.reader.read_u2()?;
         let mut fields = Vec::with_capacity(len as usize);
         for _ in 0..len {
-            fields.push(Rc::new(self.read_method(constants)?));
+            fields.push(Rc::new(self.read_method(
+                constants,
+                class_flags,
+                version,
+            )?));
         }
         Ok(fields)
     }
 
-    fn read_method(&mut self, constants: &ConstantPool) -> Result<MethodInfo> {
-        let access_flags = MethodAccessFlags::from_bits(self.reader.read_u2()?).unwrap();
-        let name = constants.get_utf8(self.reader.read_u2()?)?.to_owned();
-        let descriptor = constants.get_utf8(self.reader.read_u2()?)?.try_into()?;
+    fn read_method(
+        &mut self,
+        constants: &ConstantPool,
+        class_flags: ClassAccessFlags,
+        version: &Version,
+    ) -> Result<MethodInfo> {
+        let flags_offset = self.reader.offset();
+        let access_flags = MethodAccessFlags::from_bits_truncate(self.reader.read_u2()?);
+        let name = self.lookup(|index| constants.get_utf8(index))?;
+        let descriptor_offset = self.reader.offset();
+        let descriptor = self.lookup(|index| constants.get_utf8(index))?;
+        let descriptor = at_offset(descriptor.as_str().try_into(), descriptor_offset)?;
+        if let Err(reason) = check_method_flags(access_flags, &name, class_flags, version) {
+            let member = format!("method {}", name);
+            return Err(invalid_flags(
+                flags_offset,
+                access_flags.bits(),
+                &member,
+                reason,
+            ));
+        }
         let attributes = self.read_attributes(constants)?;
 
         Ok(MethodInfo {
@@ -145,9 +242,20 @@ impl<R: BufRead> ClassReader<R> {
     }
 
     fn read_version(&mut self) -> Result<Version> {
+        let offset = self.reader.offset();
         let minor = self.reader.read_u2()?;
         let major = self.reader.read_u2()?;
 
+        // Since Java 12 the minor version is only used to mark classes using preview features.
+        let supported = (MIN_MAJOR_VERSION..=MAX_MAJOR_VERSION).contains(&major)
+            && (major < 56 || minor == 0 || minor == 0xFFFF);
+        if !supported {
+            return Err(format_error(
+                offset,
+                format!("Unsupported class file version {}.{}", major, minor),
+            ));
+        }
+
         Ok(Version { minor, major })
     }
 
@@ -156,21 +264,22 @@ impl<R: BufRead> ClassReader<R> {
         let mut indexes = Vec::with_capacity(len as usize);
 
         for _ in 0..len {
-            indexes.push(
-                constants
-                    .get_class_info_name(self.reader.read_u2()?)?
-                    .to_owned(),
-            )
+            indexes.push(self.lookup(|index| constants.get_class_info_name(index))?)
         }
         Ok(indexes)
     }
 
-    fn read_constants(&mut self) -> Result<ConstantPool> {
+    /// Reads the constants and the offsets they were read from, indexed by their slot in the
+    /// pool.
+    fn read_constants(&mut self) -> Result<(ConstantPool, Vec<usize>)> {
+        let count_offset = self.reader.offset();
         let entries = self.reader.read_u2()?;
         let mut pool = ConstantPool::new(entries);
+        let mut offsets = Vec::with_capacity(entries as usize);
 
         let mut i = entries;
         while i > 1 {
+            let offset = self.reader.offset();
             let constant = self.read_constant()?;
 
             let size = match constant {
@@ -180,9 +289,18 @@ impl<R: BufRead> ClassReader<R> {
             i -= size;
 
             pool.add(constant);
+            offsets.resize(offsets.len() + size as usize, offset);
+        }
+
+        // A long or double can not take up the last slot on its own.
+        if i != 1 {
+            return Err(format_error(
+                count_offset,
+                format!("Invalid constant pool count {}", entries),
+            ));
         }
 
-        Ok(pool)
+        Ok((pool, offsets))
     }
 
     fn read_constant(&mut self) -> Result<Constant> {
@@ -206,27 +324,35 @@ impl<R: BufRead> ClassReader<R> {
             18 => self.read_invokedynamic_constant(),
             19 => self.read_module_constant(),
             20 => self.read_package_constant(),
-            _ => panic!("Unknown constant tag {}", tag),
+            _ => Err(format_error(
+                self.reader.offset() - 1,
+                format!("Unknown constant tag {}", tag),
+            )),
         }
     }
 
     fn read_access_flags(&mut self) -> Result<ClassAccessFlags> {
         let flags = self.reader.read_u2()?;
-        Ok(ClassAccessFlags::from_bits(flags).unwrap())
+        Ok(ClassAccessFlags::from_bits_truncate(flags))
+    }
+
+    /// Reads a constant pool index and looks it up with `get`, reporting a bad index at the
+    /// offset it was read from.
+    fn lookup<'c>(&mut self, get: impl FnOnce(u16) -> Result<&'c str>) -> Result<String> {
+        let offset = self.reader.offset();
+        let index = self.reader.read_u2()?;
+        Ok(at_offset(get(index), offset)?.to_owned())
     }
 
-    // TODO implement according to spec
-    // Naive implementation that do not correspond to spec about modified utf8
     fn read_utf8_constant(&mut self) -> Result<Constant> {
         let len = self.reader.read_u2()?;
-
-        let mut bytes = Vec::with_capacity(len as usize);
-        unsafe {
-            bytes.set_len(len as usize);
-        }
-        self.reader.read_exact(&mut bytes)?;
-
-        Ok(Utf8(String::from_utf8_lossy(&bytes).to_owned().to_string()))
+        let offset = self.reader.offset();
+        let bytes = self.reader.read_bytes(len as usize)?;
+        let units = mutf8::decode_utf16_at(&bytes, offset)?;
+        Ok(match String::from_utf16(&units) {
+            Ok(string) => Utf8(string),
+            Err(_) => Utf16(units),
+        })
     }
 
     fn read_int_constant(&mut self) -> Result<Constant> {
@@ -298,7 +424,12 @@ impl<R: BufRead> ClassReader<R> {
             7 => InvokeSpecial,
             8 => NewInvokeSpecial,
             9 => InvokeInterface,
-            x => panic!("Unknown method handle kind: {}", x),
+            x => {
+                return Err(format_error(
+                    self.reader.offset() - 1,
+                    format!("Invalid method handle kind {}", x),
+                ))
+            }
         };
         let reference_index = self.reader.read_u2()?;
         Ok(MethodHandle(reference_kind, reference_index))
@@ -335,6 +466,397 @@ impl<R: BufRead> ClassReader<R> {
     }
 }
 
+/// Checks that the constants refer to constants of the right type.
+fn check_constants(constants: &ConstantPool, offsets: &[usize]) -> Result<()> {
+    let is_utf8 = |c: &Constant| matches!(c, Utf8(_));
+    let is_string = |c: &Constant| matches!(c, Utf8(_) | Utf16(_));
+    let is_class = |c: &Constant| matches!(c, ClassRef(_));
+    let is_name_and_type = |c: &Constant| matches!(c, NameAndType(..));
+    let is_field = |c: &Constant| matches!(c, FieldRef(..));
+    let is_method = |c: &Constant| matches!(c, MethodRef(..));
+    let is_any_method = |c: &Constant| matches!(c, MethodRef(..) | InterfaceMethodRef(..));
+    let is_interface_method = |c: &Constant| matches!(c, InterfaceMethodRef(..));
+
+    for (slot, offset) in offsets.iter().enumerate() {
+        let index = slot as u16 + 1;
+        let expect = |reference: u16, valid: &dyn Fn(&Constant) -> bool, what: &str| match constants
+            .get(reference)
+        {
+            Ok(constant) if valid(constant) => Ok(()),
+            _ => Err(format_error(
+                *offset,
+                format!(
+                    "Constant {} refers to {} which is not a {}",
+                    index, reference, what
+                ),
+            )),
+        };
+
+        match constants.get(index) {
+            Ok(ClassRef(name)) | Ok(MethodType(name)) | Ok(Module(name)) | Ok(Package(name)) => {
+                expect(*name, &is_utf8, "Utf8")?
+            }
+            // Names are used as Rust strings, only string literals may hold unpaired surrogates.
+            Ok(StringRef(string)) => expect(*string, &is_string, "Utf8")?,
+            Ok(FieldRef(class, name_and_type))
+            | Ok(MethodRef(class, name_and_type))
+            | Ok(InterfaceMethodRef(class, name_and_type)) => {
+                expect(*class, &is_class, "Class")?;
+                expect(*name_and_type, &is_name_and_type, "NameAndType")?;
+            }
+            Ok(NameAndType(name, descriptor)) => {
+                expect(*name, &is_utf8, "Utf8")?;
+                expect(*descriptor, &is_utf8, "Utf8")?;
+            }
+            Ok(MethodHandle(kind, reference)) => match kind {
+                GetField | GetStatic | PutField | PutStatic => {
+                    expect(*reference, &is_field, "Fieldref")?
+                }
+                InvokeVirtual | NewInvokeSpecial => expect(*reference, &is_method, "Methodref")?,
+                InvokeStatic | InvokeSpecial => expect(
+                    *reference,
+                    &is_any_method,
+                    "Methodref or InterfaceMethodref",
+                )?,
+                InvokeInterface => expect(*reference, &is_interface_method, "InterfaceMethodref")?,
+            },
+            Ok(Dynamic(_, name_and_type)) | Ok(InvokeDynamic(_, name_and_type)) => {
+                expect(*name_and_type, &is_name_and_type, "NameAndType")?
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn invalid_flags(offset: usize, flags: u16, member: &str, reason: &str) -> Error {
+    format_error(
+        offset,
+        format!(
+            "Invalid access flags 0x{:04x} on {}, {}",
+            flags, member, reason
+        ),
+    )
+}
+
+fn check_class_flags(flags: ClassAccessFlags) -> std::result::Result<(), &'static str> {
+    if flags.contains(ClassAccessFlags::MODULE) {
+        if flags != ClassAccessFlags::MODULE {
+            return Err("a module can have no other flags");
+        }
+    } else if flags.contains(ClassAccessFlags::INTERFACE) {
+        if !flags.contains(ClassAccessFlags::ABSTRACT) {
+            return Err("an interface must be abstract");
+        }
+        if flags
+            .intersects(ClassAccessFlags::FINAL | ClassAccessFlags::SUPER | ClassAccessFlags::ENUM)
+        {
+            return Err("an interface can not be final, super or an enum");
+        }
+    } else if flags.contains(ClassAccessFlags::ANNOTATION) {
+        return Err("an annotation must be an interface");
+    } else if flags.contains(ClassAccessFlags::FINAL | ClassAccessFlags::ABSTRACT) {
+        return Err("a class can not be both final and abstract");
+    }
+    Ok(())
+}
+
+fn check_field_flags(
+    flags: FieldAccessFlags,
+    class_flags: ClassAccessFlags,
+) -> std::result::Result<(), &'static str> {
+    let access = FieldAccessFlags::ACC_PUBLIC
+        | FieldAccessFlags::ACC_PRIVATE
+        | FieldAccessFlags::ACC_PROTECTED;
+    if (flags & access).bits().count_ones() > 1 {
+        return Err("at most one of public, private and protected can be set");
+    }
+    if flags.contains(FieldAccessFlags::ACC_FINAL | FieldAccessFlags::ACC_VOLATILE) {
+        return Err("a field can not be both final and volatile");
+    }
+    if class_flags.contains(ClassAccessFlags::INTERFACE) {
+        let required = FieldAccessFlags::ACC_PUBLIC
+            | FieldAccessFlags::ACC_STATIC
+            | FieldAccessFlags::ACC_FINAL;
+        if flags - FieldAccessFlags::ACC_SYNTHETIC != required {
+            return Err("an interface field must be public, static and final");
+        }
+    }
+    Ok(())
+}
+
+fn check_method_flags(
+    flags: MethodAccessFlags,
+    name: &str,
+    class_flags: ClassAccessFlags,
+    version: &Version,
+) -> std::result::Result<(), &'static str> {
+    // The flags of class initializers are ignored, apart from static.
+    if name == "<clinit>" {
+        return Ok(());
+    }
+
+    let access = MethodAccessFlags::ACC_PUBLIC
+        | MethodAccessFlags::ACC_PRIVATE
+        | MethodAccessFlags::ACC_PROTECTED;
+    if (flags & access).bits().count_ones() > 1 {
+        return Err("at most one of public, private and protected can be set");
+    }
+
+    if class_flags.contains(ClassAccessFlags::INTERFACE) {
+        // Java 8 added static, private and default methods to interfaces.
+        if version.major < 52 {
+            let required = MethodAccessFlags::ACC_PUBLIC | MethodAccessFlags::ACC_ABSTRACT;
+            if !flags.contains(required) {
+                return Err("an interface method must be public and abstract");
+            }
+        } else {
+            if !flags.intersects(MethodAccessFlags::ACC_PUBLIC | MethodAccessFlags::ACC_PRIVATE) {
+                return Err("an interface method must be public or private");
+            }
+            if flags.intersects(
+                MethodAccessFlags::ACC_PROTECTED
+                    | MethodAccessFlags::ACC_FINAL
+                    | MethodAccessFlags::ACC_SYNCHRONIZED
+                    | MethodAccessFlags::ACC_NATIVE,
+            ) {
+                return Err(
+                    "an interface method can not be protected, final, synchronized or native",
+                );
+            }
+        }
+    }
+
+    if flags.contains(MethodAccessFlags::ACC_ABSTRACT)
+        && flags.intersects(
+            MethodAccessFlags::ACC_PRIVATE
+                | MethodAccessFlags::ACC_STATIC
+                | MethodAccessFlags::ACC_FINAL
+                | MethodAccessFlags::ACC_SYNCHRONIZED
+                | MethodAccessFlags::ACC_NATIVE,
+        )
+    {
+        return Err("an abstract method can not be private, static, final, synchronized or native");
+    }
+
+    if name == "<init>" {
+        let allowed = access
+            | MethodAccessFlags::ACC_VARARGS
+            | MethodAccessFlags::ACC_STRICT
+            | MethodAccessFlags::ACC_SYNTHETIC;
+        if !allowed.contains(flags) {
+            return Err("a constructor can only have access, varargs, strict and synthetic flags");
+        }
+    }
+    Ok(())
+}
+
+pub struct ClassWriter<W: Write> {
+    writer: W,
+    constants: ConstantPool,
+}
+
+impl ClassWriter<BufWriter<File>> {
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = File::create(path)?;
+        Ok(ClassWriter::new(BufWriter::new(file)))
+    }
+
+    pub fn to_file<P: AsRef<Path>>(class: &Class, path: P) -> Result<()> {
+        let writer = ClassWriter::create(path)?;
+        writer.write_class(class)
+    }
+}
+
+impl<W: Write> ClassWriter<W> {
+    pub fn new(writer: W) -> Self {
+        ClassWriter {
+            writer,
+            constants: ConstantPool::new(0),
+        }
+    }
+
+    /// Writes the class file. Names are written as the index of their first entry in the constant
+    /// pool of the class, so an unmodified class is written back byte for byte. Entries missing
+    /// from the pool are added to its end.
+    pub fn write_class(mut self, class: &Class) -> Result<()> {
+        // The constant pool comes first but may grow while writing the rest of the class.
+        let mut body = ClassWriter {
+            writer: Vec::new(),
+            constants: class.constants.clone(),
+        };
+        body.write_body(class)?;
+
+        self.writer.write_all(SIGNATURE)?;
+        self.writer.write_u2(class.version.minor)?;
+        self.writer.write_u2(class.version.major)?;
+        self.write_constants(&body.constants)?;
+        self.writer.write_all(&body.writer)?;
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    fn write_body(&mut self, class: &Class) -> Result<()> {
+        self.writer.write_u2(class.access_flags.bits())?;
+        self.write_class_name(&class.this_class)?;
+
+        // Object class have no super class.
+        if class.super_class.is_empty() {
+            self.writer.write_u2(0)?;
+        } else {
+            self.write_class_name(&class.super_class)?;
+        }
+
+        self.writer.write_u2(class.interfaces.len() as u16)?;
+        for interface in &class.interfaces {
+            self.write_class_name(interface)?;
+        }
+
+        self.writer.write_u2(class.fields.len() as u16)?;
+        for field in &class.fields {
+            self.writer.write_u2(field.access_flags.bits())?;
+            self.write_member(&field.name, &field.descriptor, &field.attributes)?;
+        }
+
+        self.writer.write_u2(class.methods.len() as u16)?;
+        for method in &class.methods {
+            self.writer.write_u2(method.access_flags.bits())?;
+            let descriptor = method.descriptor.to_string();
+            self.write_member(&method.name, &descriptor, &method.attributes)?;
+        }
+
+        self.write_attributes(&class.attributes)
+    }
+
+    fn write_member(
+        &mut self,
+        name: &str,
+        descriptor: &str,
+        attributes: &[Attribute],
+    ) -> Result<()> {
+        let name_index = self.constants.find_or_add_utf8(name)?;
+        let descriptor_index = self.constants.find_or_add_utf8(descriptor)?;
+        self.writer.write_u2(name_index)?;
+        self.writer.write_u2(descriptor_index)?;
+        self.write_attributes(attributes)
+    }
+
+    fn write_attributes(&mut self, attributes: &[Attribute]) -> Result<()> {
+        let mut attribute_writer = AttributeWriter::new(&mut self.writer, &mut self.constants);
+        attribute_writer.write_attributes(attributes)
+    }
+
+    fn write_class_name(&mut self, name: &str) -> Result<()> {
+        let index = self.constants.find_or_add_class(name)?;
+        self.writer.write_u2(index)
+    }
+
+    fn write_constants(&mut self, constants: &ConstantPool) -> Result<()> {
+        if constants.len() > MAX_LEN {
+            return Err(Error::new(
+                ParseError,
+                Some(format!("Too many constants: {}.", constants.len())),
+            ));
+        }
+
+        self.writer.write_u2(constants.len() as u16 + 1)?;
+        for constant in constants.iter() {
+            self.write_constant(constant)?;
+        }
+        Ok(())
+    }
+
+    fn write_utf8_constant(&mut self, bytes: &[u8]) -> Result<()> {
+        if bytes.len() > u16::MAX as usize {
+            return Err(Error::new(
+                ParseError,
+                Some(format!(
+                    "String constant of {} bytes is too long.",
+                    bytes.len()
+                )),
+            ));
+        }
+        self.writer.write_u1(1)?;
+        self.writer.write_u2(bytes.len() as u16)?;
+        self.writer.write_all(bytes)?;
+        Ok(())
+    }
+
+    fn write_constant(&mut self, constant: &Constant) -> Result<()> {
+        match constant {
+            Utf8(string) => self.write_utf8_constant(&mutf8::encode(string))?,
+            Utf16(units) => {
+                self.write_utf8_constant(&mutf8::encode_utf16(units.iter().copied()))?
+            }
+            Integer(value) => {
+                self.writer.write_u1(3)?;
+                self.writer.write_all(&value.to_be_bytes())?;
+            }
+            Float(value) => {
+                self.writer.write_u1(4)?;
+                self.writer.write_all(&value.to_be_bytes())?;
+            }
+            Long(value) => {
+                self.writer.write_u1(5)?;
+                self.writer.write_all(&value.to_be_bytes())?;
+            }
+            Double(value) => {
+                self.writer.write_u1(6)?;
+                self.writer.write_all(&value.to_be_bytes())?;
+            }
+            ClassRef(name_index) => self.write_indexes(7, &[*name_index])?,
+            StringRef(string_index) => self.write_indexes(8, &[*string_index])?,
+            FieldRef(class_index, name_and_type_index) => {
+                self.write_indexes(9, &[*class_index, *name_and_type_index])?
+            }
+            MethodRef(class_index, name_and_type_index) => {
+                self.write_indexes(10, &[*class_index, *name_and_type_index])?
+            }
+            InterfaceMethodRef(class_index, name_and_type_index) => {
+                self.write_indexes(11, &[*class_index, *name_and_type_index])?
+            }
+            NameAndType(name_index, descriptor_index) => {
+                self.write_indexes(12, &[*name_index, *descriptor_index])?
+            }
+            MethodHandle(kind, reference_index) => {
+                let reference_kind = match kind {
+                    GetField => 1,
+                    GetStatic => 2,
+                    PutField => 3,
+                    PutStatic => 4,
+                    InvokeVirtual => 5,
+                    InvokeStatic => 6,
+                    InvokeSpecial => 7,
+                    NewInvokeSpecial => 8,
+                    InvokeInterface => 9,
+                };
+                self.writer.write_u1(15)?;
+                self.writer.write_u1(reference_kind)?;
+                self.writer.write_u2(*reference_index)?;
+            }
+            MethodType(descriptor_index) => self.write_indexes(16, &[*descriptor_index])?,
+            Dynamic(bootstrap_method_attr_index, name_and_type_index) => {
+                self.write_indexes(17, &[*bootstrap_method_attr_index, *name_and_type_index])?
+            }
+            InvokeDynamic(bootstrap_method_attr_index, name_and_type_index) => {
+                self.write_indexes(18, &[*bootstrap_method_attr_index, *name_and_type_index])?
+            }
+            Module(name_index) => self.write_indexes(19, &[*name_index])?,
+            Package(name_index) => self.write_indexes(20, &[*name_index])?,
+            NOOP => (),
+        }
+        Ok(())
+    }
+
+    fn write_indexes(&mut self, tag: u8, indexes: &[u16]) -> Result<()> {
+        self.writer.write_u1(tag)?;
+        for index in indexes {
+            self.writer.write_u2(*index)?;
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::class::attribute::Attribute;
@@ -350,6 +872,11 @@ mod test {
     use std::convert::TryInto;
     use std::rc::Rc;
 
+    const JAVA_8: Version = Version {
+        minor: 0,
+        major: 52,
+    };
+
     #[test]
     fn read_signature() {
         let data: Vec<u8> = vec![0xCA, 0xFE, 0xBA, 0xBE];
@@ -405,23 +932,83 @@ mod test {
         let mut reader = ClassReader::new(data.as_slice());
         let pool = reader.read_constant_pool().unwrap();
 
-        assert_eq!(pool.get(1), &Utf8("<init>".to_owned()));
-        assert_eq!(pool.get(2), &Integer(123));
-        assert_eq!(pool.get(3), &Float(123.45));
-        assert_eq!(pool.get(4), &Long(1234567));
-        assert_eq!(pool.get(6), &Double(123.4567));
-        assert_eq!(pool.get(8), &ClassRef(13));
-        assert_eq!(pool.get(9), &StringRef(256));
-        assert_eq!(pool.get(10), &FieldRef(1, 2));
-        assert_eq!(pool.get(11), &MethodRef(3, 12));
-        assert_eq!(pool.get(12), &InterfaceMethodRef(3, 12));
-        assert_eq!(pool.get(13), &NameAndType(4, 5));
-        assert_eq!(pool.get(14), &MethodHandle(GetField, 5));
-        assert_eq!(pool.get(15), &MethodType(1));
-        assert_eq!(pool.get(16), &Dynamic(1, 5));
-        assert_eq!(pool.get(17), &InvokeDynamic(1, 5));
-        assert_eq!(pool.get(18), &Module(1));
-        assert_eq!(pool.get(19), &Package(2));
+        assert_eq!(pool.get(1).unwrap(), &Utf8("<init>".to_owned()));
+        assert_eq!(pool.get(2).unwrap(), &Integer(123));
+        assert_eq!(pool.get(3).unwrap(), &Float(123.45));
+        assert_eq!(pool.get(4).unwrap(), &Long(1234567));
+        assert_eq!(pool.get(6).unwrap(), &Double(123.4567));
+        assert_eq!(pool.get(8).unwrap(), &ClassRef(13));
+        assert_eq!(pool.get(9).unwrap(), &StringRef(256));
+        assert_eq!(pool.get(10).unwrap(), &FieldRef(1, 2));
+        assert_eq!(pool.get(11).unwrap(), &MethodRef(3, 12));
+        assert_eq!(pool.get(12).unwrap(), &InterfaceMethodRef(3, 12));
+        assert_eq!(pool.get(13).unwrap(), &NameAndType(4, 5));
+        assert_eq!(pool.get(14).unwrap(), &MethodHandle(GetField, 5));
+        assert_eq!(pool.get(15).unwrap(), &MethodType(1));
+        assert_eq!(pool.get(16).unwrap(), &Dynamic(1, 5));
+        assert_eq!(pool.get(17).unwrap(), &InvokeDynamic(1, 5));
+        assert_eq!(pool.get(18).unwrap(), &Module(1));
+        assert_eq!(pool.get(19).unwrap(), &Package(2));
+    }
+
+    #[test]
+    fn read_modified_utf8_constant() {
+        let data: Vec<u8> = vec![
+            0x00, 0x03, // Pool length
+            0x01, 0x00, 0x05, 0x61, 0xC0, 0x80, 0xC3, 0xA5, // Utf8 "a\0å"
+            0x01, 0x00, 0x06, 0xED, 0xA0, 0xBD, 0xED, 0xB8, 0x80, // Utf8 surrogate pair
+        ];
+
+        let mut reader = ClassReader::new(data.as_slice());
+        let pool = reader.read_constant_pool().unwrap();
+
+        assert_eq!(pool.get(1).unwrap(), &Utf8("a\0å".to_owned()));
+        assert_eq!(pool.get(2).unwrap(), &Utf8("😀".to_owned()));
+    }
+
+    #[test]
+    fn read_invalid_utf8_constant() {
+        let data: Vec<u8> = vec![
+            0x00, 0x02, // Pool length
+            0x01, 0x00, 0x02, 0x61, 0x00, // Utf8 with null byte
+        ];
+
+        let mut reader = ClassReader::new(data.as_slice());
+        let error = reader.read_constant_pool().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid modified UTF-8 byte 0x00 at byte 6."
+        );
+    }
+
+    #[test]
+    fn read_invalid_method_handle_kind() {
+        let data: Vec<u8> = vec![
+            0x00, 0x02, // Pool length
+            0x0f, 0x0a, 0x00, 0x01, // MethodHandle of kind 10
+        ];
+
+        let mut reader = ClassReader::new(data.as_slice());
+        let error = reader.read_constant_pool().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid method handle kind 10 at byte 3."
+        );
+    }
+
+    #[test]
+    fn read_invalid_constant_pool_count() {
+        let data: Vec<u8> = vec![
+            0x00, 0x02, // Pool length
+            0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, // Long
+        ];
+
+        let mut reader = ClassReader::new(data.as_slice());
+        let error = reader.read_constant_pool().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid constant pool count 2 at byte 0."
+        );
     }
 
     #[test]
@@ -453,7 +1040,7 @@ mod test {
     fn read_fields() {
         let mut constants = ConstantPool::new(2);
         constants.add(Utf8("field_name".to_owned()));
-        constants.add(Utf8("description".to_owned()));
+        constants.add(Utf8("J".to_owned()));
         constants.add(Utf8("attribute".to_owned()));
 
         let data: Vec<u8> = vec![
@@ -467,13 +1054,15 @@ mod test {
         ];
         let mut reader = ClassReader::new(data.as_slice());
 
-        let indexes = reader.read_fields(&constants).unwrap();
+        let indexes = reader
+            .read_fields(&constants, ClassAccessFlags::empty())
+            .unwrap();
         assert_eq!(
             indexes,
             vec![FieldInfo {
                 access_flags: FieldAccessFlags::ACC_PRIVATE,
                 name: "field_name".to_owned(),
-                descriptor: "description".to_owned(),
+                descriptor: "J".to_owned(),
                 attributes: vec![Attribute {
                     name: "attribute".to_owned(),
                     data: Unknown(vec![0x01, 0x02])
@@ -500,7 +1089,9 @@ mod test {
         ];
         let mut reader = ClassReader::new(data.as_slice());
 
-        let indexes = reader.read_methods(&constants).unwrap();
+        let indexes = reader
+            .read_methods(&constants, ClassAccessFlags::empty(), &JAVA_8)
+            .unwrap();
         assert_eq!(
             indexes,
             vec![Rc::new(MethodInfo {
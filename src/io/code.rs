@@ -2,35 +2,48 @@ use crate::binary::bytes_to_i32;
 use crate::class::code::Opcode::*;
 use crate::class::code::{Instruction, Opcode};
 use crate::error::Result;
-use crate::io::ReadBytesExt;
-use std::io::BufRead;
+use crate::io::WriteBytesExt;
+use crate::io::{format_error, Offset, ReadBytesExt};
+use std::io::{BufRead, Write};
 
-pub struct CodeReader<'r, R: BufRead> {
+pub struct CodeReader<'r, R: BufRead + Offset> {
     reader: &'r mut R,
 }
 
-impl<'r, R: BufRead> CodeReader<'r, R> {
+impl<'r, R: BufRead + Offset> CodeReader<'r, R> {
     pub fn new(reader: &'r mut R) -> CodeReader<'r, R> {
         CodeReader { reader }
     }
 
     pub fn read_code(&mut self) -> Result<Vec<Instruction>> {
+        let length_offset = self.reader.offset();
         let byte_count = self.reader.read_u4()?;
+        if byte_count == 0 || byte_count >= 65536 {
+            return Err(format_error(
+                length_offset,
+                format!("Invalid code length {}", byte_count),
+            ));
+        }
+
         let mut byte_pos = 0;
         let mut code = Vec::with_capacity(byte_count as usize);
-
-        loop {
+        while byte_pos < byte_count {
+            let offset = self.reader.offset();
             let (opcode, argc) = self.read_opcode()?;
             let (mut instructions, byte_len) = match opcode {
                 LookupSwitch => self.read_lookup_switch(opcode, byte_pos + 1)?,
                 TableSwitch => self.read_table_switch(opcode, byte_pos + 1)?,
+                Wide => self.read_wide()?,
                 _ => self.read_static_width_instruction(opcode, argc)?,
             };
             code.append(&mut instructions);
 
             byte_pos += byte_len;
-            if byte_count == byte_pos {
-                break;
+            if byte_pos > byte_count {
+                return Err(format_error(
+                    offset,
+                    "Instruction runs past the end of the code",
+                ));
             }
         }
         Ok(code)
@@ -65,9 +78,16 @@ impl<'r, R: BufRead> CodeReader<'r, R> {
         self.reader.read_bytes(pad as usize)?; // Skip padding
 
         let mut default_jump_bytes = self.reader.read_bytes(4)?;
+        let num_pairs_offset = self.reader.offset();
         let mut num_pairs_bytes = self.reader.read_bytes(4)?;
 
         let num_pairs = bytes_to_i32(&num_pairs_bytes[..]);
+        if num_pairs < 0 {
+            return Err(format_error(
+                num_pairs_offset,
+                format!("Negative lookupswitch pair count {}", num_pairs),
+            ));
+        }
 
         let mut match_offset_pairs = self.reader.read_bytes(num_pairs as usize * 8)?;
 
@@ -81,7 +101,7 @@ impl<'r, R: BufRead> CodeReader<'r, R> {
         code.push(Instruction::new_with_pad(opcode, operands, pad as u8));
 
         // Must add spacers to keep the indexes correct.
-        for _ in 0..byte_len {
+        for _ in 1..byte_len {
             code.push(Instruction::operation_spacer());
         }
 
@@ -99,13 +119,20 @@ impl<'r, R: BufRead> CodeReader<'r, R> {
         self.reader.read_bytes(pad as usize)?; // Skip padding
 
         let mut default_jump_bytes = self.reader.read_bytes(4)?;
+        let low_offset = self.reader.offset();
         let mut low_byte_bytes = self.reader.read_bytes(4)?;
         let mut high_byte_bytes = self.reader.read_bytes(4)?;
 
         let high = bytes_to_i32(&high_byte_bytes[..]);
         let low = bytes_to_i32(&low_byte_bytes[..]);
+        if low > high {
+            return Err(format_error(
+                low_offset,
+                format!("Invalid tableswitch range {} to {}", low, high),
+            ));
+        }
 
-        let len = high - low + 1;
+        let len = high as i64 - low as i64 + 1;
         let mut jump_offset_bytes = self.reader.read_bytes(len as usize * 4)?;
 
         operands.append(&mut default_jump_bytes);
@@ -119,7 +146,40 @@ impl<'r, R: BufRead> CodeReader<'r, R> {
         code.push(Instruction::new_with_pad(opcode, operands, pad as u8));
 
         // Must add spacers to keep the indexes correct.
-        for _ in 0..byte_len {
+        for _ in 1..byte_len {
+            code.push(Instruction::operation_spacer());
+        }
+
+        Ok((code, byte_len))
+    }
+
+    /// Reads a `wide` instruction. The modified opcode is followed by its two byte index, and for
+    /// `iinc` also the two byte constant.
+    fn read_wide(&mut self) -> Result<(Vec<Instruction>, u32)> {
+        let (modified, _) = self.read_opcode()?;
+        let argc = match modified {
+            IInc => 4,
+            ILoad | LLoad | FLoad | DLoad | ALoad | IStore | LStore | FStore | DStore | AStore
+            | Ret => 2,
+            _ => {
+                return Err(format_error(
+                    self.reader.offset() - 2,
+                    format!(
+                        "Invalid opcode 0x{:02x} modified by wide",
+                        opcode_byte(&modified)?
+                    ),
+                ))
+            }
+        };
+
+        let operands = self.reader.read_bytes(argc)?;
+        let instruction = Instruction::wide(modified, operands);
+        let byte_len = instruction.size() as u32;
+
+        let mut code = vec![instruction];
+
+        // Must add spacers to keep the indexes correct.
+        for _ in 1..byte_len {
             code.push(Instruction::operation_spacer());
         }
 
@@ -127,220 +187,64 @@ impl<'r, R: BufRead> CodeReader<'r, R> {
     }
 
     fn read_opcode(&mut self) -> Result<(Opcode, u8)> {
-        Ok(match self.reader.read_u1()? {
-            0x32 => (AaLoad, 0),
-            0x53 => (AaStore, 0),
-            0x01 => (AConstNull, 0),
-            0x19 => (ALoad, 1),
-            0x2a => (ALoad0, 0),
-            0x2b => (ALoad1, 0),
-            0x2c => (ALoad2, 0),
-            0x2d => (ALoad3, 0),
-            0xbd => (ANewArray, 2),
-            0xb0 => (AReturn, 0),
-            0xbe => (ArrayLength, 0),
-            0x3a => (AStore, 1),
-            0x4b => (AStore0, 0),
-            0x4c => (AStore1, 0),
-            0x4d => (AStore2, 0),
-            0x4e => (AStore3, 0),
-            0xbf => (AThrow, 0),
-            0x33 => (BaLoad, 0),
-            0x54 => (BaStore, 0),
-            0x10 => (BiPush, 1),
-            0x34 => (CaLoad, 0),
-            0x55 => (CaStore, 0),
-            0xc0 => (CheckCast, 2),
-            0x90 => (D2f, 0),
-            0x8e => (D2i, 0),
-            0x8f => (D2l, 0),
-            0x63 => (DAdd, 0),
-            0x31 => (DaLoad, 0),
-            0x52 => (DaStore, 0),
-            0x98 => (DCmpg, 0),
-            0x97 => (DCmpl, 0),
-            0x0e => (DConst0, 0),
-            0x0f => (DConst1, 0),
-            0x6f => (DDiv, 0),
-            0x18 => (DLoad, 1),
-            0x26 => (DLoad0, 0),
-            0x27 => (DLoad1, 0),
-            0x28 => (DLoad2, 0),
-            0x29 => (DLoad3, 0),
-            0x6b => (DMul, 0),
-            0x77 => (DNeg, 0),
-            0x73 => (DRem, 0),
-            0xaf => (DReturn, 0),
-            0x39 => (DStore, 1),
-            0x47 => (DStore0, 0),
-            0x48 => (DStore1, 0),
-            0x49 => (DStore2, 0),
-            0x4a => (DStore3, 0),
-            0x67 => (DSub, 0),
-            0x59 => (Dup, 0),
-            0x5a => (DupX1, 0),
-            0x5b => (DupX2, 0),
-            0x5c => (Dup2, 0),
-            0x5d => (Dup2X1, 0),
-            0x5e => (Dup2X2, 0),
-            0x8d => (F2d, 0),
-            0x8b => (F2i, 0),
-            0x8c => (F2l, 0),
-            0x62 => (FAdd, 0),
-            0x30 => (FaLoad, 0),
-            0x51 => (FaStore, 0),
-            0x96 => (FCmpg, 0),
-            0x95 => (FCmpl, 0),
-            0x0b => (FConst0, 0),
-            0x0c => (FConst1, 0),
-            0x0d => (FConst2, 0),
-            0x6e => (FDiv, 0),
-            0x17 => (FLoad, 1),
-            0x22 => (FLoad0, 0),
-            0x23 => (FLoad1, 0),
-            0x24 => (FLoad2, 0),
-            0x25 => (FLoad3, 0),
-            0x6a => (FMul, 0),
-            0x76 => (FNeg, 0),
-            0x72 => (FRem, 0),
-            0xae => (FReturn, 0),
-            0x38 => (FStore, 1),
-            0x43 => (FStore0, 0),
-            0x44 => (FStore1, 0),
-            0x45 => (FStore2, 0),
-            0x46 => (FStore3, 0),
-            0x66 => (FSub, 0),
-            0xb4 => (GetField, 2),
-            0xb2 => (GetStatic, 2),
-            0xa7 => (Goto, 2),
-            0xc8 => (GotoW, 4),
-            0x91 => (I2b, 0),
-            0x92 => (I2c, 0),
-            0x87 => (I2d, 0),
-            0x86 => (I2f, 0),
-            0x85 => (I2l, 0),
-            0x93 => (I2s, 0),
-            0x60 => (IAdd, 0),
-            0x2e => (IaLoad, 0),
-            0x7e => (IAnd, 0),
-            0x4f => (IaStore, 0),
-            0x02 => (IConstM1, 0),
-            0x03 => (IConst0, 0),
-            0x04 => (IConst1, 0),
-            0x05 => (IConst2, 0),
-            0x06 => (IConst3, 0),
-            0x07 => (IConst4, 0),
-            0x08 => (IConst5, 0),
-            0x6c => (IDiv, 0),
-            0xa5 => (IfAcmpEq, 2),
-            0xa6 => (IfAcmpNe, 2),
-            0x9f => (IfIcmpEq, 2),
-            0xa0 => (IfIcmpNe, 2),
-            0xa1 => (IfIcmpLt, 2),
-            0xa2 => (IfIcmpGe, 2),
-            0xa3 => (IfIcmpGt, 2),
-            0xa4 => (IfIcmpLe, 2),
-            0x99 => (IfEq, 2),
-            0x9a => (IfNe, 2),
-            0x9b => (IfLt, 2),
-            0x9c => (IfGe, 2),
-            0x9d => (IfGt, 2),
-            0x9e => (IfLe, 2),
-            0xc7 => (IfNonNull, 2),
-            0xc6 => (IfNull, 2),
-            0x84 => (IInc, 2),
-            0x15 => (ILoad, 1),
-            0x1a => (ILoad0, 0),
-            0x1b => (ILoad1, 0),
-            0x1c => (ILoad2, 0),
-            0x1d => (ILoad3, 0),
-            0x68 => (IMul, 0),
-            0x74 => (INeg, 0),
-            0xc1 => (Instanceof, 2),
-            0xba => (InvokeDynamic, 4),
-            0xb9 => (InvokeInterface, 4),
-            0xb7 => (InvokeSpecial, 2),
-            0xb8 => (InvokeStatic, 2),
-            0xb6 => (InvokeVirtual, 2),
-            0x80 => (IOr, 0),
-            0x70 => (IRem, 0),
-            0xac => (IReturn, 0),
-            0x78 => (IShl, 0),
-            0x7a => (IShr, 0),
-            0x36 => (IStore, 1),
-            0x3b => (IStore0, 0),
-            0x3c => (IStore1, 0),
-            0x3d => (IStore2, 0),
-            0x3e => (IStore3, 0),
-            0x64 => (ISub, 0),
-            0x7c => (IUshr, 0),
-            0x82 => (IXor, 0),
-            0xa8 => (Jsr, 2),
-            0xc9 => (JsrW, 4),
-            0x8a => (L2d, 0),
-            0x89 => (L2f, 0),
-            0x88 => (L2i, 0),
-            0x61 => (LAdd, 0),
-            0x2f => (LaLoad, 0),
-            0x7f => (LAnd, 0),
-            0x50 => (LaStore, 0),
-            0x94 => (LCmp, 0),
-            0x09 => (LConst0, 0),
-            0x0a => (LConst1, 0),
-            0x12 => (Ldc, 1),
-            0x13 => (LdcW, 2),
-            0x14 => (Ldc2W, 2),
-            0x6d => (LDiv, 0),
-            0x16 => (LLoad, 1),
-            0x1e => (LLoad0, 0),
-            0x1f => (LLoad1, 0),
-            0x20 => (LLoad2, 0),
-            0x21 => (LLoad3, 0),
-            0x69 => (LMul, 0),
-            0x75 => (LNeg, 0),
-            0xab => (LookupSwitch, 0), // Variable width
-            0x81 => (LOr, 0),
-            0x71 => (LRem, 0),
-            0xad => (LReturn, 0),
-            0x79 => (LShl, 0),
-            0x7b => (LShr, 0),
-            0x37 => (LStore, 1),
-            0x3f => (LStore0, 0),
-            0x40 => (LStore1, 0),
-            0x41 => (LStore2, 0),
-            0x42 => (LStore3, 0),
-            0x65 => (LSub, 0),
-            0x7d => (LUshr, 0),
-            0x83 => (LXor, 0),
-            0xc2 => (MonitorEnter, 0),
-            0xc3 => (MonitorExit, 0),
-            0xc5 => (MultiANewArray, 3),
-            0xbb => (New, 2),
-            0xbc => (NewArray, 1),
-            0x00 => (Nop, 0),
-            0x57 => (Pop, 0),
-            0x58 => (Pop2, 0),
-            0xb5 => (PutField, 2),
-            0xb3 => (PutStatic, 2),
-            0xa9 => (Ret, 1),
-            0xb1 => (Return, 0),
-            0x35 => (SaLoad, 0),
-            0x56 => (SaStore, 0),
-            0x11 => (SiPush, 2),
-            0x5f => (Swap, 0),
-            0xaa => (TableSwitch, 0),
-            // 0xc4 => (Wide, opcode specific length),
-            0xca => (BreakPoint, 0),
-            x => panic!("Unknown opcode {:x?}", x),
+        let byte = self.reader.read_u1()?;
+        Opcode::from_byte(byte).ok_or_else(|| {
+            format_error(
+                self.reader.offset() - 1,
+                format!("Unknown opcode 0x{:02x}", byte),
+            )
         })
     }
 }
 
+pub struct CodeWriter<'w, W: Write> {
+    writer: &'w mut W,
+}
+
+impl<'w, W: Write> CodeWriter<'w, W> {
+    pub fn new(writer: &'w mut W) -> CodeWriter<'w, W> {
+        CodeWriter { writer }
+    }
+
+    /// Writes the code length followed by the instructions. Operation spacers are dropped and the
+    /// padding of switches is recomputed from their position.
+    pub fn write_code(&mut self, instructions: &[Instruction]) -> Result<()> {
+        let mut bytes = Vec::with_capacity(instructions.len());
+
+        for instruction in instructions {
+            if instruction.opcode == OperationSpacer {
+                continue;
+            }
+
+            bytes.push(opcode_byte(&instruction.opcode)?);
+            if let Some(modified) = &instruction.modified {
+                bytes.push(opcode_byte(modified)?);
+            }
+            if let LookupSwitch | TableSwitch = instruction.opcode {
+                let pad = (4 - bytes.len() % 4) % 4;
+                bytes.resize(bytes.len() + pad, 0);
+            }
+            bytes.extend_from_slice(&instruction.operands);
+        }
+
+        self.writer.write_u4(bytes.len() as u32)?;
+        self.writer.write_all(&bytes)?;
+        Ok(())
+    }
+}
+
+fn opcode_byte(opcode: &Opcode) -> Result<u8> {
+    match opcode.byte() {
+        Some(byte) => Ok(byte),
+        None => runtime_error!("Operation spacers can not be written."),
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::class::code::Instruction;
     use crate::class::code::Opcode::*;
-    use crate::io::code::CodeReader;
+    use crate::io::code::{CodeReader, CodeWriter};
     use std::io::Cursor;
 
     #[test]
@@ -369,6 +273,103 @@ mod test {
         );
     }
 
+    #[test]
+    fn read_wide() {
+        let mut data = Cursor::new(vec![
+            0x00, 0x00, 0x00, 0x0b, // Length
+            0xc4, 0x15, 0x01, 0x00, // wide iload 256
+            0xc4, 0x84, 0x01, 0x00, 0xff, 0xff, // wide iinc 256, -1
+            0xb1, // return
+        ]);
+
+        let mut reader = CodeReader::new(&mut data);
+        let instructions = reader.read_code().unwrap();
+
+        assert_eq!(
+            instructions,
+            vec![
+                Instruction::wide(ILoad, vec![0x01, 0x00]),
+                Instruction::operation_spacer(),
+                Instruction::operation_spacer(),
+                Instruction::operation_spacer(),
+                Instruction::wide(IInc, vec![0x01, 0x00, 0xff, 0xff]),
+                Instruction::operation_spacer(),
+                Instruction::operation_spacer(),
+                Instruction::operation_spacer(),
+                Instruction::operation_spacer(),
+                Instruction::operation_spacer(),
+                Instruction::new(Return, vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn read_wide_invalid() {
+        let mut data = Cursor::new(vec![
+            0x00, 0x00, 0x00, 0x05, // Length
+            0x00, // nop
+            0xc4, 0x60, 0x00, 0x01, // wide iadd
+        ]);
+
+        let mut reader = CodeReader::new(&mut data);
+        let error = reader.read_code().unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "Invalid opcode 0x60 modified by wide at byte 5."
+        );
+    }
+
+    #[test]
+    fn read_unknown_opcode() {
+        let mut data = Cursor::new(vec![
+            0x00, 0x00, 0x00, 0x02, // Length
+            0x00, // nop
+            0xcb, // Unassigned opcode
+        ]);
+
+        let error = CodeReader::new(&mut data).read_code().unwrap_err();
+        assert_eq!(error.to_string(), "Unknown opcode 0xcb at byte 5.");
+    }
+
+    #[test]
+    fn read_invalid_length() {
+        let mut data = Cursor::new(vec![
+            0x00, 0x00, 0x00, 0x00, // Length
+        ]);
+        let error = CodeReader::new(&mut data).read_code().unwrap_err();
+        assert_eq!(error.to_string(), "Invalid code length 0 at byte 0.");
+
+        let mut data = Cursor::new(vec![
+            0x00, 0x00, 0x00, 0x02, // Length
+            0x00, // nop
+            0x10, 0x05, // bipush 5
+        ]);
+        let error = CodeReader::new(&mut data).read_code().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Instruction runs past the end of the code at byte 5."
+        );
+    }
+
+    #[test]
+    fn read_invalid_table_switch() {
+        let mut data = Cursor::new(vec![
+            0x00, 0x00, 0x00, 0x10, // Length
+            0xaa, // Opcode
+            0x00, 0x00, 0x00, // Padding
+            0x00, 0x00, 0x00, 0x01, // Default
+            0x00, 0x00, 0x00, 0x02, // low
+            0x00, 0x00, 0x00, 0x01, // high
+        ]);
+
+        let error = CodeReader::new(&mut data).read_code().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid tableswitch range 2 to 1 at byte 12."
+        );
+    }
+
     #[test]
     fn read_lookup_switch() {
         let mut data = Cursor::new(vec![
@@ -413,7 +414,6 @@ mod test {
                 Instruction::operation_spacer(),
                 Instruction::operation_spacer(),
                 Instruction::operation_spacer(),
-                Instruction::operation_spacer(),
             ]
         );
     }
@@ -463,7 +463,56 @@ mod test {
                 Instruction::operation_spacer(),
                 Instruction::operation_spacer(),
                 Instruction::operation_spacer(),
-                Instruction::operation_spacer(),
+            ]
+        );
+    }
+
+    #[test]
+    fn write_instructions() {
+        let bytes = vec![
+            0x00, 0x00, 0x00, 0x0e, // Length
+            0x03, // iconst_0
+            0x3c, // istore_1
+            0xc4, 0x84, 0x01, 0x00, 0xff, 0xff, // wide iinc 256, -1
+            0x84, 0x01, 0x01, // iinc 1, 1
+            0x12, 0x02, // ldc 2
+            0xb1, // return
+        ];
+        let instructions = CodeReader::new(&mut Cursor::new(bytes.clone()))
+            .read_code()
+            .unwrap();
+
+        let mut written = Vec::new();
+        CodeWriter::new(&mut written)
+            .write_code(&instructions)
+            .unwrap();
+        assert_eq!(written, bytes);
+    }
+
+    #[test]
+    fn write_switch_padding() {
+        let operands = vec![
+            0x00, 0x00, 0x00, 0x01, // Default
+            0x00, 0x00, 0x00, 0x00, // Pairs
+        ];
+        let instructions = vec![
+            Instruction::new(Nop, vec![]),
+            Instruction::new_with_pad(LookupSwitch, operands, 3),
+        ];
+
+        let mut written = Vec::new();
+        CodeWriter::new(&mut written)
+            .write_code(&instructions)
+            .unwrap();
+        assert_eq!(
+            written,
+            vec![
+                0x00, 0x00, 0x00, 0x0c, // Length
+                0x00, // nop
+                0xab, // Opcode
+                0x00, 0x00, // Padding
+                0x00, 0x00, 0x00, 0x01, // Default
+                0x00, 0x00, 0x00, 0x00, // Pairs
             ]
         );
     }
@@ -0,0 +1,247 @@
+//! Modified UTF-8 as used by the constant pool:
+//! https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-4.html#jvms-4.4.7
+//!
+//! It differs from standard UTF-8 in two ways. The null character is written as the two bytes
+//! `0xC0 0x80`, so the encoded string never contains a zero byte. Supplementary characters are
+//! written as their UTF-16 surrogate pair, each surrogate encoded separately as three bytes.
+//!
+//! Like a Java string the encoded string may hold unpaired surrogates, such strings can only be
+//! decoded to UTF-16 code units.
+
+use crate::error::ErrorKind::ParseError;
+use crate::error::{Error, Result};
+
+/// Decodes modified UTF-8 into a Rust string.
+///
+/// A Rust string can not hold unpaired surrogates, they are reported as errors instead of
+/// changing the string. Use `decode_utf16_at` to decode them.
+pub fn decode(bytes: &[u8]) -> Result<String> {
+    let units = code_units(bytes, 0)?;
+
+    let mut string = String::with_capacity(bytes.len());
+    for c in char::decode_utf16(units.iter().map(|(c, _)| *c)) {
+        match c {
+            Ok(c) => string.push(c),
+            Err(error) => {
+                // The code units before the surrogate were all decoded.
+                let start = units[string.encode_utf16().count()].1;
+                return Err(Error::new(
+                    ParseError,
+                    Some(format!(
+                        "Unpaired surrogate 0x{:04x} in modified UTF-8 at byte {}.",
+                        error.unpaired_surrogate(),
+                        start
+                    )),
+                ));
+            }
+        }
+    }
+    Ok(string)
+}
+
+/// Decodes modified UTF-8 read from `offset` in a larger input into UTF-16 code units, unpaired
+/// surrogates included. Errors report their position in that input.
+pub fn decode_utf16_at(bytes: &[u8], offset: usize) -> Result<Vec<u16>> {
+    Ok(code_units(bytes, offset)?
+        .into_iter()
+        .map(|(c, _)| c)
+        .collect())
+}
+
+/// Returns the UTF-16 code units and where they start in `bytes`.
+fn code_units(bytes: &[u8], offset: usize) -> Result<Vec<(u16, usize)>> {
+    let mut units = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let x = bytes[i] as u16;
+        let (c, len) = match x {
+            0x01..=0x7F => (x, 1),
+            0xC0..=0xDF => {
+                let y = continuation(bytes, offset, i, 1)?;
+                (((x & 0x1F) << 6) | y, 2)
+            }
+            0xE0..=0xEF => {
+                let y = continuation(bytes, offset, i, 1)?;
+                let z = continuation(bytes, offset, i, 2)?;
+                (((x & 0x0F) << 12) | (y << 6) | z, 3)
+            }
+            _ => return Err(invalid(offset + i, x as u8)),
+        };
+
+        units.push((c, i));
+        i += len;
+    }
+
+    Ok(units)
+}
+
+/// Encodes a Rust string as modified UTF-8.
+pub fn encode(string: &str) -> Vec<u8> {
+    encode_utf16(string.encode_utf16())
+}
+
+/// Encodes UTF-16 code units as modified UTF-8, unpaired surrogates are encoded like any other
+/// code unit.
+pub fn encode_utf16<I: IntoIterator<Item = u16>>(units: I) -> Vec<u8> {
+    let mut bytes = Vec::new();
+
+    for c in units {
+        match c {
+            0x0001..=0x007F => bytes.push(c as u8),
+            0x0000 | 0x0080..=0x07FF => {
+                bytes.push(0xC0 | (c >> 6) as u8);
+                bytes.push(0x80 | (c & 0x3F) as u8);
+            }
+            _ => {
+                bytes.push(0xE0 | (c >> 12) as u8);
+                bytes.push(0x80 | ((c >> 6) & 0x3F) as u8);
+                bytes.push(0x80 | (c & 0x3F) as u8);
+            }
+        }
+    }
+
+    bytes
+}
+
+/// Returns the payload of the continuation byte `offset` bytes after `start`, `base` is where
+/// `bytes` starts in the input.
+fn continuation(bytes: &[u8], base: usize, start: usize, offset: usize) -> Result<u16> {
+    match bytes.get(start + offset) {
+        Some(b) if b & 0xC0 == 0x80 => Ok((b & 0x3F) as u16),
+        Some(b) => Err(invalid(base + start + offset, *b)),
+        None => Err(Error::new(
+            ParseError,
+            Some(format!(
+                "Truncated modified UTF-8 sequence at byte {}.",
+                base + start
+            )),
+        )),
+    }
+}
+
+fn invalid(offset: usize, byte: u8) -> Error {
+    Error::new(
+        ParseError,
+        Some(format!(
+            "Invalid modified UTF-8 byte 0x{:02x} at byte {}.",
+            byte, offset
+        )),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use crate::io::mutf8::{decode, decode_utf16_at, encode, encode_utf16};
+
+    #[test]
+    fn decode_ascii() {
+        assert_eq!(decode(b"<init>").unwrap(), "<init>");
+    }
+
+    #[test]
+    fn decode_null() {
+        assert_eq!(decode(&[0x61, 0xC0, 0x80, 0x62]).unwrap(), "a\0b");
+    }
+
+    #[test]
+    fn decode_two_and_three_bytes() {
+        assert_eq!(decode(&[0xC3, 0xA5]).unwrap(), "å");
+        assert_eq!(
+            decode(&[0xE4, 0xB8, 0x96, 0xE7, 0x95, 0x8C]).unwrap(),
+            "世界"
+        );
+    }
+
+    #[test]
+    fn decode_supplementary() {
+        let bytes = [0xED, 0xA0, 0xBD, 0xED, 0xB8, 0x80];
+        assert_eq!(decode(&bytes).unwrap(), "😀");
+    }
+
+    #[test]
+    fn decode_unpaired_surrogate() {
+        let error = decode(&[0xED, 0xA0, 0xBD]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Unpaired surrogate 0xd83d in modified UTF-8 at byte 0."
+        );
+
+        let error = decode(&[0x61, 0xED, 0xB8, 0x80, 0x62]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Unpaired surrogate 0xde00 in modified UTF-8 at byte 1."
+        );
+
+        let error = decode(&[0xED, 0xA0, 0xBD, 0x61]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Unpaired surrogate 0xd83d in modified UTF-8 at byte 0."
+        );
+    }
+
+    #[test]
+    fn decode_utf16_unpaired_surrogate() {
+        let bytes = [0x61, 0xED, 0xA0, 0x80];
+        assert_eq!(decode_utf16_at(&bytes, 0).unwrap(), vec![0x61, 0xD800]);
+        assert_eq!(encode_utf16(vec![0x61, 0xD800]), bytes);
+
+        let error = decode_utf16_at(&[0x61, 0xED, 0xA0], 10).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Truncated modified UTF-8 sequence at byte 11."
+        );
+    }
+
+    #[test]
+    fn decode_invalid() {
+        let error = decode(&[0x61, 0x00]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid modified UTF-8 byte 0x00 at byte 1."
+        );
+
+        let error = decode(&[0xF0, 0x9F, 0x98, 0x80]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid modified UTF-8 byte 0xf0 at byte 0."
+        );
+
+        let error = decode(&[0xE4, 0x61, 0x80]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid modified UTF-8 byte 0x61 at byte 1."
+        );
+    }
+
+    #[test]
+    fn decode_truncated() {
+        let error = decode(&[0x61, 0xE4, 0xB8]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Truncated modified UTF-8 sequence at byte 1."
+        );
+    }
+
+    #[test]
+    fn encode_null_and_supplementary() {
+        assert_eq!(encode("a\0b"), vec![0x61, 0xC0, 0x80, 0x62]);
+        assert_eq!(encode("😀"), vec![0xED, 0xA0, 0xBD, 0xED, 0xB8, 0x80]);
+    }
+
+    #[test]
+    fn round_trip() {
+        let strings = [
+            "",
+            "Hello",
+            "a\0b",
+            "åäö",
+            "世界",
+            "😀 emoji 🎉",
+            "\u{FFFF}",
+        ];
+        for string in strings.iter() {
+            assert_eq!(&decode(&encode(string)).unwrap(), string);
+        }
+    }
+}
@@ -0,0 +1,874 @@
+//! Disassembles classes in the format of `javap -c -v -p`.
+
+use crate::binary::{bytes_to_i16, bytes_to_i32, bytes_to_u16};
+use crate::class::attribute::AttributeData::{
+    CodeInfo, ConstantValue, Exceptions, LineNumberTable, Signature, SourceFile,
+};
+use crate::class::attribute::{Attribute, Code};
+use crate::class::code::Opcode::*;
+use crate::class::code::{Instruction, Opcode};
+use crate::class::constant::Constant::*;
+use crate::class::constant::{Constant, MethodHandleKind};
+use crate::class::signature::{TypeParameter, TypeSignature};
+use crate::class::{Class, ClassAccessFlags, FieldInfo, MethodAccessFlags, MethodInfo};
+use crate::error::Result;
+use crate::vm::data_type::FieldType;
+use crate::vm::java_floating_string;
+use std::convert::TryInto;
+
+const CLASS_FLAGS: &[(u16, &str)] = &[
+    (0x0001, "ACC_PUBLIC"),
+    (0x0010, "ACC_FINAL"),
+    (0x0020, "ACC_SUPER"),
+    (0x0200, "ACC_INTERFACE"),
+    (0x0400, "ACC_ABSTRACT"),
+    (0x1000, "ACC_SYNTHETIC"),
+    (0x2000, "ACC_ANNOTATION"),
+    (0x4000, "ACC_ENUM"),
+    (0x8000, "ACC_MODULE"),
+];
+
+const FIELD_FLAGS: &[(u16, &str)] = &[
+    (0x0001, "ACC_PUBLIC"),
+    (0x0002, "ACC_PRIVATE"),
+    (0x0004, "ACC_PROTECTED"),
+    (0x0008, "ACC_STATIC"),
+    (0x0010, "ACC_FINAL"),
+    (0x0040, "ACC_VOLATILE"),
+    (0x0080, "ACC_TRANSIENT"),
+    (0x1000, "ACC_SYNTHETIC"),
+    (0x4000, "ACC_ENUM"),
+];
+
+const METHOD_FLAGS: &[(u16, &str)] = &[
+    (0x0001, "ACC_PUBLIC"),
+    (0x0002, "ACC_PRIVATE"),
+    (0x0004, "ACC_PROTECTED"),
+    (0x0008, "ACC_STATIC"),
+    (0x0010, "ACC_FINAL"),
+    (0x0020, "ACC_SYNCHRONIZED"),
+    (0x0040, "ACC_BRIDGE"),
+    (0x0080, "ACC_VARARGS"),
+    (0x0100, "ACC_NATIVE"),
+    (0x0400, "ACC_ABSTRACT"),
+    (0x0800, "ACC_STRICT"),
+    (0x1000, "ACC_SYNTHETIC"),
+];
+
+const FIELD_MODIFIERS: &[(u16, &str)] = &[
+    (0x0001, "public"),
+    (0x0002, "private"),
+    (0x0004, "protected"),
+    (0x0008, "static"),
+    (0x0010, "final"),
+    (0x0040, "volatile"),
+    (0x0080, "transient"),
+];
+
+const METHOD_MODIFIERS: &[(u16, &str)] = &[
+    (0x0001, "public"),
+    (0x0002, "private"),
+    (0x0004, "protected"),
+    (0x0008, "static"),
+    (0x0010, "final"),
+    (0x0020, "synchronized"),
+    (0x0100, "native"),
+    (0x0400, "abstract"),
+    (0x0800, "strictfp"),
+];
+
+/// Returns the disassembly of the class: the version, access flags, constant pool, fields and
+/// methods with their code, exception tables and line number tables.
+pub fn disassemble(class: &Class) -> Result<String> {
+    let mut disassembler = Disassembler {
+        class,
+        out: String::new(),
+    };
+    disassembler.write_class()?;
+    Ok(disassembler.out)
+}
+
+struct Disassembler<'c> {
+    class: &'c Class,
+    out: String,
+}
+
+impl<'c> Disassembler<'c> {
+    fn line<S: AsRef<str>>(&mut self, line: S) {
+        self.out.push_str(line.as_ref());
+        self.out.push('\n');
+    }
+
+    fn write_class(&mut self) -> Result<()> {
+        let class = self.class;
+        if let Some(source_file) = class.source_file() {
+            self.line(format!("  Compiled from \"{}\"", source_file));
+        }
+        self.line(self.class_declaration()?);
+        self.line(format!("  minor version: {}", class.version.minor));
+        self.line(format!("  major version: {}", class.version.major));
+        self.line(format!(
+            "  flags: {}",
+            flags(class.access_flags.bits(), CLASS_FLAGS)
+        ));
+        self.line(with_comment(
+            format!("  this_class: #{}", self.class_index(&class.this_class)),
+            &class.this_class,
+            42,
+        ));
+        if class.super_class.is_empty() {
+            self.line("  super_class: #0");
+        } else {
+            self.line(with_comment(
+                format!("  super_class: #{}", self.class_index(&class.super_class)),
+                &class.super_class,
+                42,
+            ));
+        }
+        self.line(format!(
+            "  interfaces: {}, fields: {}, methods: {}, attributes: {}",
+            class.interfaces.len(),
+            class.fields.len(),
+            class.methods.len(),
+            class.attributes.len()
+        ));
+
+        self.write_constant_pool()?;
+
+        self.line("{");
+        for (i, field) in class.fields.iter().enumerate() {
+            if i > 0 {
+                self.line("");
+            }
+            self.write_field(field)?;
+        }
+        for (i, method) in class.methods.iter().enumerate() {
+            if i > 0 || !class.fields.is_empty() {
+                self.line("");
+            }
+            self.write_method(method)?;
+        }
+        self.line("}");
+
+        for attribute in &class.attributes {
+            match &attribute.data {
+                Signature(signature) => self.signature_line("", signature),
+                SourceFile(source_file) => self.line(format!("SourceFile: \"{}\"", source_file)),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn signature_line(&mut self, indent: &str, signature: &str) {
+        let index = self.class.constants.find(&Utf8(signature.to_owned()));
+        let text = format!("Signature: #{}", index.unwrap_or(0));
+        self.line(format!("{}{}", indent, with_comment(text, signature, 40)));
+    }
+
+    fn class_declaration(&self) -> Result<String> {
+        let class = self.class;
+        let flags = class.access_flags;
+        let interface = flags.contains(ClassAccessFlags::INTERFACE);
+
+        let mut declaration = String::new();
+        if flags.contains(ClassAccessFlags::PUBLIC) {
+            declaration.push_str("public ");
+        }
+        if flags.contains(ClassAccessFlags::FINAL) {
+            declaration.push_str("final ");
+        }
+        if interface {
+            declaration.push_str("interface ");
+        } else {
+            if flags.contains(ClassAccessFlags::ABSTRACT) {
+                declaration.push_str("abstract ");
+            }
+            declaration.push_str("class ");
+        }
+        declaration.push_str(&java_name(&class.this_class));
+
+        // Generic super types are listed in full, including `java.lang.Object`.
+        if let Some(signature) = class.signature()? {
+            declaration.push_str(&type_parameters(&signature.type_parameters));
+            if !interface {
+                declaration.push_str(&format!(" extends {}", signature.super_class));
+            }
+            if !signature.interfaces.is_empty() {
+                let interfaces: Vec<String> =
+                    signature.interfaces.iter().map(|i| i.to_string()).collect();
+                declaration.push_str(if interface {
+                    " extends "
+                } else {
+                    " implements "
+                });
+                declaration.push_str(&interfaces.join(", "));
+            }
+            return Ok(declaration);
+        }
+
+        if !interface && !class.super_class.is_empty() && class.super_class != "java/lang/Object" {
+            declaration.push_str(" extends ");
+            declaration.push_str(&java_name(&class.super_class));
+        }
+        if !class.interfaces.is_empty() {
+            declaration.push_str(if interface {
+                " extends "
+            } else {
+                " implements "
+            });
+            let interfaces: Vec<String> = class.interfaces.iter().map(|i| java_name(i)).collect();
+            declaration.push_str(&interfaces.join(","));
+        }
+        Ok(declaration)
+    }
+
+    fn write_constant_pool(&mut self) -> Result<()> {
+        self.line("Constant pool:");
+
+        // Indexes are aligned to the widest one.
+        let constants = &self.class.constants;
+        let width = (constants.len() + 1).to_string().len() + 3;
+        let mut index = 1;
+        for constant in constants.iter() {
+            let (kind, arguments) = match constant {
+                Utf8(string) => ("Utf8", escape(string)),
+                Utf16(units) => ("Utf8", escape_utf16(units)),
+                Integer(value) => ("Integer", value.to_string()),
+                Float(value) => ("Float", format!("{}f", java_floating_string(*value))),
+                Long(value) => ("Long", format!("{}l", value)),
+                Double(value) => ("Double", format!("{}d", java_floating_string(*value))),
+                ClassRef(name_index) => ("Class", format!("#{}", name_index)),
+                StringRef(string_index) => ("String", format!("#{}", string_index)),
+                FieldRef(class_index, name_and_type_index) => (
+                    "Fieldref",
+                    format!("#{}.#{}", class_index, name_and_type_index),
+                ),
+                MethodRef(class_index, name_and_type_index) => (
+                    "Methodref",
+                    format!("#{}.#{}", class_index, name_and_type_index),
+                ),
+                InterfaceMethodRef(class_index, name_and_type_index) => (
+                    "InterfaceMethodref",
+                    format!("#{}.#{}", class_index, name_and_type_index),
+                ),
+                NameAndType(name_index, descriptor_index) => (
+                    "NameAndType",
+                    format!("#{}:#{}", name_index, descriptor_index),
+                ),
+                MethodHandle(kind, reference_index) => (
+                    "MethodHandle",
+                    format!("{}:#{}", reference_kind(kind).0, reference_index),
+                ),
+                MethodType(descriptor_index) => ("MethodType", format!("#{}", descriptor_index)),
+                Dynamic(bootstrap_index, name_and_type_index) => (
+                    "Dynamic",
+                    format!("#{}:#{}", bootstrap_index, name_and_type_index),
+                ),
+                Constant::InvokeDynamic(bootstrap_index, name_and_type_index) => (
+                    "InvokeDynamic",
+                    format!("#{}:#{}", bootstrap_index, name_and_type_index),
+                ),
+                Module(name_index) => ("Module", format!("#{}", name_index)),
+                Package(name_index) => ("Package", format!("#{}", name_index)),
+                NOOP => unreachable!(),
+            };
+
+            let entry = format!(
+                "{:>width$} = {:<18} {}",
+                format!("#{}", index),
+                kind,
+                arguments,
+                width = width
+            );
+            let line = match constant {
+                Utf8(_) | Utf16(_) | Integer(_) | Float(_) | Long(_) | Double(_) => entry,
+                _ => with_comment(entry, &self.constant(index, false)?, 42),
+            };
+            self.line(line);
+
+            index += if let Long(_) | Double(_) = constant {
+                2
+            } else {
+                1
+            };
+        }
+        Ok(())
+    }
+
+    fn write_field(&mut self, field: &FieldInfo) -> Result<()> {
+        let field_type = match field.signature()? {
+            Some(signature) => signature.to_string(),
+            None => java_type(&field.descriptor.as_str().try_into()?),
+        };
+        self.line(format!(
+            "  {}{} {};",
+            modifiers(field.access_flags.bits(), FIELD_MODIFIERS),
+            field_type,
+            field.name
+        ));
+        self.line(format!("    descriptor: {}", field.descriptor));
+        self.line(format!(
+            "    flags: {}",
+            flags(field.access_flags.bits(), FIELD_FLAGS)
+        ));
+
+        for attribute in &field.attributes {
+            match &attribute.data {
+                ConstantValue(constant) => {
+                    let index = self.class.constants.find(constant).unwrap_or(0);
+                    self.line(format!("    ConstantValue: {}", self.loadable(index)?));
+                }
+                Signature(signature) => self.signature_line("    ", signature),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn write_method(&mut self, method: &MethodInfo) -> Result<()> {
+        self.line(format!("  {};", self.method_declaration(method)?));
+        self.line(format!("    descriptor: {}", method.descriptor));
+        self.line(format!(
+            "    flags: {}",
+            flags(method.access_flags.bits(), METHOD_FLAGS)
+        ));
+
+        for attribute in &method.attributes {
+            match &attribute.data {
+                CodeInfo(code) => self.write_code(method, code)?,
+                Exceptions(exceptions) => {
+                    self.line("    Exceptions:");
+                    let exceptions: Vec<String> = exceptions.iter().map(|e| java_name(e)).collect();
+                    self.line(format!("      throws {}", exceptions.join(", ")));
+                }
+                Signature(signature) => self.signature_line("    ", signature),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn method_declaration(&self, method: &MethodInfo) -> Result<String> {
+        let bits = method.access_flags.bits();
+        if method.name == "<clinit>" {
+            return Ok("static {}".to_owned());
+        }
+
+        // Non-abstract instance methods of interfaces are default methods.
+        let mut modifiers = modifiers(bits, METHOD_MODIFIERS);
+        let not_default = MethodAccessFlags::ACC_PRIVATE
+            | MethodAccessFlags::ACC_STATIC
+            | MethodAccessFlags::ACC_ABSTRACT;
+        let interface = self
+            .class
+            .access_flags
+            .contains(ClassAccessFlags::INTERFACE);
+        if interface && !method.access_flags.intersects(not_default) {
+            modifiers.push_str("default ");
+        }
+
+        let signature = method.signature()?;
+        let (arguments, return_type) = match &signature {
+            Some(signature) => {
+                modifiers.push_str(&type_parameters(&signature.type_parameters));
+                if !signature.type_parameters.is_empty() {
+                    modifiers.push(' ');
+                }
+                let arguments: Vec<String> =
+                    signature.parameters.iter().map(|p| p.to_string()).collect();
+                let return_type = signature.return_type.as_ref().map(|r| r.to_string());
+                (arguments, return_type)
+            }
+            None => {
+                let arguments: Vec<String> = method
+                    .descriptor
+                    .argument_types
+                    .iter()
+                    .map(java_type)
+                    .collect();
+                let return_type = method.descriptor.return_type.as_ref().map(java_type);
+                (arguments, return_type)
+            }
+        };
+        let arguments = arguments.join(", ");
+
+        let mut declaration = if method.name == "<init>" {
+            format!(
+                "{}{}({})",
+                modifiers,
+                java_name(&self.class.this_class),
+                arguments
+            )
+        } else {
+            format!(
+                "{}{} {}({})",
+                modifiers,
+                return_type.unwrap_or_else(|| "void".to_owned()),
+                method.name,
+                arguments
+            )
+        };
+
+        // Like javap, exception classes of a signature keep their internal names.
+        let throws: Vec<String> = match signature {
+            Some(signature) if !signature.throws.is_empty() => signature
+                .throws
+                .iter()
+                .map(|t| match t {
+                    TypeSignature::Class(class) => class.name.clone(),
+                    _ => t.to_string(),
+                })
+                .collect(),
+            _ => method
+                .attributes
+                .iter()
+                .filter_map(|a| match &a.data {
+                    Exceptions(exceptions) => Some(exceptions.iter().map(|e| java_name(e))),
+                    _ => None,
+                })
+                .flatten()
+                .collect(),
+        };
+        if !throws.is_empty() {
+            declaration.push_str(" throws ");
+            declaration.push_str(&throws.join(", "));
+        }
+        Ok(declaration)
+    }
+
+    fn write_code(&mut self, method: &MethodInfo, code: &Code) -> Result<()> {
+        self.line("    Code:");
+        self.line(format!(
+            "      stack={}, locals={}, args_size={}",
+            code.max_stack,
+            code.max_locals,
+            argument_count(method)
+        ));
+
+        for (pc, instruction) in code.instructions.iter().enumerate() {
+            if instruction.opcode != OperationSpacer {
+                let text = self.instruction(pc as u16, instruction)?;
+                self.line(format!("{:>10}: {}", pc, text));
+            }
+        }
+
+        if !code.exception_handlers.is_empty() {
+            self.line("      Exception table:");
+            self.line("         from    to  target type");
+            for handler in &code.exception_handlers {
+                let catch_type = match &handler.catch_type {
+                    Some(catch_type) => format!("Class {}", catch_type),
+                    None => "any".to_owned(),
+                };
+                self.line(format!(
+                    "         {:>5} {:>5} {:>5}   {}",
+                    handler.start_pc, handler.end_pc, handler.handler_pc, catch_type
+                ));
+            }
+        }
+
+        for Attribute { data, .. } in &code.attributes {
+            if let LineNumberTable(table) = data {
+                self.line("      LineNumberTable:");
+                for (start_pc, line_number) in table {
+                    self.line(format!("        line {}: {}", line_number, start_pc));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Formats the instruction at `pc` with its operands resolved. Switches span several lines.
+    fn instruction(&self, pc: u16, instruction: &Instruction) -> Result<String> {
+        let mnemonic = instruction.opcode.mnemonic();
+        let operands = &instruction.operands;
+        let pc = pc as i32;
+
+        let text = match instruction.opcode {
+            GetField | GetStatic | PutField | PutStatic | InvokeVirtual | InvokeSpecial
+            | InvokeStatic | New | ANewArray | CheckCast | Instanceof | LdcW | Ldc2W => {
+                let index = bytes_to_u16(operands);
+                with_comment(
+                    format!("{:<13} #{}", mnemonic, index),
+                    &self.constant(index, true)?,
+                    34,
+                )
+            }
+            Ldc => {
+                let index = operands[0] as u16;
+                with_comment(
+                    format!("{:<13} #{}", mnemonic, index),
+                    &self.constant(index, true)?,
+                    34,
+                )
+            }
+            InvokeInterface | MultiANewArray => {
+                let index = bytes_to_u16(operands);
+                with_comment(
+                    format!("{:<13} #{},  {}", mnemonic, index, operands[2]),
+                    &self.constant(index, true)?,
+                    34,
+                )
+            }
+            Opcode::InvokeDynamic => {
+                let index = bytes_to_u16(operands);
+                with_comment(
+                    format!("{:<13} #{},  0", mnemonic, index),
+                    &self.constant(index, true)?,
+                    34,
+                )
+            }
+            BiPush => format!("{:<13} {}", mnemonic, operands[0] as i8),
+            SiPush => format!("{:<13} {}", mnemonic, bytes_to_i16(operands)),
+            ILoad | LLoad | FLoad | DLoad | ALoad | IStore | LStore | FStore | DStore | AStore
+            | Ret => format!("{:<13} {}", mnemonic, operands[0]),
+            IInc => format!("{:<13} {}, {}", mnemonic, operands[0], operands[1] as i8),
+            IfEq | IfNe | IfLt | IfGe | IfGt | IfLe | IfIcmpEq | IfIcmpNe | IfIcmpLt | IfIcmpGe
+            | IfIcmpGt | IfIcmpLe | IfAcmpEq | IfAcmpNe | IfNull | IfNonNull | Goto | Jsr => {
+                format!("{:<13} {}", mnemonic, pc + bytes_to_i16(operands) as i32)
+            }
+            GotoW | JsrW => format!("{:<13} {}", mnemonic, pc + bytes_to_i32(operands)),
+            NewArray => format!("{:<14} {}", mnemonic, array_type(operands[0])),
+            TableSwitch => {
+                let default = bytes_to_i32(&operands[0..4]);
+                let low = bytes_to_i32(&operands[4..8]);
+                let high = bytes_to_i32(&operands[8..12]);
+                let mut text = format!("{:<13} {{ // {} to {}\n", mnemonic, low, high);
+                for (i, offset) in operands[12..].chunks(4).enumerate() {
+                    let key = low + i as i32;
+                    text.push_str(&switch_case(&key.to_string(), pc + bytes_to_i32(offset)));
+                }
+                text.push_str(&switch_case("default", pc + default));
+                text.push_str("            }");
+                text
+            }
+            LookupSwitch => {
+                let default = bytes_to_i32(&operands[0..4]);
+                let pairs = bytes_to_i32(&operands[4..8]);
+                let mut text = format!("{:<13} {{ // {}\n", mnemonic, pairs);
+                for pair in operands[8..].chunks(8) {
+                    let key = bytes_to_i32(&pair[0..4]);
+                    let target = pc + bytes_to_i32(&pair[4..8]);
+                    text.push_str(&switch_case(&key.to_string(), target));
+                }
+                text.push_str(&switch_case("default", pc + default));
+                text.push_str("            }");
+                text
+            }
+            Wide => {
+                let index = bytes_to_u16(&operands[0..2]);
+                let modified = instruction
+                    .modified
+                    .as_ref()
+                    .map_or(mnemonic, Opcode::mnemonic);
+                let modified = format!("{}_w", modified);
+                if let Some(IInc) = instruction.modified {
+                    let constant = bytes_to_i16(&operands[2..4]);
+                    format!("{:<13} {}, {}", modified, index, constant)
+                } else {
+                    format!("{:<13} {}", modified, index)
+                }
+            }
+            _ => mnemonic.to_owned(),
+        };
+        Ok(text)
+    }
+
+    /// Formats the constant at `index` the way it is referred to. In instructions the kind of the
+    /// constant is included and members of this class are written without the class name.
+    fn constant(&self, index: u16, in_code: bool) -> Result<String> {
+        let constants = &self.class.constants;
+        let constant = constants.get(index)?;
+        let text = match constant {
+            Utf8(string) => escape(string),
+            Utf16(units) => escape_utf16(units),
+            Integer(_) | Float(_) | Long(_) | Double(_) | StringRef(_) => {
+                if in_code {
+                    self.loadable(index)?
+                } else {
+                    self.constant_value(constant)?
+                }
+            }
+            ClassRef(_) => {
+                let name = class_constant(constants.get_class_info_name(index)?);
+                if in_code {
+                    format!("class {}", name)
+                } else {
+                    name
+                }
+            }
+            FieldRef(..) | MethodRef(..) | InterfaceMethodRef(..) => {
+                let (class_name, name, descriptor) = constants.get_member_ref(index)?;
+                let member = if in_code && class_name == self.class.this_class {
+                    format!("{}:{}", member_name(name), descriptor)
+                } else {
+                    format!(
+                        "{}.{}:{}",
+                        class_constant(class_name),
+                        member_name(name),
+                        descriptor
+                    )
+                };
+                if in_code {
+                    let kind = match constant {
+                        FieldRef(..) => "Field",
+                        MethodRef(..) => "Method",
+                        _ => "InterfaceMethod",
+                    };
+                    format!("{} {}", kind, member)
+                } else {
+                    member
+                }
+            }
+            NameAndType(..) => {
+                let (name, descriptor) = constants.get_name_and_type(index)?;
+                format!("{}:{}", member_name(name), descriptor)
+            }
+            MethodHandle(kind, reference_index) => {
+                let handle = format!(
+                    "{} {}",
+                    reference_kind(kind).1,
+                    self.constant(*reference_index, false)?
+                );
+                if in_code {
+                    format!("MethodHandle {}", handle)
+                } else {
+                    handle
+                }
+            }
+            MethodType(_) => {
+                let descriptor = constants.get_method_type(index)?;
+                if in_code {
+                    format!("MethodType {}", descriptor)
+                } else {
+                    descriptor.to_owned()
+                }
+            }
+            Dynamic(bootstrap_index, name_and_type_index)
+            | Constant::InvokeDynamic(bootstrap_index, name_and_type_index) => {
+                let (name, descriptor) = constants.get_name_and_type(*name_and_type_index)?;
+                let call_site = format!("#{}:{}:{}", bootstrap_index, name, descriptor);
+                match (in_code, constant) {
+                    (false, _) => call_site,
+                    (true, Dynamic(..)) => format!("Dynamic {}", call_site),
+                    (true, _) => format!("InvokeDynamic {}", call_site),
+                }
+            }
+            Module(name_index) | Package(name_index) => constants.get_utf8(*name_index)?.to_owned(),
+            NOOP => "".to_owned(),
+        };
+        Ok(text)
+    }
+
+    /// Formats a constant loaded by `ldc` or used as a `ConstantValue`, like `int 5`.
+    fn loadable(&self, index: u16) -> Result<String> {
+        let constant = self.class.constants.get(index)?;
+        let kind = match constant {
+            Integer(_) => "int",
+            Float(_) => "float",
+            Long(_) => "long",
+            Double(_) => "double",
+            StringRef(_) => "String",
+            _ => return self.constant(index, true),
+        };
+        Ok(format!("{} {}", kind, self.constant_value(constant)?))
+    }
+
+    fn constant_value(&self, constant: &Constant) -> Result<String> {
+        Ok(match constant {
+            Integer(value) => value.to_string(),
+            Float(value) => format!("{}f", java_floating_string(*value)),
+            Long(value) => format!("{}l", value),
+            Double(value) => format!("{}d", java_floating_string(*value)),
+            StringRef(string_index) => match self.class.constants.get(*string_index)? {
+                Utf16(units) => escape_utf16(units),
+                _ => escape(self.class.constants.get_utf8(*string_index)?),
+            },
+            constant => format!("{:?}", constant),
+        })
+    }
+
+    /// Returns the index of the class in the constant pool, or 0 if it is missing.
+    fn class_index(&self, name: &str) -> u16 {
+        let constants = &self.class.constants;
+        constants
+            .find(&Utf8(name.to_owned()))
+            .and_then(|name_index| constants.find(&ClassRef(name_index)))
+            .unwrap_or(0)
+    }
+}
+
+/// Pads `text` to `column` and appends the comment.
+fn with_comment(text: String, comment: &str, column: usize) -> String {
+    if text.len() < column {
+        format!("{:<width$}// {}", text, comment, width = column)
+    } else {
+        format!("{} // {}", text, comment)
+    }
+}
+
+fn switch_case(key: &str, target: i32) -> String {
+    format!("{:>24}: {}\n", key, target)
+}
+
+fn flags(bits: u16, names: &[(u16, &str)]) -> String {
+    let names: Vec<&str> = names
+        .iter()
+        .filter(|(flag, _)| bits & flag != 0)
+        .map(|(_, name)| *name)
+        .collect();
+
+    if names.is_empty() {
+        format!("(0x{:04x})", bits)
+    } else {
+        format!("(0x{:04x}) {}", bits, names.join(", "))
+    }
+}
+
+fn modifiers(bits: u16, names: &[(u16, &str)]) -> String {
+    names
+        .iter()
+        .filter(|(flag, _)| bits & flag != 0)
+        .map(|(_, name)| format!("{} ", name))
+        .collect()
+}
+
+/// Returns the number of arguments, including `this`.
+fn argument_count(method: &MethodInfo) -> usize {
+    let this = !method.access_flags.contains(MethodAccessFlags::ACC_STATIC);
+    method.descriptor.argument_types.len() + this as usize
+}
+
+/// Formats type parameters like `<T extends java.lang.Object>`, nothing when there are none.
+fn type_parameters(parameters: &[TypeParameter]) -> String {
+    if parameters.is_empty() {
+        return String::new();
+    }
+    let parameters: Vec<String> = parameters.iter().map(|p| p.to_string()).collect();
+    format!("<{}>", parameters.join(", "))
+}
+
+fn java_name(class_name: &str) -> String {
+    class_name.replace('/', ".")
+}
+
+fn java_type(field_type: &FieldType) -> String {
+    match field_type {
+        FieldType::Byte => "byte".to_owned(),
+        FieldType::Char => "char".to_owned(),
+        FieldType::Double => "double".to_owned(),
+        FieldType::Float => "float".to_owned(),
+        FieldType::Int => "int".to_owned(),
+        FieldType::Long => "long".to_owned(),
+        FieldType::Short => "short".to_owned(),
+        FieldType::Boolean => "boolean".to_owned(),
+        FieldType::Object(class_name) => java_name(class_name),
+        FieldType::Array(component) => format!("{}[]", java_type(component)),
+    }
+}
+
+/// Array classes are quoted, like `"[I"`.
+fn class_constant(class_name: &str) -> String {
+    if class_name.starts_with('[') {
+        format!("\"{}\"", class_name)
+    } else {
+        class_name.to_owned()
+    }
+}
+
+/// Constructors and initializers are quoted, like `"<init>"`.
+fn member_name(name: &str) -> String {
+    if name.starts_with('<') {
+        format!("\"{}\"", name)
+    } else {
+        name.to_owned()
+    }
+}
+
+/// Escapes control characters the way Java writes them, like `\n` and `\u0000`.
+fn escape(string: &str) -> String {
+    let mut escaped = String::with_capacity(string.len());
+    for c in string.chars() {
+        escape_char(c, &mut escaped);
+    }
+    escaped
+}
+
+/// Escapes a string with unpaired surrogates, the surrogates are written as `\uxxxx`.
+fn escape_utf16(units: &[u16]) -> String {
+    let mut escaped = String::with_capacity(units.len());
+    for c in char::decode_utf16(units.iter().copied()) {
+        match c {
+            Ok(c) => escape_char(c, &mut escaped),
+            Err(error) => escaped.push_str(&format!("\\u{:04x}", error.unpaired_surrogate())),
+        }
+    }
+    escaped
+}
+
+fn escape_char(c: char, escaped: &mut String) {
+    match c {
+        '\t' => escaped.push_str("\\t"),
+        '\n' => escaped.push_str("\\n"),
+        '\r' => escaped.push_str("\\r"),
+        c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+        c => escaped.push(c),
+    }
+}
+
+fn reference_kind(kind: &MethodHandleKind) -> (u8, &'static str) {
+    match kind {
+        MethodHandleKind::GetField => (1, "REF_getField"),
+        MethodHandleKind::GetStatic => (2, "REF_getStatic"),
+        MethodHandleKind::PutField => (3, "REF_putField"),
+        MethodHandleKind::PutStatic => (4, "REF_putStatic"),
+        MethodHandleKind::InvokeVirtual => (5, "REF_invokeVirtual"),
+        MethodHandleKind::InvokeStatic => (6, "REF_invokeStatic"),
+        MethodHandleKind::InvokeSpecial => (7, "REF_invokeSpecial"),
+        MethodHandleKind::NewInvokeSpecial => (8, "REF_newInvokeSpecial"),
+        MethodHandleKind::InvokeInterface => (9, "REF_invokeInterface"),
+    }
+}
+
+fn array_type(atype: u8) -> &'static str {
+    match atype {
+        4 => "boolean",
+        5 => "char",
+        6 => "float",
+        7 => "double",
+        8 => "byte",
+        9 => "short",
+        10 => "int",
+        11 => "long",
+        _ => "<invalid>",
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::javap::{escape, escape_utf16};
+    use crate::vm::java_floating_string;
+
+    #[test]
+    fn decimal() {
+        assert_eq!(java_floating_string(1.5f32), "1.5");
+        assert_eq!(java_floating_string(100.0f64), "100.0");
+        assert_eq!(java_floating_string(1e10f64), "1.0E10");
+        assert_eq!(java_floating_string(-2.5e-5f64), "-2.5E-5");
+        assert_eq!(java_floating_string(f64::NEG_INFINITY), "-Infinity");
+        assert_eq!(java_floating_string(f32::NAN), "NaN");
+    }
+
+    #[test]
+    fn escape_control_characters() {
+        assert_eq!(escape("a\tb\n"), "a\\tb\\n");
+        assert_eq!(escape("a\0b"), "a\\u0000b");
+        assert_eq!(escape("å"), "å");
+    }
+
+    #[test]
+    fn escape_unpaired_surrogates() {
+        assert_eq!(escape_utf16(&[0x61, 0xD800, 0x0A]), "a\\ud800\\n");
+    }
+}
@@ -15,6 +15,8 @@ macro_rules! debug {
 
 #[macro_use]
 pub mod error;
+pub mod assembler;
 pub mod class;
 pub mod io;
+pub mod javap;
 pub mod vm;
@@ -20,12 +20,19 @@ fn main() {
     let mut native = Native::new();
 
     let mut vm = VirtualMachine::default();
-    let return_value = vm.run(
+    let result = vm.run(
         &mut class_loader,
         &mut native,
         class_name,
         method_name,
         vec![],
     );
-    println!("Returned {:?}", return_value);
+
+    match result {
+        Ok(return_value) => println!("Returned {:?}", return_value),
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    }
 }
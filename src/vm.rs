@@ -2,23 +2,27 @@ use crate::class::attribute::Code;
 use crate::class::code::Instruction;
 use crate::class::code::Opcode::AThrow;
 use crate::class::constant::Constant;
-use crate::class::MethodInfo;
-use crate::error::Result;
-use crate::vm::class_loader::ClassLoader;
-use crate::vm::data_type::Value::Reference;
+use crate::class::{ClassAccessFlags, MethodAccessFlags, MethodInfo};
+use crate::error::{Error, Result};
+use crate::vm::class_loader::{ClassLoader, InterfaceMethod};
+use crate::vm::data_type::Value::{Int, Reference};
 use crate::vm::data_type::{MethodDescriptor, ReferenceType, Value};
 use crate::vm::frame::Frame;
-use crate::vm::heap::{Heap, HeapObject};
+use crate::vm::heap::{Heap, HeapObject, HeapStatistics};
 use crate::vm::interpreter::interpret_frame;
 use crate::vm::native::Native;
+use crate::vm::native::NativeResult::{Return, Throw};
 use crate::vm::stack::Stack;
+use crate::vm::thread::{ThreadId, ThreadState, Threads, MAIN_THREAD};
 use crate::vm::VMCommand::{
-    VMAllocateReferenceArray, VMException, VMGetField, VMGetStatic, VMInternalException,
-    VMInvokeInterface, VMInvokeSpecial, VMInvokeStatic, VMInvokeVirtual, VMNative, VMPutField,
-    VMPutStatic, VMReturn,
+    VMAllocateMultiArray, VMAllocateReferenceArray, VMCheckCast, VMCollectGarbage, VMException,
+    VMGetField, VMGetStatic, VMInstanceOf, VMInternalException, VMInvokeDynamic, VMInvokeInterface,
+    VMInvokeSpecial, VMInvokeStatic, VMInvokeVirtual, VMMonitorEnter, VMMonitorExit, VMNative,
+    VMOutOfMemory, VMPutField, VMPutStatic, VMReturn, VMYield,
 };
 use std::collections::HashMap;
 use std::convert::TryInto;
+use std::fmt;
 use std::rc::Rc;
 
 #[macro_export]
@@ -37,13 +41,20 @@ macro_rules! expect_type {
     };
 }
 
+mod bootstrap;
 pub mod class_loader;
 pub mod data_type;
+pub mod exception;
 mod frame;
-mod heap;
+pub mod heap;
 mod interpreter;
+pub mod module;
+pub mod monitor;
 pub mod native;
+mod reflection;
 pub mod stack;
+pub mod thread;
+mod verifier;
 
 #[derive(Debug)]
 enum VMCommand {
@@ -52,14 +63,23 @@ enum VMCommand {
     VMInvokeSpecial(u16),
     VMInvokeVirtual(u16),
     VMInvokeInterface(u16),
+    VMInvokeDynamic(u16),
     VMPutField(u16),
     VMGetField(u16),
     VMPutStatic(u16),
     VMGetStatic(u16),
     VMAllocateReferenceArray(u16),
+    VMAllocateMultiArray(u16, u8),
+    VMCheckCast(u16),
+    VMInstanceOf(u16),
+    VMMonitorEnter(),
+    VMMonitorExit(),
+    VMCollectGarbage(),
+    VMOutOfMemory(),
     VMException(),
     VMInternalException(String), // TODO arguments to create the exception
     VMNative(),
+    VMYield(), // The time slice of the thread is used up.
 }
 
 #[derive(Debug, PartialEq)]
@@ -71,9 +91,44 @@ pub struct Object {
 
 type StaticContext = HashMap<String, HashMap<String, Value>>;
 
-pub struct VirtualMachine {}
+pub struct VirtualMachine {
+    heap: Heap,
+    time_slice: u32,
+}
 
 impl VirtualMachine {
+    /// Sets the number of instructions a thread may run before the scheduler switches to the
+    /// next thread.
+    pub fn set_time_slice(&mut self, instructions: u32) {
+        self.time_slice = instructions;
+    }
+
+    /// Sets the number of bytes that may be allocated before the garbage collector runs.
+    pub fn set_heap_limit(&mut self, limit: usize) {
+        self.heap.set_limit(limit);
+    }
+
+    /// Sets the number of bytes the heap may grow to, like `-Xmx`. Allocations that does not fit
+    /// after a collection throws `java/lang/OutOfMemoryError`.
+    pub fn set_max_heap_size(&mut self, size: usize) {
+        self.heap.set_max_size(size);
+    }
+
+    /// Garbage collection statistics for all runs made by this VM.
+    pub fn heap_statistics(&self) -> &HeapStatistics {
+        self.heap.statistics()
+    }
+
+    /// The heap is shared by all runs, this makes it possible to pass objects such as strings as
+    /// arguments and to inspect returned references.
+    pub fn heap(&self) -> &Heap {
+        &self.heap
+    }
+
+    pub fn heap_mut(&mut self) -> &mut Heap {
+        &mut self.heap
+    }
+
     pub fn run(
         &mut self,
         class_loader: &mut ClassLoader,
@@ -81,9 +136,10 @@ impl VirtualMachine {
         class_name: &str,
         method_name: &str,
         args: Vec<Value>,
-    ) -> Option<Value> {
-        let mut heap = Heap::default();
-        let mut stack = Stack::new();
+    ) -> Result<Option<Value>> {
+        // Execution needs the heap alongside a mutable borrow of self, it is put back afterwards.
+        let mut heap = std::mem::take(&mut self.heap);
+        let mut threads = Threads::new(Stack::new());
         let mut class_loader = class_loader;
         let mut native = native;
         let mut static_context: StaticContext = HashMap::new();
@@ -92,7 +148,7 @@ impl VirtualMachine {
         let init_result = self.execute(
             &mut static_context,
             &mut heap,
-            &mut stack,
+            &mut threads,
             &mut class_loader,
             "java/lang/System",
             "initializeSystemClass",
@@ -106,7 +162,7 @@ impl VirtualMachine {
         let result = self.execute(
             &mut static_context,
             &mut heap,
-            &mut stack,
+            &mut threads,
             &mut class_loader,
             class_name,
             method_name,
@@ -114,113 +170,319 @@ impl VirtualMachine {
             &mut native,
         );
 
-        if let Ok(value) = result {
-            value
-        } else {
-            println!("Stack:\n{}", stack);
-            println!("Heap: {:#?}", heap);
+        self.heap = heap;
 
-            panic!(
-                "Runtime error {:?}",
-                result.unwrap_err().message().expect("No error message.")
-            );
+        if result.is_err() {
+            debug!("Stack:\n{}", threads.current().stack);
         }
+
+        result
     }
 
+    /// Runs the static method on the main thread until all threads have terminated, the return
+    /// value is that of the main thread.
     pub fn execute(
         &mut self,
         static_context: &mut StaticContext,
         heap: &mut Heap,
-        stack: &mut Stack,
+        threads: &mut Threads,
         class_loader: &mut ClassLoader,
         init_class_name: &str,
         init_method_name: &str,
         args: Vec<Value>,
         native: &mut Native,
     ) -> Result<Option<Value>> {
-        self.prepare_static_method(class_loader, init_class_name, init_method_name, args, stack);
+        self.prepare_static_method(
+            heap,
+            class_loader,
+            init_class_name,
+            init_method_name,
+            args,
+            &mut threads.current_mut().stack,
+        )?;
 
-        loop {
-            let mut freeze_pc = false;
-            let stack_size = stack.len();
+        let mut result = None;
+        let mut budget = self.time_slice;
 
-            let frame = stack.current_frame_mut();
-            match interpret_frame(frame, heap)? {
-                VMReturn(value) => {
-                    if stack.last_frame() {
-                        return Ok(value);
-                    } else {
-                        let frame = stack.current_frame_mut();
-                        let void_return = frame.method.descriptor.return_type.is_none();
-
-                        // We must not update the pc when returning from implicit frames.
-                        if frame.implicit {
-                            freeze_pc = true;
-                        }
+        loop {
+            if budget == 0 || threads.current().state != ThreadState::Runnable {
+                if !threads.schedule(heap.monitors_mut())? {
+                    return Ok(result);
+                }
+                budget = self.time_slice;
+            }
 
-                        stack.pop();
+            let step = self.step(
+                static_context,
+                heap,
+                threads,
+                class_loader,
+                native,
+                &mut budget,
+                &mut result,
+            );
 
-                        if !void_return {
-                            stack.current_frame_mut().push_operand(value.unwrap());
-                        }
+            if let Err(error) = step {
+                // Linkage errors are thrown in Java, at the instruction that needed the class.
+                match error.linkage_error_class() {
+                    Some(name) => {
+                        let name = name.to_owned();
+                        let message = error.message().cloned();
+                        let stack = &mut threads.current_mut().stack;
+                        self.throw_error(class_loader, heap, stack, name, message)?;
                     }
+                    None => return Err(error),
                 }
-                VMInvokeStatic(index) => {
-                    self.invoke_static(class_loader, index, stack)?;
-                }
-                VMInvokeSpecial(index) => {
-                    self.invoke_special(class_loader, index, stack)?;
-                }
-                VMInvokeVirtual(index) => {
-                    self.invoke_virtual(heap, class_loader, index, stack)?;
-                }
-                VMInvokeInterface(index) => {
-                    self.invoke_interface(heap, class_loader, index, stack)?;
-                }
-                VMPutField(index) => {
-                    self.put_field(heap, index, stack);
-                }
-                VMGetField(index) => {
-                    self.get_field(heap, index, stack);
-                }
-                VMPutStatic(index) => {
-                    self.put_static(static_context, index, stack);
-                }
-                VMGetStatic(index) => {
-                    self.get_static(class_loader, static_context, index, stack);
-                }
-                VMAllocateReferenceArray(index) => {
-                    self.allocate_reference_array(heap, class_loader, index, stack)?;
-                }
-                VMException() => {
-                    // We must not update PC after exception resolution, the pc is placed at the
-                    // handler.
-                    freeze_pc = true;
-                    self.handle_exception(heap, stack);
-                }
-                VMInternalException(name) => {
+            }
+        }
+    }
+
+    /// Executes the current thread until it yields, a VM command has been carried out or the
+    /// thread can not continue.
+    #[allow(clippy::too_many_arguments)]
+    fn step(
+        &self,
+        static_context: &mut StaticContext,
+        heap: &mut Heap,
+        threads: &mut Threads,
+        class_loader: &mut ClassLoader,
+        native: &mut Native,
+        budget: &mut u32,
+        result: &mut Option<Value>,
+    ) -> Result<()> {
+        let thread = threads.current().id;
+        let stack = &mut threads.current_mut().stack;
+
+        // Synchronized methods enter their monitor before the first instruction.
+        let frame = stack.current_frame_mut();
+        if let (Some(object), false) = (frame.monitor, frame.monitor_entered) {
+            frame.monitor_entered = true;
+            if !heap.monitors_mut().enter(object, thread) {
+                // The scheduler enters the monitor for us before the thread runs again.
+                threads.set_state(ThreadState::Blocked {
+                    object,
+                    entry_count: 1,
+                });
+                return Ok(());
+            }
+        }
+
+        let mut freeze_pc = false;
+        let mut out_of_memory = false;
+        let stack_size = stack.len();
+
+        let frame = stack.current_frame_mut();
+        match interpret_frame(frame, heap, budget)? {
+            VMReturn(value) => {
+                if !self.exit_frame_monitor(heap, thread, stack.current_frame_mut()) {
+                    // The monitor of the synchronized method was exited by monitorexit.
+                    let name = "java/lang/IllegalMonitorStateException".to_owned();
                     self.throw_internal_exception(class_loader, heap, stack, name)?;
+                } else if stack.last_frame() {
+                    if thread == MAIN_THREAD {
+                        *result = value;
+                    }
+                    threads.terminate();
+                    return Ok(());
+                } else {
+                    let frame = stack.current_frame_mut();
+                    let void_return = frame.method.descriptor.return_type.is_none();
+
+                    // We must not update the pc when returning from implicit frames.
+                    if frame.implicit {
+                        freeze_pc = true;
+                    }
+
+                    stack.pop();
+
+                    if !void_return {
+                        stack.current_frame_mut().push_operand(value.unwrap());
+                    }
                 }
-                VMNative() => {
-                    self.call_native(stack, native);
+            }
+            VMInvokeStatic(index) => {
+                self.invoke_static(heap, class_loader, index, stack)?;
+            }
+            VMInvokeSpecial(index) => {
+                self.invoke_special(heap, class_loader, index, stack)?;
+            }
+            VMInvokeVirtual(index) => {
+                self.invoke_virtual(heap, class_loader, index, stack)?;
+            }
+            VMInvokeInterface(index) => {
+                self.invoke_interface(heap, class_loader, index, stack)?;
+            }
+            VMInvokeDynamic(index) => {
+                self.invoke_dynamic(heap, class_loader, index, stack)?;
+            }
+            VMPutField(index) => {
+                self.put_field(class_loader, heap, index, stack)?;
+            }
+            VMGetField(index) => {
+                self.get_field(class_loader, heap, index, stack)?;
+            }
+            VMPutStatic(index) => {
+                self.put_static(static_context, index, stack);
+            }
+            VMGetStatic(index) => {
+                self.get_static(class_loader, static_context, index, stack);
+            }
+            VMAllocateReferenceArray(index) => {
+                out_of_memory = !self.allocate_reference_array(heap, class_loader, index, stack)?;
+            }
+            VMAllocateMultiArray(index, dimensions) => {
+                out_of_memory =
+                    !self.allocate_multi_array(heap, class_loader, index, dimensions, stack)?;
+            }
+            VMCheckCast(index) => {
+                self.check_cast(class_loader, heap, index, stack)?;
+            }
+            VMInstanceOf(index) => {
+                self.instance_of(class_loader, heap, index, stack)?;
+            }
+            VMMonitorEnter() => {
+                if let Some(object) = self.monitor_enter(class_loader, heap, thread, stack)? {
+                    threads.set_state(ThreadState::Blocked {
+                        object,
+                        entry_count: 1,
+                    });
                 }
-            };
+            }
+            VMMonitorExit() => {
+                self.monitor_exit(class_loader, heap, thread, stack)?;
+            }
+            VMCollectGarbage() => {
+                // The interpreter only hands control back to us here, the collection itself is
+                // done below.
+            }
+            VMOutOfMemory() => {
+                out_of_memory = true;
+            }
+            VMException() => {
+                // We must not update PC after exception resolution, the pc is placed at the
+                // handler.
+                freeze_pc = true;
+                if let Err(error) = self.handle_exception(class_loader, heap, thread, stack) {
+                    match error.exception() {
+                        // Like the default handler in Java only the thread is terminated.
+                        Some(exception) if thread != MAIN_THREAD => {
+                            let name = threads.current().name();
+                            eprintln!("Exception in thread \"{}\" {}", name, exception);
+                            threads.terminate();
+                            return Ok(());
+                        }
+                        _ => return Err(error),
+                    }
+                }
+            }
+            VMInternalException(name) => {
+                self.throw_internal_exception(class_loader, heap, stack, name)?;
+            }
+            VMNative() => {
+                self.call_native(class_loader, heap, threads, native)?;
+            }
+            VMYield() => {
+                freeze_pc = true;
+            }
+        };
 
-            // Update pc only if we did not get a new frame, or the pc is frozen.
-            if !freeze_pc && stack.len() <= stack_size {
-                let frame = stack.current_frame_mut();
-                frame.pc_next();
+        if out_of_memory {
+            // The allocating instruction is retried after a collection, if it still does not
+            // fit we give up.
+            if heap.collected_since_allocation() {
+                let stack = &mut threads.current_mut().stack;
+                self.throw_out_of_memory_error(class_loader, heap, stack)?;
+            } else {
+                freeze_pc = true;
+                self.collect_garbage(heap, threads, static_context);
             }
         }
+
+        // Update pc only if we did not get a new frame, or the pc is frozen.
+        let stack = &mut threads.current_mut().stack;
+        if !freeze_pc && stack.len() <= stack_size {
+            let frame = stack.current_frame_mut();
+            frame.pc_next();
+        }
+
+        if heap.needs_collection() {
+            self.collect_garbage(heap, threads, static_context);
+        }
+
+        Ok(())
+    }
+
+    /// Collects everything on the heap that is not reachable from the locals and operands of any
+    /// frame of any thread, from a thread object or from a static field.
+    ///
+    /// The monitor of a synchronized frame and the object a thread is blocked or waiting on are
+    /// roots as well, `monitorenter` and `Object.wait` have already taken them off the stack.
+    ///
+    /// Exceptions in flight are always on the operand stack of either the throwing frame or the
+    /// implicit frame created by `throw_internal_exception`, so they are covered by the frames.
+    fn collect_garbage(&self, heap: &mut Heap, threads: &Threads, static_context: &StaticContext) {
+        let frame_roots = threads
+            .threads()
+            .flat_map(|thread| thread.stack.frames())
+            .flat_map(|frame| {
+                let locals = frame.local_variables.iter().flatten();
+                locals.chain(frame.operand_stack.iter())
+            });
+
+        let static_roots = static_context.values().flat_map(|fields| fields.values());
+
+        let monitor_roots = threads
+            .threads()
+            .flat_map(|thread| thread.stack.frames())
+            .filter_map(|frame| frame.monitor);
+
+        let thread_roots = threads.threads().flat_map(|thread| {
+            let state = match thread.state {
+                ThreadState::Blocked { object, .. } | ThreadState::Waiting { object, .. } => {
+                    Some(object)
+                }
+                _ => None,
+            };
+            thread.object.into_iter().chain(state)
+        });
+
+        let roots = frame_roots
+            .chain(static_roots)
+            .filter_map(|value| match value {
+                Reference(reference) => *reference,
+                _ => None,
+            })
+            .chain(monitor_roots)
+            .chain(thread_roots);
+
+        heap.collect(roots);
     }
 
-    fn call_native(&self, stack: &mut Stack, native: &mut Native) {
-        let val = native.invoke(stack);
+    fn call_native(
+        &self,
+        class_loader: &mut ClassLoader,
+        heap: &mut Heap,
+        threads: &mut Threads,
+        native: &mut Native,
+    ) -> Result<()> {
+        let thread = threads.current().id;
+        let result = native.invoke(class_loader, heap, threads)?;
+
+        let stack = &mut threads.current_mut().stack;
+        let value = match result {
+            Return(value) => value,
+            // Thrown from the native frame, so it is part of the stack trace like in Java.
+            Throw(name) => return self.throw_internal_exception(class_loader, heap, stack, name),
+        };
+
+        self.exit_frame_monitor(heap, thread, stack.current_frame_mut());
         stack.pop();
 
-        if let Some(val) = val {
-            stack.current_frame_mut().push_operand(val);
+        if let Some(value) = value {
+            stack.current_frame_mut().push_operand(value);
         }
+
+        Ok(())
     }
 
     // TODO clean and abstract this (probably true for more stuff in this module)
@@ -230,10 +492,35 @@ impl VirtualMachine {
         heap: &mut Heap,
         stack: &mut Stack,
         exception_name: String,
+    ) -> Result<()> {
+        self.throw_error(class_loader, heap, stack, exception_name, None)
+    }
+
+    /// Throws an internal exception with a detail message, like the `java/lang/VerifyError` of a
+    /// class failing to link. The message is left out if it does not fit on the heap.
+    fn throw_error(
+        &self,
+        class_loader: &mut ClassLoader,
+        heap: &mut Heap,
+        stack: &mut Stack,
+        exception_name: String,
+        message: Option<String>,
     ) -> Result<()> {
         let (exception_class, init_frame) = class_loader.resolve(&exception_name)?;
 
-        let index = heap.allocate_object(&exception_name);
+        let index = heap.allocate_exception(&exception_name);
+
+        let message = message.and_then(|message| heap.allocate_string(&message));
+        if let (Some(message), HeapObject::Instance(object)) = (message, heap.get_mut(index)) {
+            object
+                .fields
+                .insert("detailMessage".to_owned(), Reference(Some(message)));
+        }
+
+        // There is no constructor filling in the trace of internal exceptions.
+        let stack_trace = exception::stack_trace(stack);
+        exception::store_stack_trace(heap, index, &stack_trace);
+
         let code = Code::new(1, 0, vec![], vec![], vec![Instruction::new(AThrow, vec![])]);
 
         let method = MethodInfo::from_code(code);
@@ -249,32 +536,112 @@ impl VirtualMachine {
         Ok(())
     }
 
-    fn handle_exception(&self, heap: &Heap, stack: &mut Stack) {
-        let reference = stack
-            .current_frame_mut()
-            .pop_operand()
-            .expect_reference()
-            .expect("Null pointer error"); // TODO;
-        let exception = heap.get(reference).expect_instance();
+    fn throw_out_of_memory_error(
+        &self,
+        class_loader: &mut ClassLoader,
+        heap: &mut Heap,
+        stack: &mut Stack,
+    ) -> Result<()> {
+        let name = "java/lang/OutOfMemoryError".to_owned();
+        self.throw_internal_exception(class_loader, heap, stack, name)
+    }
 
-        println!("Exception thrown: {:?}", exception);
+    fn throw_null_pointer_exception(
+        &self,
+        class_loader: &mut ClassLoader,
+        heap: &mut Heap,
+        stack: &mut Stack,
+    ) -> Result<()> {
+        let name = "java/lang/NullPointerException".to_owned();
+        self.throw_internal_exception(class_loader, heap, stack, name)
+    }
+
+    /// Unwinds the stack to the closest frame that handles the exception on top of the operand
+    /// stack. If no frame handles it an `UncaughtException` error is returned.
+    fn handle_exception(
+        &self,
+        class_loader: &mut ClassLoader,
+        heap: &mut Heap,
+        thread: ThreadId,
+        stack: &mut Stack,
+    ) -> Result<()> {
+        let reference = match stack.current_frame_mut().pop_operand().expect_reference() {
+            Some(reference) => reference,
+            None => return self.throw_null_pointer_exception(class_loader, heap, stack),
+        };
+
+        debug!("Exception thrown: {:?}", heap.get(reference));
 
         loop {
             let frame = stack.current_frame_mut();
+            let exception = heap.get(reference).expect_instance();
 
-            if frame.handle_exception(&exception) {
+            if frame.handle_exception(class_loader, exception)? {
                 frame.push_operand(Reference(Some(reference)));
-                break;
+                return Ok(());
             }
 
+            // Synchronized methods release their monitor on exceptional return as well.
+            self.exit_frame_monitor(heap, thread, frame);
+
             if stack.last_frame() {
-                panic!("Uncaught exception: {:?}", exception);
+                let exception = exception::read_exception(heap, reference)?;
+                return Err(Error::uncaught_exception(exception));
             }
 
             stack.pop();
         }
     }
 
+    /// Enters the monitor of the object on the operand stack. Returns the object if its monitor
+    /// is owned by another thread, the current thread must then block until it is released.
+    fn monitor_enter(
+        &self,
+        class_loader: &mut ClassLoader,
+        heap: &mut Heap,
+        thread: ThreadId,
+        stack: &mut Stack,
+    ) -> Result<Option<ReferenceType>> {
+        let object = match stack.current_frame_mut().pop_operand().expect_reference() {
+            Some(object) => object,
+            None => {
+                self.throw_null_pointer_exception(class_loader, heap, stack)?;
+                return Ok(None);
+            }
+        };
+
+        if heap.monitors_mut().enter(object, thread) {
+            Ok(None)
+        } else {
+            Ok(Some(object))
+        }
+    }
+
+    fn monitor_exit(
+        &self,
+        class_loader: &mut ClassLoader,
+        heap: &mut Heap,
+        thread: ThreadId,
+        stack: &mut Stack,
+    ) -> Result<()> {
+        let name = match stack.current_frame_mut().pop_operand().expect_reference() {
+            Some(object) if heap.monitors_mut().exit(object, thread) => return Ok(()),
+            Some(_) => "java/lang/IllegalMonitorStateException",
+            None => "java/lang/NullPointerException",
+        };
+
+        self.throw_internal_exception(class_loader, heap, stack, name.to_owned())
+    }
+
+    /// Releases the monitor held by the frame of a synchronized method. Returns false if the
+    /// monitor was not owned anymore.
+    fn exit_frame_monitor(&self, heap: &mut Heap, thread: ThreadId, frame: &mut Frame) -> bool {
+        match frame.monitor.take() {
+            Some(object) if frame.monitor_entered => heap.monitors_mut().exit(object, thread),
+            _ => true,
+        }
+    }
+
     fn put_static(&self, static_context: &mut StaticContext, index: u16, stack: &mut Stack) {
         let frame = stack.current_frame_mut();
         let value = frame.pop_operand();
@@ -315,36 +682,152 @@ impl VirtualMachine {
         stack.current_frame_mut().push_operand(v);
     }
 
+    /// Allocates the array for `anewarray`. Returns false if the heap is full, the length is then
+    /// put back on the operand stack so the instruction can be retried after a collection.
     fn allocate_reference_array(
         &self,
         heap: &mut Heap,
         class_loader: &mut ClassLoader,
         index: u16,
         stack: &mut Stack,
-    ) -> Result<()> {
+    ) -> Result<bool> {
         let frame = stack.current_frame_mut();
         let length = frame.pop_operand().expect_int();
-        let class_name = frame.class.constants.get_class_info_name(index).unwrap();
+        let component = frame
+            .class
+            .constants
+            .get_class_info_name(index)
+            .unwrap()
+            .to_owned();
+
+        if length < 0 {
+            let exception = "java/lang/NegativeArraySizeException".to_owned();
+            self.throw_internal_exception(class_loader, heap, stack, exception)?;
+            return Ok(true);
+        }
 
-        let (class, init_frame) = class_loader.resolve(class_name)?;
-        let reference = heap.allocate_reference_array(length, class);
+        // The component class is loaded but not initialized by creating an array of it.
+        if component.starts_with('[') {
+            class_loader.load_array_element(&component)?;
+        } else {
+            class_loader.load(&component)?;
+        }
 
-        frame.push_operand(Reference(Some(reference as ReferenceType)));
+        let frame = stack.current_frame_mut();
+        match heap.allocate_reference_array(length, &component) {
+            Some(reference) => frame.push_operand(Reference(Some(reference))),
+            None => {
+                frame.push_operand(Int(length));
+                return Ok(false);
+            }
+        }
 
-        if let Some(init_frame) = init_frame {
-            stack.push(init_frame);
+        Ok(true)
+    }
+
+    /// Allocates the array for `multianewarray`. Returns false if the heap is full, like
+    /// `allocate_reference_array`.
+    fn allocate_multi_array(
+        &self,
+        heap: &mut Heap,
+        class_loader: &mut ClassLoader,
+        index: u16,
+        dimensions: u8,
+        stack: &mut Stack,
+    ) -> Result<bool> {
+        let frame = stack.current_frame_mut();
+        let mut sizes: Vec<i32> = (0..dimensions)
+            .map(|_| frame.pop_operand().expect_int())
+            .collect();
+        sizes.reverse();
+
+        let descriptor = frame
+            .class
+            .constants
+            .get_class_info_name(index)
+            .unwrap()
+            .to_owned();
+
+        if sizes.iter().any(|size| *size < 0) {
+            let exception = "java/lang/NegativeArraySizeException".to_owned();
+            self.throw_internal_exception(class_loader, heap, stack, exception)?;
+            return Ok(true);
         }
 
+        class_loader.load_array_element(&descriptor)?;
+
+        let frame = stack.current_frame_mut();
+        match heap.allocate_multi_array(&descriptor, &sizes) {
+            Some(reference) => frame.push_operand(Reference(Some(reference))),
+            None => {
+                sizes
+                    .into_iter()
+                    .for_each(|size| frame.push_operand(Int(size)));
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+
+    fn check_cast(
+        &self,
+        class_loader: &mut ClassLoader,
+        heap: &mut Heap,
+        index: u16,
+        stack: &mut Stack,
+    ) -> Result<()> {
+        let frame = stack.current_frame_mut();
+        let value = frame.pop_operand();
+
+        // Null can be cast to any type.
+        if let Some(reference) = value.clone().expect_reference() {
+            let class_name = frame.class.constants.get_class_info_name(index)?;
+            let object_class = heap.get(reference).class_name();
+
+            if !class_loader.is_assignable(&object_class, class_name)? {
+                let name = "java/lang/ClassCastException".to_owned();
+                return self.throw_internal_exception(class_loader, heap, stack, name);
+            }
+        }
+
+        frame.push_operand(value);
         Ok(())
     }
 
-    fn put_field(&self, heap: &mut Heap, index: u16, stack: &mut Stack) {
+    fn instance_of(
+        &self,
+        class_loader: &mut ClassLoader,
+        heap: &Heap,
+        index: u16,
+        stack: &mut Stack,
+    ) -> Result<()> {
+        let frame = stack.current_frame_mut();
+
+        let result = if let Some(reference) = frame.pop_operand().expect_reference() {
+            let class_name = frame.class.constants.get_class_info_name(index)?;
+            let object_class = heap.get(reference).class_name();
+            class_loader.is_assignable(&object_class, class_name)?
+        } else {
+            false
+        };
+
+        frame.push_operand(Value::Int(result as i32));
+        Ok(())
+    }
+
+    fn put_field(
+        &self,
+        class_loader: &mut ClassLoader,
+        heap: &mut Heap,
+        index: u16,
+        stack: &mut Stack,
+    ) -> Result<()> {
         let value = stack.current_frame_mut().pop_operand();
-        let reference = stack
-            .current_frame_mut()
-            .pop_operand()
-            .expect_reference()
-            .expect("Null pointer error"); // TODO;
+        let reference = match stack.current_frame_mut().pop_operand().expect_reference() {
+            Some(reference) => reference,
+            None => return self.throw_null_pointer_exception(class_loader, heap, stack),
+        };
 
         if let HeapObject::Instance(object) = heap.get_mut(reference) {
             let field = stack
@@ -369,14 +852,21 @@ impl VirtualMachine {
                 heap.get_mut(reference)
             );
         }
+
+        Ok(())
     }
 
-    fn get_field(&self, heap: &mut Heap, index: u16, stack: &mut Stack) {
-        let reference = stack
-            .current_frame_mut()
-            .pop_operand()
-            .expect_reference()
-            .expect("Null pointer error"); // TODO;
+    fn get_field(
+        &self,
+        class_loader: &mut ClassLoader,
+        heap: &mut Heap,
+        index: u16,
+        stack: &mut Stack,
+    ) -> Result<()> {
+        let reference = match stack.current_frame_mut().pop_operand().expect_reference() {
+            Some(reference) => reference,
+            None => return self.throw_null_pointer_exception(class_loader, heap, stack),
+        };
         if let HeapObject::Instance(object) = heap.get_mut(reference) {
             let field = stack
                 .current_frame_mut()
@@ -405,10 +895,13 @@ impl VirtualMachine {
                 heap.get_mut(reference)
             );
         }
+
+        Ok(())
     }
 
     fn invoke_special(
         &self,
+        heap: &mut Heap,
         class_loader: &mut ClassLoader,
         index: u16,
         stack: &mut Stack,
@@ -426,13 +919,16 @@ impl VirtualMachine {
             .current_frame_mut()
             .pop_field_types(&method.descriptor.argument_types);
 
-        let object_ref = stack.current_frame_mut().pop_operand().expect_reference();
-        args.insert(0, Reference(object_ref));
+        let object_ref = match stack.current_frame_mut().pop_operand().expect_reference() {
+            Some(object_ref) => object_ref,
+            None => return self.throw_null_pointer_exception(class_loader, heap, stack),
+        };
+        args.insert(0, Reference(Some(object_ref)));
 
         let mut frame = Frame::new(class, method);
         frame.load_arguments(args);
 
-        stack.push(frame);
+        push_method_frame(heap, frame, stack);
         stack.append(&mut init_frames);
 
         Ok(())
@@ -440,7 +936,7 @@ impl VirtualMachine {
 
     fn invoke_virtual(
         &self,
-        heap: &Heap,
+        heap: &mut Heap,
         class_loader: &mut ClassLoader,
         index: u16,
         stack: &mut Stack,
@@ -459,7 +955,10 @@ impl VirtualMachine {
         let frame = stack.current_frame_mut();
         let mut args = frame.pop_field_types(&method_descriptor.argument_types);
 
-        let object_ref = frame.pop_operand().expect_reference().unwrap();
+        let object_ref = match frame.pop_operand().expect_reference() {
+            Some(object_ref) => object_ref,
+            None => return self.throw_null_pointer_exception(class_loader, heap, stack),
+        };
         args.insert(0, Reference(Some(object_ref)));
 
         let instance = heap.get(object_ref).expect_instance();
@@ -470,7 +969,7 @@ impl VirtualMachine {
         let mut frame = Frame::new(class, method);
         frame.load_arguments(args);
 
-        stack.push(frame);
+        push_method_frame(heap, frame, stack);
         stack.append(&mut init_frames);
 
         Ok(())
@@ -489,66 +988,67 @@ impl VirtualMachine {
             .constants
             .get_interface_method_ref(index)?;
 
-        let _interface_name = interface_name.to_owned();
+        let interface_name = interface_name.to_owned();
         let method_name = method_name.to_owned();
         let descriptor = descriptor.to_owned();
 
-        let object_ref = stack
-            .current_frame_mut()
-            .pop_operand()
-            .expect_reference()
-            .expect("Null pointer error"); // TODO
-        let instance = heap.get(object_ref).expect_instance();
-
-        let mut class_name = instance.class.to_owned();
+        let method_descriptor: MethodDescriptor = descriptor.as_str().try_into()?;
 
-        loop {
-            let (class, init_frame) = class_loader.resolve(&class_name)?;
-
-            if matches!(init_frame, Some(_)) {
-                panic!("Todo init_frame on resolving interface method");
-            }
+        let frame = stack.current_frame_mut();
+        let mut args = frame.pop_field_types(&method_descriptor.argument_types);
 
-            /* TODO interfaces can extend each other etc...
-            if !class.interfaces.contains(&interface_name) {
-                println!("NOT FOUND");
-                class_name = class.super_class.to_owned();
-                continue;
-            }*/
+        let object_ref = match frame.pop_operand().expect_reference() {
+            Some(object_ref) => object_ref,
+            None => return self.throw_null_pointer_exception(class_loader, heap, stack),
+        };
+        args.insert(0, Reference(Some(object_ref)));
 
-            let method = class.resolve_method(&method_name, &descriptor);
-            if matches!(method, None) {
-                class_name = class.super_class.to_owned();
-                continue;
-            }
+        let interface = class_loader.load(&interface_name)?;
+        let object_class = heap.get(object_ref).class_name();
 
-            let method = method.unwrap();
-            let mut args = stack
-                .current_frame_mut()
-                .pop_field_types(&method.descriptor.argument_types);
+        if !interface.access_flags.contains(ClassAccessFlags::INTERFACE)
+            || !class_loader.is_assignable(&object_class, &interface_name)?
+        {
+            let name = "java/lang/IncompatibleClassChangeError".to_owned();
+            return self.throw_internal_exception(class_loader, heap, stack, name);
+        }
 
-            args.insert(0, Reference(Some(object_ref)));
+        let (class, method) =
+            match class_loader.select_interface_method(&object_class, &method_name, &descriptor)? {
+                InterfaceMethod::Selected(class, method) => (class, method),
+                InterfaceMethod::Abstract => {
+                    let name = "java/lang/AbstractMethodError".to_owned();
+                    return self.throw_internal_exception(class_loader, heap, stack, name);
+                }
+                InterfaceMethod::Ambiguous => {
+                    let name = "java/lang/IncompatibleClassChangeError".to_owned();
+                    return self.throw_internal_exception(class_loader, heap, stack, name);
+                }
+            };
 
-            let mut frame = Frame::new(class, method);
-            frame.load_arguments(args);
+        // The selected method may be a default method of an interface that is not initialized.
+        let (_, init_frame) = class_loader.resolve(&class.this_class)?;
 
-            stack.push(frame);
-            if let Some(init_frame) = init_frame {
-                stack.push(init_frame);
-            }
+        let mut frame = Frame::new(class, method);
+        frame.load_arguments(args);
 
-            return Ok(());
+        push_method_frame(heap, frame, stack);
+        if let Some(init_frame) = init_frame {
+            stack.push(init_frame);
         }
+
+        Ok(())
     }
 
     fn invoke_static(
         &self,
+        heap: &mut Heap,
         class_loader: &mut ClassLoader,
         index: u16,
         stack: &mut Stack,
     ) -> Result<()> {
         let constants = &stack.current_frame().class.constants;
-        let reference = constants.get(index);
+        let reference = constants.get(index)?;
         let is_interface = matches!(reference, Constant::InterfaceMethodRef(_, _));
 
         let (class_name, method_name, descriptor) = if is_interface {
@@ -567,36 +1067,183 @@ impl VirtualMachine {
         let mut frame = Frame::new(class, method);
         frame.load_arguments(args);
 
-        stack.push(frame);
+        push_method_frame(heap, frame, stack);
         stack.append(&mut init_frames);
         Ok(())
     }
 
+    /// Invokes the target method of the call site, the call site is bootstrapped the first time.
+    fn invoke_dynamic(
+        &self,
+        heap: &mut Heap,
+        class_loader: &mut ClassLoader,
+        index: u16,
+        stack: &mut Stack,
+    ) -> Result<()> {
+        let caller = stack.current_frame().class.clone();
+        let class_name = bootstrap::call_site_class_name(&caller, index);
+        let class = match class_loader.loaded(&class_name) {
+            Some(class) => class,
+            None => class_loader.define(bootstrap::bootstrap(&caller, index)?),
+        };
+
+        let (_, _, descriptor) = caller.constants.get_invoke_dynamic(index)?;
+        let method = match class.resolve_static_method(bootstrap::TARGET_METHOD, descriptor) {
+            Some(method) => method,
+            None => return runtime_error!("Call site {} has no target method.", class_name),
+        };
+
+        let args = stack
+            .current_frame_mut()
+            .pop_field_types(&method.descriptor.argument_types);
+
+        let mut frame = Frame::new(class, method);
+        frame.load_arguments(args);
+
+        push_method_frame(heap, frame, stack);
+        Ok(())
+    }
+
     fn prepare_static_method(
         &self,
+        heap: &mut Heap,
         class_loader: &mut ClassLoader,
         class_name: &str,
         method_name: &str,
         args: Vec<Value>,
         stack: &mut Stack,
-    ) {
-        let (class, init_frame) = class_loader.resolve(class_name).expect("Unknown class"); // TODO more info in errors
-        let method = class
-            .find_public_static_method(method_name)
-            .expect("Method not found");
+    ) -> Result<()> {
+        let (class, init_frame) = class_loader.resolve(class_name)?;
+        let method = match class.find_public_static_method(method_name) {
+            Some(method) => method,
+            None => {
+                return runtime_error!(
+                    "Public static method {} not found in class {}.",
+                    method_name,
+                    class_name
+                )
+            }
+        };
 
         let mut frame = Frame::new(class, method);
         frame.load_arguments(args);
 
-        stack.push(frame);
+        push_method_frame(heap, frame, stack);
         if let Some(init_frame) = init_frame {
             stack.push(init_frame);
         }
+
+        Ok(())
+    }
+}
+
+/// Pushes the frame of an invoked method. Synchronized methods enter the monitor of the object,
+/// or of the class for static methods, when the frame is about to run. The invoke instructions
+/// have already thrown a `NullPointerException` for a null object.
+fn push_method_frame(heap: &mut Heap, mut frame: Frame, stack: &mut Stack) {
+    let flags = frame.method.access_flags;
+    if flags.contains(MethodAccessFlags::ACC_SYNCHRONIZED) {
+        frame.monitor = if flags.contains(MethodAccessFlags::ACC_STATIC) {
+            Some(heap.class_object(&frame.class.this_class))
+        } else {
+            frame.get_local(0).expect_reference()
+        };
+    }
+
+    stack.push(frame);
+}
+
+/// Formats a float or double like `Double.toString` does, in scientific notation outside of
+/// `10^-3..10^7`. The digits are the shortest representation of the value as formatted by Rust.
+pub(crate) fn java_floating_string<T>(value: T) -> String
+where
+    T: fmt::Display + fmt::LowerExp + Into<f64> + Copy,
+{
+    let magnitude = value.into().abs();
+    if magnitude.is_nan() {
+        return "NaN".to_owned();
+    }
+
+    if magnitude.is_infinite() {
+        let sign = if value.into() < 0.0 { "-" } else { "" };
+        return format!("{}Infinity", sign);
+    }
+
+    if magnitude == 0.0 || (1e-3..1e7).contains(&magnitude) {
+        let plain = value.to_string();
+        return if plain.contains('.') {
+            plain
+        } else {
+            plain + ".0"
+        };
+    }
+
+    let scientific = format!("{:e}", value);
+    let (mantissa, exponent) = scientific.split_at(scientific.find('e').unwrap());
+    if mantissa.contains('.') {
+        format!("{}E{}", mantissa, &exponent[1..])
+    } else {
+        format!("{}.0E{}", mantissa, &exponent[1..])
     }
 }
 
 impl Default for VirtualMachine {
     fn default() -> Self {
-        VirtualMachine {}
+        VirtualMachine {
+            heap: Heap::default(),
+            time_slice: 1000,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::assembler::assemble;
+    use crate::vm::frame::Frame;
+    use crate::vm::heap::{Heap, HeapObject};
+    use crate::vm::stack::Stack;
+    use crate::vm::thread::{ThreadState, Threads};
+    use crate::vm::VirtualMachine;
+    use std::collections::HashMap;
+    use std::rc::Rc;
+
+    const SOURCE: &str = r#"
+.class public test_data/Locked
+.method public synchronized hold()V
+    return
+.end method
+"#;
+
+    #[test]
+    fn collect_garbage_keeps_monitors() {
+        let class = Rc::new(assemble(SOURCE).unwrap());
+        let mut heap = Heap::default();
+        let entered = heap.allocate_object("java/lang/Object").unwrap();
+        let blocked = heap.allocate_object("java/lang/Object").unwrap();
+        let waiting = heap.allocate_object("java/lang/Object").unwrap();
+        let garbage = heap.allocate_object("java/lang/Object").unwrap();
+
+        // The main thread is about to enter the monitor of its synchronized frame and blocks,
+        // the other thread waits. Neither object is locked or on a stack.
+        let mut frame = Frame::new(class.clone(), class.methods[0].clone());
+        frame.monitor = Some(entered);
+        let mut stack = Stack::new();
+        stack.push(frame);
+        let mut threads = Threads::new(stack);
+        threads.spawn(Stack::new(), None);
+        threads.set_state(ThreadState::Blocked {
+            object: blocked,
+            entry_count: 1,
+        });
+        threads.schedule(heap.monitors_mut()).unwrap();
+        threads.wait(waiting, 1, None);
+
+        let vm = VirtualMachine::default();
+        vm.collect_garbage(&mut heap, &threads, &HashMap::new());
+
+        for reference in [entered, blocked, waiting] {
+            assert!(matches!(heap.get(reference), HeapObject::Instance(_)));
+        }
+        assert!(matches!(heap.get(garbage), HeapObject::Null));
     }
 }
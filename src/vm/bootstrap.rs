@@ -0,0 +1,602 @@
+//! The bootstrap methods of `invokedynamic` implemented by the VM:
+//! https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.invokedynamic
+//!
+//! Like in HotSpot a class is spun for each call site. The class has a static method named
+//! `TARGET_METHOD` with the descriptor of the call site, `invokedynamic` invokes it like
+//! `invokestatic` would.
+
+use crate::class::attribute::AttributeData::CodeInfo;
+use crate::class::attribute::{Attribute, Code};
+use crate::class::code::Instruction;
+use crate::class::code::Opcode;
+use crate::class::code::Opcode::*;
+use crate::class::constant::Constant::{
+    ClassRef, FieldRef, InterfaceMethodRef, MethodRef, NameAndType, StringRef, Utf8,
+};
+use crate::class::constant::{Constant, ConstantPool, MethodHandleKind};
+use crate::class::{
+    Class, ClassAccessFlags, FieldAccessFlags, FieldInfo, MethodAccessFlags, MethodInfo, Version,
+};
+use crate::error::Result;
+use crate::vm::data_type::{FieldType, MethodDescriptor};
+use std::convert::TryInto;
+use std::rc::Rc;
+
+pub const TARGET_METHOD: &str = "linkToTargetMethod";
+
+const ARGUMENT: char = '\u{1}';
+const CONSTANT: char = '\u{2}';
+
+/// The name of the class spun for the call site at constant `index` in `caller`.
+pub fn call_site_class_name(caller: &Class, index: u16) -> String {
+    format!("{}$$CallSite${}", caller.this_class, index)
+}
+
+/// Runs the bootstrap method of the call site at constant `index` in `caller`, returning the
+/// spun class.
+pub fn bootstrap(caller: &Class, index: u16) -> Result<Class> {
+    let constants = &caller.constants;
+    let (bootstrap_index, name, descriptor) = constants.get_invoke_dynamic(index)?;
+    let bootstrap = match caller.bootstrap_method(bootstrap_index) {
+        Some(bootstrap) => bootstrap,
+        None => return runtime_error!("Bootstrap method {} not found.", bootstrap_index),
+    };
+
+    let (_, method_index) = constants.get_method_handle(bootstrap.method_ref)?;
+    let (class_name, method_name, _) = constants.get_member_ref(method_index)?;
+    let this_class = call_site_class_name(caller, index);
+    let arguments = &bootstrap.arguments;
+
+    match (class_name, method_name) {
+        ("java/lang/invoke/LambdaMetafactory", "metafactory")
+        | ("java/lang/invoke/LambdaMetafactory", "altMetafactory") => {
+            let sam_descriptor = constants.get_method_type(arguments[0])?;
+            let (kind, implementation) = constants.get_method_handle(arguments[1])?;
+            let implementation = MethodHandle {
+                kind,
+                constant: constants.get(implementation)?,
+                member: constants.get_member_ref(implementation)?,
+            };
+            spin_lambda(this_class, name, descriptor, sam_descriptor, implementation)
+        }
+        ("java/lang/invoke/StringConcatFactory", "makeConcatWithConstants") => {
+            let recipe = constants.get_string(arguments[0])?;
+            let constants = arguments[1..]
+                .iter()
+                .map(|index| constant_string(constants, *index))
+                .collect::<Result<Vec<_>>>()?;
+            spin_concat(this_class, descriptor, recipe, &constants)
+        }
+        ("java/lang/invoke/StringConcatFactory", "makeConcat") => {
+            let arguments: MethodDescriptor = descriptor.try_into()?;
+            let recipe: String = arguments.argument_types.iter().map(|_| ARGUMENT).collect();
+            spin_concat(this_class, descriptor, &recipe, &[])
+        }
+        _ => runtime_error!(
+            "Unsupported bootstrap method {}.{}.",
+            class_name,
+            method_name
+        ),
+    }
+}
+
+/// The implementation method of a lambda.
+struct MethodHandle<'a> {
+    kind: &'a MethodHandleKind,
+    constant: &'a Constant,
+    member: (&'a str, &'a str, &'a str),
+}
+
+/// Spins the class of a lambda for `LambdaMetafactory.metafactory`. The class implements the
+/// functional interface, the captured arguments are stored in fields passed on to the
+/// implementation method together with the arguments of the interface method.
+fn spin_lambda(
+    this_class: String,
+    name: &str,
+    descriptor: &str,
+    sam_descriptor: &str,
+    implementation: MethodHandle,
+) -> Result<Class> {
+    let factory: MethodDescriptor = descriptor.try_into()?;
+    let interface = match &factory.return_type {
+        Some(FieldType::Object(interface)) => interface.to_owned(),
+        _ => return runtime_error!("Invalid lambda call site descriptor {}.", descriptor),
+    };
+
+    let captured = &factory.argument_types;
+    let constructor = MethodDescriptor {
+        argument_types: captured.clone(),
+        return_type: None,
+    }
+    .to_string();
+
+    let mut builder = ClassBuilder::new(&this_class);
+    let fields: Vec<FieldInfo> = captured
+        .iter()
+        .enumerate()
+        .map(|(i, field_type)| FieldInfo {
+            access_flags: FieldAccessFlags::ACC_PRIVATE | FieldAccessFlags::ACC_FINAL,
+            name: format!("arg${}", i + 1),
+            descriptor: field_type.to_string(),
+            attributes: vec![],
+        })
+        .collect();
+
+    // The constructor stores the captured arguments.
+    let mut code = CodeBuilder::default();
+    code.push(ALoad0, vec![]);
+    let object_init = builder.method_ref("java/lang/Object", "<init>", "()V", false);
+    code.push_index(InvokeSpecial, object_init);
+    for (field, slot) in fields.iter().zip(slots(captured, 1)) {
+        code.push(ALoad0, vec![]);
+        code.load(&field_type(&field.descriptor)?, slot)?;
+        let field_ref = builder.field_ref(&this_class, &field.name, &field.descriptor);
+        code.push_index(PutField, field_ref);
+    }
+    code.push(Return, vec![]);
+    // At most `this` and a captured long or double are on the stack.
+    let flags = MethodAccessFlags::ACC_PRIVATE;
+    let init = method(flags, "<init>", &constructor, 3, code)?;
+
+    // The target method of the call site creates the lambda.
+    let mut code = CodeBuilder::default();
+    let this = builder.class(&this_class);
+    code.push_index(New, this);
+    code.push(Dup, vec![]);
+    for (field_type, slot) in captured.iter().zip(slots(captured, 0)) {
+        code.load(field_type, slot)?;
+    }
+    let init_ref = builder.method_ref(&this_class, "<init>", &constructor, false);
+    code.push_index(InvokeSpecial, init_ref);
+    code.push(AReturn, vec![]);
+    let flags = MethodAccessFlags::ACC_PUBLIC | MethodAccessFlags::ACC_STATIC;
+    let max_stack = 2 + slot_count(captured);
+    let target = method(flags, TARGET_METHOD, descriptor, max_stack, code)?;
+
+    // The method of the functional interface calls the implementation.
+    let sam: MethodDescriptor = sam_descriptor.try_into()?;
+    let (class_name, method_name, method_descriptor) = implementation.member;
+    let implementation_descriptor: MethodDescriptor = method_descriptor.try_into()?;
+    let interface_ref = matches!(implementation.constant, InterfaceMethodRef(_, _));
+
+    let mut code = CodeBuilder::default();
+    if let MethodHandleKind::NewInvokeSpecial = implementation.kind {
+        let class = builder.class(class_name);
+        code.push_index(New, class);
+        code.push(Dup, vec![]);
+    }
+    for field in &fields {
+        code.push(ALoad0, vec![]);
+        let field_ref = builder.field_ref(&this_class, &field.name, &field.descriptor);
+        code.push_index(GetField, field_ref);
+    }
+    for (field_type, slot) in sam.argument_types.iter().zip(slots(&sam.argument_types, 1)) {
+        code.load(field_type, slot)?;
+    }
+
+    let method_ref = builder.method_ref(class_name, method_name, method_descriptor, interface_ref);
+    let return_type = match implementation.kind {
+        MethodHandleKind::InvokeStatic => {
+            code.push_index(InvokeStatic, method_ref);
+            implementation_descriptor.return_type
+        }
+        MethodHandleKind::InvokeVirtual => {
+            code.push_index(InvokeVirtual, method_ref);
+            implementation_descriptor.return_type
+        }
+        MethodHandleKind::InvokeSpecial => {
+            code.push_index(InvokeSpecial, method_ref);
+            implementation_descriptor.return_type
+        }
+        MethodHandleKind::InvokeInterface => {
+            let count = 1 + slot_count(&implementation_descriptor.argument_types);
+            let [index1, index2] = method_ref.to_be_bytes();
+            code.push(InvokeInterface, vec![index1, index2, count as u8, 0]);
+            implementation_descriptor.return_type
+        }
+        MethodHandleKind::NewInvokeSpecial => {
+            code.push_index(InvokeSpecial, method_ref);
+            Some(FieldType::Object(class_name.to_owned()))
+        }
+        kind => return runtime_error!("Unsupported lambda implementation {:?}.", kind),
+    };
+    code.adapt_return(return_type.as_ref(), sam.return_type.as_ref())?;
+    // The captured values and the arguments are on the stack together, below the new object of a
+    // constructor reference.
+    let max_stack = slot_count(captured) + slot_count(&sam.argument_types) + 2;
+    let flags = MethodAccessFlags::ACC_PUBLIC;
+    let sam_method = method(flags, name, sam_descriptor, max_stack, code)?;
+
+    let mut class = builder.build(vec![interface], vec![init, target, sam_method]);
+    class.fields = fields;
+    Ok(class)
+}
+
+/// Spins the class for `StringConcatFactory.makeConcatWithConstants`. The target method turns
+/// every argument into a string with `String.valueOf` and joins them with `String.concat`.
+fn spin_concat(
+    this_class: String,
+    descriptor: &str,
+    recipe: &str,
+    constants: &[String],
+) -> Result<Class> {
+    let arguments: MethodDescriptor = descriptor.try_into()?;
+    let mut builder = ClassBuilder::new(&this_class);
+    let mut code = CodeBuilder::default();
+
+    let concat = builder.method_ref(
+        "java/lang/String",
+        "concat",
+        "(Ljava/lang/String;)Ljava/lang/String;",
+        false,
+    );
+
+    let empty = builder.string("");
+    code.push_index(LdcW, empty);
+
+    let mut argument_types = arguments.argument_types.iter();
+    let mut argument_slots = slots(&arguments.argument_types, 0);
+    let mut constants = constants.iter();
+    let mut literal = String::new();
+
+    for c in recipe.chars().chain(std::iter::once(ARGUMENT)) {
+        if c != ARGUMENT && c != CONSTANT {
+            literal.push(c);
+            continue;
+        }
+
+        if c == CONSTANT {
+            match constants.next() {
+                Some(constant) => literal.push_str(constant),
+                None => return runtime_error!("Missing constant in recipe {:?}.", recipe),
+            }
+            continue;
+        }
+
+        if !literal.is_empty() {
+            let string = builder.string(&literal);
+            code.push_index(LdcW, string);
+            code.push_index(InvokeVirtual, concat);
+            literal.clear();
+        }
+
+        // The argument added after the recipe only flushes the last literal.
+        let field_type = match argument_types.next() {
+            Some(field_type) => field_type,
+            None => break,
+        };
+
+        code.load(field_type, argument_slots.next().unwrap())?;
+        let value_of = format!("({})Ljava/lang/String;", value_of_type(field_type));
+        let value_of = builder.method_ref("java/lang/String", "valueOf", &value_of, false);
+        code.push_index(InvokeStatic, value_of);
+        code.push_index(InvokeVirtual, concat);
+    }
+
+    code.push(AReturn, vec![]);
+
+    // The string built so far and an argument, a long or double takes two slots.
+    let flags = MethodAccessFlags::ACC_PUBLIC | MethodAccessFlags::ACC_STATIC;
+    let target = method(flags, TARGET_METHOD, descriptor, 3, code)?;
+    Ok(builder.build(vec![], vec![target]))
+}
+
+/// The argument type of the `String.valueOf` overload used for `field_type`.
+fn value_of_type(field_type: &FieldType) -> &'static str {
+    match field_type {
+        FieldType::Boolean => "Z",
+        FieldType::Char => "C",
+        FieldType::Byte | FieldType::Short | FieldType::Int => "I",
+        FieldType::Long => "J",
+        FieldType::Float => "F",
+        FieldType::Double => "D",
+        FieldType::Object(_) | FieldType::Array(_) => "Ljava/lang/Object;",
+    }
+}
+
+/// The text of a constant in a concatenation recipe.
+fn constant_string(constants: &ConstantPool, index: u16) -> Result<String> {
+    Ok(match constants.get(index)? {
+        StringRef(_) => constants.get_string(index)?.to_owned(),
+        Constant::Integer(i) => i.to_string(),
+        Constant::Long(l) => l.to_string(),
+        constant => return runtime_error!("Unsupported recipe constant {:?}.", constant),
+    })
+}
+
+fn field_type(descriptor: &str) -> Result<FieldType> {
+    descriptor.try_into()
+}
+
+/// The local variable slots of `types` when they are stored from slot `start`.
+fn slots(types: &[FieldType], start: u16) -> impl Iterator<Item = u16> + '_ {
+    types.iter().scan(start, |slot, field_type| {
+        let current = *slot;
+        *slot += category(field_type);
+        Some(current)
+    })
+}
+
+fn slot_count(types: &[FieldType]) -> u16 {
+    types.iter().map(category).sum()
+}
+
+fn category(field_type: &FieldType) -> u16 {
+    match field_type {
+        FieldType::Long | FieldType::Double => 2,
+        _ => 1,
+    }
+}
+
+fn method(
+    access_flags: MethodAccessFlags,
+    name: &str,
+    descriptor: &str,
+    max_stack: u16,
+    code: CodeBuilder,
+) -> Result<Rc<MethodInfo>> {
+    let descriptor: MethodDescriptor = descriptor.try_into()?;
+    let mut max_locals = slot_count(&descriptor.argument_types);
+    if !access_flags.contains(MethodAccessFlags::ACC_STATIC) {
+        max_locals += 1;
+    }
+
+    let code = Code::new(max_stack, max_locals, vec![], vec![], code.instructions);
+
+    Ok(Rc::new(MethodInfo {
+        access_flags,
+        name: name.to_owned(),
+        descriptor,
+        attributes: vec![Attribute {
+            name: "Code".to_owned(),
+            data: CodeInfo(code),
+        }],
+    }))
+}
+
+/// Builds the constant pool of a spun class.
+struct ClassBuilder {
+    this_class: String,
+    constants: ConstantPool,
+    size: u16,
+}
+
+impl ClassBuilder {
+    fn new(this_class: &str) -> Self {
+        ClassBuilder {
+            this_class: this_class.to_owned(),
+            constants: ConstantPool::new(0),
+            size: 0,
+        }
+    }
+
+    fn add(&mut self, constant: Constant) -> u16 {
+        self.constants.add(constant);
+        self.size += 1;
+        self.size
+    }
+
+    fn utf8(&mut self, string: &str) -> u16 {
+        self.add(Utf8(string.to_owned()))
+    }
+
+    fn class(&mut self, name: &str) -> u16 {
+        let name = self.utf8(name);
+        self.add(ClassRef(name))
+    }
+
+    fn string(&mut self, string: &str) -> u16 {
+        let string = self.utf8(string);
+        self.add(StringRef(string))
+    }
+
+    fn name_and_type(&mut self, name: &str, descriptor: &str) -> u16 {
+        let name = self.utf8(name);
+        let descriptor = self.utf8(descriptor);
+        self.add(NameAndType(name, descriptor))
+    }
+
+    fn field_ref(&mut self, class: &str, name: &str, descriptor: &str) -> u16 {
+        let class = self.class(class);
+        let name_and_type = self.name_and_type(name, descriptor);
+        self.add(FieldRef(class, name_and_type))
+    }
+
+    fn method_ref(&mut self, class: &str, name: &str, descriptor: &str, interface: bool) -> u16 {
+        let class = self.class(class);
+        let name_and_type = self.name_and_type(name, descriptor);
+        if interface {
+            self.add(InterfaceMethodRef(class, name_and_type))
+        } else {
+            self.add(MethodRef(class, name_and_type))
+        }
+    }
+
+    fn build(self, interfaces: Vec<String>, methods: Vec<Rc<MethodInfo>>) -> Class {
+        Class {
+            version: Version {
+                minor: 0,
+                major: 52,
+            },
+            constants: self.constants,
+            access_flags: ClassAccessFlags::FINAL
+                | ClassAccessFlags::SUPER
+                | ClassAccessFlags::SYNTHETIC,
+            this_class: self.this_class,
+            super_class: "java/lang/Object".to_owned(),
+            interfaces,
+            fields: vec![],
+            methods,
+            attributes: vec![],
+        }
+    }
+}
+
+/// Builds the instructions of a method, laid out by byte position like read code.
+#[derive(Default)]
+struct CodeBuilder {
+    instructions: Vec<Instruction>,
+}
+
+impl CodeBuilder {
+    fn push(&mut self, opcode: Opcode, operands: Vec<u8>) {
+        let spacers = operands.len();
+        self.instructions.push(Instruction::new(opcode, operands));
+        for _ in 0..spacers {
+            self.instructions.push(Instruction::operation_spacer());
+        }
+    }
+
+    fn push_index(&mut self, opcode: Opcode, index: u16) {
+        self.push(opcode, index.to_be_bytes().to_vec());
+    }
+
+    fn load(&mut self, field_type: &FieldType, slot: u16) -> Result<()> {
+        let opcode = match field_type {
+            FieldType::Long => LLoad,
+            FieldType::Float => FLoad,
+            FieldType::Double => DLoad,
+            FieldType::Object(_) | FieldType::Array(_) => ALoad,
+            _ => ILoad,
+        };
+
+        if slot > u8::MAX as u16 {
+            return runtime_error!("Too many arguments in call site.");
+        }
+
+        self.push(opcode, vec![slot as u8]);
+        Ok(())
+    }
+
+    /// Returns the value of type `from` as `to`, dropping it for void methods.
+    fn adapt_return(&mut self, from: Option<&FieldType>, to: Option<&FieldType>) -> Result<()> {
+        let opcode = match (from, to) {
+            (None, None) => Return,
+            (Some(from), None) => {
+                self.push(if category(from) == 2 { Pop2 } else { Pop }, vec![]);
+                Return
+            }
+            (Some(from), Some(to)) if is_reference(from) == is_reference(to) => match to {
+                FieldType::Long => LReturn,
+                FieldType::Float => FReturn,
+                FieldType::Double => DReturn,
+                FieldType::Object(_) | FieldType::Array(_) => AReturn,
+                _ => IReturn,
+            },
+            _ => return runtime_error!("Boxing of lambda return values is not supported."),
+        };
+
+        self.push(opcode, vec![]);
+        Ok(())
+    }
+}
+
+fn is_reference(field_type: &FieldType) -> bool {
+    matches!(field_type, FieldType::Object(_) | FieldType::Array(_))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::class::code::Opcode;
+    use crate::class::code::Opcode::*;
+    use crate::class::constant::Constant::MethodRef;
+    use crate::class::constant::MethodHandleKind;
+    use crate::vm::bootstrap::{spin_concat, spin_lambda, MethodHandle, TARGET_METHOD};
+
+    #[test]
+    fn concat_class() {
+        let class = spin_concat(
+            "Concat".to_owned(),
+            "(ILjava/lang/String;)Ljava/lang/String;",
+            "a\u{1}b\u{2}\u{1}",
+            &["c".to_owned()],
+        )
+        .unwrap();
+
+        let method = class.find_static_method(TARGET_METHOD).unwrap();
+        assert_eq!(
+            opcodes(&method.get_code().unwrap().instructions),
+            vec![
+                LdcW,
+                LdcW,
+                InvokeVirtual, // "" + "a"
+                ILoad,
+                InvokeStatic,
+                InvokeVirtual, // + String.valueOf(int)
+                LdcW,
+                InvokeVirtual, // + "bc"
+                ALoad,
+                InvokeStatic,
+                InvokeVirtual, // + String.valueOf(Object)
+                AReturn,
+            ]
+        );
+    }
+
+    #[test]
+    fn lambda_class() {
+        let constant = MethodRef(0, 0);
+        let implementation = MethodHandle {
+            kind: &MethodHandleKind::InvokeStatic,
+            constant: &constant,
+            member: ("Caller", "lambda$0", "(JI)I"),
+        };
+
+        let class = spin_lambda(
+            "Lambda".to_owned(),
+            "apply",
+            "(J)LOperator;",
+            "(I)I",
+            implementation,
+        )
+        .unwrap();
+
+        assert_eq!(class.interfaces, vec!["Operator".to_owned()]);
+        assert_eq!(class.fields[0].name, "arg$1");
+        assert_eq!(class.fields[0].descriptor, "J");
+
+        let method = class.resolve_method("apply", "(I)I").unwrap();
+        let code = method.get_code().unwrap();
+        assert_eq!(code.max_locals, 2);
+        assert_eq!(code.max_stack, 5);
+        assert_eq!(
+            opcodes(&code.instructions),
+            vec![ALoad0, GetField, ILoad, InvokeStatic, IReturn]
+        );
+
+        let target = class.resolve_static_method(TARGET_METHOD, "(J)LOperator;");
+        assert!(target.is_some());
+    }
+
+    #[test]
+    fn boxing_not_supported() {
+        let constant = MethodRef(0, 0);
+        let implementation = MethodHandle {
+            kind: &MethodHandleKind::InvokeStatic,
+            constant: &constant,
+            member: ("Caller", "lambda$0", "()I"),
+        };
+
+        let error = spin_lambda(
+            "Lambda".to_owned(),
+            "get",
+            "()LSupplier;",
+            "()Ljava/lang/Object;",
+            implementation,
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "Boxing of lambda return values is not supported."
+        );
+    }
+
+    fn opcodes(instructions: &[crate::class::code::Instruction]) -> Vec<Opcode> {
+        instructions
+            .iter()
+            .map(|i| i.opcode.clone())
+            .filter(|o| *o != OperationSpacer)
+            .collect()
+    }
+}
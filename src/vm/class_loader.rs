@@ -1,11 +1,14 @@
-use crate::class::{Class, MethodInfo};
-use crate::error::Result;
+use crate::class::{Class, ClassAccessFlags, MethodAccessFlags, MethodInfo};
+use crate::error::{Error, Result};
 use crate::io::class::ClassReader;
 use crate::vm::class_loader::ClassSource::{Folder, Jar};
 use crate::vm::frame::Frame;
-use std::collections::HashMap;
-use std::fs::File;
+use crate::vm::module::ModuleGraph;
+use crate::vm::verifier;
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
 use std::io::BufReader;
+use std::iter;
 use std::path::Path;
 use std::rc::Rc;
 use zip::ZipArchive;
@@ -84,16 +87,73 @@ impl From<&str> for ClassSource {
     }
 }
 
+/// The sources of the modules on a module path. Each entry is a modular jar, an exploded module
+/// holding `module-info.class`, or a folder of those. Jars and folders without a module
+/// descriptor are skipped since automatic modules are not supported.
+fn module_sources(module_path: &str) -> Result<Vec<ClassSource>> {
+    let path = Path::new(module_path);
+    if module_path.ends_with(".jar") || is_exploded_module(path) {
+        return Ok(vec![module_source(path)]);
+    }
+    if !path.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut entries = fs::read_dir(path)?
+        .map(|e| e.map(|e| e.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    entries.sort();
+
+    Ok(entries
+        .iter()
+        .filter(|p| p.extension().is_some_and(|e| e == "jar") || is_exploded_module(p))
+        .map(|p| module_source(p))
+        .collect())
+}
+
+fn is_exploded_module(path: &Path) -> bool {
+    path.join("module-info.class").is_file()
+}
+
+fn module_source(path: &Path) -> ClassSource {
+    let mut path = path.to_string_lossy().into_owned();
+    if !path.ends_with(".jar") && !path.ends_with('/') {
+        path.push('/');
+    }
+    path.as_str().into()
+}
+
+/// Interfaces implemented by all arrays.
+const ARRAY_INTERFACES: &[&str] = &["java/lang/Cloneable", "java/io/Serializable"];
+
+/// The method selected for an interface invocation, see `ClassLoader::select_interface_method`.
+pub enum InterfaceMethod {
+    Selected(Rc<Class>, Rc<MethodInfo>),
+
+    /// No non-abstract method was found, `java/lang/AbstractMethodError` must be thrown.
+    Abstract,
+
+    /// More than one maximally-specific default method was found,
+    /// `java/lang/IncompatibleClassChangeError` must be thrown.
+    Ambiguous,
+}
+
 pub struct ClassLoader {
     classes: HashMap<String, Rc<Class>>,
+    initialized: HashSet<String>,
+    loading: HashSet<String>, // Classes whose super types are being loaded.
     sources: Vec<ClassSource>,
+    module_sources: Vec<ClassSource>,
 }
 
 impl ClassLoader {
     pub fn new() -> Self {
         ClassLoader {
             classes: HashMap::new(),
+            initialized: HashSet::new(),
+            loading: HashSet::new(),
             sources: Vec::new(),
+            module_sources: Vec::new(),
         }
     }
 
@@ -101,19 +161,232 @@ impl ClassLoader {
         self.sources = paths.iter().map(|s| (*s).into()).collect();
     }
 
+    /// Sets the module path, classes not found on the class path are searched for in the modules.
+    pub fn set_module_path(&mut self, paths: Vec<&str>) -> Result<()> {
+        let mut sources = Vec::new();
+        for path in paths {
+            sources.extend(module_sources(path)?);
+        }
+        self.module_sources = sources;
+        Ok(())
+    }
+
+    /// Builds the graph of the modules on the module path from their `module-info` classes. Use
+    /// `ModuleGraph::missing_requirements` to find the modules that could not be found.
+    pub fn module_graph(&self) -> Result<ModuleGraph> {
+        let mut graph = ModuleGraph::new();
+        for source in &self.module_sources {
+            if let Some(module) = source
+                .load_class("module-info")?
+                .as_ref()
+                .and_then(Class::module)
+            {
+                graph.add(module.clone());
+            }
+        }
+        Ok(graph)
+    }
+
+    /// Resolves the class, the first time a class is resolved the frame that initializes it is
+    /// returned as well.
     pub fn resolve(&mut self, class_name: &str) -> Result<(Rc<Class>, Option<Frame>)> {
+        let class = self.load(class_name)?;
+        if !self.initialized.insert(class_name.to_owned()) {
+            return Ok((class, None));
+        }
+
+        let init_frame = class.find_static_method("<clinit>").map(|m| {
+            let mut frame = Frame::new(class.clone(), m);
+            frame.implicit = true;
+            frame
+        });
+
+        Ok((class, init_frame))
+    }
+
+    /// Loads the class without initializing it, used when only the class structure is needed.
+    pub fn load(&mut self, class_name: &str) -> Result<Rc<Class>> {
         if let Some(class) = self.classes.get(class_name) {
-            Ok((class.clone(), None))
+            Ok(class.clone())
         } else {
-            let class = self.load_class(class_name)?;
-            let init_frame = class.find_static_method("<clinit>").map(|m| {
-                let mut frame = Frame::new(class.clone(), m);
-                frame.implicit = true;
-                frame
-            });
+            self.load_class(class_name)
+        }
+    }
+
+    /// Returns the class if it is already loaded or defined.
+    pub fn loaded(&self, class_name: &str) -> Option<Rc<Class>> {
+        self.classes.get(class_name).cloned()
+    }
+
+    /// Defines a class created at runtime, like the classes spun for `invokedynamic` call sites.
+    pub fn define(&mut self, class: Class) -> Rc<Class> {
+        let class = Rc::new(class);
+        self.classes.insert(class.this_class.clone(), class.clone());
+        class
+    }
+
+    /// Loads the class of the elements of the array type `descriptor`, like `[[Ljava/lang/String;`,
+    /// unless the elements are primitives.
+    pub fn load_array_element(&mut self, descriptor: &str) -> Result<()> {
+        match descriptor.trim_start_matches('[').strip_prefix('L') {
+            Some(element) => self.load(element.trim_end_matches(';')).map(|_| ()),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns true if a value of type `from` can be assigned to type `to`, according to the
+    /// rules of `checkcast`:
+    /// https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.checkcast
+    ///
+    /// Types are either class names or array descriptors, like `java/lang/String` or `[[I`.
+    pub fn is_assignable(&mut self, from: &str, to: &str) -> Result<bool> {
+        if from == to || to == "java/lang/Object" {
+            return Ok(true);
+        }
+
+        match (from.strip_prefix('['), to.strip_prefix('[')) {
+            (Some(from_component), Some(to_component)) => {
+                // Primitive components are only assignable to the same type, checked above.
+                match (
+                    reference_component(from_component),
+                    reference_component(to_component),
+                ) {
+                    (Some(from), Some(to)) => self.is_assignable(from, to),
+                    _ => Ok(false),
+                }
+            }
+            (Some(_), None) => Ok(ARRAY_INTERFACES.contains(&to)),
+            (None, Some(_)) => Ok(false),
+            (None, None) => self.is_subclass(from, to),
+        }
+    }
+
+    /// Selects the method to invoke when `invokeinterface` is used on an object of class
+    /// `class_name`, according to:
+    /// https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.invokeinterface
+    ///
+    /// Methods declared by the class or its super classes are preferred. If there are none the
+    /// maximally-specific default method of the super interfaces is selected.
+    pub fn select_interface_method(
+        &mut self,
+        class_name: &str,
+        method_name: &str,
+        descriptor: &str,
+    ) -> Result<InterfaceMethod> {
+        let mut current_class = class_name.to_owned();
+        while !current_class.is_empty() {
+            let class = self.load(&current_class)?;
+
+            if let Some(method) = find_instance_method(&class, method_name, descriptor) {
+                if method
+                    .access_flags
+                    .contains(MethodAccessFlags::ACC_ABSTRACT)
+                {
+                    return Ok(InterfaceMethod::Abstract);
+                }
+                return Ok(InterfaceMethod::Selected(class, method));
+            }
+
+            current_class = class.super_class.to_owned();
+        }
+
+        let mut defaults = self
+            .maximally_specific_methods(class_name, method_name, descriptor)?
+            .into_iter()
+            .filter(|(_, m)| !m.access_flags.contains(MethodAccessFlags::ACC_ABSTRACT))
+            .collect::<Vec<_>>();
+
+        match defaults.len() {
+            0 => Ok(InterfaceMethod::Abstract),
+            1 => {
+                let (class, method) = defaults.remove(0);
+                Ok(InterfaceMethod::Selected(class, method))
+            }
+            _ => Ok(InterfaceMethod::Ambiguous),
+        }
+    }
 
-            Ok((class, init_frame))
+    /// Finds the methods declared by super interfaces of `class_name` that are not overridden by
+    /// a more specific super interface, see JVMS 5.4.3.3.
+    fn maximally_specific_methods(
+        &mut self,
+        class_name: &str,
+        method_name: &str,
+        descriptor: &str,
+    ) -> Result<Vec<(Rc<Class>, Rc<MethodInfo>)>> {
+        let mut candidates = Vec::new();
+        for interface_name in self.super_interfaces(class_name)? {
+            let interface = self.load(&interface_name)?;
+            if let Some(method) = find_instance_method(&interface, method_name, descriptor) {
+                candidates.push((interface, method));
+            }
+        }
+
+        let mut methods = Vec::new();
+        for (interface, method) in &candidates {
+            let mut overridden = false;
+            for (other, _) in &candidates {
+                if other.this_class != interface.this_class
+                    && self.is_subclass(&other.this_class, &interface.this_class)?
+                {
+                    overridden = true;
+                    break;
+                }
+            }
+
+            if !overridden {
+                methods.push((interface.clone(), method.clone()));
+            }
+        }
+
+        Ok(methods)
+    }
+
+    /// All interfaces implemented by `class_name`, directly or through super classes and super
+    /// interfaces.
+    fn super_interfaces(&mut self, class_name: &str) -> Result<Vec<String>> {
+        let mut interfaces = Vec::new();
+        let mut visited = HashSet::new();
+        let mut pending = vec![class_name.to_owned()];
+
+        while let Some(name) = pending.pop() {
+            if name.is_empty() || name == "java/lang/Object" || !visited.insert(name.clone()) {
+                continue;
+            }
+
+            let class = self.load(&name)?;
+            if class.access_flags.contains(ClassAccessFlags::INTERFACE) {
+                interfaces.push(name);
+            }
+
+            pending.push(class.super_class.to_owned());
+            pending.extend(class.interfaces.iter().cloned());
         }
+
+        Ok(interfaces)
+    }
+
+    /// Returns true if `class_name` is `target`, extends it or implements it, directly or through
+    /// a super class or super interface.
+    fn is_subclass(&mut self, class_name: &str, target: &str) -> Result<bool> {
+        let mut pending = vec![class_name.to_owned()];
+
+        while let Some(name) = pending.pop() {
+            if name == target {
+                return Ok(true);
+            }
+
+            // Object is the root of all classes and implements nothing, no need to load it.
+            if name.is_empty() || name == "java/lang/Object" {
+                continue;
+            }
+
+            let class = self.load(&name)?;
+            pending.push(class.super_class.to_owned());
+            pending.extend(class.interfaces.iter().cloned());
+        }
+
+        Ok(false)
     }
 
     pub fn resolve_static_method(
@@ -136,10 +409,12 @@ impl ClassLoader {
             }
 
             if class.super_class.is_empty() {
-                panic!(
+                return runtime_error!(
                     "Could not resolve method {}:{} on class {}",
-                    method_name, descriptor, class_name
-                )
+                    method_name,
+                    descriptor,
+                    class_name
+                );
             }
 
             current_class = class.super_class.to_owned();
@@ -166,29 +441,142 @@ impl ClassLoader {
             }
 
             if class.super_class.is_empty() {
-                panic!(
+                return runtime_error!(
                     "Could not resolve method {}:{} on class {}",
-                    method_name, descriptor, class_name
-                )
+                    method_name,
+                    descriptor,
+                    class_name
+                );
             }
 
             current_class = class.super_class.to_owned();
         }
     }
 
-    fn load_class(&mut self, class_name: &str) -> Result<Rc<Class>> {
-        for source in &self.sources {
+    /// Reads a class from the class path without loading or verifying it.
+    pub fn read_class(&self, class_name: &str) -> Result<Class> {
+        match self.find_class(class_name)? {
+            Some(class) => Ok(class),
+            None => runtime_error!("Could not resolve class {}", class_name),
+        }
+    }
+
+    fn find_class(&self, class_name: &str) -> Result<Option<Class>> {
+        for source in self.sources.iter().chain(&self.module_sources) {
             if let Some(class) = source.load_class(class_name)? {
-                let c = Rc::new(class);
-                let r = c.clone();
+                return Ok(Some(class));
+            }
+        }
+        Ok(None)
+    }
 
-                self.classes = self.classes.clone();
-                self.classes.insert(c.this_class.clone(), c);
+    fn load_class(&mut self, class_name: &str) -> Result<Rc<Class>> {
+        match self.try_load_class(class_name)? {
+            Some(class) => Ok(class),
+            None => runtime_error!("Could not resolve class {}", class_name),
+        }
+    }
+
+    /// Loads the class if it can be found, the direct super types found are loaded first. A class
+    /// that is its own super type throws `java/lang/ClassCircularityError`.
+    fn try_load_class(&mut self, class_name: &str) -> Result<Option<Rc<Class>>> {
+        if let Some(class) = self.classes.get(class_name) {
+            return Ok(Some(class.clone()));
+        }
 
-                return Ok(r);
+        if self.loading.contains(class_name) {
+            return Err(Error::linkage_error(
+                "java/lang/ClassCircularityError",
+                class_name.replace('/', "."),
+            ));
+        }
+
+        let class = match self.find_class(class_name)? {
+            Some(class) => class,
+            None => return Ok(None),
+        };
+
+        self.loading.insert(class_name.to_owned());
+        let checked = self.check_sealed_super_types(&class);
+        self.loading.remove(class_name);
+        checked?;
+        verifier::verify(&class)?;
+
+        let c = Rc::new(class);
+        let r = c.clone();
+
+        self.classes = self.classes.clone();
+        self.classes.insert(c.this_class.clone(), c);
+
+        Ok(Some(r))
+    }
+
+    /// Checks that the sealed direct super types of `class` permit it, see JVMS 5.3.5. All classes
+    /// are in the unnamed module, so only the packages are compared. Super types that can not be
+    /// found are left to fail when they are resolved.
+    fn check_sealed_super_types(&mut self, class: &Class) -> Result<()> {
+        let super_types = iter::once(&class.super_class)
+            .chain(&class.interfaces)
+            .filter(|name| !name.is_empty());
+
+        for name in super_types {
+            let super_type = match self.try_load_class(name)? {
+                Some(super_type) => super_type,
+                None => continue,
+            };
+            let permitted = match super_type.permitted_subclasses() {
+                Some(permitted) => permitted,
+                None => continue,
+            };
+
+            // Outside of named modules the subclass must be in the package of the sealed type.
+            let same_package = package(&class.this_class) == package(name);
+            if !same_package || !permitted.contains(&class.this_class) {
+                let kind = if super_type
+                    .access_flags
+                    .contains(ClassAccessFlags::INTERFACE)
+                {
+                    "implement sealed interface"
+                } else {
+                    "inherit from sealed class"
+                };
+                return Err(Error::linkage_error(
+                    "java/lang/IncompatibleClassChangeError",
+                    format!(
+                        "class {} cannot {} {}",
+                        class.this_class.replace('/', "."),
+                        kind,
+                        name.replace('/', ".")
+                    ),
+                ));
             }
         }
+        Ok(())
+    }
+}
+
+/// The package of a class in internal form, empty for the unnamed package.
+fn package(class_name: &str) -> &str {
+    class_name.rfind('/').map_or("", |i| &class_name[..i])
+}
+
+/// Finds a method that can be invoked on an instance, static and private methods does not
+/// take part in method selection.
+fn find_instance_method(class: &Class, name: &str, descriptor: &str) -> Option<Rc<MethodInfo>> {
+    class.resolve_method(name, descriptor).filter(|m| {
+        !m.access_flags
+            .intersects(MethodAccessFlags::ACC_STATIC | MethodAccessFlags::ACC_PRIVATE)
+    })
+}
 
-        panic!("Could not resolve class {}", class_name)
+/// Returns the type of the elements of an array with component descriptor `component`, or `None`
+/// if the elements are primitives.
+fn reference_component(component: &str) -> Option<&str> {
+    if component.starts_with('[') {
+        Some(component)
+    } else {
+        component
+            .strip_prefix('L')
+            .and_then(|c| c.strip_suffix(';'))
     }
 }
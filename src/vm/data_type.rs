@@ -1,6 +1,9 @@
 use crate::error::Error;
+use crate::error::ErrorKind::ParseError;
 use crate::vm::data_type::Value::*;
 use std::convert::{TryFrom, TryInto};
+use std::fmt;
+use std::fmt::Formatter;
 
 pub type BooleanType = bool;
 pub type CharType = char;
@@ -76,14 +79,15 @@ impl Value {
 
     pub fn expect_type(self, field_type: &FieldType) -> Value {
         match field_type {
-            FieldType::Byte => Byte(self.expect_byte()),
-            FieldType::Char => Char(self.expect_char()),
+            // Like in the JVM the narrow integer types are ints on the operand stack.
+            FieldType::Byte => Int(self.expect_int_like()),
+            FieldType::Char => Int(self.expect_int_like()),
             FieldType::Double => Double(self.expect_double()),
             FieldType::Float => Float(self.expect_float()),
             FieldType::Int => Int(self.expect_int_like()),
             FieldType::Boolean => Int(self.expect_int_like()),
             FieldType::Long => Long(self.expect_long()),
-            FieldType::Short => Short(self.expect_short()),
+            FieldType::Short => Int(self.expect_int_like()),
             FieldType::Object(_) => Reference(self.expect_reference()),
             FieldType::Array(_) => Reference(self.expect_reference()),
         }
@@ -147,11 +151,10 @@ impl FieldType {
     }
 }
 
-impl<'a> TryFrom<&'a str> for FieldType {
-    type Error = Error;
-
-    fn try_from(s: &'a str) -> std::result::Result<FieldType, Self::Error> {
-        Ok(match s.chars().next().unwrap() {
+impl FieldType {
+    /// Parses the field type at the start of `s`, the rest of the string is ignored.
+    fn parse_prefix(s: &str) -> Option<FieldType> {
+        Some(match s.chars().next()? {
             'B' => FieldType::Byte,
             'C' => FieldType::Char,
             'D' => FieldType::Double,
@@ -160,16 +163,48 @@ impl<'a> TryFrom<&'a str> for FieldType {
             'J' => FieldType::Long,
             'S' => FieldType::Short,
             'Z' => FieldType::Boolean,
-            'L' => {
-                let index = s.find(';').unwrap();
-                FieldType::Object((&s[1..index]).to_owned())
-            }
-            '[' => FieldType::Array(Box::new(s[1..].try_into()?)),
-            _ => panic!("Invalid field type '{}'", s),
+            'L' => match s.find(';')? {
+                1 => return None,
+                index => FieldType::Object(s[1..index].to_owned()),
+            },
+            '[' => FieldType::Array(Box::new(Self::parse_prefix(&s[1..])?)),
+            _ => return None,
         })
     }
 }
 
+impl<'a> TryFrom<&'a str> for FieldType {
+    type Error = Error;
+
+    fn try_from(s: &'a str) -> std::result::Result<FieldType, Self::Error> {
+        match FieldType::parse_prefix(s) {
+            Some(field_type) if field_type.str_len() == s.len() => Ok(field_type),
+            _ => Err(Error::new(
+                ParseError,
+                Some(format!("Invalid field descriptor '{}'.", s)),
+            )),
+        }
+    }
+}
+
+/// Formats the type as a descriptor, like `I` or `[Ljava/lang/String;`.
+impl fmt::Display for FieldType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldType::Byte => write!(f, "B"),
+            FieldType::Char => write!(f, "C"),
+            FieldType::Double => write!(f, "D"),
+            FieldType::Float => write!(f, "F"),
+            FieldType::Int => write!(f, "I"),
+            FieldType::Long => write!(f, "J"),
+            FieldType::Short => write!(f, "S"),
+            FieldType::Boolean => write!(f, "Z"),
+            FieldType::Object(class_name) => write!(f, "L{};", class_name),
+            FieldType::Array(component) => write!(f, "[{}", component),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct FieldRef {
     pub class_name: String,
@@ -223,24 +258,24 @@ pub struct MethodDescriptor {
 }
 
 impl MethodDescriptor {
-    fn parse_argument_str(s: &str) -> std::result::Result<Vec<FieldType>, Error> {
+    fn parse_argument_str(s: &str) -> Option<Vec<FieldType>> {
         let mut argument_types = Vec::new();
 
         let mut i = 0;
         while i < s.len() {
-            let field_type: FieldType = (&s[i..]).try_into()?;
+            let field_type = FieldType::parse_prefix(&s[i..])?;
             i += field_type.str_len();
 
             argument_types.push(field_type);
         }
-        Ok(argument_types)
+        Some(argument_types)
     }
 
-    fn parse_return_type(s: &str) -> std::result::Result<Option<FieldType>, Error> {
+    fn parse_return_type(s: &str) -> Option<Option<FieldType>> {
         if s == "V" {
-            Ok(None)
+            Some(None)
         } else {
-            Ok(Some(s.try_into()?))
+            s.try_into().ok().map(Some)
         }
     }
 }
@@ -249,22 +284,45 @@ impl TryFrom<&str> for MethodDescriptor {
     type Error = Error;
 
     fn try_from(s: &str) -> std::result::Result<MethodDescriptor, Self::Error> {
+        let invalid = || {
+            Error::new(
+                ParseError,
+                Some(format!("Invalid method descriptor '{}'.", s)),
+            )
+        };
+
         let parts: Vec<&str> = s.split(|c| c == '(' || c == ')').collect();
-        if parts.len() != 3 || !parts[0].is_empty() || parts[2].len() < 1 {
-            panic!("Invalid method descriptor '{}'.", s);
+        if parts.len() != 3 || !parts[0].is_empty() || parts[2].is_empty() {
+            return Err(invalid());
         }
 
         Ok(MethodDescriptor {
-            argument_types: Self::parse_argument_str(parts[1])?,
-            return_type: Self::parse_return_type(parts[2])?,
+            argument_types: Self::parse_argument_str(parts[1]).ok_or_else(invalid)?,
+            return_type: Self::parse_return_type(parts[2]).ok_or_else(invalid)?,
         })
     }
 }
 
+/// Formats the descriptor as a raw JVM method descriptor, like `(IJ)F`.
+impl fmt::Display for MethodDescriptor {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "(")?;
+        for argument_type in &self.argument_types {
+            write!(f, "{}", argument_type)?;
+        }
+        write!(f, ")")?;
+
+        match &self.return_type {
+            Some(return_type) => write!(f, "{}", return_type),
+            None => write!(f, "V"),
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::vm::data_type::FieldType::*;
-    use crate::vm::data_type::MethodDescriptor;
+    use crate::vm::data_type::{FieldType, MethodDescriptor};
     use std::convert::TryInto;
 
     #[test]
@@ -310,4 +368,41 @@ mod test {
             }
         );
     }
+
+    #[test]
+    fn parse_invalid_descriptors() {
+        for raw in &["", "Ljava/lang/Object", "L;", "[", "Q", "II"] {
+            let error = TryInto::<FieldType>::try_into(*raw).unwrap_err();
+            assert_eq!(
+                error.to_string(),
+                format!("Invalid field descriptor '{}'.", raw)
+            );
+        }
+
+        for raw in &[
+            "",
+            "()",
+            "(I",
+            "I)V",
+            "(Ljava/lang/Object)V",
+            "(X)V",
+            "()II",
+        ] {
+            let error = TryInto::<MethodDescriptor>::try_into(*raw).unwrap_err();
+            assert_eq!(
+                error.to_string(),
+                format!("Invalid method descriptor '{}'.", raw)
+            );
+        }
+    }
+
+    #[test]
+    fn display_method_descriptor() {
+        let raw = "(BCDFIJSZLjava/lang/Object;[[I)[Ljava/lang/String;";
+        let descriptor: MethodDescriptor = raw.try_into().unwrap();
+        assert_eq!(descriptor.to_string(), raw);
+
+        let descriptor: MethodDescriptor = "()V".try_into().unwrap();
+        assert_eq!(descriptor.to_string(), "()V");
+    }
 }
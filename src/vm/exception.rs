@@ -0,0 +1,266 @@
+use crate::error::Result;
+use crate::vm::data_type::ReferenceType;
+use crate::vm::data_type::Value::{Int, Reference};
+use crate::vm::frame::Frame;
+use crate::vm::heap::Heap;
+use crate::vm::heap::HeapObject::Instance;
+use crate::vm::stack::Stack;
+use std::fmt;
+use std::fmt::Formatter;
+
+/// A Java exception that was not caught by any frame.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JavaException {
+    pub class_name: String,
+    pub message: Option<String>,
+
+    /// The frames of the stack when the exception was created, innermost frame first.
+    pub stack_trace: Vec<StackTraceElement>,
+}
+
+/// A frame in a Java stack trace, like `java.lang.StackTraceElement`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StackTraceElement {
+    pub class_name: String,
+    pub method_name: String,
+    pub file_name: Option<String>,
+    pub line_number: Option<u16>,
+}
+
+impl StackTraceElement {
+    pub fn from_frame(frame: &Frame) -> Self {
+        StackTraceElement {
+            class_name: frame.class.this_class.to_owned(),
+            method_name: frame.method.name.to_owned(),
+            file_name: frame.class.source_file().map(str::to_owned),
+            line_number: frame.code.as_ref().and_then(|c| c.line_number(frame.pc)),
+        }
+    }
+}
+
+/// Captures the Java stack trace of `stack`, innermost frame first.
+///
+/// Frames created by the VM itself, like the one throwing internal exceptions, are not part of
+/// the trace.
+pub fn stack_trace(stack: &Stack) -> Vec<StackTraceElement> {
+    stack
+        .frames()
+        .rev()
+        .filter(|frame| frame.method.name != "<Internal>")
+        .map(StackTraceElement::from_frame)
+        .collect()
+}
+
+/// Captures the stack trace for `Throwable.fillInStackTrace` called on `throwable`.
+///
+/// Like in HotSpot the trace starts where the throwable was created, the frames filling in the
+/// trace and the constructors of the throwable itself are left out.
+pub fn throwable_stack_trace(stack: &Stack, throwable: ReferenceType) -> Vec<StackTraceElement> {
+    let this = Some(Reference(Some(throwable)));
+    stack
+        .frames()
+        .rev()
+        .skip_while(|frame| {
+            frame.method.name == "fillInStackTrace"
+                || (frame.method.name == "<init>" && frame.local_variables.first() == Some(&this))
+        })
+        .filter(|frame| frame.method.name != "<Internal>")
+        .map(StackTraceElement::from_frame)
+        .collect()
+}
+
+/// Stores `stack_trace` in the `stackTrace` field of `throwable` as an array of
+/// `java/lang/StackTraceElement`. The strings of the elements are interned, like in HotSpot.
+///
+/// Returns false if the trace does not fit on the heap, the field is left as it was then.
+pub fn store_stack_trace(
+    heap: &mut Heap,
+    throwable: ReferenceType,
+    stack_trace: &[StackTraceElement],
+) -> bool {
+    let size = stack_trace.len() as i32;
+    let array = match heap.allocate_reference_array(size, "java/lang/StackTraceElement") {
+        Some(array) => array,
+        None => return false,
+    };
+
+    for (i, element) in stack_trace.iter().enumerate() {
+        let reference = match allocate_element(heap, element) {
+            Some(reference) => reference,
+            None => return false,
+        };
+        heap.get_mut(array).expect_mut_reference_array().1[i] = Some(reference);
+    }
+
+    if let Instance(object) = heap.get_mut(throwable) {
+        object
+            .fields
+            .insert("stackTrace".to_owned(), Reference(Some(array)));
+    }
+    true
+}
+
+fn allocate_element(heap: &mut Heap, element: &StackTraceElement) -> Option<ReferenceType> {
+    let declaring_class = heap.intern_string(&element.class_name.replace('/', "."))?;
+    let method_name = heap.intern_string(&element.method_name)?;
+    let file_name = match &element.file_name {
+        Some(file_name) => Some(heap.intern_string(file_name)?),
+        None => None,
+    };
+    let line_number = element.line_number.map_or(-1, i32::from);
+
+    let reference = heap.allocate_object("java/lang/StackTraceElement")?;
+    if let Instance(object) = heap.get_mut(reference) {
+        let fields = &mut object.fields;
+        fields.insert(
+            "declaringClass".to_owned(),
+            Reference(Some(declaring_class)),
+        );
+        fields.insert("methodName".to_owned(), Reference(Some(method_name)));
+        fields.insert("fileName".to_owned(), Reference(file_name));
+        fields.insert("lineNumber".to_owned(), Int(line_number));
+    }
+    Some(reference)
+}
+
+/// Reads the throwable at `reference`, with the stack trace stored by `store_stack_trace`.
+pub fn read_exception(heap: &Heap, reference: ReferenceType) -> Result<JavaException> {
+    let object = heap.get(reference).expect_instance();
+
+    let message = match object.fields.get("detailMessage") {
+        Some(Reference(Some(message))) => Some(heap.get_string(*message)?),
+        _ => None,
+    };
+
+    let elements = match object.fields.get("stackTrace") {
+        Some(Reference(Some(array))) => heap.get(*array).expect_reference_array().1.clone(),
+        _ => vec![],
+    };
+
+    let stack_trace = elements
+        .into_iter()
+        .flatten()
+        .map(|element| read_element(heap, element))
+        .collect::<Result<_>>()?;
+
+    Ok(JavaException {
+        class_name: object.class.to_owned(),
+        message,
+        stack_trace,
+    })
+}
+
+fn read_element(heap: &Heap, reference: ReferenceType) -> Result<StackTraceElement> {
+    let fields = &heap.get(reference).expect_instance().fields;
+    let string = |name: &str| match fields.get(name) {
+        Some(Reference(Some(string))) => heap.get_string(*string).map(Some),
+        _ => Ok(None),
+    };
+
+    Ok(StackTraceElement {
+        class_name: string("declaringClass")?
+            .unwrap_or_default()
+            .replace('.', "/"),
+        method_name: string("methodName")?.unwrap_or_default(),
+        file_name: string("fileName")?,
+        line_number: match fields.get("lineNumber") {
+            Some(Int(line)) if *line >= 0 => Some(*line as u16),
+            _ => None,
+        },
+    })
+}
+
+impl fmt::Display for JavaException {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.class_name.replace('/', "."))?;
+        if let Some(message) = &self.message {
+            write!(f, ": {}", message)?;
+        }
+
+        for element in &self.stack_trace {
+            write!(f, "\n\tat {}", element)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for StackTraceElement {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}.{}",
+            self.class_name.replace('/', "."),
+            self.method_name
+        )?;
+
+        match (&self.file_name, self.line_number) {
+            (Some(file), Some(line)) => write!(f, "({}:{})", file, line),
+            (Some(file), None) => write!(f, "({})", file),
+            _ => write!(f, "(Unknown Source)"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::vm::exception::{
+        read_exception, store_stack_trace, JavaException, StackTraceElement,
+    };
+    use crate::vm::heap::Heap;
+
+    #[test]
+    fn display() {
+        let exception = JavaException {
+            class_name: "java/lang/RuntimeException".to_owned(),
+            message: Some("Failure".to_owned()),
+            stack_trace: vec![
+                StackTraceElement {
+                    class_name: "a/B".to_owned(),
+                    method_name: "c".to_owned(),
+                    file_name: Some("B.java".to_owned()),
+                    line_number: Some(10),
+                },
+                StackTraceElement {
+                    class_name: "a/D".to_owned(),
+                    method_name: "e".to_owned(),
+                    file_name: None,
+                    line_number: None,
+                },
+            ],
+        };
+
+        assert_eq!(
+            exception.to_string(),
+            "java.lang.RuntimeException: Failure\n\tat a.B.c(B.java:10)\n\tat a.D.e(Unknown Source)"
+        );
+    }
+
+    #[test]
+    fn store_and_read_stack_trace() {
+        let mut heap = Heap::default();
+        let throwable = heap.allocate_object("java/lang/Error").unwrap();
+
+        let stack_trace = vec![
+            StackTraceElement {
+                class_name: "a/B".to_owned(),
+                method_name: "c".to_owned(),
+                file_name: Some("B.java".to_owned()),
+                line_number: Some(10),
+            },
+            StackTraceElement {
+                class_name: "a/D".to_owned(),
+                method_name: "e".to_owned(),
+                file_name: None,
+                line_number: None,
+            },
+        ];
+
+        assert!(store_stack_trace(&mut heap, throwable, &stack_trace));
+
+        let exception = read_exception(&heap, throwable).unwrap();
+        assert_eq!(exception.class_name, "java/lang/Error");
+        assert_eq!(exception.message, None);
+        assert_eq!(exception.stack_trace, stack_trace);
+    }
+}
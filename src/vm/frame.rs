@@ -1,8 +1,10 @@
-use crate::class::attribute::{Code, ExceptionHandler};
+use crate::class::attribute::Code;
 use crate::class::code::Opcode::OperationSpacer;
 use crate::class::{Class, MethodInfo};
+use crate::error::Result;
+use crate::vm::class_loader::ClassLoader;
 use crate::vm::data_type::Value::*;
-use crate::vm::data_type::{FieldType, Value};
+use crate::vm::data_type::{FieldType, ReferenceType, Value};
 use crate::vm::Object;
 use core::fmt;
 use std::cmp::{max, min};
@@ -19,6 +21,8 @@ pub struct Frame {
     pub method: Rc<MethodInfo>,
     pub code: Option<Rc<Code>>,
     pub implicit: bool, // Implicit frames are created by the VM and not by java code.
+    pub monitor: Option<ReferenceType>, // The object locked by a synchronized method.
+    pub monitor_entered: bool, // False until the frame has entered its monitor.
 }
 
 impl Frame {
@@ -36,6 +40,8 @@ impl Frame {
             method,
             code,
             implicit: false,
+            monitor: None,
+            monitor_entered: false,
         }
     }
 
@@ -118,28 +124,50 @@ impl Frame {
         }
     }
 
-    pub fn handle_exception(&mut self, exception: &Object) -> bool {
-        if let Some(handler) = self.find_exception_handler(exception) {
-            self.pc = handler.handler_pc;
-            true
+    /// Moves the pc to the handler of `exception` and clears the operand stack. Returns false if
+    /// no handler in this frame catches the exception.
+    pub fn handle_exception(
+        &mut self,
+        class_loader: &mut ClassLoader,
+        exception: &Object,
+    ) -> Result<bool> {
+        if let Some(handler_pc) = self.find_exception_handler(class_loader, exception)? {
+            self.pc = handler_pc;
+            self.set_operand_stack(vec![]);
+            Ok(true)
         } else {
-            false
+            Ok(false)
         }
     }
 
-    fn find_exception_handler(&self, exception: &Object) -> Option<&ExceptionHandler> {
-        self.code.as_ref()?
-            .exception_handlers
-            .iter()
-            .find(|e| {
-                if let Some(catch_type) = &e.catch_type {
-                    catch_type == &exception.class
-                        && (self.pc as u16) >= e.start_pc
-                        && (self.pc as u16) < e.end_pc
-                } else {
-                    false
-                }
-            })
+    /// Finds the first handler covering the pc that catches the exception class or one of its
+    /// super classes. Handlers without a catch type catch everything, they are used by `finally`.
+    fn find_exception_handler(
+        &self,
+        class_loader: &mut ClassLoader,
+        exception: &Object,
+    ) -> Result<Option<u16>> {
+        let handlers = match &self.code {
+            Some(code) => &code.exception_handlers,
+            None => return Ok(None),
+        };
+
+        for handler in handlers {
+            if self.pc < handler.start_pc || self.pc >= handler.end_pc {
+                continue;
+            }
+
+            let catches = match &handler.catch_type {
+                Some(catch_type) => class_loader.is_assignable(&exception.class, catch_type)?,
+                None => true,
+            };
+
+            if catches {
+                return Ok(Some(handler.handler_pc));
+            }
+        }
+
+        Ok(None)
     }
 }
 
@@ -171,7 +199,19 @@ impl fmt::Display for Frame {
         }
 
         writeln!(f, "Operands: {:?}", self.operand_stack)?;
-        writeln!(f, "Locals: {:?}", self.local_variables)?;
+
+        // Locals are named when the class is compiled with debug information.
+        write!(f, "Locals: [")?;
+        for (slot, value) in self.local_variables.iter().enumerate() {
+            if slot > 0 {
+                write!(f, ", ")?;
+            }
+            match code.local_variable(slot as u16, self.pc) {
+                Some(variable) => write!(f, "{}: {:?}", variable.name, value)?,
+                None => write!(f, "{:?}", value)?,
+            }
+        }
+        writeln!(f, "]")?;
 
         Ok(())
     }
@@ -179,11 +219,15 @@ impl fmt::Display for Frame {
 
 #[cfg(test)]
 mod test {
-    use crate::class::attribute::Code;
+    use crate::class::attribute::AttributeData::LocalVariableTable;
+    use crate::class::attribute::{Attribute, Code, ExceptionHandler, LocalVariable};
     use crate::class::constant::ConstantPool;
     use crate::class::{Class, MethodInfo};
+    use crate::vm::class_loader::ClassLoader;
     use crate::vm::data_type::Value::Int;
     use crate::vm::frame::Frame;
+    use crate::vm::Object;
+    use std::collections::HashMap;
     use std::rc::Rc;
 
     #[test]
@@ -197,6 +241,28 @@ mod test {
         assert_eq!(frame.get_local(1), Int(13));
     }
 
+    #[test]
+    fn display_local_variable_names() {
+        let constants = ConstantPool::new(0);
+        let class = Class::from_constant_pool(constants);
+        let attribute = Attribute {
+            name: "LocalVariableTable".to_owned(),
+            data: LocalVariableTable(vec![LocalVariable {
+                start_pc: 0,
+                length: 1,
+                name: "count".to_owned(),
+                descriptor: "I".to_owned(),
+                index: 1,
+            }]),
+        };
+        let method = MethodInfo::from_code(Code::new(0, 3, vec![], vec![attribute], vec![]));
+        let mut frame = Frame::new(Rc::new(class), Rc::new(method));
+        frame.set_local(1, Int(7));
+
+        let display = frame.to_string();
+        assert!(display.contains("Locals: [None, count: Some(Int(7)), None]"));
+    }
+
     #[test]
     #[should_panic]
     fn get_uninitialized_local() {
@@ -207,4 +273,86 @@ mod test {
 
         frame.get_local(0); // Will panic, has not been initialized
     }
+
+    #[test]
+    fn handle_exception() {
+        let handlers = vec![
+            ExceptionHandler {
+                start_pc: 0,
+                end_pc: 2,
+                handler_pc: 10,
+                catch_type: Some("java/lang/Exception".to_owned()),
+            },
+            ExceptionHandler {
+                start_pc: 2,
+                end_pc: 4,
+                handler_pc: 20,
+                catch_type: Some("java/lang/Exception".to_owned()),
+            },
+        ];
+        let mut frame = frame_with_handlers(handlers);
+        frame.pc = 3;
+        frame.push_operand(Int(1));
+
+        let mut class_loader = ClassLoader::new();
+        let handled = frame
+            .handle_exception(&mut class_loader, &exception("java/lang/Exception"))
+            .unwrap();
+
+        assert!(handled);
+        assert_eq!(frame.pc, 20);
+        assert!(frame.operand_stack.is_empty());
+    }
+
+    #[test]
+    fn handle_exception_catch_all() {
+        let handlers = vec![ExceptionHandler {
+            start_pc: 0,
+            end_pc: 2,
+            handler_pc: 10,
+            catch_type: None,
+        }];
+        let mut frame = frame_with_handlers(handlers);
+
+        let mut class_loader = ClassLoader::new();
+        let handled = frame
+            .handle_exception(&mut class_loader, &exception("java/lang/Exception"))
+            .unwrap();
+
+        assert!(handled);
+        assert_eq!(frame.pc, 10);
+    }
+
+    #[test]
+    fn handle_exception_outside_range() {
+        let handlers = vec![ExceptionHandler {
+            start_pc: 0,
+            end_pc: 2,
+            handler_pc: 10,
+            catch_type: None,
+        }];
+        let mut frame = frame_with_handlers(handlers);
+        frame.pc = 2;
+
+        let mut class_loader = ClassLoader::new();
+        let handled = frame
+            .handle_exception(&mut class_loader, &exception("java/lang/Exception"))
+            .unwrap();
+
+        assert!(!handled);
+        assert_eq!(frame.pc, 2);
+    }
+
+    fn frame_with_handlers(handlers: Vec<ExceptionHandler>) -> Frame {
+        let class = Class::from_constant_pool(ConstantPool::new(0));
+        let method = MethodInfo::from_code(Code::new(1, 0, handlers, vec![], vec![]));
+        Frame::new(Rc::new(class), Rc::new(method))
+    }
+
+    fn exception(class: &str) -> Object {
+        Object {
+            class: class.to_owned(),
+            fields: HashMap::new(),
+        }
+    }
 }
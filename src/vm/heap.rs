@@ -1,12 +1,13 @@
-use crate::class::Class;
-use crate::vm::data_type::ReferenceType;
+use crate::error::{Error, Result};
+use crate::vm::data_type::{ReferenceType, Value};
 use crate::vm::heap::HeapObject::{
     ByteArray, CharArray, DoubleArray, FloatArray, Instance, IntArray, LongArray, Null,
     ReferenceArray, ShortArray,
 };
+use crate::vm::monitor::Monitors;
 use crate::vm::Object;
+use std::cmp::min;
 use std::collections::HashMap;
-use std::rc::Rc;
 
 #[derive(Debug, PartialEq)]
 pub enum HeapObject {
@@ -45,6 +46,42 @@ impl HeapObject {
         }
     }
 
+    /// The class name of an instance, or the descriptor of an array such as `[I`.
+    pub fn class_name(&self) -> String {
+        match self {
+            ByteArray(_) => "[B".to_owned(),
+            CharArray(_) => "[C".to_owned(),
+            ShortArray(_) => "[S".to_owned(),
+            IntArray(_) => "[I".to_owned(),
+            LongArray(_) => "[J".to_owned(),
+            FloatArray(_) => "[F".to_owned(),
+            DoubleArray(_) => "[D".to_owned(),
+            ReferenceArray((class, _)) if class.starts_with('[') => format!("[{}", class),
+            ReferenceArray((class, _)) => format!("[L{};", class),
+            Instance(o) => o.class.to_owned(),
+            Null => panic!("Tried to get class name of null"),
+        }
+    }
+
+    /// Approximate number of bytes the object occupies on the heap.
+    pub fn size(&self) -> usize {
+        match self {
+            Instance(o) => OBJECT_HEADER_SIZE + o.fields.len() * 8,
+            Null => 0,
+            array => OBJECT_HEADER_SIZE + array.array_length() * array.element_size(),
+        }
+    }
+
+    fn element_size(&self) -> usize {
+        match self {
+            ByteArray(_) => 1,
+            CharArray(_) | ShortArray(_) => 2,
+            IntArray(_) | FloatArray(_) | ReferenceArray(_) => 4,
+            LongArray(_) | DoubleArray(_) => 8,
+            o => panic!("Tried to get element size of {:?}", o),
+        }
+    }
+
     pub fn expect_byte_array(&self) -> &Vec<u8> {
         expect_type!(self, ByteArray)
     }
@@ -106,73 +143,269 @@ impl HeapObject {
     }
 }
 
-// TODO implement a real heap
+/// Default number of bytes that may be allocated before the first garbage collection is triggered.
+pub const DEFAULT_HEAP_LIMIT: usize = 64 * 1024 * 1024;
+
+/// Default number of bytes the heap may grow to before allocations fail, like `-Xmx`.
+pub const DEFAULT_MAX_HEAP_SIZE: usize = 512 * 1024 * 1024;
+
+/// Approximate size of an object header, used when estimating the heap usage.
+const OBJECT_HEADER_SIZE: usize = 16;
+
+/// Statistics about the garbage collections that has been made on a heap.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct HeapStatistics {
+    /// Number of collections made.
+    pub collections: u64,
+
+    /// Total number of objects reclaimed by all collections.
+    pub objects_freed: u64,
+
+    /// Total number of bytes reclaimed by all collections.
+    pub bytes_freed: u64,
+
+    /// Number of live objects after the last collection.
+    pub live_objects: u64,
+
+    /// Number of bytes in use after the last collection.
+    pub live_bytes: u64,
+}
+
+/// Garbage collected heap.
+///
+/// Objects are stored in slots that are addressed by references. When the estimated size of the
+/// heap exceeds the limit the VM runs a mark-and-sweep collection, see `Heap::collect`. The heap
+/// never grows past its max size, allocations that would not fit returns `None`. Slots of
+/// reclaimed objects are reused by later allocations, so references must never be kept outside of
+/// the roots given to the collector.
+///
+/// Interned strings, class objects and objects with an owned monitor are always considered
+/// reachable.
 #[derive(Debug)]
 pub struct Heap {
     objects: Vec<HeapObject>,
+    free_slots: Vec<ReferenceType>,
+    strings: HashMap<Vec<u16>, ReferenceType>,
+    class_objects: HashMap<String, ReferenceType>,
+    monitors: Monitors,
+    size: usize,
+    limit: usize,
+    max_size: usize,
+    collected: bool,
+    statistics: HeapStatistics,
     null: HeapObject,
 }
 
-// TODO DRY up and heapify
+// TODO DRY up
 impl Heap {
-    pub fn allocate_reference_array(&mut self, size: i32, class: Rc<Class>) -> u32 {
-        let index = self.objects.len() as u32;
-        self.objects.push(ReferenceArray((
-            class.this_class.to_string(),
-            vec![None; size as usize],
-        )));
-        index
+    pub fn new(limit: usize, max_size: usize) -> Self {
+        Heap {
+            objects: Vec::new(),
+            free_slots: Vec::new(),
+            strings: HashMap::new(),
+            class_objects: HashMap::new(),
+            monitors: Monitors::default(),
+            size: 0,
+            limit: min(limit, max_size),
+            max_size,
+            collected: false,
+            statistics: HeapStatistics::default(),
+            null: Null,
+        }
+    }
+
+    /// Allocates an array of references to `component`, either a class name or the descriptor of
+    /// an array such as `[I`.
+    pub fn allocate_reference_array(
+        &mut self,
+        size: i32,
+        component: &str,
+    ) -> Option<ReferenceType> {
+        self.allocate(array_size(size, 4), || {
+            ReferenceArray((component.to_owned(), vec![None; size as usize]))
+        })
+    }
+
+    /// Allocates an array of type `descriptor`, like `[I` or `[Ljava/lang/String;`.
+    pub fn allocate_array(&mut self, descriptor: &str, size: i32) -> Option<ReferenceType> {
+        match &descriptor[1..] {
+            "Z" | "B" => self.allocate_byte_array(size),
+            "C" => self.allocate_char_array(size),
+            "S" => self.allocate_short_array(size),
+            "I" => self.allocate_int_array(size),
+            "J" => self.allocate_long_array(size),
+            "F" => self.allocate_float_array(size),
+            "D" => self.allocate_double_array(size),
+            c if c.starts_with('[') => self.allocate_reference_array(size, c),
+            c => self.allocate_reference_array(size, &c[1..c.len() - 1]),
+        }
+    }
+
+    /// Allocates a multi dimensional array of type `descriptor`, like `[[I`, with the lengths of
+    /// the outermost dimensions in `sizes`. Any dimensions after those are left as null.
+    pub fn allocate_multi_array(
+        &mut self,
+        descriptor: &str,
+        sizes: &[i32],
+    ) -> Option<ReferenceType> {
+        let (&size, inner_sizes) = sizes.split_first()?;
+        if inner_sizes.is_empty() {
+            return self.allocate_array(descriptor, size);
+        }
+
+        let component = &descriptor[1..];
+        let array = self.allocate_reference_array(size, component)?;
+        for i in 0..size as usize {
+            let element = self.allocate_multi_array(component, inner_sizes)?;
+            self.get_mut(array).expect_mut_reference_array().1[i] = Some(element);
+        }
+
+        Some(array)
+    }
+
+    pub fn allocate_byte_array(&mut self, size: i32) -> Option<ReferenceType> {
+        self.allocate(array_size(size, 1), || ByteArray(vec![0; size as usize]))
+    }
+
+    pub fn allocate_char_array(&mut self, size: i32) -> Option<ReferenceType> {
+        self.allocate(array_size(size, 2), || CharArray(vec!['\0'; size as usize]))
+    }
+
+    pub fn allocate_float_array(&mut self, size: i32) -> Option<ReferenceType> {
+        self.allocate(array_size(size, 4), || FloatArray(vec![0.0; size as usize]))
     }
 
-    pub fn allocate_byte_array(&mut self, size: i32) -> u32 {
-        let index = self.objects.len() as u32;
-        self.objects.push(ByteArray(vec![0; size as usize]));
-        index
+    pub fn allocate_double_array(&mut self, size: i32) -> Option<ReferenceType> {
+        self.allocate(array_size(size, 8), || {
+            DoubleArray(vec![0.0; size as usize])
+        })
     }
 
-    pub fn allocate_char_array(&mut self, size: i32) -> u32 {
-        let index = self.objects.len() as u32;
-        self.objects.push(CharArray(vec!['\0'; size as usize]));
-        index
+    pub fn allocate_int_array(&mut self, size: i32) -> Option<ReferenceType> {
+        self.allocate(array_size(size, 4), || IntArray(vec![0; size as usize]))
     }
 
-    pub fn allocate_float_array(&mut self, size: i32) -> u32 {
-        let index = self.objects.len() as u32;
-        self.objects.push(FloatArray(vec![0.0; size as usize]));
-        index
+    pub fn allocate_long_array(&mut self, size: i32) -> Option<ReferenceType> {
+        self.allocate(array_size(size, 8), || LongArray(vec![0; size as usize]))
     }
 
-    pub fn allocate_double_array(&mut self, size: i32) -> u32 {
-        let index = self.objects.len() as u32;
-        self.objects.push(DoubleArray(vec![0.0; size as usize]));
-        index
+    pub fn allocate_short_array(&mut self, size: i32) -> Option<ReferenceType> {
+        self.allocate(array_size(size, 2), || ShortArray(vec![0; size as usize]))
     }
 
-    pub fn allocate_int_array(&mut self, size: i32) -> u32 {
-        let index = self.objects.len() as u32;
-        self.objects.push(IntArray(vec![0; size as usize]));
-        index
+    pub fn allocate_object(&mut self, class: &str) -> Option<ReferenceType> {
+        self.allocate(OBJECT_HEADER_SIZE, || new_instance(class))
     }
 
-    pub fn allocate_long_array(&mut self, size: i32) -> u32 {
-        let index = self.objects.len() as u32;
-        self.objects.push(LongArray(vec![0; size as usize]));
-        index
+    /// Allocates a `java/lang/String` holding `string`. The characters are stored as UTF-16 in a
+    /// byte array, high byte first, in the `value` field.
+    pub fn allocate_string(&mut self, string: &str) -> Option<ReferenceType> {
+        self.allocate_utf16(string.encode_utf16())
     }
 
-    pub fn allocate_short_array(&mut self, size: i32) -> u32 {
-        let index = self.objects.len() as u32;
-        self.objects.push(ShortArray(vec![0; size as usize]));
-        index
+    /// Allocates a `java/lang/String` from UTF-16 code units, which unlike a Rust string may
+    /// include unpaired surrogates.
+    pub fn allocate_utf16<I: IntoIterator<Item = u16>>(
+        &mut self,
+        units: I,
+    ) -> Option<ReferenceType> {
+        let bytes: Vec<u8> = units
+            .into_iter()
+            .flat_map(|c| c.to_be_bytes().to_vec())
+            .collect();
+
+        let value = self.allocate(array_size(bytes.len() as i32, 1), || ByteArray(bytes))?;
+        // If the instance does not fit the array is left unreferenced for the collector.
+        self.allocate(OBJECT_HEADER_SIZE + 8, || {
+            let mut object = Object {
+                class: "java/lang/String".to_owned(),
+                fields: HashMap::new(),
+            };
+            object
+                .fields
+                .insert("value".to_owned(), Value::Reference(Some(value)));
+            Instance(object)
+        })
     }
 
-    pub fn allocate_object(&mut self, class: &str) -> u32 {
-        let index = self.objects.len() as u32;
-        self.objects.push(Instance(Object {
-            class: class.to_owned(),
-            fields: HashMap::new(),
-        }));
-        index
+    /// Returns the interned `java/lang/String` for `string`, allocating it the first time. Equal
+    /// strings always get the same reference.
+    pub fn intern_string(&mut self, string: &str) -> Option<ReferenceType> {
+        self.intern_utf16(&string.encode_utf16().collect::<Vec<_>>())
+    }
+
+    /// Like `intern_string` for UTF-16 code units.
+    pub fn intern_utf16(&mut self, units: &[u16]) -> Option<ReferenceType> {
+        if let Some(reference) = self.strings.get(units) {
+            return Some(*reference);
+        }
+
+        let reference = self.allocate_utf16(units.iter().copied())?;
+        self.strings.insert(units.to_vec(), reference);
+        Some(reference)
+    }
+
+    /// Reads the `java/lang/String` at `reference` into a Rust string.
+    pub fn get_string(&self, reference: ReferenceType) -> Result<String> {
+        let chars = self.get_utf16(reference)?;
+        String::from_utf16(&chars)
+            .map_err(|e| Error::runtime(format!("Invalid string on heap: {}", e)))
+    }
+
+    /// Reads the UTF-16 code units of the `java/lang/String` at `reference`.
+    pub fn get_utf16(&self, reference: ReferenceType) -> Result<Vec<u16>> {
+        let value = match self.get(reference) {
+            Instance(o) if o.class == "java/lang/String" => o.fields.get("value"),
+            o => return runtime_error!("Expected a string, found {:?}.", o),
+        };
+
+        let bytes = match value {
+            Some(Value::Reference(Some(r))) => self.get(*r).expect_byte_array(),
+            v => return runtime_error!("Unexpected string value {:?}.", v),
+        };
+
+        Ok(bytes
+            .chunks(2)
+            .map(|c| u16::from_be_bytes([c[0], *c.get(1).unwrap_or(&0)]))
+            .collect())
+    }
+
+    /// Returns the `java/lang/Class` object of `class`, allocating it the first time. Like
+    /// exceptions thrown by the VM class objects are allowed to exceed the max heap size.
+    pub fn class_object(&mut self, class: &str) -> ReferenceType {
+        if let Some(reference) = self.class_objects.get(class) {
+            return *reference;
+        }
+
+        self.size += OBJECT_HEADER_SIZE;
+        self.collected = false;
+        let reference = self.insert(new_instance("java/lang/Class"));
+        self.class_objects.insert(class.to_owned(), reference);
+        reference
+    }
+
+    /// Returns the name of the class `reference` is the `java/lang/Class` object of.
+    pub fn class_object_name(&self, reference: ReferenceType) -> Option<&str> {
+        self.class_objects
+            .iter()
+            .find(|(_, r)| **r == reference)
+            .map(|(name, _)| name.as_str())
+    }
+
+    pub fn monitors(&self) -> &Monitors {
+        &self.monitors
+    }
+
+    pub fn monitors_mut(&mut self) -> &mut Monitors {
+        &mut self.monitors
+    }
+
+    /// Allocates an exception thrown by the VM itself. These are allowed to exceed the max heap
+    /// size, otherwise there would be no room left for the `OutOfMemoryError`.
+    pub fn allocate_exception(&mut self, class: &str) -> ReferenceType {
+        self.size += OBJECT_HEADER_SIZE;
+        self.collected = false;
+        self.insert(new_instance(class))
     }
 
     pub fn get(&self, reference: ReferenceType) -> &HeapObject {
@@ -185,13 +418,416 @@ impl Heap {
             .get_mut(reference as usize)
             .expect("Tried to get non existing heap object.")
     }
+
+    /// Returns true if the heap has grown past its limit and garbage should be collected.
+    pub fn needs_collection(&self) -> bool {
+        self.size > self.limit
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = min(limit, self.max_size);
+    }
+
+    pub fn max_size(&self) -> usize {
+        self.max_size
+    }
+
+    /// Sets the max heap size, the limit is lowered if it no longer fits.
+    pub fn set_max_size(&mut self, max_size: usize) {
+        self.max_size = max_size;
+        self.limit = min(self.limit, max_size);
+    }
+
+    /// Returns true if nothing has been allocated since the last collection. If an allocation
+    /// fails in this state there is no point in collecting again before giving up.
+    pub fn collected_since_allocation(&self) -> bool {
+        self.collected
+    }
+
+    /// Estimated number of bytes in use, including garbage not yet collected.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn statistics(&self) -> &HeapStatistics {
+        &self.statistics
+    }
+
+    /// Mark-and-sweep collection. Everything not reachable from `roots` is reclaimed and its slot
+    /// is made available for new allocations.
+    ///
+    /// If the live objects still do not fit within the limit after the collection the limit is
+    /// doubled, up to the max heap size, to avoid collecting on every allocation.
+    pub fn collect<I: IntoIterator<Item = ReferenceType>>(&mut self, roots: I) {
+        let marked = self.mark(roots);
+
+        let mut objects_freed = 0;
+        let mut bytes_freed = 0;
+        let mut live_bytes = 0;
+
+        for (index, object) in self.objects.iter_mut().enumerate() {
+            if matches!(object, Null) {
+                continue;
+            }
+
+            let size = object.size();
+            if marked[index] {
+                live_bytes += size;
+            } else {
+                *object = Null;
+                self.free_slots.push(index as ReferenceType);
+                objects_freed += 1;
+                bytes_freed += size;
+            }
+        }
+
+        let statistics = &mut self.statistics;
+        statistics.collections += 1;
+        statistics.objects_freed += objects_freed;
+        statistics.bytes_freed += bytes_freed as u64;
+        statistics.live_objects = (self.objects.len() - self.free_slots.len()) as u64;
+        statistics.live_bytes = live_bytes as u64;
+
+        debug!(
+            "[GC] Freed {} objects ({} bytes), {} bytes live",
+            objects_freed, bytes_freed, live_bytes
+        );
+
+        self.size = live_bytes;
+        self.collected = true;
+        while self.size > self.limit && self.limit < self.max_size {
+            self.limit = min(self.limit * 2, self.max_size);
+        }
+    }
+
+    fn mark<I: IntoIterator<Item = ReferenceType>>(&self, roots: I) -> Vec<bool> {
+        let mut marked = vec![false; self.objects.len()];
+        let mut pending: Vec<ReferenceType> = roots.into_iter().collect();
+        pending.extend(self.strings.values());
+        pending.extend(self.class_objects.values());
+        pending.extend(self.monitors.locked_objects());
+
+        while let Some(reference) = pending.pop() {
+            match marked.get_mut(reference as usize) {
+                Some(true) | None => continue,
+                Some(m) => *m = true,
+            }
+
+            match &self.objects[reference as usize] {
+                Instance(object) => {
+                    pending.extend(object.fields.values().filter_map(|v| match v {
+                        Value::Reference(r) => *r,
+                        _ => None,
+                    }))
+                }
+                ReferenceArray((_, array)) => pending.extend(array.iter().flatten()),
+                _ => {}
+            }
+        }
+
+        marked
+    }
+
+    /// Allocates an object of `size` bytes, or returns `None` if it would not fit within the max
+    /// heap size. The object is not created until we know it fits.
+    fn allocate<F>(&mut self, size: usize, create: F) -> Option<ReferenceType>
+    where
+        F: FnOnce() -> HeapObject,
+    {
+        if size > self.max_size.saturating_sub(self.size) {
+            return None;
+        }
+
+        self.size += size;
+        self.collected = false;
+        Some(self.insert(create()))
+    }
+
+    fn insert(&mut self, object: HeapObject) -> ReferenceType {
+        if let Some(index) = self.free_slots.pop() {
+            self.objects[index as usize] = object;
+            index
+        } else {
+            self.objects.push(object);
+            (self.objects.len() - 1) as ReferenceType
+        }
+    }
+}
+
+fn new_instance(class: &str) -> HeapObject {
+    Instance(Object {
+        class: class.to_owned(),
+        fields: HashMap::new(),
+    })
+}
+
+fn array_size(length: i32, element_size: usize) -> usize {
+    // Negative lengths are rejected before allocation, this only guards against overflow.
+    (length.max(0) as usize)
+        .saturating_mul(element_size)
+        .saturating_add(OBJECT_HEADER_SIZE)
 }
 
 impl Default for Heap {
     fn default() -> Self {
-        Heap {
-            objects: Vec::new(),
-            null: Null,
+        Heap::new(DEFAULT_HEAP_LIMIT, DEFAULT_MAX_HEAP_SIZE)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::vm::data_type::Value::{Int, Reference};
+    use crate::vm::heap::Heap;
+    use crate::vm::heap::HeapObject::{Instance, Null};
+
+    #[test]
+    fn collect_unreachable() {
+        let mut heap = Heap::default();
+        let kept = heap.allocate_int_array(10).unwrap();
+        let garbage = heap.allocate_int_array(10).unwrap();
+
+        heap.collect(vec![kept]);
+
+        assert_eq!(heap.get(kept).array_length(), 10);
+        assert_eq!(heap.get(garbage), &Null);
+        assert_eq!(heap.statistics().collections, 1);
+        assert_eq!(heap.statistics().objects_freed, 1);
+        assert_eq!(heap.statistics().live_objects, 1);
+    }
+
+    #[test]
+    fn collect_keeps_transitively_reachable() {
+        let mut heap = Heap::default();
+        let object = heap.allocate_object("Object").unwrap();
+        let field_array = heap.allocate_int_array(1).unwrap();
+        let array_element = heap.allocate_object("Element").unwrap();
+
+        if let Instance(o) = heap.get_mut(object) {
+            o.fields
+                .insert("field".to_owned(), Reference(Some(field_array)));
+            o.fields
+                .insert("primitive".to_owned(), Int(array_element as i32));
         }
+
+        let array = heap.allocate_reference_array(1, "Element").unwrap();
+        heap.get_mut(array).expect_mut_reference_array().1[0] = Some(array_element);
+
+        heap.collect(vec![object, array]);
+
+        assert_eq!(heap.statistics().objects_freed, 0);
+        assert_eq!(heap.statistics().live_objects, 4);
+    }
+
+    #[test]
+    fn reuse_freed_slots() {
+        let mut heap = Heap::default();
+        heap.allocate_int_array(10).unwrap();
+        let garbage = heap.allocate_int_array(10).unwrap();
+
+        heap.collect(vec![0]);
+
+        let reference = heap.allocate_long_array(1).unwrap();
+        assert_eq!(reference, garbage);
+        assert_eq!(heap.get(reference).expect_long_array(), &vec![0]);
+    }
+
+    #[test]
+    fn collection_needed_when_limit_exceeded() {
+        let mut heap = Heap::new(100, 1000);
+        heap.allocate_int_array(10).unwrap();
+        assert!(!heap.needs_collection());
+
+        let reference = heap.allocate_int_array(10).unwrap();
+        assert!(heap.needs_collection());
+
+        heap.collect(vec![]);
+        assert!(!heap.needs_collection());
+        assert_eq!(heap.size(), 0);
+        assert_eq!(heap.get(reference), &Null);
+    }
+
+    #[test]
+    fn grow_limit_when_live_objects_do_not_fit() {
+        let mut heap = Heap::new(100, 1000);
+        let reference = heap.allocate_int_array(100).unwrap();
+
+        heap.collect(vec![reference]);
+
+        assert!(!heap.needs_collection());
+        assert_eq!(heap.limit(), 800);
+    }
+
+    #[test]
+    fn limit_never_grows_past_max_size() {
+        let mut heap = Heap::new(100, 500);
+        let reference = heap.allocate_int_array(100).unwrap();
+
+        heap.collect(vec![reference]);
+
+        assert_eq!(heap.limit(), 500);
+    }
+
+    #[test]
+    fn allocation_fails_when_max_size_exceeded() {
+        let mut heap = Heap::new(100, 100);
+        assert!(heap.allocate_int_array(10).is_some());
+        assert!(heap.allocate_int_array(10).is_none());
+        assert!(heap.allocate_int_array(i32::MAX).is_none());
+    }
+
+    #[test]
+    fn allocate_exception_when_max_size_exceeded() {
+        let mut heap = Heap::new(100, 100);
+        heap.allocate_int_array(20).unwrap();
+
+        let reference = heap.allocate_exception("java/lang/OutOfMemoryError");
+        assert_eq!(
+            heap.get(reference).expect_instance().class,
+            "java/lang/OutOfMemoryError"
+        );
+        assert!(heap.allocate_object("Object").is_none());
+    }
+
+    #[test]
+    fn collected_since_allocation() {
+        let mut heap = Heap::default();
+        assert!(!heap.collected_since_allocation());
+
+        heap.collect(vec![]);
+        assert!(heap.collected_since_allocation());
+
+        heap.allocate_object("Object").unwrap();
+        assert!(!heap.collected_since_allocation());
+    }
+
+    #[test]
+    fn string_round_trip() {
+        let mut heap = Heap::default();
+        let reference = heap.allocate_string("Hello, 世界").unwrap();
+
+        assert_eq!(
+            heap.get(reference).expect_instance().class,
+            "java/lang/String"
+        );
+        assert_eq!(heap.get_string(reference).unwrap(), "Hello, 世界");
+    }
+
+    #[test]
+    fn intern_string() {
+        let mut heap = Heap::default();
+        let reference = heap.intern_string("interned").unwrap();
+
+        assert_eq!(heap.intern_string("interned"), Some(reference));
+        assert_ne!(heap.allocate_string("interned"), Some(reference));
+        assert_ne!(heap.intern_string("other"), Some(reference));
+    }
+
+    #[test]
+    fn interned_strings_survive_collection() {
+        let mut heap = Heap::default();
+        let interned = heap.intern_string("interned").unwrap();
+        let garbage = heap.allocate_string("garbage").unwrap();
+
+        heap.collect(vec![]);
+
+        assert_eq!(heap.get_string(interned).unwrap(), "interned");
+        assert_eq!(heap.get(garbage), &Null);
+        assert_eq!(heap.statistics().objects_freed, 2);
+    }
+
+    #[test]
+    fn class_object() {
+        let mut heap = Heap::default();
+        let class = heap.class_object("a/B");
+
+        assert_eq!(heap.class_object("a/B"), class);
+        assert_ne!(heap.class_object("a/C"), class);
+        assert_eq!(heap.class_object_name(class), Some("a/B"));
+        assert_eq!(heap.get(class).class_name(), "java/lang/Class");
+    }
+
+    #[test]
+    fn locked_objects_and_class_objects_survive_collection() {
+        let mut heap = Heap::default();
+        let class = heap.class_object("a/B");
+        let locked = heap.allocate_object("Object").unwrap();
+        let unlocked = heap.allocate_object("Object").unwrap();
+
+        heap.monitors_mut().enter(locked, 0);
+        heap.collect(vec![]);
+
+        assert_eq!(heap.get(class).class_name(), "java/lang/Class");
+        assert_eq!(heap.get(locked).class_name(), "Object");
+        assert_eq!(heap.get(unlocked), &Null);
+    }
+
+    #[test]
+    fn get_string_of_non_string() {
+        let mut heap = Heap::default();
+        let reference = heap.allocate_object("Object").unwrap();
+
+        assert!(heap.get_string(reference).is_err());
+    }
+
+    #[test]
+    fn class_name() {
+        let mut heap = Heap::default();
+        let object = heap.allocate_object("java/lang/Object").unwrap();
+        let ints = heap.allocate_int_array(1).unwrap();
+
+        let strings = heap
+            .allocate_reference_array(1, "java/lang/String")
+            .unwrap();
+        let int_arrays = heap.allocate_reference_array(1, "[I").unwrap();
+
+        assert_eq!(heap.get(object).class_name(), "java/lang/Object");
+        assert_eq!(heap.get(ints).class_name(), "[I");
+        assert_eq!(heap.get(strings).class_name(), "[Ljava/lang/String;");
+        assert_eq!(heap.get(int_arrays).class_name(), "[[I");
+    }
+
+    #[test]
+    fn allocate_array() {
+        let mut heap = Heap::default();
+        let longs = heap.allocate_array("[J", 2).unwrap();
+        let strings = heap.allocate_array("[Ljava/lang/String;", 3).unwrap();
+
+        assert_eq!(heap.get(longs).class_name(), "[J");
+        assert_eq!(heap.get(strings).class_name(), "[Ljava/lang/String;");
+        assert_eq!(heap.get(strings).array_length(), 3);
+    }
+
+    #[test]
+    fn allocate_multi_array() {
+        let mut heap = Heap::default();
+        let array = heap.allocate_multi_array("[[[I", &[3, 4, 5]).unwrap();
+
+        let (component, outer) = heap.get(array).expect_reference_array().clone();
+        assert_eq!(component, "[[I");
+        assert_eq!(outer.len(), 3);
+
+        let (component, middle) = heap.get(outer[2].unwrap()).expect_reference_array().clone();
+        assert_eq!(component, "[I");
+        assert_eq!(middle.len(), 4);
+
+        let inner = heap.get(middle[3].unwrap());
+        assert_eq!(inner.class_name(), "[I");
+        assert_eq!(inner.array_length(), 5);
+    }
+
+    #[test]
+    fn allocate_multi_array_partially() {
+        let mut heap = Heap::default();
+        let array = heap
+            .allocate_multi_array("[[Ljava/lang/String;", &[2])
+            .unwrap();
+
+        let (component, elements) = heap.get(array).expect_reference_array();
+        assert_eq!(component, "[Ljava/lang/String;");
+        assert_eq!(elements, &vec![None, None]);
     }
 }
@@ -26,10 +26,10 @@ use crate::vm::data_type::{
     ByteType, CharType, DoubleType, FloatType, IntType, LongType, ShortType,
 };
 use crate::vm::frame::Frame;
+use crate::vm::heap::Heap;
 use crate::vm::heap::HeapObject::{
     ByteArray, CharArray, DoubleArray, FloatArray, IntArray, LongArray, ShortArray,
 };
-use crate::vm::heap::{Heap, HeapObject};
 use crate::vm::interpreter::arithmetic::*;
 use crate::vm::interpreter::control_transfer::*;
 use crate::vm::interpreter::load_and_store::*;
@@ -38,9 +38,10 @@ use crate::vm::interpreter::stack_management::*;
 use crate::vm::interpreter::InterpretResult::{Command, InternalException, Jump, Normal};
 use crate::vm::VMCommand;
 use crate::vm::VMCommand::{
-    VMAllocateReferenceArray, VMException, VMGetField, VMGetStatic, VMInternalException,
-    VMInvokeInterface, VMInvokeSpecial, VMInvokeStatic, VMInvokeVirtual, VMNative, VMPutField,
-    VMPutStatic, VMReturn,
+    VMAllocateMultiArray, VMAllocateReferenceArray, VMCheckCast, VMCollectGarbage, VMException,
+    VMGetField, VMGetStatic, VMInstanceOf, VMInternalException, VMInvokeDynamic, VMInvokeInterface,
+    VMInvokeSpecial, VMInvokeStatic, VMInvokeVirtual, VMMonitorEnter, VMMonitorExit, VMNative,
+    VMPutField, VMPutStatic, VMReturn, VMYield,
 };
 
 macro_rules! jump (
@@ -64,12 +65,33 @@ enum InterpretResult {
     Command(VMCommand),
 }
 
-pub(super) fn interpret_frame(frame: &mut Frame, heap: &mut Heap) -> Result<VMCommand> {
+/// Hands control back to the VM after an allocation if the heap is full, since only the VM knows
+/// all the roots.
+fn allocated(heap: &Heap) -> InterpretResult {
+    if heap.needs_collection() {
+        Command(VMCollectGarbage())
+    } else {
+        Normal
+    }
+}
+
+/// Interprets instructions until the VM is needed. At most `budget` instructions are run, the
+/// budget is decreased by the instructions run and `VMYield` is returned when it is used up.
+pub(super) fn interpret_frame(
+    frame: &mut Frame,
+    heap: &mut Heap,
+    budget: &mut u32,
+) -> Result<VMCommand> {
     loop {
         if frame.code.is_none() {
             return Ok(VMNative());
         }
 
+        if *budget == 0 {
+            return Ok(VMYield());
+        }
+        *budget -= 1;
+
         let instruction = &frame.code.as_ref().unwrap().clone().instructions[frame.pc as usize];
 
         debug!("-------------");
@@ -156,8 +178,8 @@ fn interpret_instruction(
 
         BiPush => push_byte(frame, &instruction.operands),
         SiPush => push_short(frame, &instruction.operands),
-        Ldc => push_constant(frame, &instruction.operands)?,
-        LdcW => push_constant_wide(frame, &instruction.operands)?,
+        Ldc => return push_constant(frame, heap, &instruction.operands),
+        LdcW => return push_constant_wide(frame, heap, &instruction.operands),
         Ldc2W => push_constant_long(frame, &instruction.operands)?,
         AConstNull => push_null(frame),
 
@@ -179,7 +201,7 @@ fn interpret_instruction(
         DConst0 => frame.push_operand(Double(0.0)),
         DConst1 => frame.push_operand(Double(1.0)),
 
-        // Wide => TODO
+        Wide => return wide(frame, instruction),
 
         // Arithmetic:
         IAdd => arithmetic!(frame, Int, +),
@@ -261,11 +283,14 @@ fn interpret_instruction(
         D2f => convert!(frame, Double, Float, [FloatType]),
 
         // Object creation and manipulation:
-        New => new_object(frame, heap, &instruction.operands),
+        New => return Ok(new_object(frame, heap, &instruction.operands)),
 
-        NewArray => new_array(frame, heap, &instruction.operands)?,
+        NewArray => return new_array(frame, heap, &instruction.operands),
         ANewArray => vm_command!(VMAllocateReferenceArray(reference(&instruction.operands,))),
-        // Multianewarray => TODO
+        MultiANewArray => vm_command!(VMAllocateMultiArray(
+            reference(&instruction.operands),
+            instruction.operands[2]
+        )),
         GetField => vm_command!(VMGetField(reference(&instruction.operands))),
         PutField => vm_command!(VMPutField(reference(&instruction.operands))),
         GetStatic => vm_command!(VMGetStatic(reference(&instruction.operands))),
@@ -289,26 +314,8 @@ fn interpret_instruction(
         AaStore => reference_array_store(frame, heap),
         ArrayLength => array_length(frame, heap)?,
 
-        CheckCast => {
-            // TODO
-            eprintln!(
-                "TODO CheckCast not implemented: {:?}",
-                heap.get(reference(&instruction.operands) as u32)
-            );
-        }
-        Instanceof => {
-            // TODO
-            let _objectref = frame.pop_operand();
-            eprintln!(
-                "TODO instance of {:?}",
-                heap.get(reference(&instruction.operands) as u32)
-            );
-            if heap.get(reference(&instruction.operands) as u32) == &HeapObject::Null {
-                frame.push_operand(Int(0));
-            } else {
-                frame.push_operand(Int(1));
-            }
-        }
+        CheckCast => vm_command!(VMCheckCast(reference(&instruction.operands))),
+        Instanceof => vm_command!(VMInstanceOf(reference(&instruction.operands))),
 
         // Operand stack management:
         Pop => pop_operand(frame),
@@ -341,8 +348,8 @@ fn interpret_instruction(
         IfAcmpEq => jump!(if_cmp_operands!(frame, instruction, Reference, ==)),
         IfAcmpNe => jump!(if_cmp_operands!(frame, instruction, Reference, !=)),
 
-        TableSwitch => table_switch(frame, &instruction.operands),
-        LookupSwitch => lookup_switch(frame, &instruction.operands),
+        TableSwitch => jump!(table_switch(frame, &instruction.operands)),
+        LookupSwitch => jump!(lookup_switch(frame, &instruction.operands)),
 
         Goto => jump!(goto(frame, &instruction.operands)),
         GotoW => jump!(goto_wide(frame, &instruction.operands)),
@@ -355,7 +362,7 @@ fn interpret_instruction(
         InvokeInterface => vm_command!(VMInvokeInterface(reference(&instruction.operands))),
         InvokeSpecial => vm_command!(VMInvokeSpecial(reference(&instruction.operands))),
         InvokeStatic => vm_command!(VMInvokeStatic(reference(&instruction.operands))),
-        // Invokedynamic => TODO
+        InvokeDynamic => vm_command!(VMInvokeDynamic(reference(&instruction.operands))),
         Return => vm_command!(VMReturn(None)),
         IReturn => vm_command!(VMReturn(Some(Int(frame.pop_operand().expect_int_like())))),
         LReturn => vm_command!(VMReturn(Some(Long(frame.pop_operand().expect_long())))),
@@ -370,18 +377,14 @@ fn interpret_instruction(
         // Throwing exceptions:
         AThrow => vm_command!(VMException()),
 
+        // Synchronization:
+        MonitorEnter => vm_command!(VMMonitorEnter()),
+        MonitorExit => vm_command!(VMMonitorExit()),
+
         // Implementation specific
         OperationSpacer => panic!("Tried to parse operation as instruction in {}", frame),
         ImpDep2 => eprintln!("ImpDep2 not implemented"),
         BreakPoint => eprintln!("Breakpoint not implemented"),
-
-        _ => unimplemented!(
-            "Opcode {:?} is not implemented in interpreter",
-            instruction.opcode
-        ),
-        // Synchronization
-        // MonitorEnter => TODO
-        // MonitorExit => TODO
     }
 
     Ok(Normal)
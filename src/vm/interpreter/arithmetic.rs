@@ -1,6 +1,7 @@
 //! Contains implementation of all instructions under:
 //! https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-2.html#jvms-2.11.3
 
+use crate::binary::{bytes_to_i16, bytes_to_u16};
 use crate::vm::data_type::Value::{Double, Float, Int, Long};
 use crate::vm::frame::Frame;
 
@@ -60,6 +61,13 @@ pub fn int_increase(frame: &mut Frame, operands: &[u8]) {
     frame.set_local(index, Int(value + constant));
 }
 
+pub fn int_increase_wide(frame: &mut Frame, operands: &[u8]) {
+    let index = bytes_to_u16(&operands[0..2]);
+    let constant = bytes_to_i16(&operands[2..4]) as i32;
+    let value = frame.get_local(index).expect_int();
+    frame.set_local(index, Int(value.wrapping_add(constant)));
+}
+
 // TODO NaN
 pub fn double_compare_g(frame: &mut Frame) {
     let (left, right) = pop2!(Double, frame);
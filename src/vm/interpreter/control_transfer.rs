@@ -62,7 +62,7 @@ pub fn table_switch(frame: &mut Frame, operands: &[u8]) {
     }
 
     let offset_index = ((index - low) * 4 + 12) as usize;
-    let offset = bytes_to_i32(&operands[offset_index..(offset_index + 8)]);
+    let offset = bytes_to_i32(&operands[offset_index..(offset_index + 4)]);
 
     frame.pc_offset_wide(offset);
 }
@@ -95,19 +95,23 @@ pub fn goto_wide(frame: &mut Frame, operands: &[u8]) {
 }
 
 pub fn jump_subroutine(frame: &mut Frame, operands: &[u8]) {
-    frame.push_operand(ReturnAddress(frame.pc as ReturnAddressType));
+    frame.push_operand(ReturnAddress(frame.pc as ReturnAddressType + 3));
     frame.pc_offset(bytes_to_i16(operands));
 }
 
 pub fn jump_subroutine_wide(frame: &mut Frame, operands: &[u8]) {
-    frame.push_operand(ReturnAddress(frame.pc as ReturnAddressType));
+    frame.push_operand(ReturnAddress(frame.pc as ReturnAddressType + 5));
     frame.pc_offset_wide(bytes_to_i32(operands));
 }
 
 pub fn return_from_subroutine(frame: &mut Frame, operands: &[u8]) {
     let index = operands[0] as u16;
-    let offset = frame.get_local(index).expect_return_address();
-    frame.pc_offset(offset as i16);
+    frame.pc = frame.get_local(index).expect_return_address() as u16;
+}
+
+pub fn return_from_subroutine_wide(frame: &mut Frame, operands: &[u8]) {
+    let index = bytes_to_u16(operands);
+    frame.pc = frame.get_local(index).expect_return_address() as u16;
 }
 
 #[cfg(test)]
@@ -439,7 +443,7 @@ mod test {
             start_pc: 4,
             instruction: Jsr; [0x00, 0x05],
             final_pc: 9,
-            final_stack: [ReturnAddress(4)],
+            final_stack: [ReturnAddress(7)],
         );
     }
 
@@ -449,7 +453,7 @@ mod test {
             start_pc: 4,
             instruction: JsrW; [0x00, 0x00, 0x00, 0x07],
             final_pc: 11,
-            final_stack: [ReturnAddress(4)],
+            final_stack: [ReturnAddress(9)],
         );
     }
 
@@ -459,7 +463,7 @@ mod test {
             start_pc: 4,
             start_locals: {4 => ReturnAddress(96)},
             instruction: Ret; [0x04],
-            final_pc: 100,
+            final_pc: 96,
         );
     }
 }
@@ -1,11 +1,20 @@
 //! Contains implementation of all instructions under:
 //! https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-2.html#jvms-2.11.2
 
+use crate::binary::bytes_to_u16;
+use crate::class::code::Instruction;
+use crate::class::code::Opcode::*;
 use crate::class::constant::Constant;
 use crate::error::Result;
 use crate::vm::data_type::IntType;
 use crate::vm::data_type::Value::*;
 use crate::vm::frame::Frame;
+use crate::vm::heap::Heap;
+use crate::vm::interpreter::arithmetic::int_increase_wide;
+use crate::vm::interpreter::control_transfer::return_from_subroutine_wide;
+use crate::vm::interpreter::InterpretResult::{Command, Jump, Normal};
+use crate::vm::interpreter::{allocated, InterpretResult};
+use crate::vm::VMCommand::VMOutOfMemory;
 
 #[macro_export]
 macro_rules! load {
@@ -45,6 +54,33 @@ macro_rules! store {
     }};
 }
 
+/// Executes the instruction modified by `wide`.
+pub fn wide(frame: &mut Frame, instruction: &Instruction) -> Result<InterpretResult> {
+    let operands = &instruction.operands;
+    let index = bytes_to_u16(&operands[0..2]);
+
+    match &instruction.modified {
+        Some(ILoad) => load!(frame, Int(_), index),
+        Some(LLoad) => load!(frame, Long(_), index),
+        Some(FLoad) => load!(frame, Float(_), index),
+        Some(DLoad) => load!(frame, Double(_), index),
+        Some(ALoad) => load!(frame, Reference(_), index),
+        Some(IStore) => store!(frame, Int(_), index),
+        Some(LStore) => store!(frame, Long(_), index),
+        Some(FStore) => store!(frame, Float(_), index),
+        Some(DStore) => store!(frame, Double(_), index),
+        Some(AStore) => store!(frame, Reference(_) | ReturnAddress(_), index),
+        Some(IInc) => int_increase_wide(frame, operands),
+        Some(Ret) => {
+            return_from_subroutine_wide(frame, operands);
+            return Ok(Jump);
+        }
+        _ => return runtime_error!("Invalid wide instruction {}.", instruction),
+    }
+
+    Ok(Normal)
+}
+
 pub fn push_byte(frame: &mut Frame, operands: &[u8]) {
     frame.push_operand(Int(operands[0] as IntType));
 }
@@ -55,36 +91,61 @@ pub fn push_short(frame: &mut Frame, operands: &[u8]) {
     frame.push_operand(Short((b1 << 8) | b2));
 }
 
-pub fn push_constant(frame: &mut Frame, operands: &[u8]) -> Result<()> {
+pub fn push_constant(
+    frame: &mut Frame,
+    heap: &mut Heap,
+    operands: &[u8],
+) -> Result<InterpretResult> {
     let index = operands[0] as u16;
-    push_constant_index(frame, index)
+    push_constant_index(frame, heap, index)
 }
 
-pub fn push_constant_wide(frame: &mut Frame, operands: &[u8]) -> Result<()> {
+pub fn push_constant_wide(
+    frame: &mut Frame,
+    heap: &mut Heap,
+    operands: &[u8],
+) -> Result<InterpretResult> {
     let index_b1 = operands[0] as u16;
     let index_b2 = operands[1] as u16;
-    push_constant_index(frame, (index_b1 << 2) | index_b2)
+    push_constant_index(frame, heap, (index_b1 << 8) | index_b2)
 }
 
-fn push_constant_index(frame: &mut Frame, index: u16) -> Result<()> {
-    let value = match frame.class.constants.get(index) {
+fn push_constant_index(frame: &mut Frame, heap: &mut Heap, index: u16) -> Result<InterpretResult> {
+    let value = match frame.class.constants.get(index)? {
         Constant::Integer(i) => Int(*i),
         Constant::Float(f) => Float(*f),
+        Constant::StringRef(string_index) => {
+            let interned = match frame.class.constants.get(*string_index)? {
+                Constant::Utf16(units) => heap.intern_utf16(units),
+                _ => heap.intern_string(frame.class.constants.get_utf8(*string_index)?),
+            };
+            return match interned {
+                Some(reference) => {
+                    frame.push_operand(Reference(Some(reference)));
+                    Ok(allocated(heap))
+                }
+                None => Ok(Command(VMOutOfMemory())),
+            };
+        }
+        Constant::ClassRef(name_index) => {
+            let class_name = frame.class.constants.get_utf8(*name_index)?;
+            Reference(Some(heap.class_object(class_name)))
+        }
         // TODO reference and reference resolution
         constant => return runtime_error!("ldc not implemented for constant {:?}", constant),
     };
 
     frame.push_operand(value);
 
-    Ok(())
+    Ok(Normal)
 }
 
 pub fn push_constant_long(frame: &mut Frame, operands: &[u8]) -> Result<()> {
     let index_b1 = operands[0] as u16;
     let index_b2 = operands[1] as u16;
-    let index = (index_b1 << 2) | index_b2;
+    let index = (index_b1 << 8) | index_b2;
 
-    let value = match frame.class.constants.get(index) {
+    let value = match frame.class.constants.get(index)? {
         Constant::Long(l) => Long(*l),
         Constant::Double(d) => Double(*d),
         // TODO reference and reference resolution
@@ -105,6 +166,7 @@ mod test {
     use crate::class::code::Opcode::*;
     use crate::class::constant::Constant;
     use crate::vm::data_type::Value::*;
+    use crate::vm::heap::Heap;
 
     #[test]
     fn iload() {
@@ -625,4 +687,192 @@ mod test {
             final_stack: [Double(10.1)],
         );
     }
+
+    #[test]
+    fn ldc_string() {
+        let mut heap = Heap::default();
+        test_instruction!(
+            heap: heap,
+            constants: [Constant::StringRef(2), Constant::Utf8("Hello".to_owned())],
+            instruction: Ldc; [0x01],
+            final_stack: [Reference(Some(1))],
+        );
+
+        assert_eq!(heap.get_string(1).unwrap(), "Hello");
+    }
+
+    #[test]
+    fn ldc_class() {
+        let mut heap = Heap::default();
+        let reference = heap.class_object("a/B");
+        test_instruction!(
+            heap: heap,
+            constants: [Constant::ClassRef(2), Constant::Utf8("a/B".to_owned())],
+            instruction: Ldc; [0x01],
+            final_stack: [Reference(Some(reference))],
+        );
+    }
+
+    #[test]
+    fn ldc_string_interned() {
+        let mut heap = Heap::default();
+        let reference = heap.intern_string("Hello").unwrap();
+        test_instruction!(
+            heap: heap,
+            constants: [Constant::StringRef(2), Constant::Utf8("Hello".to_owned())],
+            instruction: Ldc; [0x01],
+            final_stack: [Reference(Some(reference))],
+        );
+    }
+
+    #[test]
+    fn wide_iload() {
+        test_instruction!(
+            max_locals: 300,
+            start_locals: { 0x0123 => Int(6) },
+            modified: ILoad,
+            instruction: Wide; [0x01, 0x23],
+            final_pc: 4,
+            final_stack: [Int(6)],
+        );
+    }
+
+    #[test]
+    fn wide_lload() {
+        test_instruction!(
+            max_locals: 300,
+            start_locals_long: { 0x0100 => Long(8) },
+            modified: LLoad,
+            instruction: Wide; [0x01, 0x00],
+            final_stack: [Long(8)],
+        );
+    }
+
+    #[test]
+    fn wide_fload() {
+        test_instruction!(
+            max_locals: 300,
+            start_locals: { 0x0101 => Float(1.5) },
+            modified: FLoad,
+            instruction: Wide; [0x01, 0x01],
+            final_stack: [Float(1.5)],
+        );
+    }
+
+    #[test]
+    fn wide_dload() {
+        test_instruction!(
+            max_locals: 300,
+            start_locals_long: { 0x0102 => Double(2.5) },
+            modified: DLoad,
+            instruction: Wide; [0x01, 0x02],
+            final_stack: [Double(2.5)],
+        );
+    }
+
+    #[test]
+    fn wide_aload() {
+        test_instruction!(
+            max_locals: 300,
+            start_locals: { 0x0104 => Reference(Some(3)) },
+            modified: ALoad,
+            instruction: Wide; [0x01, 0x04],
+            final_stack: [Reference(Some(3))],
+        );
+    }
+
+    #[test]
+    fn wide_istore() {
+        test_instruction!(
+            max_locals: 300,
+            start_stack: [Int(6)],
+            modified: IStore,
+            instruction: Wide; [0x01, 0x23],
+            final_pc: 4,
+            final_stack: [],
+            final_locals: { 0x0123 => Int(6) },
+        );
+    }
+
+    #[test]
+    fn wide_lstore() {
+        test_instruction!(
+            max_locals: 300,
+            start_stack: [Long(8)],
+            modified: LStore,
+            instruction: Wide; [0x01, 0x00],
+            final_stack: [],
+            final_locals_long: { 0x0100 => Long(8) },
+        );
+    }
+
+    #[test]
+    fn wide_fstore() {
+        test_instruction!(
+            max_locals: 300,
+            start_stack: [Float(1.5)],
+            modified: FStore,
+            instruction: Wide; [0x01, 0x01],
+            final_stack: [],
+            final_locals: { 0x0101 => Float(1.5) },
+        );
+    }
+
+    #[test]
+    fn wide_dstore() {
+        test_instruction!(
+            max_locals: 300,
+            start_stack: [Double(2.5)],
+            modified: DStore,
+            instruction: Wide; [0x01, 0x02],
+            final_stack: [],
+            final_locals_long: { 0x0102 => Double(2.5) },
+        );
+    }
+
+    #[test]
+    fn wide_astore() {
+        test_instruction!(
+            max_locals: 300,
+            start_stack: [ReturnAddress(7)],
+            modified: AStore,
+            instruction: Wide; [0x01, 0x04],
+            final_stack: [],
+            final_locals: { 0x0104 => ReturnAddress(7) },
+        );
+    }
+
+    #[test]
+    fn wide_iinc() {
+        test_instruction!(
+            max_locals: 300,
+            start_locals: { 0x0123 => Int(10) },
+            modified: IInc,
+            instruction: Wide; [0x01, 0x23, 0x03, 0xe8],
+            final_pc: 6,
+            final_locals: { 0x0123 => Int(1010) },
+        );
+    }
+
+    #[test]
+    fn wide_iinc_negative() {
+        test_instruction!(
+            start_locals: { 1 => Int(10) },
+            modified: IInc,
+            instruction: Wide; [0x00, 0x01, 0xff, 0x00],
+            final_locals: { 1 => Int(-246) },
+        );
+    }
+
+    #[test]
+    fn wide_ret() {
+        test_instruction!(
+            start_pc: 4,
+            max_locals: 300,
+            start_locals: { 0x0104 => ReturnAddress(96) },
+            modified: Ret,
+            instruction: Wide; [0x01, 0x04],
+            final_pc: 96,
+        );
+    }
 }
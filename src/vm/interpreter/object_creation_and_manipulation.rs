@@ -1,8 +1,10 @@
 use crate::error::Result;
-use crate::vm::data_type::ReferenceType;
 use crate::vm::data_type::Value::{Int, Reference};
 use crate::vm::frame::Frame;
 use crate::vm::heap::Heap;
+use crate::vm::interpreter::InterpretResult::{Command, InternalException};
+use crate::vm::interpreter::{allocated, InterpretResult};
+use crate::vm::VMCommand::VMOutOfMemory;
 
 #[macro_export]
 macro_rules! array_load (
@@ -41,8 +43,14 @@ macro_rules! array_store (
     }}
 );
 
-pub fn new_array(frame: &mut Frame, heap: &mut Heap, operands: &[u8]) -> Result<()> {
+pub fn new_array(frame: &mut Frame, heap: &mut Heap, operands: &[u8]) -> Result<InterpretResult> {
     let len = frame.pop_operand().expect_int();
+    if len < 0 {
+        return Ok(InternalException(
+            "java/lang/NegativeArraySizeException".to_owned(),
+        ));
+    }
+
     let reference = match operands[0] {
         5 => heap.allocate_char_array(len),
         6 => heap.allocate_float_array(len),
@@ -54,8 +62,14 @@ pub fn new_array(frame: &mut Frame, heap: &mut Heap, operands: &[u8]) -> Result<
         a => return runtime_error!("Unknown array type {}.", a),
     };
 
-    frame.push_operand(Reference(Some(reference as ReferenceType)));
-    Ok(())
+    if let Some(reference) = reference {
+        frame.push_operand(Reference(Some(reference)));
+        Ok(allocated(heap))
+    } else {
+        // Restore the operand so the instruction can be retried after a collection.
+        frame.push_operand(Int(len));
+        Ok(Command(VMOutOfMemory()))
+    }
 }
 
 pub fn array_length(frame: &mut Frame, heap: &Heap) -> Result<()> {
@@ -68,15 +82,20 @@ pub fn array_length(frame: &mut Frame, heap: &Heap) -> Result<()> {
     Ok(())
 }
 
-pub fn new_object(frame: &mut Frame, heap: &mut Heap, operands: &[u8]) {
+pub fn new_object(frame: &mut Frame, heap: &mut Heap, operands: &[u8]) -> InterpretResult {
     let index = ((operands[0] as u16) << 8) | operands[1] as u16;
     let class = frame
         .class
         .constants
         .get_class_info_name(index as u16)
         .unwrap();
-    let reference = heap.allocate_object(class);
-    frame.push_operand(Reference(Some(reference as ReferenceType)));
+
+    if let Some(reference) = heap.allocate_object(class) {
+        frame.push_operand(Reference(Some(reference)));
+        allocated(heap)
+    } else {
+        Command(VMOutOfMemory())
+    }
 }
 
 pub fn reference_array_store(frame: &mut Frame, heap: &mut Heap) {
@@ -90,7 +109,7 @@ pub fn reference_array_store(frame: &mut Frame, heap: &mut Heap) {
         .expect_reference()
         .expect("Null pointer error"); // TODO
 
-    let object_type = heap.get(value).expect_instance().class.to_owned();
+    let object_type = heap.get(value).class_name();
     let (array_type, array) = heap.get_mut(reference).expect_mut_reference_array();
 
     // TODO better type check, probably need to move to VM? Or can we get immutable access to all we need from here?
@@ -149,7 +168,7 @@ mod test {
     #[test]
     fn iastore() {
         let mut heap = Heap::default();
-        heap.allocate_int_array(10);
+        heap.allocate_int_array(10).unwrap();
 
         test_instruction!(
             heap: heap,
@@ -165,7 +184,7 @@ mod test {
     #[test]
     fn iaload() {
         let mut heap = Heap::default();
-        heap.allocate_int_array(10);
+        heap.allocate_int_array(10).unwrap();
 
         {
             let array = heap.get_mut(0).expect_mut_int_array();
@@ -5,9 +5,11 @@ macro_rules! test_instruction {
             $(heap: $heap:expr,)?
             $(constants: [$($constant:expr),*],)?
             $(start_pc: $start_pc:expr,)?
+            $(max_locals: $max_locals:expr,)?
             $(start_stack: [$($stack:expr),*],)?
             $(start_locals: {$($local_idx:expr => $local_value:expr),*},)?
             $(start_locals_long: {$($local_l_idx:expr => $local_l_value:expr),*},)?
+            $(modified: $modified:expr,)?
             instruction: $instruction:expr $(;[$($operands:expr),*])?,
             $(final_pc: $final_pc:expr,)?
             $(final_stack: [$($expect_stack:expr),*],)?
@@ -33,14 +35,18 @@ macro_rules! test_instruction {
                 instructions.push(Instruction::new(Opcode::Nop, vec![]));
             })?
 
-            instructions.push(Instruction::new($instruction, vec![$($($operands),*)?]));
+            let mut _instruction = Instruction::new($instruction, vec![$($($operands),*)?]);
+            $(_instruction.modified = Some($modified);)?
+            instructions.push(_instruction);
 
             for _ in 0..100 {
                 instructions.push(Instruction::new(Opcode::Return, vec![]));
             }
 
             let _class = Class::from_constant_pool(_constants);
-            let _code = Code::new(10, 10, vec![], vec![], instructions);
+            let _max_locals = 10;
+            $(let _max_locals = $max_locals;)?
+            let _code = Code::new(10, _max_locals, vec![], vec![], instructions);
 
             let _method = MethodInfo::from_code(_code);
             let mut frame = Frame::new(Rc::new(_class), Rc::new(_method));
@@ -55,10 +61,12 @@ macro_rules! test_instruction {
             let mut _heap = Heap::default();
             let _heap_ref = &mut _heap;
             $(let _heap_ref = &mut $heap;)?
+            let mut budget = u32::MAX;
 
             interpret_frame(
                 &mut frame,
-                _heap_ref
+                _heap_ref,
+                &mut budget
             ).expect("Interpretation failed.");
 
             // Assert
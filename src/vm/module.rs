@@ -0,0 +1,90 @@
+//! The graph of modules found on a module path:
+//! https://docs.oracle.com/javase/specs/jls/se17/html/jls-7.html#jls-7.7
+
+use crate::class::attribute::ModuleDescriptor;
+use crate::class::RequiresFlags;
+use std::collections::HashSet;
+
+/// The base module of the platform, required by every module. It is provided by the VM rather
+/// than read from the module path.
+pub const JAVA_BASE: &str = "java.base";
+
+/// The modules found on a module path, in the order they were found.
+#[derive(Debug, Default)]
+pub struct ModuleGraph {
+    modules: Vec<ModuleDescriptor>,
+}
+
+impl ModuleGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the module to the graph. Like with `--module-path`, the first module found with a
+    /// name wins, so `false` is returned and the module is ignored if the name is taken.
+    pub fn add(&mut self, module: ModuleDescriptor) -> bool {
+        if self.module(&module.name).is_some() {
+            return false;
+        }
+        self.modules.push(module);
+        true
+    }
+
+    pub fn module(&self, name: &str) -> Option<&ModuleDescriptor> {
+        self.modules.iter().find(|m| m.name == name)
+    }
+
+    pub fn modules(&self) -> impl Iterator<Item = &ModuleDescriptor> {
+        self.modules.iter()
+    }
+
+    /// The modules read by `name`, the modules it requires and those required transitively by the
+    /// modules it reads. Modules missing from the graph are left out.
+    pub fn reads(&self, name: &str) -> Vec<&str> {
+        let mut reads = Vec::new();
+        let mut visited = HashSet::new();
+        let mut pending = match self.module(name) {
+            Some(module) => module.requires.iter().collect::<Vec<_>>(),
+            None => return reads,
+        };
+        pending.reverse();
+
+        while let Some(requires) = pending.pop() {
+            let module = match self.module(&requires.module) {
+                Some(module) => module,
+                None => continue,
+            };
+            if !visited.insert(&module.name) {
+                continue;
+            }
+
+            reads.push(module.name.as_str());
+            pending.extend(
+                module
+                    .requires
+                    .iter()
+                    .rev()
+                    .filter(|r| r.flags.contains(RequiresFlags::TRANSITIVE)),
+            );
+        }
+
+        reads
+    }
+
+    /// The requirements that can not be satisfied by the graph, as pairs of the module and the
+    /// module it requires. Static requirements are optional at runtime and `java.base` is always
+    /// present, so neither is reported.
+    pub fn missing_requirements(&self) -> Vec<(&str, &str)> {
+        self.modules
+            .iter()
+            .flat_map(|module| {
+                module
+                    .requires
+                    .iter()
+                    .filter(|r| !r.flags.contains(RequiresFlags::STATIC_PHASE))
+                    .filter(|r| r.module != JAVA_BASE && self.module(&r.module).is_none())
+                    .map(move |r| (module.name.as_str(), r.module.as_str()))
+            })
+            .collect()
+    }
+}
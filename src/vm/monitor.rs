@@ -0,0 +1,146 @@
+//! Object monitors, used by `monitorenter`, `monitorexit` and synchronized methods:
+//! https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-2.html#jvms-2.11.10
+
+use crate::vm::data_type::ReferenceType;
+use crate::vm::thread::ThreadId;
+use std::collections::HashMap;
+
+/// The monitor of an object owned by a thread. The owner may enter it again, the monitor is
+/// released when it has been exited as many times as it was entered.
+#[derive(Debug, PartialEq)]
+struct Monitor {
+    owner: ThreadId,
+    entry_count: u32,
+}
+
+/// The monitors of all objects. Only owned monitors are stored, an object without an entry is
+/// unlocked.
+#[derive(Debug, Default)]
+pub struct Monitors {
+    monitors: HashMap<ReferenceType, Monitor>,
+}
+
+impl Monitors {
+    /// Enters the monitor of `object`. Returns false if it is owned by another thread, the
+    /// thread must then wait for it to be released.
+    pub fn enter(&mut self, object: ReferenceType, thread: ThreadId) -> bool {
+        self.enter_times(object, thread, 1)
+    }
+
+    /// Enters the monitor of `object` `entry_count` times at once, used to re-enter a monitor
+    /// released by `release`.
+    pub fn enter_times(
+        &mut self,
+        object: ReferenceType,
+        thread: ThreadId,
+        entry_count: u32,
+    ) -> bool {
+        let monitor = self.monitors.entry(object).or_insert(Monitor {
+            owner: thread,
+            entry_count: 0,
+        });
+
+        if monitor.owner != thread {
+            return false;
+        }
+
+        monitor.entry_count += entry_count;
+        true
+    }
+
+    /// Exits the monitor of `object`. Returns false if `thread` is not the owner, which is an
+    /// `IllegalMonitorStateException` in Java.
+    pub fn exit(&mut self, object: ReferenceType, thread: ThreadId) -> bool {
+        match self.monitors.get_mut(&object) {
+            Some(monitor) if monitor.owner == thread => {
+                monitor.entry_count -= 1;
+                if monitor.entry_count == 0 {
+                    self.monitors.remove(&object);
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Releases the monitor of `object` no matter how many times it was entered, like
+    /// `Object.wait` does. Returns the entry count, or `None` if `thread` is not the owner.
+    pub fn release(&mut self, object: ReferenceType, thread: ThreadId) -> Option<u32> {
+        match self.monitors.get(&object) {
+            Some(monitor) if monitor.owner == thread => {
+                self.monitors.remove(&object).map(|m| m.entry_count)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn owner(&self, object: ReferenceType) -> Option<ThreadId> {
+        self.monitors.get(&object).map(|m| m.owner)
+    }
+
+    /// The objects with an owned monitor.
+    pub fn locked_objects(&self) -> impl Iterator<Item = ReferenceType> + '_ {
+        self.monitors.keys().copied()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::vm::monitor::Monitors;
+
+    #[test]
+    fn enter_and_exit() {
+        let mut monitors = Monitors::default();
+
+        assert!(monitors.enter(1, 0));
+        assert_eq!(monitors.owner(1), Some(0));
+
+        assert!(monitors.exit(1, 0));
+        assert_eq!(monitors.owner(1), None);
+    }
+
+    #[test]
+    fn recursive_enter() {
+        let mut monitors = Monitors::default();
+
+        assert!(monitors.enter(1, 0));
+        assert!(monitors.enter(1, 0));
+
+        assert!(monitors.exit(1, 0));
+        assert_eq!(monitors.owner(1), Some(0));
+
+        assert!(monitors.exit(1, 0));
+        assert_eq!(monitors.owner(1), None);
+    }
+
+    #[test]
+    fn owned_by_other_thread() {
+        let mut monitors = Monitors::default();
+
+        assert!(monitors.enter(1, 0));
+        assert!(!monitors.enter(1, 1));
+        assert!(!monitors.exit(1, 1));
+        assert_eq!(monitors.owner(1), Some(0));
+    }
+
+    #[test]
+    fn release_and_enter_again() {
+        let mut monitors = Monitors::default();
+
+        assert!(monitors.enter(1, 0));
+        assert!(monitors.enter(1, 0));
+        assert_eq!(monitors.release(1, 1), None);
+        assert_eq!(monitors.release(1, 0), Some(2));
+        assert_eq!(monitors.owner(1), None);
+
+        assert!(monitors.enter_times(1, 0, 2));
+        assert!(monitors.exit(1, 0));
+        assert_eq!(monitors.owner(1), Some(0));
+    }
+
+    #[test]
+    fn exit_unowned() {
+        let mut monitors = Monitors::default();
+        assert!(!monitors.exit(1, 0));
+    }
+}
@@ -1,9 +1,26 @@
+use crate::error::Result;
+use crate::vm::class_loader::ClassLoader;
 use crate::vm::data_type::Value;
-use crate::vm::stack::Stack;
+use crate::vm::data_type::Value::Reference;
+use crate::vm::heap::Heap;
+use crate::vm::native::NativeResult::{Return, Throw};
+use crate::vm::thread::Threads;
 use std::collections::HashMap;
 
+/// The result of a native method.
+#[derive(Debug, PartialEq)]
+pub enum NativeResult {
+    /// The value returned, `None` for void methods.
+    Return(Option<Value>),
+    /// The name of the exception class thrown, like `java/lang/NullPointerException`.
+    Throw(String),
+}
+
+/// A native method. Its arguments are the locals of the current frame of the current thread.
+pub type NativeMethod = fn(&mut ClassLoader, &mut Heap, &mut Threads) -> Result<NativeResult>;
+
 pub struct Native {
-    methods: HashMap<String, fn(stack: &mut Stack) -> Option<Value>>,
+    methods: HashMap<String, NativeMethod>,
 }
 
 impl Native {
@@ -15,35 +32,40 @@ impl Native {
         java_lang_float::auto_register_natives(&mut native);
         java_lang_double::auto_register_natives(&mut native);
         java_lang_throwable::auto_register_natives(&mut native);
+        java_lang_thread::auto_register_natives(&mut native);
+        java_lang_object::auto_register_natives(&mut native);
+        java_lang_string::auto_register_natives(&mut native);
+        java_lang_class::auto_register_natives(&mut native);
 
         native
     }
 
-    pub fn register_method(
-        &mut self,
-        class_name: &str,
-        method_name: &str,
-        method: fn(stack: &mut Stack) -> Option<Value>,
-    ) {
+    pub fn register_method(&mut self, class_name: &str, method_name: &str, method: NativeMethod) {
         let key = Self::method_key(class_name, method_name);
         self.methods.insert(key, method);
     }
 
-    pub fn invoke(&mut self, stack: &mut Stack) -> Option<Value> {
-        let frame = stack.current_frame_mut();
+    pub fn invoke(
+        &mut self,
+        class_loader: &mut ClassLoader,
+        heap: &mut Heap,
+        threads: &mut Threads,
+    ) -> Result<NativeResult> {
+        let frame = threads.current().stack.current_frame();
         if frame.method.name == "registerNatives" {
-            self.register_natives(&frame.class.this_class);
-            return None;
+            let class_name = frame.class.this_class.clone();
+            self.register_natives(&class_name);
+            return Ok(Return(None));
         }
 
         let key = Self::method_key(&frame.class.this_class, &frame.method.name);
-        if !self.methods.contains_key(&key) {
-            eprintln!("Called undefined native method: {}", key);
-            return None;
+        match self.methods.get(&key) {
+            Some(method) => method(class_loader, heap, threads),
+            None => {
+                eprintln!("Called undefined native method: {}", key);
+                Ok(Return(None))
+            }
         }
-
-        let method = self.methods.get(&key).unwrap();
-        method(stack)
     }
 
     fn register_natives(&mut self, class_name: &str) {
@@ -63,72 +85,165 @@ impl Native {
     }
 }
 
+/// Returns the string allocated on the heap, or throws if it does not fit.
+fn allocated_string(heap: &mut Heap, string: &str) -> NativeResult {
+    match heap.allocate_string(string) {
+        Some(reference) => Return(Some(Reference(Some(reference)))),
+        None => Throw("java/lang/OutOfMemoryError".to_owned()),
+    }
+}
+
 mod java_lang_object {
-    use crate::vm::data_type::Value;
+    use crate::error::Result;
+    use crate::vm::class_loader::ClassLoader;
     use crate::vm::data_type::Value::Int;
-    use crate::vm::native::Native;
-    use crate::vm::stack::Stack;
+    use crate::vm::heap::Heap;
+    use crate::vm::native::NativeResult::{Return, Throw};
+    use crate::vm::native::{allocated_string, Native, NativeResult};
+    use crate::vm::thread::Threads;
 
     pub fn register_natives(native: &mut Native) {
         native.register_method("java/lang/Object", "hashCode", init_properties);
     }
 
-    fn init_properties(stack: &mut Stack) -> Option<Value> {
+    pub fn auto_register_natives(native: &mut Native) {
+        native.register_method("java/lang/Object", "wait", wait);
+        native.register_method("java/lang/Object", "notify", notify);
+        native.register_method("java/lang/Object", "notifyAll", notify_all);
+        native.register_method("java/lang/Object", "toString", to_string);
+    }
+
+    fn init_properties(
+        _: &mut ClassLoader,
+        _: &mut Heap,
+        threads: &mut Threads,
+    ) -> Result<NativeResult> {
         println!(
             "MOCK, hashCode arg: {:?}",
-            stack.current_frame_mut().get_local(0)
+            threads.current().stack.current_frame().get_local(0)
         );
-        Some(Int(0))
+        Ok(Return(Some(Int(0))))
     }
-}
 
-mod java_lang_throwable {
-    use crate::vm::data_type::Value;
-    use crate::vm::data_type::Value::Reference;
-    use crate::vm::native::Native;
-    use crate::vm::stack::Stack;
+    /// `Object.wait` with or without a timeout. The monitor is released while waiting and
+    /// entered again, as many times as before, when the thread is woken up.
+    fn wait(_: &mut ClassLoader, heap: &mut Heap, threads: &mut Threads) -> Result<NativeResult> {
+        let thread = threads.current();
+        let frame = thread.stack.current_frame();
+        let object = match frame.get_local(0).expect_reference() {
+            Some(object) => object,
+            None => return Ok(Throw("java/lang/NullPointerException".to_owned())),
+        };
 
-    pub fn auto_register_natives(native: &mut Native) {
-        native.register_method(
-            "java/lang/Throwable",
-            "fillInStackTrace",
-            fill_in_stack_trace,
-        );
+        let timeout = if frame.method.descriptor.argument_types.is_empty() {
+            0
+        } else {
+            frame.get_local(1).expect_long()
+        };
+
+        if timeout < 0 {
+            return Ok(Throw("java/lang/IllegalArgumentException".to_owned()));
+        }
+
+        let entry_count = match heap.monitors_mut().release(object, thread.id) {
+            Some(entry_count) => entry_count,
+            None => return Ok(Throw("java/lang/IllegalMonitorStateException".to_owned())),
+        };
+
+        // A timeout of zero means waiting until notified.
+        let deadline = if timeout == 0 {
+            None
+        } else {
+            Some(threads.time() + timeout as u64)
+        };
+
+        threads.wait(object, entry_count, deadline);
+        Ok(Return(None))
     }
 
-    fn fill_in_stack_trace(stack: &mut Stack) -> Option<Value> {
-        println!(
-            "MOCK, fillInStackTrace arg: {:?}",
-            stack.current_frame_mut().get_local(0)
-        );
-        Some(Reference(None))
+    fn notify(_: &mut ClassLoader, heap: &mut Heap, threads: &mut Threads) -> Result<NativeResult> {
+        Ok(wake_waiting(heap, threads, false))
+    }
+
+    fn notify_all(
+        _: &mut ClassLoader,
+        heap: &mut Heap,
+        threads: &mut Threads,
+    ) -> Result<NativeResult> {
+        Ok(wake_waiting(heap, threads, true))
+    }
+
+    fn wake_waiting(heap: &Heap, threads: &mut Threads, all: bool) -> NativeResult {
+        let thread = threads.current();
+        let object = match thread.stack.current_frame().get_local(0).expect_reference() {
+            Some(object) => object,
+            None => return Throw("java/lang/NullPointerException".to_owned()),
+        };
+
+        if heap.monitors().owner(object) != Some(thread.id) {
+            return Throw("java/lang/IllegalMonitorStateException".to_owned());
+        }
+
+        threads.notify(object, all);
+        Return(None)
+    }
+
+    /// The default `Object.toString`, the class name and the identity hash code of the object.
+    fn to_string(
+        _: &mut ClassLoader,
+        heap: &mut Heap,
+        threads: &mut Threads,
+    ) -> Result<NativeResult> {
+        let this = threads.current().stack.current_frame().get_local(0);
+        let object = match this.expect_reference() {
+            Some(object) => object,
+            None => return Ok(Throw("java/lang/NullPointerException".to_owned())),
+        };
+
+        let class_name = heap.get(object).class_name().replace('/', ".");
+        Ok(allocated_string(
+            heap,
+            &format!("{}@{:x}", class_name, object),
+        ))
     }
 }
 
 mod java_lang_system {
-    use crate::vm::data_type::Value;
+    use crate::error::Result;
+    use crate::vm::class_loader::ClassLoader;
     use crate::vm::data_type::Value::Reference;
-    use crate::vm::native::Native;
-    use crate::vm::stack::Stack;
+    use crate::vm::heap::Heap;
+    use crate::vm::native::NativeResult::Return;
+    use crate::vm::native::{Native, NativeResult};
+    use crate::vm::thread::Threads;
 
     pub fn register_natives(native: &mut Native) {
         native.register_method("java/lang/System", "initProperties", init_properties);
     }
 
-    fn init_properties(stack: &mut Stack) -> Option<Value> {
+    fn init_properties(
+        _: &mut ClassLoader,
+        _: &mut Heap,
+        threads: &mut Threads,
+    ) -> Result<NativeResult> {
         println!(
             "MOCK, initProperties arg: {:?}",
-            stack.current_frame_mut().get_local(0)
+            threads.current().stack.current_frame().get_local(0)
         );
-        Some(Reference(None))
+        Ok(Return(Some(Reference(None))))
     }
 }
 
 mod java_lang_class {
+    use crate::error::Result;
+    use crate::vm::class_loader::ClassLoader;
     use crate::vm::data_type::Value;
-    use crate::vm::data_type::Value::Reference;
-    use crate::vm::native::Native;
-    use crate::vm::stack::Stack;
+    use crate::vm::data_type::Value::{Int, Reference};
+    use crate::vm::heap::Heap;
+    use crate::vm::native::NativeResult::{Return, Throw};
+    use crate::vm::native::{Native, NativeResult};
+    use crate::vm::reflection;
+    use crate::vm::thread::Threads;
 
     pub fn register_natives(native: &mut Native) {
         native.register_method(
@@ -140,25 +255,325 @@ mod java_lang_class {
         native.register_method("java/lang/Class", "getPrimitiveClass", get_primitive_class);
     }
 
-    fn desired_assertion_status0(_stack: &mut Stack) -> Option<Value> {
+    pub fn auto_register_natives(native: &mut Native) {
+        native.register_method("java/lang/Class", "isRecord", is_record);
+        native.register_method("java/lang/Class", "getRecordComponents", record_components);
+    }
+
+    fn desired_assertion_status0(
+        _: &mut ClassLoader,
+        _: &mut Heap,
+        _: &mut Threads,
+    ) -> Result<NativeResult> {
         println!("MOCK desiredAssertionStatus0, return Int(0)");
-        Some(Value::Int(0))
+        Ok(Return(Some(Value::Int(0))))
     }
 
-    fn get_primitive_class(stack: &mut Stack) -> Option<Value> {
+    fn get_primitive_class(
+        _: &mut ClassLoader,
+        _: &mut Heap,
+        threads: &mut Threads,
+    ) -> Result<NativeResult> {
         println!(
             "MOCK getPrimitiveClass, arg: {:?}",
-            stack.current_frame_mut().get_local(0)
+            threads.current().stack.current_frame().get_local(0)
         );
-        Some(Reference(None))
+        Ok(Return(Some(Reference(None))))
+    }
+
+    fn is_record(
+        class_loader: &mut ClassLoader,
+        heap: &mut Heap,
+        threads: &mut Threads,
+    ) -> Result<NativeResult> {
+        let this = threads.current().stack.current_frame().get_local(0);
+        let class_object = match this.expect_reference() {
+            Some(class_object) => class_object,
+            None => return Ok(Throw("java/lang/NullPointerException".to_owned())),
+        };
+
+        let record = reflection::reflected_class(class_loader, heap, class_object)?
+            .is_some_and(|class| class.record_components().is_some());
+        Ok(Return(Some(Int(record as i32))))
+    }
+
+    /// `Class.getRecordComponents`, null if the class is not a record.
+    fn record_components(
+        class_loader: &mut ClassLoader,
+        heap: &mut Heap,
+        threads: &mut Threads,
+    ) -> Result<NativeResult> {
+        let this = threads.current().stack.current_frame().get_local(0);
+        let class_object = match this.expect_reference() {
+            Some(class_object) => class_object,
+            None => return Ok(Throw("java/lang/NullPointerException".to_owned())),
+        };
+
+        let class = match reflection::reflected_class(class_loader, heap, class_object)? {
+            Some(class) => class,
+            None => return Ok(Return(Some(Reference(None)))),
+        };
+        let components = match class.record_components() {
+            Some(components) => components,
+            None => return Ok(Return(Some(Reference(None)))),
+        };
+
+        match reflection::allocate_record_components(heap, &class, components)? {
+            Some(array) => Ok(Return(Some(Reference(Some(array))))),
+            None => Ok(Throw("java/lang/OutOfMemoryError".to_owned())),
+        }
     }
 }
 
-mod java_lang_float {
-    use crate::vm::data_type::Value;
+mod java_lang_string {
+    use crate::error::Result;
+    use crate::vm::class_loader::ClassLoader;
     use crate::vm::data_type::Value::Int;
-    use crate::vm::native::Native;
+    use crate::vm::data_type::{FieldType, Value};
+    use crate::vm::heap::Heap;
+    use crate::vm::java_floating_string;
+    use crate::vm::native::NativeResult::Throw;
+    use crate::vm::native::{allocated_string, Native, NativeResult};
+    use crate::vm::thread::Threads;
+
+    pub fn auto_register_natives(native: &mut Native) {
+        native.register_method("java/lang/String", "concat", concat);
+        native.register_method("java/lang/String", "valueOf", value_of);
+    }
+
+    fn concat(_: &mut ClassLoader, heap: &mut Heap, threads: &mut Threads) -> Result<NativeResult> {
+        let frame = threads.current().stack.current_frame();
+        let this = frame.get_local(0).expect_reference();
+        let other = frame.get_local(1).expect_reference();
+
+        let (this, other) = match (this, other) {
+            (Some(this), Some(other)) => (this, other),
+            _ => return Ok(Throw("java/lang/NullPointerException".to_owned())),
+        };
+
+        let string = heap.get_string(this)? + &heap.get_string(other)?;
+        Ok(allocated_string(heap, &string))
+    }
+
+    /// `String.valueOf` for primitives, the overload is chosen by the argument type.
+    fn value_of(
+        _: &mut ClassLoader,
+        heap: &mut Heap,
+        threads: &mut Threads,
+    ) -> Result<NativeResult> {
+        let frame = threads.current().stack.current_frame();
+        let value = frame.get_local(0);
+
+        let string = match (&frame.method.descriptor.argument_types[0], value) {
+            (FieldType::Boolean, Int(b)) => (b != 0).to_string(),
+            (FieldType::Char, Int(c)) => String::from_utf16_lossy(&[c as u16]),
+            (FieldType::Float, Value::Float(f)) => java_floating_string(f),
+            (FieldType::Double, Value::Double(d)) => java_floating_string(d),
+            (_, Int(i)) => i.to_string(),
+            (_, Value::Long(l)) => l.to_string(),
+            (_, value) => return runtime_error!("Tried to use {:?} in String.valueOf.", value),
+        };
+
+        Ok(allocated_string(heap, &string))
+    }
+}
+
+mod java_lang_throwable {
+    use crate::error::Result;
+    use crate::vm::class_loader::ClassLoader;
+    use crate::vm::exception;
+    use crate::vm::heap::Heap;
+    use crate::vm::native::NativeResult::Return;
+    use crate::vm::native::{Native, NativeResult};
+    use crate::vm::thread::Threads;
+
+    pub fn auto_register_natives(native: &mut Native) {
+        native.register_method(
+            "java/lang/Throwable",
+            "fillInStackTrace",
+            fill_in_stack_trace,
+        );
+        native.register_method("java/lang/Throwable", "printStackTrace", print_stack_trace);
+    }
+
+    fn fill_in_stack_trace(
+        _: &mut ClassLoader,
+        heap: &mut Heap,
+        threads: &mut Threads,
+    ) -> Result<NativeResult> {
+        let stack = &threads.current().stack;
+        let this = stack.current_frame().get_local(0);
+        let reference = this.clone().expect_reference().expect("Null pointer error"); // TODO
+
+        let stack_trace = exception::throwable_stack_trace(stack, reference);
+
+        // A throwable is still usable without its trace, so a full heap is not an error here.
+        exception::store_stack_trace(heap, reference, &stack_trace);
+
+        Ok(Return(Some(this)))
+    }
+
+    fn print_stack_trace(
+        _: &mut ClassLoader,
+        heap: &mut Heap,
+        threads: &mut Threads,
+    ) -> Result<NativeResult> {
+        let reference = threads
+            .current()
+            .stack
+            .current_frame()
+            .get_local(0)
+            .expect_reference()
+            .expect("Null pointer error"); // TODO
+
+        eprintln!("{}", exception::read_exception(heap, reference)?);
+        Ok(Return(None))
+    }
+}
+
+mod java_lang_thread {
+    use crate::error::Result;
+    use crate::vm::class_loader::ClassLoader;
+    use crate::vm::data_type::Value::{Int, Reference};
+    use crate::vm::frame::Frame;
+    use crate::vm::heap::Heap;
+    use crate::vm::native::NativeResult::{Return, Throw};
+    use crate::vm::native::{Native, NativeResult};
+    use crate::vm::push_method_frame;
     use crate::vm::stack::Stack;
+    use crate::vm::thread::{ThreadState, Threads};
+
+    pub fn auto_register_natives(native: &mut Native) {
+        native.register_method("java/lang/Thread", "holdsLock", holds_lock);
+        native.register_method("java/lang/Thread", "start", start);
+        native.register_method("java/lang/Thread", "join", join);
+        native.register_method("java/lang/Thread", "isAlive", is_alive);
+        native.register_method("java/lang/Thread", "currentThread", current_thread);
+        native.register_method("java/lang/Thread", "sleep", sleep);
+        native.register_method("java/lang/Thread", "yield", yield_thread);
+    }
+
+    fn holds_lock(
+        _: &mut ClassLoader,
+        heap: &mut Heap,
+        threads: &mut Threads,
+    ) -> Result<NativeResult> {
+        let thread = threads.current();
+        let object = match thread.stack.current_frame().get_local(0).expect_reference() {
+            Some(object) => object,
+            None => return Ok(Throw("java/lang/NullPointerException".to_owned())),
+        };
+
+        let owner = heap.monitors().owner(object);
+        Ok(Return(Some(Int((owner == Some(thread.id)) as i32))))
+    }
+
+    /// Starts a new thread running the `run` method of the thread object.
+    fn start(
+        class_loader: &mut ClassLoader,
+        heap: &mut Heap,
+        threads: &mut Threads,
+    ) -> Result<NativeResult> {
+        let this = threads.current().stack.current_frame().get_local(0);
+        let object = this.clone().expect_reference().expect("Null pointer error"); // TODO
+
+        if threads.find(object).is_some() {
+            return Ok(Throw("java/lang/IllegalThreadStateException".to_owned()));
+        }
+
+        let class_name = heap.get(object).class_name();
+        let (class, method, mut init_frames) =
+            class_loader.resolve_method(&class_name, "run", "()V")?;
+
+        let mut frame = Frame::new(class, method);
+        frame.load_arguments(vec![this]);
+
+        let mut stack = Stack::new();
+        push_method_frame(heap, frame, &mut stack);
+        stack.append(&mut init_frames);
+
+        threads.spawn(stack, Some(object));
+        Ok(Return(None))
+    }
+
+    fn join(_: &mut ClassLoader, _: &mut Heap, threads: &mut Threads) -> Result<NativeResult> {
+        let this = threads.current().stack.current_frame().get_local(0);
+        let object = this.expect_reference().expect("Null pointer error"); // TODO
+
+        // Joining a thread that is not started returns at once, like joining a terminated one.
+        if let Some(thread) = threads.find(object) {
+            let id = thread.id;
+            threads.set_state(ThreadState::Joining(id));
+        }
+
+        Ok(Return(None))
+    }
+
+    fn is_alive(_: &mut ClassLoader, _: &mut Heap, threads: &mut Threads) -> Result<NativeResult> {
+        let this = threads.current().stack.current_frame().get_local(0);
+        let object = this.expect_reference().expect("Null pointer error"); // TODO
+
+        let alive = match threads.find(object) {
+            Some(thread) => thread.state != ThreadState::Terminated,
+            None => false,
+        };
+
+        Ok(Return(Some(Int(alive as i32))))
+    }
+
+    /// The thread object of the current thread. The main thread was not started from Java, its
+    /// object is allocated the first time it is asked for.
+    fn current_thread(
+        _: &mut ClassLoader,
+        heap: &mut Heap,
+        threads: &mut Threads,
+    ) -> Result<NativeResult> {
+        let thread = threads.current_mut();
+        if thread.object.is_none() {
+            thread.object = heap.allocate_object("java/lang/Thread");
+        }
+
+        match thread.object {
+            Some(object) => Ok(Return(Some(Reference(Some(object))))),
+            None => Ok(Throw("java/lang/OutOfMemoryError".to_owned())),
+        }
+    }
+
+    fn sleep(_: &mut ClassLoader, _: &mut Heap, threads: &mut Threads) -> Result<NativeResult> {
+        let millis = threads
+            .current()
+            .stack
+            .current_frame()
+            .get_local(0)
+            .expect_long();
+
+        if millis < 0 {
+            return Ok(Throw("java/lang/IllegalArgumentException".to_owned()));
+        }
+
+        threads.set_state(ThreadState::Sleeping(threads.time() + millis as u64));
+        Ok(Return(None))
+    }
+
+    fn yield_thread(
+        _: &mut ClassLoader,
+        _: &mut Heap,
+        threads: &mut Threads,
+    ) -> Result<NativeResult> {
+        // Sleeping until now lets all other runnable threads run first.
+        threads.set_state(ThreadState::Sleeping(threads.time()));
+        Ok(Return(None))
+    }
+}
+
+mod java_lang_float {
+    use crate::error::Result;
+    use crate::vm::class_loader::ClassLoader;
+    use crate::vm::data_type::Value::Int;
+    use crate::vm::heap::Heap;
+    use crate::vm::native::NativeResult::Return;
+    use crate::vm::native::{Native, NativeResult};
+    use crate::vm::thread::Threads;
 
     pub fn auto_register_natives(native: &mut Native) {
         native.register_method(
@@ -168,20 +583,27 @@ mod java_lang_float {
         );
     }
 
-    fn float_to_raw_int_bits(stack: &mut Stack) -> Option<Value> {
+    fn float_to_raw_int_bits(
+        _: &mut ClassLoader,
+        _: &mut Heap,
+        threads: &mut Threads,
+    ) -> Result<NativeResult> {
         println!(
             "MOCK floatToRawIntBits, arg: {:?}",
-            stack.current_frame_mut().get_local(0)
+            threads.current().stack.current_frame().get_local(0)
         );
-        Some(Int(0))
+        Ok(Return(Some(Int(0))))
     }
 }
 
 mod java_lang_double {
-    use crate::vm::data_type::Value;
+    use crate::error::Result;
+    use crate::vm::class_loader::ClassLoader;
     use crate::vm::data_type::Value::{Double, Int};
-    use crate::vm::native::Native;
-    use crate::vm::stack::Stack;
+    use crate::vm::heap::Heap;
+    use crate::vm::native::NativeResult::Return;
+    use crate::vm::native::{Native, NativeResult};
+    use crate::vm::thread::Threads;
 
     pub fn auto_register_natives(native: &mut Native) {
         native.register_method(
@@ -193,19 +615,27 @@ mod java_lang_double {
         native.register_method("java/lang/Double", "longBitsToDouble", long_bits_to_double);
     }
 
-    fn double_to_raw_int_bits(stack: &mut Stack) -> Option<Value> {
+    fn double_to_raw_int_bits(
+        _: &mut ClassLoader,
+        _: &mut Heap,
+        threads: &mut Threads,
+    ) -> Result<NativeResult> {
         println!(
             "MOCK doubleToRawIntBits, arg: {:?}",
-            stack.current_frame_mut().get_local(0)
+            threads.current().stack.current_frame().get_local(0)
         );
-        Some(Int(0))
+        Ok(Return(Some(Int(0))))
     }
 
-    fn long_bits_to_double(stack: &mut Stack) -> Option<Value> {
+    fn long_bits_to_double(
+        _: &mut ClassLoader,
+        _: &mut Heap,
+        threads: &mut Threads,
+    ) -> Result<NativeResult> {
         println!(
             "MOCK longBitsToDouble, arg: {:?}",
-            stack.current_frame_mut().get_local(0)
+            threads.current().stack.current_frame().get_local(0)
         );
-        Some(Double(0.0))
+        Ok(Return(Some(Double(0.0))))
     }
 }
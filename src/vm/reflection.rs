@@ -0,0 +1,108 @@
+//! The parts of `java.lang.Class` that describe the structure of a class.
+
+use crate::class::attribute::{self, RecordComponent};
+use crate::class::Class;
+use crate::error::Result;
+use crate::vm::class_loader::ClassLoader;
+use crate::vm::data_type::Value::Reference;
+use crate::vm::data_type::{FieldType, ReferenceType};
+use crate::vm::heap::Heap;
+use crate::vm::heap::HeapObject::Instance;
+use std::convert::TryInto;
+use std::rc::Rc;
+
+const PRIMITIVE_CLASSES: &[&str] = &[
+    "byte", "char", "double", "float", "int", "long", "short", "boolean",
+];
+
+/// Returns the class `class_object` is the `java/lang/Class` object of, `None` for arrays and
+/// primitive types since they have no class file.
+pub fn reflected_class(
+    class_loader: &mut ClassLoader,
+    heap: &Heap,
+    class_object: ReferenceType,
+) -> Result<Option<Rc<Class>>> {
+    match heap.class_object_name(class_object) {
+        Some(name) if !name.starts_with('[') && !PRIMITIVE_CLASSES.contains(&name) => {
+            let name = name.to_owned();
+            Ok(Some(class_loader.load(&name)?))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Allocates the `java/lang/reflect/RecordComponent` array of a record class.
+///
+/// Returns `None` if the array does not fit on the heap.
+pub fn allocate_record_components(
+    heap: &mut Heap,
+    record: &Class,
+    components: &[RecordComponent],
+) -> Result<Option<ReferenceType>> {
+    let size = components.len() as i32;
+    let array = match heap.allocate_reference_array(size, "java/lang/reflect/RecordComponent") {
+        Some(array) => array,
+        None => return Ok(None),
+    };
+
+    for (i, component) in components.iter().enumerate() {
+        let reference = match allocate_component(heap, record, component)? {
+            Some(reference) => reference,
+            None => return Ok(None),
+        };
+        heap.get_mut(array).expect_mut_reference_array().1[i] = Some(reference);
+    }
+    Ok(Some(array))
+}
+
+fn allocate_component(
+    heap: &mut Heap,
+    record: &Class,
+    component: &RecordComponent,
+) -> Result<Option<ReferenceType>> {
+    let field_type: FieldType = component.descriptor.as_str().try_into()?;
+    let declaring_record = heap.class_object(&record.this_class);
+    let component_type = heap.class_object(&class_object_name(&field_type));
+
+    let name = match heap.intern_string(&component.name) {
+        Some(name) => name,
+        None => return Ok(None),
+    };
+    let signature = match attribute::signature(&component.attributes) {
+        Some(signature) => match heap.intern_string(signature) {
+            Some(signature) => Some(signature),
+            None => return Ok(None),
+        },
+        None => None,
+    };
+
+    let reference = match heap.allocate_object("java/lang/reflect/RecordComponent") {
+        Some(reference) => reference,
+        None => return Ok(None),
+    };
+    if let Instance(object) = heap.get_mut(reference) {
+        let fields = &mut object.fields;
+        fields.insert("clazz".to_owned(), Reference(Some(declaring_record)));
+        fields.insert("name".to_owned(), Reference(Some(name)));
+        fields.insert("type".to_owned(), Reference(Some(component_type)));
+        fields.insert("signature".to_owned(), Reference(signature));
+    }
+    Ok(Some(reference))
+}
+
+/// The name the class object of a type is stored under, the class name for classes, the
+/// descriptor for arrays and the keyword for primitive types.
+fn class_object_name(field_type: &FieldType) -> String {
+    match field_type {
+        FieldType::Byte => "byte".to_owned(),
+        FieldType::Char => "char".to_owned(),
+        FieldType::Double => "double".to_owned(),
+        FieldType::Float => "float".to_owned(),
+        FieldType::Int => "int".to_owned(),
+        FieldType::Long => "long".to_owned(),
+        FieldType::Short => "short".to_owned(),
+        FieldType::Boolean => "boolean".to_owned(),
+        FieldType::Object(class_name) => class_name.clone(),
+        FieldType::Array(_) => field_type.to_string(),
+    }
+}
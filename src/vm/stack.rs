@@ -32,6 +32,10 @@ impl Stack {
         self.stack.pop().expect("Tried to pop from empty stack.")
     }
 
+    pub fn frames(&self) -> impl DoubleEndedIterator<Item = &Frame> {
+        self.stack.iter()
+    }
+
     pub fn current_frame(&self) -> &Frame {
         self.stack
             .last()
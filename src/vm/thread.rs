@@ -0,0 +1,355 @@
+//! Green threads. Every Java thread has its own stack, they are interleaved on a single OS thread
+//! by a deterministic round-robin scheduler.
+//!
+//! Time is virtual, the clock advances one millisecond every time the scheduler runs and jumps
+//! ahead when all threads are sleeping. A run is therefore always reproducible.
+
+use crate::error::Result;
+use crate::vm::data_type::ReferenceType;
+use crate::vm::monitor::Monitors;
+use crate::vm::stack::Stack;
+
+pub type ThreadId = u32;
+
+pub const MAIN_THREAD: ThreadId = 0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ThreadState {
+    Runnable,
+
+    /// Waiting to enter the monitor of `object`, entering it `entry_count` times. Used both for
+    /// contended monitors and to re-enter the monitor after `Object.wait`.
+    Blocked {
+        object: ReferenceType,
+        entry_count: u32,
+    },
+
+    /// In `Object.wait`, until notified or the deadline has passed. `sequence` orders the
+    /// waiting threads so that `notify` wakes the one that has waited the longest.
+    Waiting {
+        object: ReferenceType,
+        entry_count: u32,
+        deadline: Option<u64>,
+        sequence: u64,
+    },
+
+    Joining(ThreadId),
+    Sleeping(u64),
+    Terminated,
+}
+
+#[derive(Debug)]
+pub struct Thread {
+    pub id: ThreadId,
+    pub stack: Stack,
+    pub state: ThreadState,
+
+    /// The `java/lang/Thread` object, allocated when first asked for on the main thread.
+    pub object: Option<ReferenceType>,
+}
+
+impl Thread {
+    /// The name used by Java for threads that are not given one.
+    pub fn name(&self) -> String {
+        if self.id == MAIN_THREAD {
+            "main".to_owned()
+        } else {
+            format!("Thread-{}", self.id - 1)
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Threads {
+    threads: Vec<Thread>,
+    current: usize,
+    time: u64,
+    waits: u64,
+}
+
+impl Threads {
+    /// Creates the scheduler with `main` as the running main thread.
+    pub fn new(main: Stack) -> Self {
+        let mut threads = Threads {
+            threads: Vec::new(),
+            current: 0,
+            time: 0,
+            waits: 0,
+        };
+        threads.spawn(main, None);
+        threads
+    }
+
+    pub fn spawn(&mut self, stack: Stack, object: Option<ReferenceType>) -> ThreadId {
+        let id = self.threads.len() as ThreadId;
+        self.threads.push(Thread {
+            id,
+            stack,
+            state: ThreadState::Runnable,
+            object,
+        });
+        id
+    }
+
+    pub fn current(&self) -> &Thread {
+        &self.threads[self.current]
+    }
+
+    pub fn current_mut(&mut self) -> &mut Thread {
+        &mut self.threads[self.current]
+    }
+
+    pub fn get(&self, id: ThreadId) -> &Thread {
+        &self.threads[id as usize]
+    }
+
+    /// The thread started for the `java/lang/Thread` object.
+    pub fn find(&self, object: ReferenceType) -> Option<&Thread> {
+        self.threads.iter().find(|t| t.object == Some(object))
+    }
+
+    pub fn threads(&self) -> impl Iterator<Item = &Thread> {
+        self.threads.iter()
+    }
+
+    /// Virtual time in milliseconds.
+    pub fn time(&self) -> u64 {
+        self.time
+    }
+
+    /// Sets the state of the current thread, anything but `Runnable` makes the scheduler switch
+    /// to another thread.
+    pub fn set_state(&mut self, state: ThreadState) {
+        self.current_mut().state = state;
+    }
+
+    /// Makes the current thread wait on `object` after it released the monitor `entry_count`
+    /// times, until notified or the deadline has passed.
+    pub fn wait(&mut self, object: ReferenceType, entry_count: u32, deadline: Option<u64>) {
+        let sequence = self.waits;
+        self.waits += 1;
+        self.set_state(ThreadState::Waiting {
+            object,
+            entry_count,
+            deadline,
+            sequence,
+        });
+    }
+
+    /// Terminates the current thread. Its frames are dropped so they are no longer roots.
+    pub fn terminate(&mut self) {
+        let thread = self.current_mut();
+        thread.state = ThreadState::Terminated;
+        thread.stack = Stack::new();
+    }
+
+    /// Wakes up threads waiting on `object`, all of them or only the one that has waited the
+    /// longest. They still need to re-enter the monitor before they continue.
+    pub fn notify(&mut self, object: ReferenceType, all: bool) {
+        let mut waiting: Vec<_> = self
+            .threads
+            .iter_mut()
+            .filter_map(|t| match t.state {
+                ThreadState::Waiting {
+                    object: o,
+                    entry_count,
+                    sequence,
+                    ..
+                } if o == object => Some((sequence, entry_count, t)),
+                _ => None,
+            })
+            .collect();
+        waiting.sort_by_key(|(sequence, ..)| *sequence);
+        if !all {
+            waiting.truncate(1);
+        }
+
+        for (_, entry_count, thread) in waiting {
+            thread.state = ThreadState::Blocked {
+                object,
+                entry_count,
+            };
+        }
+    }
+
+    /// Switches to the next thread that can run, in round-robin order. Returns false when all
+    /// threads have terminated.
+    pub fn schedule(&mut self, monitors: &mut Monitors) -> Result<bool> {
+        self.time += 1;
+
+        loop {
+            let count = self.threads.len();
+            for offset in 1..=count {
+                let index = (self.current + offset) % count;
+                if self.wake(index, monitors) {
+                    self.current = index;
+                    return Ok(true);
+                }
+            }
+
+            if self
+                .threads
+                .iter()
+                .all(|t| t.state == ThreadState::Terminated)
+            {
+                return Ok(false);
+            }
+
+            match self.next_deadline() {
+                Some(deadline) => self.time = deadline,
+                None => return runtime_error!("Deadlock, all threads are waiting."),
+            }
+        }
+    }
+
+    /// Makes the thread runnable if what it is waiting for has happened.
+    fn wake(&mut self, index: usize, monitors: &mut Monitors) -> bool {
+        let thread = &self.threads[index];
+        let state = match thread.state {
+            ThreadState::Waiting {
+                object,
+                entry_count,
+                deadline: Some(deadline),
+                ..
+            } if deadline <= self.time => ThreadState::Blocked {
+                object,
+                entry_count,
+            },
+            ThreadState::Joining(id) if self.get(id).state == ThreadState::Terminated => {
+                ThreadState::Runnable
+            }
+            ThreadState::Sleeping(deadline) if deadline <= self.time => ThreadState::Runnable,
+            state => state,
+        };
+
+        let state = match state {
+            ThreadState::Blocked {
+                object,
+                entry_count,
+            } if monitors.enter_times(object, thread.id, entry_count) => ThreadState::Runnable,
+            state => state,
+        };
+
+        self.threads[index].state = state;
+        state == ThreadState::Runnable
+    }
+
+    fn next_deadline(&self) -> Option<u64> {
+        self.threads
+            .iter()
+            .filter_map(|t| match t.state {
+                ThreadState::Sleeping(deadline) => Some(deadline),
+                ThreadState::Waiting { deadline, .. } => deadline,
+                _ => None,
+            })
+            .min()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::vm::monitor::Monitors;
+    use crate::vm::stack::Stack;
+    use crate::vm::thread::{ThreadState, Threads, MAIN_THREAD};
+
+    #[test]
+    fn round_robin() {
+        let mut monitors = Monitors::default();
+        let mut threads = Threads::new(Stack::new());
+        threads.spawn(Stack::new(), None);
+        threads.spawn(Stack::new(), None);
+
+        let mut order = vec![];
+        for _ in 0..4 {
+            threads.schedule(&mut monitors).unwrap();
+            order.push(threads.current().id);
+        }
+
+        assert_eq!(order, vec![1, 2, 0, 1]);
+    }
+
+    #[test]
+    fn all_terminated() {
+        let mut monitors = Monitors::default();
+        let mut threads = Threads::new(Stack::new());
+        threads.terminate();
+
+        assert!(!threads.schedule(&mut monitors).unwrap());
+    }
+
+    #[test]
+    fn sleeping_until_deadline() {
+        let mut monitors = Monitors::default();
+        let mut threads = Threads::new(Stack::new());
+        threads.spawn(Stack::new(), None);
+
+        threads.set_state(ThreadState::Sleeping(threads.time() + 10));
+        threads.schedule(&mut monitors).unwrap();
+        assert_eq!(threads.current().id, 1);
+
+        // The clock jumps to the deadline when no other thread can run.
+        threads.terminate();
+        threads.schedule(&mut monitors).unwrap();
+        assert_eq!(threads.current().id, MAIN_THREAD);
+        assert_eq!(threads.time(), 10);
+    }
+
+    #[test]
+    fn blocked_until_monitor_released() {
+        let mut monitors = Monitors::default();
+        let mut threads = Threads::new(Stack::new());
+        threads.spawn(Stack::new(), None);
+
+        monitors.enter(7, 1);
+        threads.set_state(ThreadState::Blocked {
+            object: 7,
+            entry_count: 1,
+        });
+        threads.schedule(&mut monitors).unwrap();
+        assert_eq!(threads.current().id, 1);
+
+        monitors.exit(7, 1);
+        threads.schedule(&mut monitors).unwrap();
+        assert_eq!(threads.current().id, MAIN_THREAD);
+        assert_eq!(monitors.owner(7), Some(MAIN_THREAD));
+    }
+
+    #[test]
+    fn notify_one() {
+        let mut monitors = Monitors::default();
+        let mut threads = Threads::new(Stack::new());
+        threads.spawn(Stack::new(), None);
+        threads.spawn(Stack::new(), None);
+
+        // Thread 1 waits before the main thread, so it is the one notified.
+        threads.schedule(&mut monitors).unwrap();
+        threads.wait(7, 2, None);
+        threads.schedule(&mut monitors).unwrap();
+        threads.schedule(&mut monitors).unwrap();
+        assert_eq!(threads.current().id, MAIN_THREAD);
+        threads.wait(7, 1, None);
+
+        threads.notify(7, false);
+        assert_eq!(
+            threads.get(1).state,
+            ThreadState::Blocked {
+                object: 7,
+                entry_count: 2
+            }
+        );
+        assert!(matches!(
+            threads.get(MAIN_THREAD).state,
+            ThreadState::Waiting { entry_count: 1, .. }
+        ));
+    }
+
+    #[test]
+    fn deadlock() {
+        let mut monitors = Monitors::default();
+        let mut threads = Threads::new(Stack::new());
+        threads.set_state(ThreadState::Joining(MAIN_THREAD));
+
+        let error = threads.schedule(&mut monitors).unwrap_err();
+        assert_eq!(error.to_string(), "Deadlock, all threads are waiting.");
+    }
+}
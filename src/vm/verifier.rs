@@ -0,0 +1,1345 @@
+//! Verification by type checking, run when a class is linked:
+//! https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-4.html#jvms-4.10.1
+//!
+//! The types of the locals and operands at the start of each basic block are given by the
+//! `StackMapTable` attribute. Each instruction is checked to be applied to operands of the right
+//! type, and the types flowing into a block must be assignable to its frame.
+//!
+//! This is less than the JVMS type checker does:
+//!
+//! - References are only checked to be references. Any class is accepted where another class is
+//!   expected, so passing a `java/lang/String` where a `java/lang/Thread` is expected passes
+//!   verification. Whether one class is assignable to another is left to the runtime checks of
+//!   the interpreter. Classes are verified while they are loaded, so checking the hierarchy here
+//!   would load classes that refer back to the class being loaded.
+//! - The frame of an exception handler is only checked against the locals at the start of each
+//!   instruction it covers, like `exceptionStackFrame` in JVMS 4.10.1.6. Locals stored by the
+//!   instruction itself are not checked.
+//!
+//! Class files older than version 50 have no stack maps, only the structure of their code is
+//! checked.
+
+use crate::binary::{bytes_to_i16, bytes_to_i32};
+use crate::class::attribute::AttributeData::CodeInfo;
+use crate::class::attribute::{Code, StackMapFrame, VerificationType};
+use crate::class::code::Opcode::*;
+use crate::class::code::{Instruction, Opcode};
+use crate::class::constant::Constant;
+use crate::class::{Class, MethodAccessFlags, MethodInfo};
+use crate::error::{Error, Result};
+use crate::vm::data_type::{FieldType, MethodDescriptor};
+use std::collections::HashMap;
+use std::convert::TryInto;
+
+const VERIFY_ERROR: &str = "java/lang/VerifyError";
+
+/// The first class file version with stack maps.
+const TYPE_CHECKING_VERSION: u16 = 50;
+
+/// Verifies the code of all methods of the class, a failure is a `java/lang/VerifyError`
+/// naming the method and pc.
+pub fn verify(class: &Class) -> Result<()> {
+    for method in &class.methods {
+        let code = method.attributes.iter().find_map(|a| match &a.data {
+            CodeInfo(code) => Some(code),
+            _ => None,
+        });
+
+        if let Some(code) = code {
+            MethodVerifier::new(class, method, code).verify()?;
+        }
+    }
+    Ok(())
+}
+
+/// The type of a local or operand. Unlike in stack maps a long or double local is followed by a
+/// `Top` in the next slot, on the operand stack they are a single entry.
+#[derive(Debug, Clone, PartialEq)]
+enum Type {
+    Top,
+    Int,
+    Float,
+    Long,
+    Double,
+    Null,
+    UninitializedThis,
+    Uninitialized(u16),
+
+    /// A class name or an array descriptor, like `java/lang/String` or `[I`.
+    Reference(String),
+}
+
+impl Type {
+    fn from_field_type(field_type: &FieldType) -> Type {
+        match field_type {
+            FieldType::Byte
+            | FieldType::Char
+            | FieldType::Int
+            | FieldType::Short
+            | FieldType::Boolean => Type::Int,
+            FieldType::Float => Type::Float,
+            FieldType::Long => Type::Long,
+            FieldType::Double => Type::Double,
+            FieldType::Object(class_name) => Type::Reference(class_name.to_owned()),
+            FieldType::Array(_) => Type::Reference(field_type.to_string()),
+        }
+    }
+
+    fn from_descriptor(descriptor: &str) -> Result<Type> {
+        let field_type: FieldType = descriptor.try_into()?;
+        Ok(Type::from_field_type(&field_type))
+    }
+
+    fn from_verification_type(verification_type: &VerificationType) -> Type {
+        match verification_type {
+            VerificationType::Top => Type::Top,
+            VerificationType::Integer => Type::Int,
+            VerificationType::Float => Type::Float,
+            VerificationType::Double => Type::Double,
+            VerificationType::Long => Type::Long,
+            VerificationType::Null => Type::Null,
+            VerificationType::UninitializedThis => Type::UninitializedThis,
+            VerificationType::Object(class_name) => Type::Reference(class_name.to_owned()),
+            VerificationType::Uninitialized(offset) => Type::Uninitialized(*offset),
+        }
+    }
+
+    fn size(&self) -> usize {
+        match self {
+            Type::Long | Type::Double => 2,
+            _ => 1,
+        }
+    }
+
+    fn is_reference(&self) -> bool {
+        matches!(
+            self,
+            Type::Null | Type::UninitializedThis | Type::Uninitialized(_) | Type::Reference(_)
+        )
+    }
+
+    /// Null or an initialized reference.
+    fn is_initialized_reference(&self) -> bool {
+        matches!(self, Type::Null | Type::Reference(_))
+    }
+
+    fn is_assignable_to(&self, to: &Type) -> bool {
+        match (self, to) {
+            (_, Type::Top) => true,
+            (Type::Null, Type::Reference(_)) | (Type::Reference(_), Type::Reference(_)) => true,
+            (from, to) => from == to,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Frame {
+    locals: Vec<Type>,
+    stack: Vec<Type>,
+}
+
+impl Frame {
+    fn is_assignable_to(&self, to: &Frame) -> bool {
+        self.locals.len() == to.locals.len()
+            && self.stack.len() == to.stack.len()
+            && self
+                .locals
+                .iter()
+                .zip(&to.locals)
+                .chain(self.stack.iter().zip(&to.stack))
+                .all(|(from, to)| from.is_assignable_to(to))
+    }
+
+    /// Replaces the uninitialized type once the object is initialized by its constructor.
+    fn initialize(&mut self, uninitialized: &Type, initialized: &Type) {
+        for value in self.locals.iter_mut().chain(self.stack.iter_mut()) {
+            if value == uninitialized {
+                *value = initialized.clone();
+            }
+        }
+    }
+}
+
+struct MethodVerifier<'a> {
+    class: &'a Class,
+    method: &'a MethodInfo,
+    code: &'a Code,
+    pc: u16,
+
+    /// The start of each instruction, indexed by byte offset.
+    starts: Vec<bool>,
+
+    /// The frames of the stack map, by offset.
+    frames: HashMap<u16, Frame>,
+}
+
+impl<'a> MethodVerifier<'a> {
+    fn new(class: &'a Class, method: &'a MethodInfo, code: &'a Code) -> Self {
+        MethodVerifier {
+            class,
+            method,
+            code,
+            pc: 0,
+            starts: vec![],
+            frames: HashMap::new(),
+        }
+    }
+
+    fn verify(&mut self) -> Result<()> {
+        self.check_structure()?;
+
+        if self.class.version.major >= TYPE_CHECKING_VERSION {
+            self.check_types()?;
+        }
+
+        Ok(())
+    }
+
+    fn error<T>(&self, message: &str) -> Result<T> {
+        Err(Error::linkage_error(
+            VERIFY_ERROR,
+            format!(
+                "{}.{}{} at pc {}: {}",
+                self.class.this_class, self.method.name, self.method.descriptor, self.pc, message
+            ),
+        ))
+    }
+
+    fn instructions(&self) -> impl Iterator<Item = (u16, &'a Instruction)> {
+        let instructions = &self.code.instructions;
+        let mut pc = 0;
+        std::iter::from_fn(move || {
+            let instruction = instructions.get(pc as usize)?;
+            let current = pc;
+            pc += instruction.size();
+            Some((current, instruction))
+        })
+    }
+
+    /// Checks that branch targets, exception handlers and local variable indexes are within
+    /// the code, and that execution can not fall off the end of it.
+    fn check_structure(&mut self) -> Result<()> {
+        let length = self.code.instructions.len();
+        if length == 0 {
+            return self.error("Code is empty");
+        }
+
+        self.starts = vec![false; length];
+        for (pc, instruction) in self.instructions() {
+            self.pc = pc;
+            if instruction.opcode == OperationSpacer
+                || pc as usize + instruction.size() as usize > length
+            {
+                return self.error("Truncated instruction");
+            }
+            self.starts[pc as usize] = true;
+        }
+
+        let mut last = None;
+        for (pc, instruction) in self.instructions() {
+            self.pc = pc;
+            for target in self.targets(instruction)? {
+                self.check_target(target)?;
+            }
+            if let Some((index, size)) = local_index(instruction) {
+                if index as usize + size > self.code.max_locals as usize {
+                    return self.error(&format!("Local variable index {} out of bounds", index));
+                }
+            }
+            last = Some(instruction);
+        }
+
+        if let Some(instruction) = last {
+            if falls_through(instruction) {
+                return self.error("Falling off the end of the code");
+            }
+        }
+
+        for handler in &self.code.exception_handlers {
+            self.pc = handler.start_pc;
+            let end_valid = handler.end_pc as usize == length
+                || self.starts.get(handler.end_pc as usize) == Some(&true);
+            if handler.start_pc >= handler.end_pc
+                || !self.is_start(handler.start_pc as i32)
+                || !end_valid
+            {
+                return self.error("Illegal exception handler range");
+            }
+            if !self.is_start(handler.handler_pc as i32) {
+                return self.error(&format!(
+                    "Illegal exception handler pc {}",
+                    handler.handler_pc
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn is_start(&self, target: i32) -> bool {
+        target >= 0 && self.starts.get(target as usize) == Some(&true)
+    }
+
+    fn check_target(&self, target: i32) -> Result<()> {
+        if self.is_start(target) {
+            Ok(())
+        } else {
+            self.error(&format!("Illegal branch target {}", target))
+        }
+    }
+
+    /// The targets of a branch or switch instruction at the current pc.
+    fn targets(&self, instruction: &Instruction) -> Result<Vec<i32>> {
+        let pc = self.pc as i32;
+        let operands = &instruction.operands;
+        let targets = match instruction.opcode {
+            IfEq | IfNe | IfLt | IfGe | IfGt | IfLe | IfIcmpEq | IfIcmpNe | IfIcmpLt | IfIcmpGe
+            | IfIcmpGt | IfIcmpLe | IfAcmpEq | IfAcmpNe | IfNull | IfNonNull | Goto | Jsr => {
+                vec![pc + bytes_to_i16(operands) as i32]
+            }
+            GotoW | JsrW => vec![pc + bytes_to_i32(operands)],
+            TableSwitch => {
+                let low = bytes_to_i32(&operands[4..8]);
+                let high = bytes_to_i32(&operands[8..12]);
+                if low > high {
+                    return self.error("Illegal tableswitch bounds");
+                }
+                let offsets = operands[12..].chunks(4).map(|o| pc + bytes_to_i32(o));
+                std::iter::once(pc + bytes_to_i32(&operands[0..4]))
+                    .chain(offsets)
+                    .collect()
+            }
+            LookupSwitch => {
+                let offsets = operands[8..].chunks(8).map(|o| pc + bytes_to_i32(&o[4..8]));
+                std::iter::once(pc + bytes_to_i32(&operands[0..4]))
+                    .chain(offsets)
+                    .collect()
+            }
+            _ => vec![],
+        };
+        Ok(targets)
+    }
+
+    fn check_types(&mut self) -> Result<()> {
+        let initial = self.initial_locals()?;
+        self.decode_stack_map(&initial)?;
+
+        let mut state = Some(self.expand_frame(&initial, vec![])?);
+        for (pc, instruction) in self.instructions() {
+            self.pc = pc;
+
+            if let Some(frame) = self.frames.get(&pc) {
+                if let Some(state) = &state {
+                    if !state.is_assignable_to(frame) {
+                        return self.error("Frame is not assignable to the stack map frame");
+                    }
+                }
+                state = Some(frame.clone());
+            }
+
+            let mut frame = match state {
+                Some(frame) => frame,
+                None => return self.error("Expecting a stack map frame"),
+            };
+
+            self.check_exception_handlers(&frame)?;
+            state = if self.execute(instruction, &mut frame)? {
+                Some(frame)
+            } else {
+                None
+            };
+        }
+
+        Ok(())
+    }
+
+    /// The locals on entry to the method as stack map types, longs and doubles take up one entry.
+    fn initial_locals(&self) -> Result<Vec<Type>> {
+        let mut locals = vec![];
+        if !self
+            .method
+            .access_flags
+            .contains(MethodAccessFlags::ACC_STATIC)
+        {
+            if self.method.name == "<init>" && self.class.this_class != "java/lang/Object" {
+                locals.push(Type::UninitializedThis);
+            } else {
+                locals.push(Type::Reference(self.class.this_class.to_owned()));
+            }
+        }
+
+        for argument in &self.method.descriptor.argument_types {
+            locals.push(Type::from_field_type(argument));
+        }
+
+        Ok(locals)
+    }
+
+    fn expand_frame(&self, locals: &[Type], stack: Vec<Type>) -> Result<Frame> {
+        let mut expanded = Vec::with_capacity(self.code.max_locals as usize);
+        for local in locals {
+            expanded.push(local.clone());
+            if local.size() == 2 {
+                expanded.push(Type::Top);
+            }
+        }
+
+        if expanded.len() > self.code.max_locals as usize {
+            return self.error("Stack map frame has more locals than max_locals");
+        }
+        expanded.resize(self.code.max_locals as usize, Type::Top);
+
+        let frame = Frame {
+            locals: expanded,
+            stack,
+        };
+        self.check_stack_size(&frame)?;
+        Ok(frame)
+    }
+
+    fn decode_stack_map(&mut self, initial: &[Type]) -> Result<()> {
+        let mut locals = initial.to_vec();
+        let mut offset: Option<u16> = None;
+
+        for stack_map_frame in self.code.stack_map_table() {
+            let delta = stack_map_frame.offset_delta();
+            let pc = match offset {
+                None => Some(delta),
+                Some(offset) => offset.checked_add(delta).and_then(|o| o.checked_add(1)),
+            };
+            self.pc = pc.unwrap_or(u16::MAX);
+            let pc = match pc {
+                Some(pc) if self.is_start(pc as i32) => pc,
+                _ => return self.error("Stack map frame is not at an instruction"),
+            };
+            offset = Some(pc);
+
+            let stack = match stack_map_frame {
+                StackMapFrame::Same { .. } => vec![],
+                StackMapFrame::SameLocals1StackItem { stack, .. } => {
+                    vec![Type::from_verification_type(stack)]
+                }
+                StackMapFrame::Chop { count, .. } => {
+                    let count = *count as usize;
+                    if count > locals.len() {
+                        return self.error("Stack map frame chops too many locals");
+                    }
+                    locals.truncate(locals.len() - count);
+                    vec![]
+                }
+                StackMapFrame::Append { locals: added, .. } => {
+                    locals.extend(added.iter().map(Type::from_verification_type));
+                    vec![]
+                }
+                StackMapFrame::Full {
+                    locals: full_locals,
+                    stack,
+                    ..
+                } => {
+                    locals = full_locals
+                        .iter()
+                        .map(Type::from_verification_type)
+                        .collect();
+                    stack.iter().map(Type::from_verification_type).collect()
+                }
+            };
+
+            let frame = self.expand_frame(&locals, stack)?;
+            self.frames.insert(pc, frame);
+        }
+
+        Ok(())
+    }
+
+    /// The locals at each instruction covered by an exception handler must be assignable to the
+    /// frame of the handler, with the caught exception as the only operand.
+    fn check_exception_handlers(&self, frame: &Frame) -> Result<()> {
+        let handlers = self
+            .code
+            .exception_handlers
+            .iter()
+            .filter(|h| h.start_pc <= self.pc && self.pc < h.end_pc);
+
+        for handler in handlers {
+            let catch_type = handler
+                .catch_type
+                .clone()
+                .unwrap_or_else(|| "java/lang/Throwable".to_owned());
+            let handler_frame = Frame {
+                locals: frame.locals.clone(),
+                stack: vec![Type::Reference(catch_type)],
+            };
+            self.check_jump(handler.handler_pc as i32, &handler_frame)?;
+        }
+
+        Ok(())
+    }
+
+    fn check_jump(&self, target: i32, frame: &Frame) -> Result<()> {
+        match self.frames.get(&(target as u16)) {
+            Some(target_frame) if frame.is_assignable_to(target_frame) => Ok(()),
+            Some(_) => self.error(&format!(
+                "Frame is not assignable to the stack map frame at {}",
+                target
+            )),
+            None => self.error(&format!("Expecting a stack map frame at {}", target)),
+        }
+    }
+
+    fn check_stack_size(&self, frame: &Frame) -> Result<()> {
+        let size: usize = frame.stack.iter().map(Type::size).sum();
+        if size > self.code.max_stack as usize {
+            self.error("Operand stack overflow")
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Applies the instruction to the types of the frame. Returns false if execution does not
+    /// continue with the next instruction.
+    fn execute(&self, instruction: &Instruction, frame: &mut Frame) -> Result<bool> {
+        let operands = &instruction.operands;
+        match instruction.opcode {
+            Nop => {}
+
+            // Constants
+            AConstNull => self.push(frame, Type::Null)?,
+            IConstM1 | IConst0 | IConst1 | IConst2 | IConst3 | IConst4 | IConst5 | BiPush
+            | SiPush => self.push(frame, Type::Int)?,
+            LConst0 | LConst1 => self.push(frame, Type::Long)?,
+            FConst0 | FConst1 | FConst2 => self.push(frame, Type::Float)?,
+            DConst0 | DConst1 => self.push(frame, Type::Double)?,
+            Ldc => self.load_constant(frame, operands[0] as u16, false)?,
+            LdcW => self.load_constant(frame, index(operands), false)?,
+            Ldc2W => self.load_constant(frame, index(operands), true)?,
+
+            // Loads
+            ILoad => self.load(frame, operands[0] as u16, Type::Int)?,
+            LLoad => self.load(frame, operands[0] as u16, Type::Long)?,
+            FLoad => self.load(frame, operands[0] as u16, Type::Float)?,
+            DLoad => self.load(frame, operands[0] as u16, Type::Double)?,
+            ALoad => self.load_reference(frame, operands[0] as u16)?,
+            ILoad0 | ILoad1 | ILoad2 | ILoad3 => {
+                self.load(frame, short_form_slot(&instruction.opcode), Type::Int)?
+            }
+            LLoad0 | LLoad1 | LLoad2 | LLoad3 => {
+                self.load(frame, short_form_slot(&instruction.opcode), Type::Long)?
+            }
+            FLoad0 | FLoad1 | FLoad2 | FLoad3 => {
+                self.load(frame, short_form_slot(&instruction.opcode), Type::Float)?
+            }
+            DLoad0 | DLoad1 | DLoad2 | DLoad3 => {
+                self.load(frame, short_form_slot(&instruction.opcode), Type::Double)?
+            }
+            ALoad0 | ALoad1 | ALoad2 | ALoad3 => {
+                self.load_reference(frame, short_form_slot(&instruction.opcode))?
+            }
+            IaLoad | BaLoad | CaLoad | SaLoad => self.array_load(frame, Some(Type::Int))?,
+            LaLoad => self.array_load(frame, Some(Type::Long))?,
+            FaLoad => self.array_load(frame, Some(Type::Float))?,
+            DaLoad => self.array_load(frame, Some(Type::Double))?,
+            AaLoad => self.array_load(frame, None)?,
+
+            // Stores
+            IStore => self.store(frame, operands[0] as u16, Type::Int)?,
+            LStore => self.store(frame, operands[0] as u16, Type::Long)?,
+            FStore => self.store(frame, operands[0] as u16, Type::Float)?,
+            DStore => self.store(frame, operands[0] as u16, Type::Double)?,
+            AStore => self.store_reference(frame, operands[0] as u16)?,
+            IStore0 | IStore1 | IStore2 | IStore3 => {
+                self.store(frame, short_form_slot(&instruction.opcode), Type::Int)?
+            }
+            LStore0 | LStore1 | LStore2 | LStore3 => {
+                self.store(frame, short_form_slot(&instruction.opcode), Type::Long)?
+            }
+            FStore0 | FStore1 | FStore2 | FStore3 => {
+                self.store(frame, short_form_slot(&instruction.opcode), Type::Float)?
+            }
+            DStore0 | DStore1 | DStore2 | DStore3 => {
+                self.store(frame, short_form_slot(&instruction.opcode), Type::Double)?
+            }
+            AStore0 | AStore1 | AStore2 | AStore3 => {
+                self.store_reference(frame, short_form_slot(&instruction.opcode))?
+            }
+            IaStore | BaStore | CaStore | SaStore => self.array_store(frame, Type::Int)?,
+            LaStore => self.array_store(frame, Type::Long)?,
+            FaStore => self.array_store(frame, Type::Float)?,
+            DaStore => self.array_store(frame, Type::Double)?,
+            AaStore => {
+                self.pop_reference(frame)?;
+                self.pop(frame, &Type::Int)?;
+                self.pop_array(frame)?;
+            }
+            Wide => return self.execute_wide(instruction, frame),
+
+            // Stack management
+            Pop => self.pop_words(frame, 1).map(|_| ())?,
+            Pop2 => self.pop_words(frame, 2).map(|_| ())?,
+            Dup => self.dup(frame, 1, 0)?,
+            DupX1 => self.dup(frame, 1, 1)?,
+            DupX2 => self.dup(frame, 1, 2)?,
+            Dup2 => self.dup(frame, 2, 0)?,
+            Dup2X1 => self.dup(frame, 2, 1)?,
+            Dup2X2 => self.dup(frame, 2, 2)?,
+            Swap => {
+                let value1 = self.pop_words(frame, 1)?;
+                let value2 = self.pop_words(frame, 1)?;
+                frame.stack.extend(value1);
+                frame.stack.extend(value2);
+            }
+
+            // Arithmetic
+            IAdd | ISub | IMul | IDiv | IRem | IShl | IShr | IUshr | IAnd | IOr | IXor => {
+                self.binary(frame, Type::Int, Type::Int)?
+            }
+            LAdd | LSub | LMul | LDiv | LRem | LAnd | LOr | LXor => {
+                self.binary(frame, Type::Long, Type::Long)?
+            }
+            LShl | LShr | LUshr => {
+                self.pop(frame, &Type::Int)?;
+                self.pop(frame, &Type::Long)?;
+                self.push(frame, Type::Long)?;
+            }
+            FAdd | FSub | FMul | FDiv | FRem => self.binary(frame, Type::Float, Type::Float)?,
+            DAdd | DSub | DMul | DDiv | DRem => self.binary(frame, Type::Double, Type::Double)?,
+            INeg => self.convert(frame, Type::Int, Type::Int)?,
+            LNeg => self.convert(frame, Type::Long, Type::Long)?,
+            FNeg => self.convert(frame, Type::Float, Type::Float)?,
+            DNeg => self.convert(frame, Type::Double, Type::Double)?,
+            IInc => self.increment(frame, operands[0] as u16)?,
+            LCmp => self.binary(frame, Type::Long, Type::Int)?,
+            FCmpl | FCmpg => self.binary(frame, Type::Float, Type::Int)?,
+            DCmpl | DCmpg => self.binary(frame, Type::Double, Type::Int)?,
+
+            // Conversions
+            I2l => self.convert(frame, Type::Int, Type::Long)?,
+            I2f => self.convert(frame, Type::Int, Type::Float)?,
+            I2d => self.convert(frame, Type::Int, Type::Double)?,
+            L2i => self.convert(frame, Type::Long, Type::Int)?,
+            L2f => self.convert(frame, Type::Long, Type::Float)?,
+            L2d => self.convert(frame, Type::Long, Type::Double)?,
+            F2i => self.convert(frame, Type::Float, Type::Int)?,
+            F2l => self.convert(frame, Type::Float, Type::Long)?,
+            F2d => self.convert(frame, Type::Float, Type::Double)?,
+            D2i => self.convert(frame, Type::Double, Type::Int)?,
+            D2l => self.convert(frame, Type::Double, Type::Long)?,
+            D2f => self.convert(frame, Type::Double, Type::Float)?,
+            I2b | I2c | I2s => self.convert(frame, Type::Int, Type::Int)?,
+
+            // Control transfer
+            IfEq | IfNe | IfLt | IfGe | IfGt | IfLe => {
+                self.pop(frame, &Type::Int)?;
+                self.branch(instruction, frame)?;
+            }
+            IfIcmpEq | IfIcmpNe | IfIcmpLt | IfIcmpGe | IfIcmpGt | IfIcmpLe => {
+                self.pop(frame, &Type::Int)?;
+                self.pop(frame, &Type::Int)?;
+                self.branch(instruction, frame)?;
+            }
+            IfAcmpEq | IfAcmpNe => {
+                self.pop_reference(frame)?;
+                self.pop_reference(frame)?;
+                self.branch(instruction, frame)?;
+            }
+            IfNull | IfNonNull => {
+                self.pop_reference(frame)?;
+                self.branch(instruction, frame)?;
+            }
+            Goto | GotoW => {
+                self.branch(instruction, frame)?;
+                return Ok(false);
+            }
+            TableSwitch | LookupSwitch => {
+                self.pop(frame, &Type::Int)?;
+                self.branch(instruction, frame)?;
+                return Ok(false);
+            }
+            Jsr | JsrW | Ret => {
+                return self.error("jsr and ret are not allowed in class files with stack maps")
+            }
+
+            // Method invocation and return
+            InvokeVirtual | InvokeSpecial | InvokeStatic | InvokeInterface => {
+                self.invoke(instruction, frame)?
+            }
+            InvokeDynamic => {
+                let (_, _, descriptor) =
+                    self.class.constants.get_invoke_dynamic(index(operands))?;
+                let descriptor: MethodDescriptor = descriptor.try_into()?;
+                self.pop_arguments(frame, &descriptor)?;
+                if let Some(return_type) = &descriptor.return_type {
+                    self.push(frame, Type::from_field_type(return_type))?;
+                }
+            }
+            Return => {
+                if self.method.descriptor.return_type.is_some() {
+                    return self.error("Wrong return type");
+                }
+                if frame.locals.contains(&Type::UninitializedThis) {
+                    return self.error("Constructor must call super() or this() before return");
+                }
+                return Ok(false);
+            }
+            IReturn | LReturn | FReturn | DReturn | AReturn => {
+                let return_type = match &self.method.descriptor.return_type {
+                    Some(return_type) => Type::from_field_type(return_type),
+                    None => return self.error("Wrong return type"),
+                };
+                let expected = match instruction.opcode {
+                    IReturn => Type::Int,
+                    LReturn => Type::Long,
+                    FReturn => Type::Float,
+                    DReturn => Type::Double,
+                    _ => Type::Reference("java/lang/Object".to_owned()),
+                };
+                if !return_type.is_assignable_to(&expected) {
+                    return self.error("Wrong return type");
+                }
+                self.pop(frame, &return_type)?;
+                return Ok(false);
+            }
+
+            // Fields
+            GetStatic | PutStatic | GetField | PutField => self.access_field(instruction, frame)?,
+
+            // Objects and arrays
+            New => self.push(frame, Type::Uninitialized(self.pc))?,
+            NewArray => {
+                let component = match operands[0] {
+                    4 => "Z",
+                    5 => "C",
+                    6 => "F",
+                    7 => "D",
+                    8 => "B",
+                    9 => "S",
+                    10 => "I",
+                    11 => "J",
+                    _ => return self.error("Illegal newarray type"),
+                };
+                self.pop(frame, &Type::Int)?;
+                self.push(frame, Type::Reference(format!("[{}", component)))?;
+            }
+            ANewArray => {
+                let component = self.class.constants.get_class_info_name(index(operands))?;
+                self.pop(frame, &Type::Int)?;
+                self.push(frame, Type::Reference(array_of(component)))?;
+            }
+            MultiANewArray => {
+                let class_name = self.class.constants.get_class_info_name(index(operands))?;
+                let dimensions = operands[2] as usize;
+                if dimensions == 0
+                    || class_name.len() - class_name.trim_start_matches('[').len() < dimensions
+                {
+                    return self.error("Illegal multianewarray dimensions");
+                }
+                for _ in 0..dimensions {
+                    self.pop(frame, &Type::Int)?;
+                }
+                self.push(frame, Type::Reference(class_name.to_owned()))?;
+            }
+            ArrayLength => {
+                self.pop_array(frame)?;
+                self.push(frame, Type::Int)?;
+            }
+            CheckCast => {
+                let class_name = self.class.constants.get_class_info_name(index(operands))?;
+                self.pop_reference(frame)?;
+                self.push(frame, Type::Reference(class_name.to_owned()))?;
+            }
+            Instanceof => {
+                self.pop_reference(frame)?;
+                self.push(frame, Type::Int)?;
+            }
+            AThrow => {
+                self.pop_reference(frame)?;
+                return Ok(false);
+            }
+            MonitorEnter | MonitorExit => self.pop_reference(frame).map(|_| ())?,
+
+            ImpDep2 | BreakPoint | OperationSpacer => return self.error("Illegal opcode"),
+        }
+
+        Ok(true)
+    }
+
+    fn execute_wide(&self, instruction: &Instruction, frame: &mut Frame) -> Result<bool> {
+        let slot = index(&instruction.operands);
+        match &instruction.modified {
+            Some(ILoad) => self.load(frame, slot, Type::Int)?,
+            Some(LLoad) => self.load(frame, slot, Type::Long)?,
+            Some(FLoad) => self.load(frame, slot, Type::Float)?,
+            Some(DLoad) => self.load(frame, slot, Type::Double)?,
+            Some(ALoad) => self.load_reference(frame, slot)?,
+            Some(IStore) => self.store(frame, slot, Type::Int)?,
+            Some(LStore) => self.store(frame, slot, Type::Long)?,
+            Some(FStore) => self.store(frame, slot, Type::Float)?,
+            Some(DStore) => self.store(frame, slot, Type::Double)?,
+            Some(AStore) => self.store_reference(frame, slot)?,
+            Some(IInc) => self.increment(frame, slot)?,
+            Some(Ret) => {
+                return self.error("jsr and ret are not allowed in class files with stack maps")
+            }
+            _ => return self.error("Invalid wide instruction"),
+        }
+        Ok(true)
+    }
+
+    fn push(&self, frame: &mut Frame, value: Type) -> Result<()> {
+        frame.stack.push(value);
+        self.check_stack_size(frame)
+    }
+
+    fn pop(&self, frame: &mut Frame, expected: &Type) -> Result<Type> {
+        match frame.stack.pop() {
+            Some(value) if value.is_assignable_to(expected) => Ok(value),
+            Some(_) => self.error("Bad type on operand stack"),
+            None => self.error("Operand stack underflow"),
+        }
+    }
+
+    /// Pops an initialized reference or null.
+    fn pop_reference(&self, frame: &mut Frame) -> Result<Type> {
+        match frame.stack.pop() {
+            Some(value) if value.is_initialized_reference() => Ok(value),
+            Some(_) => self.error("Bad type on operand stack, expected a reference"),
+            None => self.error("Operand stack underflow"),
+        }
+    }
+
+    fn pop_array(&self, frame: &mut Frame) -> Result<Type> {
+        match self.pop_reference(frame)? {
+            Type::Reference(descriptor) if !descriptor.starts_with('[') => {
+                self.error("Bad type on operand stack, expected an array")
+            }
+            array => Ok(array),
+        }
+    }
+
+    /// Pops values taking up exactly `words` slots, a long or double can not be split.
+    fn pop_words(&self, frame: &mut Frame, words: usize) -> Result<Vec<Type>> {
+        let mut values = vec![];
+        let mut size = 0;
+        while size < words {
+            match frame.stack.pop() {
+                Some(value) => {
+                    size += value.size();
+                    values.insert(0, value);
+                }
+                None => return self.error("Operand stack underflow"),
+            }
+        }
+
+        if size != words {
+            return self.error("Bad type on operand stack, long or double split");
+        }
+        Ok(values)
+    }
+
+    /// The `dup` instructions, duplicating the top `words` slots below the `under` slots under
+    /// them.
+    fn dup(&self, frame: &mut Frame, words: usize, under: usize) -> Result<()> {
+        let top = self.pop_words(frame, words)?;
+        let below = self.pop_words(frame, under)?;
+        frame.stack.extend(top.iter().cloned());
+        frame.stack.extend(below);
+        frame.stack.extend(top);
+        self.check_stack_size(frame)
+    }
+
+    fn binary(&self, frame: &mut Frame, operand: Type, result: Type) -> Result<()> {
+        self.pop(frame, &operand)?;
+        self.pop(frame, &operand)?;
+        self.push(frame, result)
+    }
+
+    fn convert(&self, frame: &mut Frame, from: Type, to: Type) -> Result<()> {
+        self.pop(frame, &from)?;
+        self.push(frame, to)
+    }
+
+    fn load(&self, frame: &mut Frame, slot: u16, expected: Type) -> Result<()> {
+        match frame.locals.get(slot as usize) {
+            Some(value) if *value == expected => self.push(frame, expected),
+            Some(_) => self.error("Bad local variable type"),
+            None => self.error("Local variable index out of bounds"),
+        }
+    }
+
+    fn load_reference(&self, frame: &mut Frame, slot: u16) -> Result<()> {
+        match frame.locals.get(slot as usize) {
+            Some(value) if value.is_reference() => {
+                let value = value.clone();
+                self.push(frame, value)
+            }
+            Some(_) => self.error("Bad local variable type"),
+            None => self.error("Local variable index out of bounds"),
+        }
+    }
+
+    fn store(&self, frame: &mut Frame, slot: u16, expected: Type) -> Result<()> {
+        let value = self.pop(frame, &expected)?;
+        self.set_local(frame, slot, value)
+    }
+
+    fn store_reference(&self, frame: &mut Frame, slot: u16) -> Result<()> {
+        match frame.stack.pop() {
+            Some(value) if value.is_reference() => self.set_local(frame, slot, value),
+            Some(_) => self.error("Bad type on operand stack, expected a reference"),
+            None => self.error("Operand stack underflow"),
+        }
+    }
+
+    fn set_local(&self, frame: &mut Frame, slot: u16, value: Type) -> Result<()> {
+        let slot = slot as usize;
+        if slot + value.size() > frame.locals.len() {
+            return self.error("Local variable index out of bounds");
+        }
+
+        // Overwriting the second half of a long or double invalidates the first half.
+        if slot > 0 && frame.locals[slot - 1].size() == 2 {
+            frame.locals[slot - 1] = Type::Top;
+        }
+        if value.size() == 2 {
+            frame.locals[slot + 1] = Type::Top;
+        }
+        frame.locals[slot] = value;
+        Ok(())
+    }
+
+    fn increment(&self, frame: &mut Frame, slot: u16) -> Result<()> {
+        match frame.locals.get(slot as usize) {
+            Some(Type::Int) => Ok(()),
+            Some(_) => self.error("Bad local variable type"),
+            None => self.error("Local variable index out of bounds"),
+        }
+    }
+
+    /// Array loads, `component` is the type of the elements or `None` for arrays of references.
+    fn array_load(&self, frame: &mut Frame, component: Option<Type>) -> Result<()> {
+        self.pop(frame, &Type::Int)?;
+        let array = self.pop_array(frame)?;
+
+        let element = match (component, array) {
+            (Some(component), _) => component,
+            (None, Type::Reference(descriptor)) => match &descriptor[1..] {
+                component if component.starts_with('[') || component.starts_with('L') => {
+                    Type::from_descriptor(component)?
+                }
+                _ => return self.error("Bad type on operand stack, expected a reference array"),
+            },
+            (None, _) => Type::Null,
+        };
+        self.push(frame, element)
+    }
+
+    fn array_store(&self, frame: &mut Frame, component: Type) -> Result<()> {
+        self.pop(frame, &component)?;
+        self.pop(frame, &Type::Int)?;
+        self.pop_array(frame).map(|_| ())
+    }
+
+    fn load_constant(&self, frame: &mut Frame, index: u16, wide: bool) -> Result<()> {
+        let constants = &self.class.constants;
+        let value = match (constants.get(index), wide) {
+            (Ok(Constant::Integer(_)), false) => Type::Int,
+            (Ok(Constant::Float(_)), false) => Type::Float,
+            (Ok(Constant::StringRef(_)), false) => Type::Reference("java/lang/String".to_owned()),
+            (Ok(Constant::ClassRef(_)), false) => Type::Reference("java/lang/Class".to_owned()),
+            (Ok(Constant::MethodType(_)), false) => {
+                Type::Reference("java/lang/invoke/MethodType".to_owned())
+            }
+            (Ok(Constant::MethodHandle(_, _)), false) => {
+                Type::Reference("java/lang/invoke/MethodHandle".to_owned())
+            }
+            (Ok(Constant::Dynamic(_, name_and_type)), _) => {
+                let (_, descriptor) = constants.get_name_and_type(*name_and_type)?;
+                let value = Type::from_descriptor(descriptor)?;
+                if (value.size() == 2) != wide {
+                    return self.error("Illegal constant type");
+                }
+                value
+            }
+            (Ok(Constant::Long(_)), true) => Type::Long,
+            (Ok(Constant::Double(_)), true) => Type::Double,
+            _ => return self.error("Illegal constant type"),
+        };
+        self.push(frame, value)
+    }
+
+    fn branch(&self, instruction: &Instruction, frame: &Frame) -> Result<()> {
+        for target in self.targets(instruction)? {
+            self.check_jump(target, frame)?;
+        }
+        Ok(())
+    }
+
+    fn pop_arguments(&self, frame: &mut Frame, descriptor: &MethodDescriptor) -> Result<()> {
+        for argument in descriptor.argument_types.iter().rev() {
+            self.pop(frame, &Type::from_field_type(argument))?;
+        }
+        Ok(())
+    }
+
+    fn invoke(&self, instruction: &Instruction, frame: &mut Frame) -> Result<()> {
+        let (class_name, name, descriptor) = self
+            .class
+            .constants
+            .get_member_ref(index(&instruction.operands))?;
+        let descriptor: MethodDescriptor = descriptor.try_into()?;
+        let opcode = &instruction.opcode;
+
+        if name.starts_with('<') && (name != "<init>" || *opcode != InvokeSpecial) {
+            return self.error(&format!("Illegal call to {}", name));
+        }
+
+        self.pop_arguments(frame, &descriptor)?;
+
+        if name == "<init>" {
+            let object = match frame.stack.pop() {
+                Some(object) => object,
+                None => return self.error("Operand stack underflow"),
+            };
+            let initialized = match &object {
+                // A constructor calls a constructor of its own class or of the super class.
+                Type::UninitializedThis
+                    if class_name == self.class.this_class
+                        || class_name == self.class.super_class =>
+                {
+                    self.class.this_class.to_owned()
+                }
+                Type::Uninitialized(pc) => match self.code.instructions.get(*pc as usize) {
+                    Some(new) if new.opcode == New => {
+                        let constants = &self.class.constants;
+                        constants
+                            .get_class_info_name(index(&new.operands))?
+                            .to_owned()
+                    }
+                    _ => return self.error("Uninitialized object not created by new"),
+                },
+                _ => return self.error("Bad type on operand stack, expected uninitialized"),
+            };
+            if matches!(object, Type::Uninitialized(_)) && class_name != initialized {
+                return self.error("Bad constructor call");
+            }
+            frame.initialize(&object, &Type::Reference(initialized));
+        } else if *opcode != InvokeStatic {
+            self.pop_reference(frame)?;
+        }
+
+        if let Some(return_type) = &descriptor.return_type {
+            self.push(frame, Type::from_field_type(return_type))?;
+        }
+        Ok(())
+    }
+
+    fn access_field(&self, instruction: &Instruction, frame: &mut Frame) -> Result<()> {
+        let (_, _, descriptor) = self
+            .class
+            .constants
+            .get_member_ref(index(&instruction.operands))?;
+        let field_type = Type::from_descriptor(descriptor)?;
+
+        match instruction.opcode {
+            GetStatic => self.push(frame, field_type)?,
+            PutStatic => self.pop(frame, &field_type).map(|_| ())?,
+            GetField => {
+                self.pop_reference(frame)?;
+                self.push(frame, field_type)?;
+            }
+            _ => {
+                self.pop(frame, &field_type)?;
+                // Constructors may assign the fields of this before calling super().
+                match frame.stack.pop() {
+                    Some(object)
+                        if object.is_initialized_reference()
+                            || object == Type::UninitializedThis => {}
+                    Some(_) => {
+                        return self.error("Bad type on operand stack, expected a reference")
+                    }
+                    None => return self.error("Operand stack underflow"),
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn index(operands: &[u8]) -> u16 {
+    ((operands[0] as u16) << 8) | operands[1] as u16
+}
+
+/// The slot of the short forms of load and store, like `iload_2`.
+fn short_form_slot(opcode: &Opcode) -> u16 {
+    match opcode {
+        ILoad0 | LLoad0 | FLoad0 | DLoad0 | ALoad0 | IStore0 | LStore0 | FStore0 | DStore0
+        | AStore0 => 0,
+        ILoad1 | LLoad1 | FLoad1 | DLoad1 | ALoad1 | IStore1 | LStore1 | FStore1 | DStore1
+        | AStore1 => 1,
+        ILoad2 | LLoad2 | FLoad2 | DLoad2 | ALoad2 | IStore2 | LStore2 | FStore2 | DStore2
+        | AStore2 => 2,
+        _ => 3,
+    }
+}
+
+/// The local variable accessed by the instruction and the number of slots it takes up.
+fn local_index(instruction: &Instruction) -> Option<(u16, usize)> {
+    let operands = &instruction.operands;
+    match instruction.opcode {
+        ILoad | FLoad | ALoad | IStore | FStore | AStore | IInc | Ret => {
+            Some((operands[0] as u16, 1))
+        }
+        LLoad | DLoad | LStore | DStore => Some((operands[0] as u16, 2)),
+        ILoad0 | ILoad1 | ILoad2 | ILoad3 | FLoad0 | FLoad1 | FLoad2 | FLoad3 | ALoad0 | ALoad1
+        | ALoad2 | ALoad3 | IStore0 | IStore1 | IStore2 | IStore3 | FStore0 | FStore1 | FStore2
+        | FStore3 | AStore0 | AStore1 | AStore2 | AStore3 => {
+            Some((short_form_slot(&instruction.opcode), 1))
+        }
+        LLoad0 | LLoad1 | LLoad2 | LLoad3 | DLoad0 | DLoad1 | DLoad2 | DLoad3 | LStore0
+        | LStore1 | LStore2 | LStore3 | DStore0 | DStore1 | DStore2 | DStore3 => {
+            Some((short_form_slot(&instruction.opcode), 2))
+        }
+        Wide => {
+            let size = match instruction.modified {
+                Some(LLoad | DLoad | LStore | DStore) => 2,
+                _ => 1,
+            };
+            Some((index(operands), size))
+        }
+        _ => None,
+    }
+}
+
+fn falls_through(instruction: &Instruction) -> bool {
+    !matches!(
+        instruction.opcode,
+        Goto | GotoW
+            | TableSwitch
+            | LookupSwitch
+            | Ret
+            | Return
+            | IReturn
+            | LReturn
+            | FReturn
+            | DReturn
+            | AReturn
+            | AThrow
+    )
+}
+
+/// The descriptor of an array of `component`, a class name or array descriptor.
+fn array_of(component: &str) -> String {
+    if component.starts_with('[') {
+        format!("[{}", component)
+    } else {
+        format!("[L{};", component)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::class::attribute::AttributeData::StackMapTable;
+    use crate::class::attribute::{Attribute, Code, ExceptionHandler, StackMapFrame};
+    use crate::class::constant::ConstantPool;
+    use crate::class::{Class, MethodAccessFlags, MethodInfo, Version};
+    use crate::error::Result;
+    use crate::io::code::CodeReader;
+    use crate::vm::verifier::verify;
+    use std::convert::TryInto;
+    use std::io::Cursor;
+    use std::rc::Rc;
+
+    #[test]
+    fn valid_loop() {
+        let bytecode = [
+            0x03, // iconst_0
+            0x3c, // istore_1
+            0x1b, // iload_1
+            0x1a, // iload_0
+            0xa0, 0x00, 0x05, // if_icmpne +5
+            0x1b, // iload_1
+            0xac, // ireturn
+            0x84, 0x01, 0x01, // iinc 1 1
+            0xa7, 0xff, 0xf6, // goto -10
+        ];
+        let frames = vec![
+            StackMapFrame::Append {
+                offset_delta: 2,
+                locals: vec![crate::class::attribute::VerificationType::Integer],
+            },
+            StackMapFrame::Same { offset_delta: 6 },
+        ];
+
+        let code = code_with_frames(2, 2, &bytecode, frames);
+        assert!(verify_method(52, "(I)I", code).is_ok());
+    }
+
+    #[test]
+    fn stack_underflow() {
+        let code = code(2, 0, &[0x60, 0xb1]); // iadd, return
+        assert_error(
+            verify_method(52, "()V", code),
+            "<Anonymous>.test()V at pc 0: Operand stack underflow",
+        );
+    }
+
+    #[test]
+    fn stack_overflow() {
+        let code = code(1, 0, &[0x03, 0x03, 0x57, 0x57, 0xb1]); // iconst_0, iconst_0, pop, pop
+        assert_error(
+            verify_method(52, "()V", code),
+            "<Anonymous>.test()V at pc 1: Operand stack overflow",
+        );
+    }
+
+    #[test]
+    fn bad_operand_type() {
+        let code = code(1, 0, &[0x0b, 0xac]); // fconst_0, ireturn
+        assert_error(
+            verify_method(52, "()I", code),
+            "<Anonymous>.test()I at pc 1: Bad type on operand stack",
+        );
+    }
+
+    #[test]
+    fn bad_return_type() {
+        let code = code(1, 0, &[0x03, 0xac]); // iconst_0, ireturn
+        assert_error(
+            verify_method(52, "()V", code),
+            "<Anonymous>.test()V at pc 1: Wrong return type",
+        );
+    }
+
+    #[test]
+    fn uninitialized_local() {
+        let code = code(1, 2, &[0x1b, 0xac]); // iload_1, ireturn
+        assert_error(
+            verify_method(52, "()I", code),
+            "<Anonymous>.test()I at pc 0: Bad local variable type",
+        );
+    }
+
+    #[test]
+    fn local_out_of_bounds() {
+        let code = code(1, 1, &[0x03, 0x3c, 0xb1]); // iconst_0, istore_1, return
+        assert_error(
+            verify_method(49, "()V", code),
+            "<Anonymous>.test()V at pc 1: Local variable index 1 out of bounds",
+        );
+    }
+
+    #[test]
+    fn branch_into_instruction() {
+        let code = code(1, 0, &[0xa7, 0x00, 0x04, 0x10, 0x01, 0xb1]); // goto +4, bipush 1
+        assert_error(
+            verify_method(49, "()V", code),
+            "<Anonymous>.test()V at pc 0: Illegal branch target 4",
+        );
+    }
+
+    #[test]
+    fn falling_off_end() {
+        let code = code(1, 0, &[0x03]); // iconst_0
+        assert_error(
+            verify_method(49, "()V", code),
+            "<Anonymous>.test()V at pc 0: Falling off the end of the code",
+        );
+    }
+
+    #[test]
+    fn missing_stack_map_frame() {
+        let bytecode = [0xa7, 0x00, 0x03, 0xb1]; // goto +3, return
+
+        // Old class files have no stack maps.
+        assert!(verify_method(49, "()V", code(0, 0, &bytecode)).is_ok());
+        assert_error(
+            verify_method(52, "()V", code(0, 0, &bytecode)),
+            "<Anonymous>.test()V at pc 0: Expecting a stack map frame at 3",
+        );
+    }
+
+    #[test]
+    fn illegal_exception_handler() {
+        let mut code = code(1, 0, &[0xb1]); // return
+        code.exception_handlers.push(ExceptionHandler {
+            start_pc: 0,
+            end_pc: 0,
+            handler_pc: 0,
+            catch_type: None,
+        });
+        assert_error(
+            verify_method(49, "()V", code),
+            "<Anonymous>.test()V at pc 0: Illegal exception handler range",
+        );
+    }
+
+    #[test]
+    fn constructor_must_initialize_this() {
+        let code = code(0, 1, &[0xb1]); // return
+        let mut class = Class::from_constant_pool(ConstantPool::new(0));
+        class.version = Version {
+            minor: 0,
+            major: 52,
+        };
+        class.methods.push(Rc::new(method(
+            MethodAccessFlags::ACC_PUBLIC,
+            "<init>",
+            "()V",
+            code,
+        )));
+
+        assert_error(
+            verify(&class),
+            "<Anonymous>.<init>()V at pc 0: Constructor must call super() or this() before return",
+        );
+    }
+
+    fn code(max_stack: u16, max_locals: u16, bytecode: &[u8]) -> Code {
+        code_with_frames(max_stack, max_locals, bytecode, vec![])
+    }
+
+    fn code_with_frames(
+        max_stack: u16,
+        max_locals: u16,
+        bytecode: &[u8],
+        frames: Vec<StackMapFrame>,
+    ) -> Code {
+        let mut data = (bytecode.len() as u32).to_be_bytes().to_vec();
+        data.extend_from_slice(bytecode);
+        let instructions = CodeReader::new(&mut Cursor::new(data)).read_code().unwrap();
+
+        let attributes = if frames.is_empty() {
+            vec![]
+        } else {
+            vec![Attribute {
+                name: "StackMapTable".to_owned(),
+                data: StackMapTable(frames),
+            }]
+        };
+        Code::new(max_stack, max_locals, vec![], attributes, instructions)
+    }
+
+    fn method(flags: MethodAccessFlags, name: &str, descriptor: &str, code: Code) -> MethodInfo {
+        let mut method = MethodInfo::from_code(code);
+        method.access_flags = flags;
+        method.name = name.to_owned();
+        method.descriptor = descriptor.try_into().unwrap();
+        method
+    }
+
+    fn verify_method(major: u16, descriptor: &str, code: Code) -> Result<()> {
+        let mut class = Class::from_constant_pool(ConstantPool::new(0));
+        class.version = Version { minor: 0, major };
+        let flags = MethodAccessFlags::ACC_PUBLIC | MethodAccessFlags::ACC_STATIC;
+        class
+            .methods
+            .push(Rc::new(method(flags, "test", descriptor, code)));
+
+        verify(&class)
+    }
+
+    fn assert_error(result: Result<()>, message: &str) {
+        let error = result.unwrap_err();
+        assert_eq!(error.linkage_error_class(), Some("java/lang/VerifyError"));
+        assert_eq!(error.to_string(), message);
+    }
+}
@@ -10,12 +10,14 @@ fn test_load_archive() {
 
     let mut native = Native::new();
     let mut vm = VirtualMachine::default();
-    let value = vm.run(
-        &mut class_loader,
-        &mut native,
-        "test_data/Archive",
-        "hello_from_archive",
-        vec![],
-    );
+    let value = vm
+        .run(
+            &mut class_loader,
+            &mut native,
+            "test_data/Archive",
+            "hello_from_archive",
+            vec![],
+        )
+        .unwrap();
     assert_eq!(value, Some(Int(2)))
 }
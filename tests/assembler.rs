@@ -0,0 +1,145 @@
+use rjvm::assembler::assemble;
+use rjvm::vm::class_loader::ClassLoader;
+use rjvm::vm::data_type::Value;
+use rjvm::vm::data_type::Value::{Int, Long};
+use rjvm::vm::native::Native;
+use rjvm::vm::VirtualMachine;
+
+const SOURCE: &str = r#"
+.bytecode 49.0
+.source Assembled.java
+.class public test_data/Assembled
+
+; 1 2 3 4 -> 3 4 1 2 3 4, subtracted from the top: 3 - (6 - (-2 - (3 - (-1)))).
+.method public static duplicateInts()I
+    .limit stack 6
+    iconst_1
+    iconst_2
+    iconst_3
+    iconst_4
+    dup2_x2
+    isub
+    isub
+    isub
+    isub
+    isub
+    ireturn
+.end method
+
+; 1L 10L -> 10L 1L 10L, 10 - (1 - 10) = 19.
+.method public static duplicateLongs()J
+    .limit stack 6
+    lconst_1
+    ldc2_w 10
+    dup2_x2
+    lsub
+    lsub
+    lreturn
+.end method
+
+.method public static wideLocals()I
+    .limit stack 1
+    .limit locals 300
+    iconst_5
+    istore 299
+    iinc 299 1000
+    iload 299
+    ireturn
+.end method
+
+.method public static caught()I
+    .limit stack 2
+    .catch java/lang/RuntimeException from Start to End using Handler
+Start:
+    new java/lang/RuntimeException
+    dup
+    invokespecial java/lang/RuntimeException/<init>()V
+    athrow
+End:
+    iconst_0
+    ireturn
+Handler:
+    pop
+    iconst_1
+    ireturn
+.end method
+
+.method public static select(I)I
+    .limit stack 1
+    iload_0
+    lookupswitch
+        1: One
+        100: Hundred
+        default: Other
+One:
+    bipush 10
+    ireturn
+Hundred:
+    bipush 20
+    ireturn
+Other:
+    iconst_0
+    ireturn
+.end method
+
+; Calls a subroutine adding 5 to a local twice, which needs a class file without stack maps.
+.method public static subroutine()I
+    .limit stack 1
+    .limit locals 2
+    iconst_0
+    istore_0
+    jsr Add
+    jsr Add
+    iload_0
+    ireturn
+Add:
+    astore_1
+    iinc 0 5
+    ret 1
+.end method
+"#;
+
+fn run(method: &str, args: Vec<Value>) -> Option<Value> {
+    let mut class_loader = ClassLoader::new();
+    class_loader.set_paths(vec!["./tests/", "./jre/"]);
+    class_loader.define(assemble(SOURCE).unwrap());
+
+    let mut native = Native::new();
+    let mut vm = VirtualMachine::default();
+    vm.run(
+        &mut class_loader,
+        &mut native,
+        "test_data/Assembled",
+        method,
+        args,
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_dup2_x2() {
+    assert_eq!(run("duplicateInts", vec![]), Some(Int(-3)));
+    assert_eq!(run("duplicateLongs", vec![]), Some(Long(19)));
+}
+
+#[test]
+fn test_wide_locals() {
+    assert_eq!(run("wideLocals", vec![]), Some(Int(1005)));
+}
+
+#[test]
+fn test_exception_handler() {
+    assert_eq!(run("caught", vec![]), Some(Int(1)));
+}
+
+#[test]
+fn test_lookup_switch() {
+    assert_eq!(run("select", vec![Int(1)]), Some(Int(10)));
+    assert_eq!(run("select", vec![Int(100)]), Some(Int(20)));
+    assert_eq!(run("select", vec![Int(7)]), Some(Int(0)));
+}
+
+#[test]
+fn test_subroutine() {
+    assert_eq!(run("subroutine", vec![]), Some(Int(10)));
+}
@@ -0,0 +1,358 @@
+use rjvm::class::attribute::AttributeData::{
+    LocalVariableTypeTable, RuntimeInvisibleAnnotations, RuntimeInvisibleTypeAnnotations,
+};
+use rjvm::class::attribute::{
+    Annotation, Code, ElementValue, EnclosingMethod, InnerClass, LocalVariable, StackMapFrame,
+    TypeAnnotationTarget, VerificationType,
+};
+use rjvm::class::signature::{TypeArgument, TypeSignature};
+use rjvm::class::InnerClassAccessFlags;
+use rjvm::io::class::ClassReader;
+use std::rc::Rc;
+
+#[test]
+fn test_inner_classes() {
+    let class = ClassReader::from_file("./tests/test_data/Nested.class").unwrap();
+
+    let inner = class
+        .inner_classes()
+        .iter()
+        .find(|c| c.inner_class == "test_data/Nested$Inner")
+        .unwrap();
+    assert_eq!(
+        inner,
+        &InnerClass {
+            inner_class: "test_data/Nested$Inner".to_owned(),
+            outer_class: Some("test_data/Nested".to_owned()),
+            inner_name: Some("Inner".to_owned()),
+            access_flags: InnerClassAccessFlags::PRIVATE | InnerClassAccessFlags::STATIC,
+        }
+    );
+
+    let anonymous = class
+        .inner_classes()
+        .iter()
+        .find(|c| c.inner_class == "test_data/Nested$1")
+        .unwrap();
+    assert_eq!(anonymous.outer_class, None);
+    assert_eq!(anonymous.inner_name, None);
+}
+
+#[test]
+fn test_enclosing_method() {
+    let local = ClassReader::from_file("./tests/test_data/Nested$1Local.class").unwrap();
+    assert_eq!(
+        local.enclosing_method(),
+        Some(&EnclosingMethod {
+            class: "test_data/Nested".to_owned(),
+            method: Some(("local".to_owned(), "()Ljava/lang/Runnable;".to_owned())),
+        })
+    );
+
+    // Declared in a static initializer.
+    let anonymous = ClassReader::from_file("./tests/test_data/Nested$1.class").unwrap();
+    assert_eq!(
+        anonymous.enclosing_method(),
+        Some(&EnclosingMethod {
+            class: "test_data/Nested".to_owned(),
+            method: None,
+        })
+    );
+
+    let class = ClassReader::from_file("./tests/test_data/Nested.class").unwrap();
+    assert_eq!(class.enclosing_method(), None);
+}
+
+#[test]
+fn test_nest() {
+    let host = ClassReader::from_file("./tests/test_data/Nested.class").unwrap();
+    assert_eq!(host.nest_host(), "test_data/Nested");
+    assert_eq!(
+        host.nest_members(),
+        &[
+            "test_data/Nested$Inner".to_owned(),
+            "test_data/Nested$1".to_owned(),
+            "test_data/Nested$1Local".to_owned(),
+        ]
+    );
+
+    let member = ClassReader::from_file("./tests/test_data/Nested$Inner.class").unwrap();
+    assert_eq!(member.nest_host(), "test_data/Nested");
+    assert!(member.nest_members().is_empty());
+}
+
+#[test]
+fn test_bootstrap_methods() {
+    let class = ClassReader::from_file("./tests/test_data/Nested.class").unwrap();
+
+    let bootstrap = class.bootstrap_method(0).unwrap();
+    let (class_name, method_name, _) = class
+        .constants
+        .get_member_ref(
+            class
+                .constants
+                .get_method_handle(bootstrap.method_ref)
+                .unwrap()
+                .1,
+        )
+        .unwrap();
+    assert_eq!(class_name, "java/lang/invoke/LambdaMetafactory");
+    assert_eq!(method_name, "metafactory");
+    assert_eq!(bootstrap.arguments.len(), 3);
+
+    assert!(class.bootstrap_method(1).is_none());
+}
+
+#[test]
+fn test_stack_map_table() {
+    let code = method_code("./tests/test_data/StackMaps.class", "sum");
+
+    assert_eq!(
+        code.stack_map_table(),
+        &[
+            StackMapFrame::Append {
+                offset_delta: 4,
+                locals: vec![VerificationType::Integer, VerificationType::Integer],
+            },
+            StackMapFrame::Chop {
+                offset_delta: 14,
+                count: 1,
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_local_variable_table() {
+    let code = method_code("./tests/test_data/StackMaps.class", "sum");
+
+    assert_eq!(code.local_variable(0, 0).unwrap().name, "count");
+    assert_eq!(code.local_variable(1, 2).unwrap().name, "sum");
+    assert_eq!(code.local_variable(2, 4).unwrap().name, "i");
+    // The loop variable is out of scope after the loop.
+    assert_eq!(code.local_variable(2, 19), None);
+}
+
+#[test]
+fn test_local_variable_type_table() {
+    let code = method_code("./tests/test_data/StackMaps.class", "first");
+
+    let table = code
+        .attributes
+        .iter()
+        .find_map(|a| match &a.data {
+            LocalVariableTypeTable(table) => Some(table),
+            _ => None,
+        })
+        .unwrap();
+
+    assert_eq!(
+        table,
+        &vec![LocalVariable {
+            start_pc: 0,
+            length: 22,
+            name: "strings".to_owned(),
+            descriptor: "Ljava/util/List<Ljava/lang/String;>;".to_owned(),
+            index: 0,
+        }]
+    );
+}
+
+#[test]
+fn test_annotations() {
+    let class = ClassReader::from_file("./tests/test_data/Annotated.class").unwrap();
+
+    let info = &class.annotations()[0];
+    assert_eq!(info.type_descriptor, "Ltest_data/Annotated$Info;");
+    assert_eq!(
+        info.element("name"),
+        Some(&ElementValue::String("class".to_owned()))
+    );
+    assert_eq!(info.element("count"), Some(&ElementValue::Int(3)));
+    assert_eq!(
+        info.element("kind"),
+        Some(&ElementValue::Enum {
+            type_descriptor: "Ljava/lang/annotation/ElementType;".to_owned(),
+            name: "TYPE".to_owned(),
+        })
+    );
+    assert_eq!(
+        info.element("type"),
+        Some(&ElementValue::Class("[Ljava/lang/String;".to_owned()))
+    );
+    assert_eq!(
+        info.element("tags"),
+        Some(&ElementValue::Array(vec![
+            ElementValue::String("a".to_owned()),
+            ElementValue::String("b".to_owned()),
+        ]))
+    );
+    assert_eq!(
+        info.element("nested"),
+        Some(&ElementValue::Annotation(tag("nested")))
+    );
+    assert_eq!(info.element("b"), None);
+
+    // Annotations with class retention are not visible at runtime.
+    assert_eq!(class.annotations().len(), 1);
+    let invisible = class
+        .attributes
+        .iter()
+        .find_map(|a| match &a.data {
+            RuntimeInvisibleAnnotations(annotations) => Some(annotations),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(
+        invisible[0].type_descriptor,
+        "Ltest_data/Annotated$Invisible;"
+    );
+
+    assert_eq!(class.fields[0].annotations(), &[tag("field")]);
+
+    let method = class.methods.iter().find(|m| m.name == "method").unwrap();
+    assert_eq!(method.annotations(), &[tag("method")]);
+    assert_eq!(
+        method.parameter_annotations(),
+        &[vec![], vec![tag("parameter")]]
+    );
+}
+
+#[test]
+fn test_annotation_default() {
+    let class = ClassReader::from_file("./tests/test_data/Annotated$Info.class").unwrap();
+    let default = |name: &str| {
+        let method = class.methods.iter().find(|m| m.name == name).unwrap();
+        method.annotation_default().cloned()
+    };
+
+    assert_eq!(default("name"), None);
+    assert_eq!(default("type"), Some(ElementValue::Class("V".to_owned())));
+    assert_eq!(default("tags"), Some(ElementValue::Array(vec![])));
+    assert_eq!(
+        default("nested"),
+        Some(ElementValue::Annotation(tag("default")))
+    );
+    assert_eq!(default("b"), Some(ElementValue::Byte(1)));
+    assert_eq!(default("c"), Some(ElementValue::Char('c' as u16)));
+    assert_eq!(default("s"), Some(ElementValue::Short(2)));
+    assert_eq!(default("j"), Some(ElementValue::Long(3)));
+    assert_eq!(default("f"), Some(ElementValue::Float(4.5)));
+    assert_eq!(default("d"), Some(ElementValue::Double(6.5)));
+    assert_eq!(default("z"), Some(ElementValue::Boolean(true)));
+}
+
+#[test]
+fn test_type_annotations() {
+    let class = ClassReader::from_file("./tests/test_data/Annotated.class").unwrap();
+    let method = class.methods.iter().find(|m| m.name == "method").unwrap();
+    let type_annotations = |attributes: &[rjvm::class::attribute::Attribute]| {
+        attributes
+            .iter()
+            .find_map(|a| match &a.data {
+                RuntimeInvisibleTypeAnnotations(annotations) => Some(annotations.clone()),
+                _ => None,
+            })
+            .unwrap()
+    };
+
+    let throws = type_annotations(&method.attributes);
+    assert_eq!(throws[0].target_type, 0x17);
+    assert_eq!(throws[0].target, TypeAnnotationTarget::Throws(0));
+    assert_eq!(
+        throws[0].annotation.type_descriptor,
+        "Ltest_data/Annotated$Typed;"
+    );
+
+    let code = method.get_code().unwrap();
+    let targets: Vec<TypeAnnotationTarget> = type_annotations(&code.attributes)
+        .into_iter()
+        .map(|a| a.target)
+        .collect();
+    assert_eq!(
+        targets,
+        vec![
+            TypeAnnotationTarget::LocalVariable(vec![(5, 13, 3)]),
+            TypeAnnotationTarget::Catch(0),
+        ]
+    );
+}
+
+fn tag(value: &str) -> Annotation {
+    Annotation {
+        type_descriptor: "Ltest_data/Annotated$Tag;".to_owned(),
+        elements: vec![("value".to_owned(), ElementValue::String(value.to_owned()))],
+    }
+}
+
+#[test]
+fn test_signatures() {
+    let class = ClassReader::from_file("./tests/test_data/Annotated.class").unwrap();
+    let signature = class.signature().unwrap().unwrap();
+    assert_eq!(
+        signature.type_parameters[0].to_string(),
+        "T extends java.lang.Object"
+    );
+    assert_eq!(signature.super_class.name, "java/lang/Object");
+    assert!(signature.interfaces.is_empty());
+    assert_eq!(class.fields[0].signature().unwrap(), None);
+
+    let class = ClassReader::from_file("./tests/test_data/StackMaps.class").unwrap();
+    assert_eq!(class.signature().unwrap(), None);
+
+    let method = class.methods.iter().find(|m| m.name == "first").unwrap();
+    let signature = method.signature().unwrap().unwrap();
+    assert_eq!(signature.return_type, Some(TypeSignature::Int));
+    match &signature.parameters[0] {
+        TypeSignature::Class(list) => {
+            assert_eq!(list.name, "java/util/List");
+            assert!(matches!(list.type_arguments[0], TypeArgument::Exact(_)));
+        }
+        parameter => panic!("Unexpected parameter {:?}", parameter),
+    }
+    assert_eq!(format!("{:#}", signature.parameters[0]), "List<String>");
+}
+
+fn method_code(path: &str, name: &str) -> Rc<Code> {
+    let class = ClassReader::from_file(path).unwrap();
+    let method = class.methods.iter().find(|m| m.name == name).unwrap();
+    method.get_code().unwrap()
+}
+
+#[test]
+fn test_record_components() {
+    let class = ClassReader::from_file("./tests/test_data/Point.class").unwrap();
+    let components = class.record_components().unwrap();
+
+    let names = components
+        .iter()
+        .map(|c| (c.name.as_str(), c.descriptor.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        vec![
+            ("x", "I"),
+            ("label", "Ljava/lang/String;"),
+            ("tags", "Ljava/util/List;"),
+        ]
+    );
+
+    assert_eq!(components[0].signature().unwrap(), None);
+    let signature = components[2].signature().unwrap().unwrap();
+    assert_eq!(signature.to_string(), "java.util.List<java.lang.String>");
+
+    let class = ClassReader::from_file("./tests/test_data/Car.class").unwrap();
+    assert_eq!(class.record_components(), None);
+}
+
+#[test]
+fn test_permitted_subclasses() {
+    let class = ClassReader::from_file("./tests/test_data/Vehicle.class").unwrap();
+    assert_eq!(
+        class.permitted_subclasses(),
+        Some(&["test_data/Car".to_owned()][..])
+    );
+
+    let class = ClassReader::from_file("./tests/test_data/Car.class").unwrap();
+    assert_eq!(class.permitted_subclasses(), None);
+}
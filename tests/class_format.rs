@@ -0,0 +1,137 @@
+use rjvm::error::ErrorKind;
+use rjvm::io::class::ClassReader;
+use std::fs;
+
+/// Reads `Square.class` after `modify` has changed its bytes, returning the format error.
+fn format_error(modify: impl FnOnce(&mut Vec<u8>)) -> String {
+    class_format_error("./tests/test_data/Square.class", modify)
+}
+
+fn class_format_error(path: &str, modify: impl FnOnce(&mut Vec<u8>)) -> String {
+    let mut bytes = fs::read(path).unwrap();
+    modify(&mut bytes);
+
+    let error = ClassReader::new(bytes.as_slice()).read_class().unwrap_err();
+    assert!(matches!(error.kind(), ErrorKind::ParseError));
+    error.to_string()
+}
+
+fn set_u2(bytes: &mut [u8], offset: usize, value: u16) {
+    bytes[offset..offset + 2].copy_from_slice(&value.to_be_bytes());
+}
+
+#[test]
+fn test_magic_and_version() {
+    assert_eq!(
+        format_error(|bytes| bytes[0] = 0xCB),
+        "Invalid file signature."
+    );
+    assert_eq!(
+        format_error(|bytes| set_u2(bytes, 6, 99)),
+        "Unsupported class file version 99.0 at byte 4."
+    );
+    assert_eq!(
+        format_error(|bytes| {
+            set_u2(bytes, 4, 1);
+            set_u2(bytes, 6, 60);
+        }),
+        "Unsupported class file version 60.1 at byte 4."
+    );
+}
+
+#[test]
+fn test_constant_pool() {
+    assert_eq!(
+        format_error(|bytes| bytes[10] = 2),
+        "Unknown constant tag 2 at byte 10."
+    );
+
+    // The class of the first method reference is set to its name and type.
+    assert_eq!(
+        format_error(|bytes| set_u2(bytes, 11, 3)),
+        "Constant 1 refers to 3 which is not a Class at byte 10."
+    );
+
+    // This class is set to index 0 and then to the name of the super class.
+    assert_eq!(
+        format_error(|bytes| set_u2(bytes, 168, 0)),
+        "Invalid constant pool index 0 at byte 168."
+    );
+    assert_eq!(
+        format_error(|bytes| set_u2(bytes, 168, 4)),
+        "Tried to get Utf8(\"java/lang/Object\") as a class reference at byte 168."
+    );
+}
+
+#[test]
+fn test_access_flags() {
+    assert_eq!(
+        format_error(|bytes| set_u2(bytes, 166, 0x0220)),
+        "Invalid access flags 0x0220 on class, an interface must be abstract at byte 166."
+    );
+    assert_eq!(
+        format_error(|bytes| set_u2(bytes, 180, 0x0003)),
+        "Invalid access flags 0x0003 on method <init>, at most one of public, private and \
+         protected can be set at byte 180."
+    );
+    assert_eq!(
+        format_error(|bytes| set_u2(bytes, 180, 0x0008)),
+        "Invalid access flags 0x0008 on method <init>, a constructor can only have access, \
+         varargs, strict and synthetic flags at byte 180."
+    );
+}
+
+#[test]
+fn test_super_class() {
+    assert_eq!(
+        format_error(|bytes| set_u2(bytes, 170, 0)),
+        "Class test_data/Square has no super class at byte 170."
+    );
+}
+
+#[test]
+fn test_descriptors() {
+    // The descriptor of `sides` is set to its name and then to an unterminated argument list.
+    assert_eq!(
+        format_error(|bytes| set_u2(bytes, 227, 13)),
+        "Invalid method descriptor 'sides' at byte 227."
+    );
+    assert_eq!(
+        format_error(|bytes| bytes[137] = b'I'),
+        "Invalid method descriptor '(II' at byte 227."
+    );
+
+    // The `I` constant used as the descriptor of the first field becomes an unknown type.
+    assert_eq!(
+        class_format_error("./tests/test_data/Point.class", |bytes| bytes[95] = b'Q'),
+        "Invalid field descriptor 'Q' at byte 1092."
+    );
+}
+
+#[test]
+fn test_code() {
+    assert_eq!(
+        format_error(|bytes| bytes[202] = 0xcb),
+        "Unknown opcode 0xcb at byte 202."
+    );
+}
+
+#[test]
+fn test_attribute_length() {
+    assert_eq!(
+        format_error(|bytes| bytes[270] = 3),
+        "Attribute SourceFile has length 3 but its data is 2 bytes at byte 265."
+    );
+}
+
+#[test]
+fn test_truncated_and_extra_bytes() {
+    assert_eq!(
+        format_error(|bytes| bytes.truncate(100)),
+        "Unexpected end of class file at byte 100."
+    );
+    assert_eq!(
+        format_error(|bytes| bytes.push(0)),
+        "Extra bytes after the end of the class file at byte 273."
+    );
+}
@@ -0,0 +1,53 @@
+use rjvm::class::attribute::AttributeData::SourceFile;
+use rjvm::io::class::{ClassReader, ClassWriter};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn class_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            class_files(&path, files);
+        } else if path.extension().is_some_and(|e| e == "class") {
+            files.push(path);
+        }
+    }
+}
+
+#[test]
+fn test_round_trip() {
+    let mut files = Vec::new();
+    for dir in &["./tests/test_data", "./tests/java_tests", "./jre"] {
+        if Path::new(dir).exists() {
+            class_files(Path::new(dir), &mut files);
+        }
+    }
+    assert!(!files.is_empty());
+
+    for path in files {
+        let bytes = fs::read(&path).unwrap();
+        let class = ClassReader::new(bytes.as_slice()).read_class().unwrap();
+
+        let mut written = Vec::new();
+        ClassWriter::new(&mut written).write_class(&class).unwrap();
+        assert!(bytes == written, "{} differs", path.display());
+    }
+}
+
+#[test]
+fn test_added_constants() {
+    let mut class = ClassReader::from_file("./tests/test_data/Square.class").unwrap();
+    let constants = class.constants.len();
+    for attribute in &mut class.attributes {
+        if let SourceFile(name) = &mut attribute.data {
+            *name = "Renamed.java".to_owned();
+        }
+    }
+
+    let mut written = Vec::new();
+    ClassWriter::new(&mut written).write_class(&class).unwrap();
+
+    let class = ClassReader::new(written.as_slice()).read_class().unwrap();
+    assert_eq!(class.constants.len(), constants + 1);
+    assert_eq!(class.source_file(), Some("Renamed.java"));
+}
@@ -0,0 +1,76 @@
+use rjvm::vm::class_loader::ClassLoader;
+use rjvm::vm::data_type::Value::Int;
+use rjvm::vm::native::Native;
+use rjvm::vm::VirtualMachine;
+
+#[test]
+fn test_collect_garbage_in_loop() {
+    let mut class_loader = ClassLoader::new();
+    class_loader.set_paths(vec!["./tests/"]);
+
+    let mut native = Native::new();
+    let mut vm = VirtualMachine::default();
+    vm.set_heap_limit(16 * 1024);
+
+    let value = vm
+        .run(
+            &mut class_loader,
+            &mut native,
+            "test_data/GarbageCollection",
+            "allocate_in_loop",
+            vec![],
+        )
+        .unwrap();
+
+    assert_eq!(value, Some(Int(4950)));
+
+    let statistics = vm.heap_statistics();
+    assert!(statistics.collections > 0);
+    assert!(statistics.live_bytes <= 16 * 1024);
+}
+
+#[test]
+fn test_keep_reachable_objects() {
+    let mut class_loader = ClassLoader::new();
+    class_loader.set_paths(vec!["./tests/"]);
+
+    let mut native = Native::new();
+    let mut vm = VirtualMachine::default();
+    vm.set_heap_limit(16 * 1024);
+
+    let value = vm
+        .run(
+            &mut class_loader,
+            &mut native,
+            "test_data/GarbageCollection",
+            "keep_reachable",
+            vec![],
+        )
+        .unwrap();
+
+    assert_eq!(value, Some(Int(30)));
+    assert!(vm.heap_statistics().collections > 0);
+}
+
+#[test]
+fn test_collect_garbage_before_max_heap_size_exceeded() {
+    let mut class_loader = ClassLoader::new();
+    class_loader.set_paths(vec!["./tests/"]);
+
+    let mut native = Native::new();
+    let mut vm = VirtualMachine::default();
+    vm.set_max_heap_size(1024);
+
+    let value = vm
+        .run(
+            &mut class_loader,
+            &mut native,
+            "test_data/GarbageCollection",
+            "allocate_in_loop",
+            vec![],
+        )
+        .unwrap();
+
+    assert_eq!(value, Some(Int(4950)));
+    assert!(vm.heap_statistics().collections > 0);
+}
@@ -0,0 +1,67 @@
+use rjvm::vm::class_loader::ClassLoader;
+use rjvm::vm::data_type::Value::Int;
+use rjvm::vm::native::Native;
+use rjvm::vm::VirtualMachine;
+
+#[test]
+fn test_invoke_interface_method() {
+    let mut class_loader = ClassLoader::new();
+    class_loader.set_paths(vec!["./tests/", "./jre/"]);
+
+    let mut native = Native::new();
+    let mut vm = VirtualMachine::default();
+
+    let value = vm
+        .run(
+            &mut class_loader,
+            &mut native,
+            "test_data/InterfaceMethods",
+            "sides",
+            vec![],
+        )
+        .unwrap();
+
+    assert_eq!(value, Some(Int(4)));
+}
+
+#[test]
+fn test_abstract_method_error() {
+    let mut class_loader = ClassLoader::new();
+    class_loader.set_paths(vec!["./tests/", "./jre/"]);
+
+    let mut native = Native::new();
+    let mut vm = VirtualMachine::default();
+
+    let value = vm
+        .run(
+            &mut class_loader,
+            &mut native,
+            "test_data/InterfaceMethods",
+            "abstract_method",
+            vec![],
+        )
+        .unwrap();
+
+    assert_eq!(value, Some(Int(-1)));
+}
+
+#[test]
+fn test_incompatible_class_change_error() {
+    let mut class_loader = ClassLoader::new();
+    class_loader.set_paths(vec!["./tests/", "./jre/"]);
+
+    let mut native = Native::new();
+    let mut vm = VirtualMachine::default();
+
+    let value = vm
+        .run(
+            &mut class_loader,
+            &mut native,
+            "test_data/InterfaceMethods",
+            "conflicting_default_methods",
+            vec![],
+        )
+        .unwrap();
+
+    assert_eq!(value, Some(Int(-1)));
+}
@@ -2,6 +2,8 @@ package java_tests;
 
 import java.lang.Object;
 import java.lang.ArrayIndexOutOfBoundsException;
+import java.lang.NegativeArraySizeException;
+import java.lang.String;
 
 import static vadeen.test.Assertion.*;
 
@@ -96,4 +98,57 @@ public class ArrayTests {
         } catch (java.lang.ArrayIndexOutOfBoundsException e) {
         }
     }
+
+    public static void test_multi_dimensional_array() {
+        int[][][] a = new int[3][4][5];
+        a[2][3][4] = 7;
+
+        assertEquals(a.length, 3);
+        assertEquals(a[1].length, 4);
+        assertEquals(a[1][2].length, 5);
+        assertEquals(a[2][3][4], 7);
+        assertEquals(a[0][0][0], 0);
+    }
+
+    public static void test_multi_dimensional_array_type() {
+        Object a = new int[2][2];
+        Object b = new String[2][3];
+
+        assertEquals(a instanceof int[][], true);
+        assertEquals(b instanceof String[][], true);
+        assertEquals(b instanceof Object[], true);
+    }
+
+    public static void test_partially_allocated_array() {
+        String[][] a = new String[2][];
+        Object row = a[1];
+
+        assertEquals(a.length, 2);
+        assertEquals(row, null);
+
+        a[1] = new String[3];
+        assertEquals(a[1].length, 3);
+    }
+
+    public static void test_negative_array_size() {
+        int size = -1;
+
+        try {
+            int[] a = new int[size];
+            assertEquals(1, 2); // Should never happen
+        } catch (NegativeArraySizeException e) {
+        }
+
+        try {
+            Object[] a = new Object[size];
+            assertEquals(1, 2); // Should never happen
+        } catch (NegativeArraySizeException e) {
+        }
+
+        try {
+            int[][] a = new int[2][size];
+            assertEquals(1, 2); // Should never happen
+        } catch (NegativeArraySizeException e) {
+        }
+    }
 }
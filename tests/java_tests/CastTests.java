@@ -0,0 +1,97 @@
+package java_tests;
+
+import java.lang.ClassCastException;
+import java.lang.Object;
+
+import static vadeen.test.Assertion.*;
+
+public class CastTests {
+
+    private interface Named {
+    }
+
+    private interface Titled extends Named {
+    }
+
+    private static class Animal {
+    }
+
+    private static class Dog extends Animal implements Titled {
+    }
+
+    private static class Cat extends Animal {
+    }
+
+    public static void test_instanceof_class() {
+        Object dog = new Dog();
+
+        assertEquals(dog instanceof Dog, true);
+        assertEquals(dog instanceof Animal, true);
+        assertEquals(dog instanceof Object, true);
+        assertEquals(dog instanceof Cat, false);
+    }
+
+    public static void test_instanceof_interface() {
+        Object dog = new Dog();
+        Object cat = new Cat();
+
+        assertEquals(dog instanceof Titled, true);
+        assertEquals(dog instanceof Named, true);
+        assertEquals(cat instanceof Named, false);
+    }
+
+    public static void test_instanceof_null() {
+        Object o = null;
+
+        assertEquals(o instanceof Object, false);
+    }
+
+    public static void test_instanceof_array() {
+        Object ints = new int[1];
+        Object dogs = new Dog[1];
+
+        assertEquals(ints instanceof int[], true);
+        assertEquals(ints instanceof long[], false);
+        assertEquals(ints instanceof Object[], false);
+        assertEquals(dogs instanceof Dog[], true);
+        assertEquals(dogs instanceof Animal[], true);
+        assertEquals(dogs instanceof Named[], true);
+        assertEquals(dogs instanceof Object[], true);
+        assertEquals(dogs instanceof Cat[], false);
+    }
+
+    public static void test_checkcast() {
+        Object dog = new Dog();
+        Animal animal = (Animal) dog;
+        Named named = (Named) dog;
+        Object nothing = null;
+        Cat cat = (Cat) nothing;
+
+        assertEquals(animal, dog);
+        assertEquals(named, dog);
+    }
+
+    public static void test_checkcast_fails() {
+        int i = 0;
+        Object dog = new Dog();
+        try {
+            Cat cat = (Cat) dog;
+        } catch (ClassCastException e) {
+            i = 1;
+        }
+
+        assertEquals(i, 1);
+    }
+
+    public static void test_checkcast_array_fails() {
+        int i = 0;
+        Object animals = new Animal[1];
+        try {
+            Dog[] dogs = (Dog[]) animals;
+        } catch (ClassCastException e) {
+            i = 1;
+        }
+
+        assertEquals(i, 1);
+    }
+}
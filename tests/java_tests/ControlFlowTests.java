@@ -62,6 +62,25 @@ public class ControlFlowTests {
         assertEquals(i, 1);
     }
 
+    public static void test_switch_in_loop() {
+        int sum = 0;
+
+        for (int i = 0; i != 4; i++) {
+            switch (i) {
+                case 1:
+                    sum += 10;
+                    break;
+                case 2:
+                    sum += 20;
+                    break;
+                default:
+                    sum += 1;
+            }
+        }
+
+        assertEquals(sum, 32);
+    }
+
     public static void test_table_switch() {
         int i = 2;
 
@@ -71,4 +71,156 @@ public class ExceptionTests {
         assertEquals(i, 2);
     }
 
+    public static void test_catch_out_of_memory_error() {
+        int i = 0;
+        try {
+            long[] array = new long[200000000];
+        } catch (OutOfMemoryError e) {
+            i = 1;
+        }
+        assertEquals(i, 1);
+    }
+
+    public static void test_catch_super_class() {
+        int i = 0;
+        try {
+            throw new RuntimeException();
+        } catch (Exception e) {
+            i = 1;
+        }
+        assertEquals(i, 1);
+    }
+
+    public static void test_catch_throwable() {
+        int i = 0;
+        try {
+            int[] array = new int[1];
+            array[2] = 1;
+        } catch (Throwable e) {
+            i = 1;
+        }
+        assertEquals(i, 1);
+    }
+
+    public static void test_first_matching_handler() {
+        int i = 0;
+        try {
+            throw new RuntimeException();
+        } catch (ClassCastException e) {
+            i = 1;
+        } catch (RuntimeException e) {
+            i = 2;
+        } catch (Exception e) {
+            i = 3;
+        }
+        assertEquals(i, 2);
+    }
+
+    public static void test_finally_on_exception() {
+        int i = 0;
+        try {
+            try {
+                i++;
+                throw new Exception();
+            } finally {
+                i++;
+            }
+        } catch (Exception e) {
+            i++;
+        }
+        assertEquals(i, 3);
+    }
+
+    public static void test_throw_null() {
+        int i = 0;
+        try {
+            throw null;
+        } catch (NullPointerException e) {
+            i = 1;
+        }
+        assertEquals(i, 1);
+    }
+
+    public static void test_null_field_access() {
+        Holder holder = null;
+        int i = 0;
+        try {
+            i = holder.value;
+        } catch (NullPointerException e) {
+            i = 1;
+        }
+        try {
+            holder.value = 2;
+        } catch (NullPointerException e) {
+            i++;
+        }
+        assertEquals(i, 2);
+    }
+
+    public static void test_null_virtual_call() {
+        Holder holder = null;
+        int i = 0;
+        try {
+            i = holder.get();
+        } catch (NullPointerException e) {
+            i = 1;
+        }
+        assertEquals(i, 1);
+    }
+
+    public static void test_null_interface_call() {
+        Getter getter = null;
+        int i = 0;
+        try {
+            i = getter.get();
+        } catch (NullPointerException e) {
+            i = 1;
+        }
+        assertEquals(i, 1);
+    }
+
+    public static void test_catch_from_called_method() {
+        int i = 0;
+        try {
+            throwIndexOutOfBounds();
+        } catch (IndexOutOfBoundsException e) {
+            i = 1;
+        }
+        assertEquals(i, 1);
+    }
+
+    public static void test_finally_in_called_method() {
+        int[] counter = new int[1];
+        try {
+            throwWithFinally(counter);
+        } catch (RuntimeException e) {
+            counter[0]++;
+        }
+        assertEquals(counter[0], 2);
+    }
+
+    private static void throwIndexOutOfBounds() {
+        int[] array = new int[1];
+        array[1] = 1;
+    }
+
+    private static void throwWithFinally(int[] counter) {
+        try {
+            throw new RuntimeException();
+        } finally {
+            counter[0]++;
+        }
+    }
+
+    interface Getter {
+        int get();
+    }
+
+    static class Holder implements Getter {
+        int value;
+
+        public int get() {
+            return value;
+        }
+    }
 }
@@ -0,0 +1,104 @@
+package java_tests;
+
+import static vadeen.test.Assertion.*;
+
+public class InterfaceTests {
+
+    interface Base {
+        int base();
+
+        default int value() {
+            return 1;
+        }
+    }
+
+    interface Extended extends Base {
+        default int value() {
+            return 2;
+        }
+    }
+
+    interface Other {
+        int other();
+    }
+
+    static class Impl implements Extended, Other {
+        public int base() {
+            return 10;
+        }
+
+        public int other() {
+            return 20;
+        }
+    }
+
+    static abstract class AbstractImpl implements Base {
+        public int base() {
+            return 30;
+        }
+    }
+
+    static class SubImpl extends AbstractImpl {
+    }
+
+    static class Overriding extends SubImpl {
+        public int value() {
+            return 3;
+        }
+    }
+
+    static class Counter {
+        static int initialized = 0;
+
+        static int initialize() {
+            initialized++;
+            return 0;
+        }
+    }
+
+    interface Initialized {
+        int INIT = Counter.initialize();
+
+        default int initialized() {
+            return Counter.initialized;
+        }
+    }
+
+    static class InitializedImpl implements Initialized {
+    }
+
+    public static void test_superinterface_method() {
+        Extended e = new Impl();
+        assertEquals(e.base(), 10);
+    }
+
+    public static void test_second_interface() {
+        Other o = new Impl();
+        assertEquals(o.other(), 20);
+    }
+
+    public static void test_default_method() {
+        Base b = new SubImpl();
+        assertEquals(b.value(), 1);
+    }
+
+    public static void test_maximally_specific_default_method() {
+        Base b = new Impl();
+        assertEquals(b.value(), 2);
+    }
+
+    public static void test_method_from_super_class() {
+        Base b = new SubImpl();
+        assertEquals(b.base(), 30);
+    }
+
+    public static void test_class_method_overrides_default_method() {
+        Base b = new Overriding();
+        assertEquals(b.value(), 3);
+    }
+
+    public static void test_default_method_initializes_interface() {
+        Initialized i = new InitializedImpl();
+        assertEquals(i.initialized(), 1);
+    }
+}
@@ -0,0 +1,157 @@
+package java_tests;
+
+import java.lang.Object;
+import java.lang.Runnable;
+
+import static vadeen.test.Assertion.*;
+
+public class LambdaTests {
+
+    public static void test_lambda_without_captures() {
+        IntOperator square = x -> x * x;
+
+        assertEquals(square.apply(7), 49);
+    }
+
+    public static void test_lambda_capturing_locals() {
+        int offset = 10;
+        long factor = 3;
+        IntOperator operator = x -> (int) ((x + offset) * factor);
+
+        assertEquals(operator.apply(2), 36);
+    }
+
+    public static void test_lambda_capturing_this() {
+        Accumulator accumulator = new Accumulator();
+        Runnable runnable = accumulator.adder(5);
+
+        runnable.run();
+        runnable.run();
+
+        assertEquals(accumulator.total, 10);
+    }
+
+    public static void test_lambda_with_object_arguments() {
+        Combiner<Box> combiner = (a, b) -> new Box(a.value + b.value);
+
+        Box box = combiner.combine(new Box(3), new Box(4));
+
+        assertEquals(box.value, 7);
+    }
+
+    public static void test_lambda_returning_long() {
+        LongSupplier supplier = () -> 5L;
+
+        assertEquals(supplier.get(), 5L);
+    }
+
+    public static void test_static_method_reference() {
+        IntOperator operator = LambdaTests::triple;
+
+        assertEquals(operator.apply(4), 12);
+    }
+
+    public static void test_virtual_method_reference() {
+        Box box = new Box(9);
+        IntSupplier supplier = box::get;
+
+        assertEquals(supplier.get(), 9);
+    }
+
+    public static void test_unbound_method_reference() {
+        BoxReader reader = Box::get;
+
+        assertEquals(reader.read(new Box(6)), 6);
+    }
+
+    public static void test_interface_method_reference() {
+        IntSupplier inner = () -> 8;
+        IntSupplier supplier = inner::get;
+
+        assertEquals(supplier.get(), 8);
+    }
+
+    public static void test_constructor_reference() {
+        BoxFactory factory = Box::new;
+
+        assertEquals(factory.create(11).value, 11);
+    }
+
+    public static void test_ignored_return_value() {
+        Accumulator accumulator = new Accumulator();
+        Runnable runnable = accumulator::increment;
+
+        runnable.run();
+
+        assertEquals(accumulator.total, 1);
+    }
+
+    public static void test_same_call_site_twice() {
+        Object first = null;
+        Object second = null;
+
+        for (int i = 0; i != 2; i++) {
+            IntOperator operator = x -> x + 1;
+            if (first == null) {
+                first = operator;
+            } else {
+                second = operator;
+            }
+        }
+
+        assertEquals(((IntOperator) first).apply(1), 2);
+        assertEquals(((IntOperator) second).apply(2), 3);
+    }
+
+    private static int triple(int x) {
+        return x * 3;
+    }
+
+    interface IntOperator {
+        int apply(int x);
+    }
+
+    interface IntSupplier {
+        int get();
+    }
+
+    interface LongSupplier {
+        long get();
+    }
+
+    interface Combiner<T> {
+        T combine(T a, T b);
+    }
+
+    interface BoxReader {
+        int read(Box box);
+    }
+
+    interface BoxFactory {
+        Box create(int value);
+    }
+
+    private static class Box {
+        final int value;
+
+        Box(int value) {
+            this.value = value;
+        }
+
+        int get() {
+            return value;
+        }
+    }
+
+    private static class Accumulator {
+        int total;
+
+        Runnable adder(int amount) {
+            return () -> total += amount;
+        }
+
+        int increment() {
+            return ++total;
+        }
+    }
+}
@@ -0,0 +1,41 @@
+package java_tests;
+
+import java.lang.Object;
+import java.lang.RuntimeException;
+import java.lang.StackTraceElement;
+
+import static vadeen.test.Assertion.*;
+
+public class StackTraceTests {
+
+    public static void test_stack_trace_starts_at_creation() {
+        StackTraceElement[] trace = create().getStackTrace();
+        Object method = trace[0].getMethodName();
+
+        assertEquals(method, "create");
+        assertEquals(trace[0].getLineNumber(), 39);
+        assertEquals(trace[1].getLineNumber(), 12);
+    }
+
+    public static void test_stack_trace_element() {
+        StackTraceElement element = new RuntimeException().getStackTrace()[0];
+        Object className = element.getClassName();
+        Object fileName = element.getFileName();
+
+        assertEquals(className, "java_tests.StackTraceTests");
+        assertEquals(fileName, "StackTraceTests.java");
+    }
+
+    public static void test_internal_exception_stack_trace() {
+        int[] array = new int[1];
+        try {
+            array[1] = 1;
+        } catch (RuntimeException e) {
+            assertEquals(e.getStackTrace()[0].getLineNumber(), 32);
+        }
+    }
+
+    private static RuntimeException create() {
+        return new RuntimeException();
+    }
+}
@@ -0,0 +1,71 @@
+package java_tests;
+
+import java.lang.Object;
+import java.lang.String;
+
+import static vadeen.test.Assertion.*;
+
+public class StringConcatTests {
+
+    public static void test_concat_strings() {
+        String name = "world";
+
+        assertEquals(("hello " + name + "!").equals("hello world!"), true);
+    }
+
+    public static void test_concat_primitives() {
+        int i = 12;
+        i = -i;
+        long l = 34L;
+        boolean b = true;
+        char c = 'x';
+
+        String s = i + "," + l + "," + b + "," + c;
+
+        assertEquals(s.equals("-12,34,true,x"), true);
+    }
+
+    public static void test_concat_floating_point() {
+        float f = 1.5f;
+        double d = 2.0;
+        double large = 1e10;
+        double small = 0.0001;
+
+        String s = f + " " + d + " " + large + " " + small;
+
+        assertEquals(s.equals("1.5 2.0 1.0E10 1.0E-4"), true);
+    }
+
+    public static void test_concat_null() {
+        String s = null;
+
+        assertEquals(("value: " + s).equals("value: null"), true);
+    }
+
+    public static void test_concat_objects() {
+        Named named = new Named();
+
+        assertEquals(("<" + named + ">").equals("<named>"), true);
+    }
+
+    public static void test_concat_default_to_string() {
+        Object o = new Object();
+        String s = "" + o;
+
+        assertEquals(s.equals(o.toString()), true);
+        assertEquals(s.equals("java.lang.Object@"), false);
+    }
+
+    public static void test_concat_unicode() {
+        String s = "å" + 1;
+
+        assertEquals(s.length(), 2);
+        assertEquals(s.equals("å1"), true);
+    }
+
+    private static class Named {
+        public String toString() {
+            return "named";
+        }
+    }
+}
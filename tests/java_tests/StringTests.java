@@ -0,0 +1,22 @@
+package java_tests;
+
+import java.lang.Object;
+import java.lang.String;
+
+import static vadeen.test.Assertion.*;
+
+public class StringTests {
+
+    public static void test_string_length() {
+        String s = "hello";
+
+        assertEquals(s.length(), 5);
+    }
+
+    public static void test_literals_are_same_instance() {
+        Object a = "hello";
+        Object b = "hello";
+
+        assertEquals(a, b);
+    }
+}
@@ -0,0 +1,93 @@
+package java_tests;
+
+import java.lang.Object;
+import java.lang.RuntimeException;
+import java.lang.Thread;
+
+import static vadeen.test.Assertion.*;
+
+public class SynchronizedTests {
+
+    public static void test_synchronized_block() {
+        Object o = new Object();
+
+        synchronized (o) {
+            assertEquals(Thread.holdsLock(o), true);
+
+            synchronized (o) {
+                assertEquals(Thread.holdsLock(o), true);
+            }
+
+            assertEquals(Thread.holdsLock(o), true);
+        }
+
+        assertEquals(Thread.holdsLock(o), false);
+    }
+
+    public static void test_synchronized_method() {
+        Counter counter = new Counter();
+
+        assertEquals(counter.holdsOwnLock(), true);
+        assertEquals(Thread.holdsLock(counter), false);
+    }
+
+    public static void test_static_synchronized_method() {
+        assertEquals(Counter.holdsClassLock(), true);
+        assertEquals(Thread.holdsLock(Counter.class), false);
+    }
+
+    public static void test_synchronized_method_in_synchronized_block() {
+        Counter counter = new Counter();
+
+        synchronized (counter) {
+            counter.increment();
+            counter.increment();
+            assertEquals(Thread.holdsLock(counter), true);
+        }
+
+        assertEquals(counter.count, 2);
+        assertEquals(Thread.holdsLock(counter), false);
+    }
+
+    public static void test_exception_releases_synchronized_method() {
+        Counter counter = new Counter();
+
+        try {
+            counter.fail();
+        } catch (RuntimeException e) {
+            assertEquals(Thread.holdsLock(counter), false);
+        }
+    }
+
+    public static void test_exception_releases_synchronized_block() {
+        Object o = new Object();
+
+        try {
+            synchronized (o) {
+                throw new RuntimeException();
+            }
+        } catch (RuntimeException e) {
+            assertEquals(Thread.holdsLock(o), false);
+        }
+    }
+
+    private static class Counter {
+        int count;
+
+        synchronized void increment() {
+            count++;
+        }
+
+        synchronized boolean holdsOwnLock() {
+            return Thread.holdsLock(this);
+        }
+
+        static synchronized boolean holdsClassLock() {
+            return Thread.holdsLock(Counter.class);
+        }
+
+        synchronized void fail() {
+            throw new RuntimeException();
+        }
+    }
+}
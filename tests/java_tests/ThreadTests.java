@@ -0,0 +1,278 @@
+package java_tests;
+
+import java.lang.IllegalMonitorStateException;
+import java.lang.IllegalThreadStateException;
+import java.lang.InterruptedException;
+import java.lang.Object;
+import java.lang.Runnable;
+import java.lang.Thread;
+
+import static vadeen.test.Assertion.*;
+
+public class ThreadTests {
+
+    public static void test_start_and_join() throws InterruptedException {
+        Log log = new Log();
+        Thread thread = new Thread(new Writer(log, 1, 3));
+
+        thread.start();
+        thread.join();
+
+        assertEquals(log.size, 3);
+        assertEquals(log.entries[2], 1);
+    }
+
+    public static void test_thread_subclass() throws InterruptedException {
+        Log log = new Log();
+        WriterThread thread = new WriterThread(log);
+
+        thread.start();
+        thread.join();
+
+        assertEquals(log.size, 1);
+        assertEquals(log.entries[0], 7);
+    }
+
+    public static void test_yield_interleaves_threads() throws InterruptedException {
+        Log log = new Log();
+        Thread first = new Thread(new Writer(log, 1, 3));
+        Thread second = new Thread(new Writer(log, 2, 3));
+
+        first.start();
+        second.start();
+        first.join();
+        second.join();
+
+        assertEquals(log.size, 6);
+        assertEquals(log.entries[0], 1);
+        assertEquals(log.entries[1], 2);
+        assertEquals(log.entries[2], 1);
+        assertEquals(log.entries[3], 2);
+        assertEquals(log.entries[4], 1);
+        assertEquals(log.entries[5], 2);
+    }
+
+    public static void test_sleep() throws InterruptedException {
+        final Log log = new Log();
+        Thread thread = new Thread(new Runnable() {
+            public void run() {
+                try {
+                    Thread.sleep(10);
+                } catch (InterruptedException e) {
+                }
+                log.add(2);
+            }
+        });
+
+        thread.start();
+        log.add(1);
+        Thread.sleep(20);
+        log.add(3);
+
+        assertEquals(log.entries[0], 1);
+        assertEquals(log.entries[1], 2);
+        assertEquals(log.entries[2], 3);
+    }
+
+    public static void test_wait_and_notify() throws InterruptedException {
+        final Box box = new Box();
+        Thread producer = new Thread(new Runnable() {
+            public void run() {
+                try {
+                    for (int i = 1; i != 6; i++) {
+                        box.put(i);
+                    }
+                } catch (InterruptedException e) {
+                }
+            }
+        });
+
+        producer.start();
+
+        int sum = 0;
+        for (int i = 0; i != 5; i++) {
+            sum += box.take();
+        }
+
+        assertEquals(sum, 15);
+    }
+
+    public static void test_contended_monitor() throws InterruptedException {
+        Counter counter = new Counter();
+        Thread first = new Thread(new Incrementer(counter));
+        Thread second = new Thread(new Incrementer(counter));
+
+        first.start();
+        second.start();
+        first.join();
+        second.join();
+
+        assertEquals(counter.count, 20);
+    }
+
+    public static void test_wait_without_monitor() throws InterruptedException {
+        Object o = new Object();
+        boolean thrown = false;
+
+        try {
+            o.wait();
+        } catch (IllegalMonitorStateException e) {
+            thrown = true;
+        }
+
+        assertEquals(thrown, true);
+    }
+
+    public static void test_notify_without_monitor() {
+        Object o = new Object();
+        boolean thrown = false;
+
+        try {
+            o.notify();
+        } catch (IllegalMonitorStateException e) {
+            thrown = true;
+        }
+
+        assertEquals(thrown, true);
+    }
+
+    public static void test_timed_wait() throws InterruptedException {
+        Object o = new Object();
+
+        synchronized (o) {
+            o.wait(5);
+            assertEquals(Thread.holdsLock(o), true);
+        }
+    }
+
+    public static void test_current_thread() throws InterruptedException {
+        Thread main = Thread.currentThread();
+        assertEquals(main == Thread.currentThread(), true);
+
+        CurrentThread thread = new CurrentThread();
+        thread.start();
+        thread.join();
+
+        assertEquals(thread.current == thread, true);
+    }
+
+    public static void test_is_alive() throws InterruptedException {
+        Thread thread = new Thread(new Writer(new Log(), 1, 1));
+        assertEquals(thread.isAlive(), false);
+
+        thread.start();
+        assertEquals(thread.isAlive(), true);
+
+        thread.join();
+        assertEquals(thread.isAlive(), false);
+    }
+
+    public static void test_start_twice() throws InterruptedException {
+        Thread thread = new Thread(new Writer(new Log(), 1, 1));
+        boolean thrown = false;
+
+        thread.start();
+        try {
+            thread.start();
+        } catch (IllegalThreadStateException e) {
+            thrown = true;
+        }
+        thread.join();
+
+        assertEquals(thrown, true);
+    }
+
+    private static class Log {
+        int[] entries = new int[10];
+        int size;
+
+        void add(int entry) {
+            entries[size++] = entry;
+        }
+    }
+
+    private static class Writer implements Runnable {
+        private final Log log;
+        private final int entry;
+        private final int count;
+
+        Writer(Log log, int entry, int count) {
+            this.log = log;
+            this.entry = entry;
+            this.count = count;
+        }
+
+        public void run() {
+            for (int i = 0; i != count; i++) {
+                log.add(entry);
+                Thread.yield();
+            }
+        }
+    }
+
+    private static class WriterThread extends Thread {
+        private final Log log;
+
+        WriterThread(Log log) {
+            this.log = log;
+        }
+
+        public void run() {
+            log.add(7);
+        }
+    }
+
+    private static class CurrentThread extends Thread {
+        Thread current;
+
+        public void run() {
+            current = Thread.currentThread();
+        }
+    }
+
+    private static class Box {
+        private int value;
+        private boolean full;
+
+        synchronized void put(int value) throws InterruptedException {
+            while (full) {
+                wait();
+            }
+            this.value = value;
+            full = true;
+            notifyAll();
+        }
+
+        synchronized int take() throws InterruptedException {
+            while (!full) {
+                wait();
+            }
+            full = false;
+            notifyAll();
+            return value;
+        }
+    }
+
+    private static class Counter {
+        int count;
+    }
+
+    private static class Incrementer implements Runnable {
+        private final Counter counter;
+
+        Incrementer(Counter counter) {
+            this.counter = counter;
+        }
+
+        public void run() {
+            for (int i = 0; i != 10; i++) {
+                synchronized (counter) {
+                    int count = counter.count;
+                    // The other thread runs now, but must wait for the monitor.
+                    Thread.yield();
+                    counter.count = count + 1;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use rjvm::io::class::ClassReader;
+use rjvm::javap;
+use rjvm::vm::class_loader::ClassLoader;
+
+#[test]
+fn test_disassemble() {
+    let class = ClassReader::from_file("./tests/test_data/Exceptions.class").unwrap();
+    let disassembly = javap::disassemble(&class).unwrap();
+    let lines: Vec<&str> = disassembly.lines().collect();
+
+    assert_eq!(lines[0], "  Compiled from \"Exceptions.java\"");
+    assert_eq!(lines[1], "public class test_data.Exceptions");
+    assert!(lines.contains(&"  flags: (0x0021) ACC_PUBLIC, ACC_SUPER"));
+    assert!(lines
+        .contains(&"   #1 = Methodref          #2.#3          // java/lang/Object.\"<init>\":()V"));
+
+    let handled = disassembly
+        .split("\n\n")
+        .find(|method| method.contains("public static int handled();"))
+        .unwrap();
+    assert_eq!(
+        handled,
+        "  public static int handled();
+    descriptor: ()I
+    flags: (0x0009) ACC_PUBLIC, ACC_STATIC
+    Code:
+      stack=1, locals=1, args_size=0
+         0: invokestatic  #7                  // Method fail:()I
+         3: ireturn
+         4: astore_0
+         5: iconst_1
+         6: ireturn
+      Exception table:
+         from    to  target type
+             0     3     4   Class java/lang/RuntimeException
+      LineNumberTable:
+        line 20: 0
+        line 21: 4
+        line 22: 5"
+    );
+}
+
+#[test]
+fn test_disassemble_from_archive() {
+    let mut class_loader = ClassLoader::new();
+    class_loader.set_paths(vec!["./tests/archive.jar"]);
+
+    let class = class_loader.read_class("test_data/Archive").unwrap();
+    let disassembly = javap::disassemble(&class).unwrap();
+    assert!(disassembly.contains(
+        "         1: invokespecial #1                  // Method java/lang/Object.\"<init>\":()V"
+    ));
+    assert!(disassembly.ends_with("}\nSourceFile: \"Archive.java\"\n"));
+}
+
+#[test]
+fn test_disassemble_unpaired_surrogate() {
+    let class = ClassReader::from_file("./tests/test_data/Strings.class").unwrap();
+    let disassembly = javap::disassemble(&class).unwrap();
+    assert!(disassembly.contains(" = Utf8               a\\ud800\n"));
+    assert!(disassembly.contains("// String a\\ud800\n"));
+}
+
+#[test]
+fn test_disassemble_generics() {
+    let class = ClassReader::from_file("./tests/test_data/StackMaps.class").unwrap();
+    let disassembly = javap::disassemble(&class).unwrap();
+    assert!(disassembly.contains("  public static int first(java.util.List<java.lang.String>);\n"));
+    assert!(disassembly.contains(
+        "    Signature: #40                          // (Ljava/util/List<Ljava/lang/String;>;)I\n"
+    ));
+
+    let class = ClassReader::from_file("./tests/test_data/Annotated.class").unwrap();
+    let disassembly = javap::disassemble(&class).unwrap();
+    assert_eq!(
+        disassembly.lines().nth(1),
+        Some(
+            "public class test_data.Annotated<T extends java.lang.Object> extends java.lang.Object"
+        )
+    );
+}
@@ -0,0 +1,143 @@
+use rjvm::class::attribute::{Exports, ModuleDescriptor, Provides, Requires};
+use rjvm::class::{ClassAccessFlags, ExportsFlags, ModuleFlags, RequiresFlags};
+use rjvm::io::class::{ClassReader, ClassWriter};
+use rjvm::vm::class_loader::ClassLoader;
+use std::fs::File;
+use std::io::Read;
+use zip::ZipArchive;
+
+const MODULE_PATH: &str = "./tests/test_data/modules/path";
+
+fn read_jar_entry(jar: &str, name: &str) -> Vec<u8> {
+    let mut zip = ZipArchive::new(File::open(jar).unwrap()).unwrap();
+    let mut bytes = Vec::new();
+    zip.by_name(name).unwrap().read_to_end(&mut bytes).unwrap();
+    bytes
+}
+
+fn requires(module: &str, flags: RequiresFlags, version: &str) -> Requires {
+    Requires {
+        module: module.to_owned(),
+        flags,
+        version: Some(version.to_owned()),
+    }
+}
+
+#[test]
+fn test_module_descriptor() {
+    let bytes = read_jar_entry(
+        "./tests/test_data/modules/path/com.example.app.jar",
+        "module-info.class",
+    );
+    let class = ClassReader::new(bytes.as_slice()).read_class().unwrap();
+    assert!(class.access_flags.contains(ClassAccessFlags::MODULE));
+
+    let module = class.module().unwrap();
+    assert_eq!(
+        module,
+        &ModuleDescriptor {
+            name: "com.example.app".to_owned(),
+            flags: ModuleFlags::empty(),
+            version: Some("1.0".to_owned()),
+            requires: vec![
+                Requires {
+                    module: "java.base".to_owned(),
+                    flags: RequiresFlags::MANDATED,
+                    version: module.requires[0].version.clone(),
+                },
+                requires("com.example.lib", RequiresFlags::empty(), "1.0"),
+                requires("com.example.optional", RequiresFlags::STATIC_PHASE, "1.0"),
+                requires("com.example.gone", RequiresFlags::empty(), "1.0"),
+            ],
+            exports: vec![Exports {
+                package: "com/example/app".to_owned(),
+                flags: ExportsFlags::empty(),
+                to: vec![],
+            }],
+            opens: vec![Exports {
+                package: "com/example/app/internal".to_owned(),
+                flags: ExportsFlags::empty(),
+                to: vec!["com.example.lib".to_owned()],
+            }],
+            uses: vec!["com/example/lib/Service".to_owned()],
+            provides: vec![Provides {
+                service: "com/example/lib/Service".to_owned(),
+                with: vec!["com/example/app/internal/ServiceImpl".to_owned()],
+            }],
+        }
+    );
+
+    assert_eq!(
+        class.module_packages(),
+        &["com/example/app", "com/example/app/internal"]
+    );
+    assert_eq!(class.module_main_class(), Some("com/example/app/Main"));
+
+    let mut written = Vec::new();
+    ClassWriter::new(&mut written).write_class(&class).unwrap();
+    assert!(bytes == written);
+}
+
+#[test]
+fn test_open_module() {
+    let class =
+        ClassReader::from_file("./tests/test_data/modules/path/com.example.util/module-info.class")
+            .unwrap();
+
+    let module = class.module().unwrap();
+    assert_eq!(module.name, "com.example.util");
+    assert_eq!(module.flags, ModuleFlags::OPEN);
+    assert!(class.module_packages().is_empty());
+    assert_eq!(class.module_main_class(), None);
+}
+
+#[test]
+fn test_module_graph() {
+    let mut class_loader = ClassLoader::new();
+    class_loader.set_module_path(vec![MODULE_PATH]).unwrap();
+    let graph = class_loader.module_graph().unwrap();
+
+    let names = graph.modules().map(|m| m.name.as_str()).collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        vec!["com.example.app", "com.example.lib", "com.example.util"]
+    );
+
+    // The util module is read through the transitive requirement of the lib module.
+    assert_eq!(
+        graph.reads("com.example.app"),
+        vec!["com.example.lib", "com.example.util"]
+    );
+    assert_eq!(graph.reads("com.example.util"), Vec::<&str>::new());
+
+    // The static requirement on the optional module is not needed at runtime.
+    assert_eq!(
+        graph.missing_requirements(),
+        vec![("com.example.app", "com.example.gone")]
+    );
+}
+
+#[test]
+fn test_module_path_entries() {
+    let mut class_loader = ClassLoader::new();
+    class_loader
+        .set_module_path(vec![
+            "./tests/test_data/modules/path/com.example.lib",
+            "./tests/test_data/modules/path/com.example.app.jar",
+        ])
+        .unwrap();
+    let graph = class_loader.module_graph().unwrap();
+
+    assert!(graph.module("com.example.util").is_none());
+    assert_eq!(
+        graph.missing_requirements(),
+        vec![
+            ("com.example.lib", "com.example.util"),
+            ("com.example.app", "com.example.gone"),
+        ]
+    );
+
+    // Classes are loaded from the modules as well.
+    let class = class_loader.read_class("com/example/app/Main").unwrap();
+    assert_eq!(class.this_class, "com/example/app/Main");
+}
@@ -0,0 +1,54 @@
+use rjvm::vm::class_loader::ClassLoader;
+use rjvm::vm::native::Native;
+use rjvm::vm::VirtualMachine;
+
+#[test]
+fn test_exit_unowned_monitor() {
+    let mut class_loader = ClassLoader::new();
+    class_loader.set_paths(vec!["./tests/", "./jre/"]);
+
+    let mut native = Native::new();
+    let mut vm = VirtualMachine::default();
+
+    let error = vm
+        .run(
+            &mut class_loader,
+            &mut native,
+            "test_data/Monitors",
+            "exit_unowned",
+            vec![],
+        )
+        .unwrap_err();
+
+    assert_eq!(
+        error.to_string(),
+        "Uncaught exception java.lang.IllegalMonitorStateException\n\
+         \tat test_data.Monitors.exit_unowned(Monitors.java:12)"
+    );
+}
+
+#[test]
+fn test_return_from_synchronized_method_without_monitor() {
+    let mut class_loader = ClassLoader::new();
+    class_loader.set_paths(vec!["./tests/", "./jre/"]);
+
+    let mut native = Native::new();
+    let mut vm = VirtualMachine::default();
+
+    let error = vm
+        .run(
+            &mut class_loader,
+            &mut native,
+            "test_data/Monitors",
+            "exit_synchronized",
+            vec![],
+        )
+        .unwrap_err();
+
+    assert_eq!(
+        error.to_string(),
+        "Uncaught exception java.lang.IllegalMonitorStateException\n\
+         \tat test_data.Monitors.exited(Monitors.java:21)\n\
+         \tat test_data.Monitors.exit_synchronized(Monitors.java:16)"
+    );
+}
@@ -1,6 +1,7 @@
 use rjvm::vm::class_loader::ClassLoader;
 use rjvm::vm::data_type::Value::Int;
 use rjvm::vm::native::Native;
+use rjvm::vm::native::NativeResult::Return;
 use rjvm::vm::VirtualMachine;
 
 #[test]
@@ -9,16 +10,20 @@ fn test_load_archive() {
     class_loader.set_paths(vec!["./tests/"]);
 
     let mut native = Native::new();
-    native.register_method("test_data/Native", "native_method", |_| Some(Int(20)));
+    native.register_method("test_data/Native", "native_method", |_, _, _| {
+        Ok(Return(Some(Int(20))))
+    });
 
     let mut vm = VirtualMachine::default();
-    let value = vm.run(
-        &mut class_loader,
-        &mut native,
-        "test_data/Native",
-        "call_native",
-        vec![],
-    );
+    let value = vm
+        .run(
+            &mut class_loader,
+            &mut native,
+            "test_data/Native",
+            "call_native",
+            vec![],
+        )
+        .unwrap();
 
     assert_eq!(value, Some(Int(20)))
 }
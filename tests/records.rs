@@ -0,0 +1,86 @@
+use rjvm::vm::class_loader::ClassLoader;
+use rjvm::vm::data_type::Value;
+use rjvm::vm::data_type::Value::Int;
+use rjvm::vm::native::Native;
+use rjvm::vm::VirtualMachine;
+
+fn run(method: &str) -> Option<Value> {
+    let mut class_loader = ClassLoader::new();
+    class_loader.set_paths(vec!["./tests/", "./jre/"]);
+
+    let mut native = Native::new();
+    let mut vm = VirtualMachine::default();
+    vm.run(
+        &mut class_loader,
+        &mut native,
+        "test_data/Records",
+        method,
+        vec![],
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_permitted_subclass() {
+    assert_eq!(run("permitted_subclass"), Some(Int(4)));
+}
+
+#[test]
+fn test_sealed_interface() {
+    assert_eq!(run("sealed_interface"), Some(Int(-1)));
+}
+
+#[test]
+fn test_sealed_interface_error() {
+    let mut class_loader = ClassLoader::new();
+    class_loader.set_paths(vec!["./tests/", "./jre/"]);
+
+    let error = class_loader.load("test_data/Intruder").unwrap_err();
+    assert_eq!(
+        error.linkage_error_class(),
+        Some("java/lang/IncompatibleClassChangeError")
+    );
+    assert_eq!(
+        error.message().unwrap(),
+        "class test_data.Intruder cannot implement sealed interface test_data.Vehicle"
+    );
+    assert!(class_loader.loaded("test_data/Intruder").is_none());
+}
+
+#[test]
+fn test_sealed_class_other_package() {
+    let mut class_loader = ClassLoader::new();
+    class_loader.set_paths(vec!["./tests/", "./jre/"]);
+
+    // Truck is public and permitted, but in another package than Fleet.
+    let error = class_loader.load("test_data/fleet/Truck").unwrap_err();
+    assert_eq!(
+        error.linkage_error_class(),
+        Some("java/lang/IncompatibleClassChangeError")
+    );
+    assert_eq!(
+        error.message().unwrap(),
+        "class test_data.fleet.Truck cannot inherit from sealed class test_data.Fleet"
+    );
+}
+
+#[test]
+fn test_class_circularity_error() {
+    let mut class_loader = ClassLoader::new();
+    class_loader.set_paths(vec!["./tests/", "./jre/"]);
+
+    // Chicken and Egg extend each other.
+    let error = class_loader.load("test_data/Chicken").unwrap_err();
+    assert_eq!(
+        error.linkage_error_class(),
+        Some("java/lang/ClassCircularityError")
+    );
+    assert_eq!(error.message().unwrap(), "test_data.Chicken");
+    assert!(class_loader.loaded("test_data/Egg").is_none());
+    assert!(class_loader.load("test_data/Egg").is_err());
+}
+
+#[test]
+fn test_record_components() {
+    assert_eq!(run("record_components"), Some(Int(3)));
+}
@@ -1,12 +1,18 @@
 use rjvm::vm::class_loader::ClassLoader;
-use rjvm::vm::data_type::Value;
-use rjvm::vm::native::Native;
-use rjvm::vm::stack::Stack;
+use rjvm::vm::heap::Heap;
+use rjvm::vm::native::NativeResult::Return;
+use rjvm::vm::native::{Native, NativeResult};
+use rjvm::vm::thread::Threads;
 use rjvm::vm::VirtualMachine;
 use std::fs::read_dir;
 use std::path::Path;
 
-fn java_assert_equals(stack: &mut Stack) -> Option<Value> {
+fn java_assert_equals(
+    _: &mut ClassLoader,
+    _: &mut Heap,
+    threads: &mut Threads,
+) -> rjvm::error::Result<NativeResult> {
+    let stack = &threads.current().stack;
     let frame = stack.current_frame();
     let left = &frame.local_variables[0].as_ref().unwrap();
     let right = &frame.local_variables[left.get_category() as usize]
@@ -14,7 +20,7 @@ fn java_assert_equals(stack: &mut Stack) -> Option<Value> {
         .unwrap();
 
     if left == right {
-        return None;
+        return Ok(Return(None));
     }
 
     eprintln!("Stack: \n{}", stack);
@@ -40,7 +46,8 @@ fn run_java_tests() {
     let mut vm = VirtualMachine::default();
     for (class_name, test_methods) in classes {
         for method in &test_methods {
-            vm.run(&mut class_loader, &mut native, &class_name, method, vec![]);
+            vm.run(&mut class_loader, &mut native, &class_name, method, vec![])
+                .unwrap_or_else(|e| panic!("{}.{} failed: {}", class_name, method, e));
         }
     }
 }
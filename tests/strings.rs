@@ -0,0 +1,136 @@
+use rjvm::vm::class_loader::ClassLoader;
+use rjvm::vm::data_type::Value::{Int, Reference};
+use rjvm::vm::native::Native;
+use rjvm::vm::VirtualMachine;
+
+#[test]
+fn test_string_constant() {
+    let mut class_loader = ClassLoader::new();
+    class_loader.set_paths(vec!["./tests/"]);
+
+    let mut native = Native::new();
+    let mut vm = VirtualMachine::default();
+
+    let value = vm
+        .run(
+            &mut class_loader,
+            &mut native,
+            "test_data/Strings",
+            "hello",
+            vec![],
+        )
+        .unwrap();
+
+    let reference = value.unwrap().expect_reference().unwrap();
+    assert_eq!(vm.heap().get_string(reference).unwrap(), "Hello, 世界");
+}
+
+#[test]
+fn test_string_constant_supplementary_characters() {
+    let mut class_loader = ClassLoader::new();
+    class_loader.set_paths(vec!["./tests/"]);
+
+    let mut native = Native::new();
+    let mut vm = VirtualMachine::default();
+
+    let value = vm
+        .run(
+            &mut class_loader,
+            &mut native,
+            "test_data/Strings",
+            "supplementary",
+            vec![],
+        )
+        .unwrap();
+
+    let reference = value.unwrap().expect_reference().unwrap();
+    assert_eq!(vm.heap().get_string(reference).unwrap(), "😀 emoji");
+}
+
+#[test]
+fn test_string_constant_embedded_null() {
+    let mut class_loader = ClassLoader::new();
+    class_loader.set_paths(vec!["./tests/"]);
+
+    let mut native = Native::new();
+    let mut vm = VirtualMachine::default();
+
+    let value = vm
+        .run(
+            &mut class_loader,
+            &mut native,
+            "test_data/Strings",
+            "embedded_null",
+            vec![],
+        )
+        .unwrap();
+
+    let reference = value.unwrap().expect_reference().unwrap();
+    assert_eq!(vm.heap().get_string(reference).unwrap(), "a\0b");
+}
+
+#[test]
+fn test_string_constant_unpaired_surrogate() {
+    let mut class_loader = ClassLoader::new();
+    class_loader.set_paths(vec!["./tests/"]);
+
+    let mut native = Native::new();
+    let mut vm = VirtualMachine::default();
+
+    let value = vm
+        .run(
+            &mut class_loader,
+            &mut native,
+            "test_data/Strings",
+            "unpaired_surrogate",
+            vec![],
+        )
+        .unwrap();
+
+    let reference = value.unwrap().expect_reference().unwrap();
+    assert_eq!(vm.heap().get_utf16(reference).unwrap(), vec![0x61, 0xD800]);
+}
+
+#[test]
+fn test_string_literals_are_interned() {
+    let mut class_loader = ClassLoader::new();
+    class_loader.set_paths(vec!["./tests/"]);
+
+    let mut native = Native::new();
+    let mut vm = VirtualMachine::default();
+
+    let value = vm
+        .run(
+            &mut class_loader,
+            &mut native,
+            "test_data/Strings",
+            "literals_are_interned",
+            vec![],
+        )
+        .unwrap();
+
+    assert_eq!(value, Some(Int(1)));
+}
+
+#[test]
+fn test_string_argument() {
+    let mut class_loader = ClassLoader::new();
+    class_loader.set_paths(vec!["./tests/"]);
+
+    let mut native = Native::new();
+    let mut vm = VirtualMachine::default();
+    let argument = vm.heap_mut().allocate_string("argument").unwrap();
+
+    let value = vm
+        .run(
+            &mut class_loader,
+            &mut native,
+            "test_data/Strings",
+            "echo",
+            vec![Reference(Some(argument))],
+        )
+        .unwrap();
+
+    assert_eq!(value, Some(Reference(Some(argument))));
+    assert_eq!(vm.heap().get_string(argument).unwrap(), "argument");
+}
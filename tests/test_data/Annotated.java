@@ -0,0 +1,66 @@
+package test_data;
+
+import java.lang.annotation.ElementType;
+import java.lang.annotation.Retention;
+import java.lang.annotation.RetentionPolicy;
+import java.lang.annotation.Target;
+
+@Annotated.Info(name = "class", count = 3, kind = ElementType.TYPE, type = String[].class,
+        tags = {"a", "b"}, nested = @Annotated.Tag("nested"))
+@Annotated.Invisible
+public class Annotated<@Annotated.Typed T> {
+
+    @Retention(RetentionPolicy.RUNTIME)
+    @interface Info {
+        String name();
+
+        int count() default 1;
+
+        ElementType kind() default ElementType.FIELD;
+
+        Class<?> type() default void.class;
+
+        String[] tags() default {};
+
+        Tag nested() default @Tag("default");
+
+        byte b() default 1;
+
+        char c() default 'c';
+
+        short s() default 2;
+
+        long j() default 3L;
+
+        float f() default 4.5f;
+
+        double d() default 6.5;
+
+        boolean z() default true;
+    }
+
+    @Retention(RetentionPolicy.RUNTIME)
+    @interface Tag {
+        String value();
+    }
+
+    @interface Invisible {
+    }
+
+    @Target(ElementType.TYPE_USE)
+    @interface Typed {
+    }
+
+    @Tag("field")
+    private @Typed String field;
+
+    @Tag("method")
+    public int method(@Invisible int a, @Tag("parameter") int b) throws @Typed Exception {
+        @Typed Object local = field;
+        try {
+            return local.hashCode() + a + b;
+        } catch (@Typed RuntimeException e) {
+            return 0;
+        }
+    }
+}
@@ -0,0 +1,8 @@
+package test_data;
+
+/**
+ * Compiled against a version of Right without value(), so that Left and Right have conflicting
+ * default methods.
+ */
+class Both implements Left, Right {
+}
@@ -0,0 +1,7 @@
+package test_data;
+
+final class Car implements Vehicle {
+    public int wheels() {
+        return 4;
+    }
+}
@@ -0,0 +1,8 @@
+package test_data;
+
+/**
+ * javac rejects the cycle with Egg, so this was compiled extending another class which was then
+ * renamed to Egg in the class file. Loading either class throws ClassCircularityError.
+ */
+class Chicken extends Egg {
+}
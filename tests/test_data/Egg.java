@@ -0,0 +1,8 @@
+package test_data;
+
+/**
+ * Compiled against a version of Chicken that does not extend Egg, so that loading either class
+ * throws ClassCircularityError.
+ */
+class Egg extends Chicken {
+}
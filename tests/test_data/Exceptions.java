@@ -0,0 +1,39 @@
+package test_data;
+
+public class Exceptions {
+
+    public static int uncaught() {
+        return fail();
+    }
+
+    private static int fail() {
+        throw new RuntimeException("Failure");
+    }
+
+    public static int uncaught_internal() {
+        int[] array = new int[1];
+        return array[1];
+    }
+
+    public static int handled() {
+        try {
+            return fail();
+        } catch (RuntimeException e) {
+            return 1;
+        }
+    }
+
+    public static int rethrown() {
+        RuntimeException e = create();
+        throw e;
+    }
+
+    private static RuntimeException create() {
+        return new RuntimeException();
+    }
+
+    public static int printed() {
+        create().printStackTrace();
+        return 1;
+    }
+}
@@ -0,0 +1,9 @@
+package test_data;
+
+/**
+ * Permits a class in another package, which is only allowed in a named module. It is compiled
+ * as part of one, so that loading Truck from the unnamed module throws
+ * IncompatibleClassChangeError.
+ */
+public sealed class Fleet permits test_data.fleet.Truck {
+}
@@ -0,0 +1,31 @@
+package test_data;
+
+public class GarbageCollection {
+
+    private static int[] kept;
+
+    public static int allocate_in_loop() {
+        int sum = 0;
+        for (int i = 0; i != 100; i++) {
+            int[] array = new int[100];
+            array[99] = i;
+            sum += array[99];
+        }
+        return sum;
+    }
+
+    public static int keep_reachable() {
+        int[] local = new int[100];
+        local[0] = 10;
+
+        kept = new int[100];
+        kept[0] = 20;
+
+        for (int i = 0; i != 100; i++) {
+            int[] array = new int[100];
+            array[0] = i;
+        }
+
+        return local[0] + kept[0];
+    }
+}
@@ -0,0 +1,30 @@
+package test_data;
+
+/**
+ * Square and Both are compiled against older versions of Shape and Right, see their documentation.
+ */
+public class InterfaceMethods {
+
+    public static int sides() {
+        Shape shape = new Square();
+        return shape.sides();
+    }
+
+    public static int abstract_method() {
+        Shape shape = new Square();
+        try {
+            return shape.area();
+        } catch (AbstractMethodError e) {
+            return -1;
+        }
+    }
+
+    public static int conflicting_default_methods() {
+        Left left = new Both();
+        try {
+            return left.value();
+        } catch (IncompatibleClassChangeError e) {
+            return -1;
+        }
+    }
+}
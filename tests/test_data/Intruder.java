@@ -0,0 +1,11 @@
+package test_data;
+
+/**
+ * Compiled against a version of Vehicle that is not sealed, so that loading it throws
+ * IncompatibleClassChangeError.
+ */
+final class Intruder implements Vehicle {
+    public int wheels() {
+        return 3;
+    }
+}
@@ -0,0 +1,10 @@
+package test_data;
+
+// The class file is patched after compiling, `value` returns null instead of 1 and fails
+// verification.
+public class Invalid {
+
+    public static int value() {
+        return 1;
+    }
+}
@@ -0,0 +1,7 @@
+package test_data;
+
+interface Left {
+    default int value() {
+        return 1;
+    }
+}
@@ -0,0 +1,22 @@
+package test_data;
+
+/**
+ * javac never emits an unbalanced monitorexit, so after compilation each getClass call is
+ * replaced by monitorexit followed by aconst_null and nop, which are popped like the result of
+ * getClass was.
+ */
+public class Monitors {
+
+    public static void exit_unowned() {
+        Object o = new Object();
+        o.getClass();
+    }
+
+    public static void exit_synchronized() {
+        new Monitors().exited();
+    }
+
+    private synchronized void exited() {
+        getClass();
+    }
+}
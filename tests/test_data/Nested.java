@@ -0,0 +1,31 @@
+package test_data;
+
+import java.lang.Runnable;
+
+// Compiled with --release 11 for the NestHost and NestMembers attributes.
+public class Nested {
+
+    private static class Inner {
+    }
+
+    public static Object inner() {
+        return new Inner();
+    }
+
+    public static Runnable local() {
+        class Local implements Runnable {
+            public void run() {
+            }
+        }
+        return new Local();
+    }
+
+    public static Runnable lambda() {
+        return () -> {};
+    }
+
+    static final Runnable ANONYMOUS = new Runnable() {
+        public void run() {
+        }
+    };
+}
@@ -0,0 +1,6 @@
+package test_data;
+
+import java.util.List;
+
+record Point(int x, String label, List<String> tags) {
+}
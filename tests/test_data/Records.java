@@ -0,0 +1,44 @@
+package test_data;
+
+import java.lang.reflect.RecordComponent;
+
+/**
+ * Intruder is compiled against an older version of Vehicle, see its documentation.
+ */
+public class Records {
+
+    public static int permitted_subclass() {
+        Vehicle vehicle = new Car();
+        return vehicle.wheels();
+    }
+
+    public static int sealed_interface() {
+        try {
+            Vehicle vehicle = new Intruder();
+            return vehicle.wheels();
+        } catch (IncompatibleClassChangeError e) {
+            return -1;
+        }
+    }
+
+    public static int record_components() {
+        if (!Point.class.isRecord() || Records.class.isRecord()) {
+            return -1;
+        }
+
+        RecordComponent[] components = Point.class.getRecordComponents();
+        if (components.length != 3 || !components[1].getName().equals("label")) {
+            return -2;
+        }
+        if (components[1].getType() != String.class || components[1].getDeclaringRecord() != Point.class) {
+            return -3;
+        }
+        if (components[1].getGenericSignature() != null) {
+            return -4;
+        }
+        if (!components[2].getGenericSignature().equals("Ljava/util/List<Ljava/lang/String;>;")) {
+            return -5;
+        }
+        return components.length;
+    }
+}
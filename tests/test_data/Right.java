@@ -0,0 +1,7 @@
+package test_data;
+
+interface Right {
+    default int value() {
+        return 2;
+    }
+}
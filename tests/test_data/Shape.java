@@ -0,0 +1,7 @@
+package test_data;
+
+interface Shape {
+    int sides();
+
+    int area();
+}
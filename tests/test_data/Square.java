@@ -0,0 +1,11 @@
+package test_data;
+
+/**
+ * Compiled against a version of Shape without area(), so that invoking it throws
+ * AbstractMethodError.
+ */
+class Square implements Shape {
+    public int sides() {
+        return 4;
+    }
+}
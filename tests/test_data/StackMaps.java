@@ -0,0 +1,23 @@
+package test_data;
+
+import java.util.List;
+
+// Compiled with -g for the LocalVariableTable and LocalVariableTypeTable attributes.
+public class StackMaps {
+
+    public static int sum(int count) {
+        int sum = 0;
+        for (int i = 0; i != count; i++) {
+            sum += i;
+        }
+        return sum;
+    }
+
+    public static int first(List<String> strings) {
+        if (strings == null) {
+            return 0;
+        }
+        String first = strings.get(0);
+        return first.length();
+    }
+}
@@ -0,0 +1,33 @@
+package test_data;
+
+public class Strings {
+
+    public static String hello() {
+        return "Hello, 世界";
+    }
+
+    public static String supplementary() {
+        return "\uD83D\uDE00 emoji";
+    }
+
+    public static String unpaired_surrogate() {
+        return "a\uD800";
+    }
+
+    public static String embedded_null() {
+        return "a\u0000b";
+    }
+
+    public static int literals_are_interned() {
+        String a = "interned";
+        String b = "interned";
+        if (a == b) {
+            return 1;
+        }
+        return 0;
+    }
+
+    public static String echo(String s) {
+        return s;
+    }
+}
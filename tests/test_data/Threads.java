@@ -0,0 +1,77 @@
+package test_data;
+
+public class Threads {
+
+    public static void deadlock() {
+        final Object a = new Object();
+        final Object b = new Object();
+
+        Thread thread = new Thread(new Runnable() {
+            public void run() {
+                synchronized (b) {
+                    Thread.yield();
+                    synchronized (a) {
+                    }
+                }
+            }
+        });
+        thread.start();
+
+        synchronized (a) {
+            Thread.yield();
+            synchronized (b) {
+            }
+        }
+    }
+
+    public static int uncaught_in_thread() throws InterruptedException {
+        Thread thread = new Thread(new Runnable() {
+            public void run() {
+                throw new RuntimeException("Failure");
+            }
+        });
+        thread.start();
+        thread.join();
+        return 5;
+    }
+
+    /**
+     * Returns the number of times the two threads were switched while they were writing.
+     */
+    public static int switches() throws InterruptedException {
+        final int[] log = new int[40];
+        Thread first = new Thread(new Writer(log, 1));
+        Thread second = new Thread(new Writer(log, 2));
+
+        first.start();
+        second.start();
+        first.join();
+        second.join();
+
+        int switches = 0;
+        for (int i = 1; i != 40; i++) {
+            if (log[i] != log[i - 1]) {
+                switches++;
+            }
+        }
+        return switches;
+    }
+
+    private static class Writer implements Runnable {
+        private static int next;
+
+        private final int[] log;
+        private final int entry;
+
+        Writer(int[] log, int entry) {
+            this.log = log;
+            this.entry = entry;
+        }
+
+        public void run() {
+            for (int i = 0; i != 20; i++) {
+                log[next++] = entry;
+            }
+        }
+    }
+}
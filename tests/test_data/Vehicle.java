@@ -0,0 +1,5 @@
+package test_data;
+
+sealed interface Vehicle permits Car {
+    int wheels();
+}
@@ -0,0 +1,23 @@
+package test_data;
+
+import java.lang.VerifyError;
+
+public class Verification {
+
+    public static int caught() {
+        try {
+            return Invalid.value();
+        } catch (VerifyError e) {
+            return 1;
+        }
+    }
+
+    public static String message() {
+        try {
+            Invalid.value();
+            return null;
+        } catch (VerifyError e) {
+            return e.getMessage();
+        }
+    }
+}
@@ -0,0 +1,6 @@
+package test_data.fleet;
+
+import test_data.Fleet;
+
+public final class Truck extends Fleet {
+}
@@ -0,0 +1,6 @@
+package com.example.app;
+
+public class Main {
+    public static void main(String[] args) {
+    }
+}
@@ -0,0 +1,6 @@
+package com.example.app.internal;
+
+import com.example.lib.Service;
+
+public class ServiceImpl implements Service {
+}
@@ -0,0 +1,9 @@
+module com.example.app {
+    requires com.example.lib;
+    requires static com.example.optional;
+    requires com.example.gone;
+    exports com.example.app;
+    opens com.example.app.internal to com.example.lib;
+    uses com.example.lib.Service;
+    provides com.example.lib.Service with com.example.app.internal.ServiceImpl;
+}
@@ -0,0 +1,4 @@
+package com.example.gone;
+
+public class Api {
+}
@@ -0,0 +1 @@
+module com.example.gone { exports com.example.gone; }
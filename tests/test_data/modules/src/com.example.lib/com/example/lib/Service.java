@@ -0,0 +1,4 @@
+package com.example.lib;
+
+public interface Service {
+}
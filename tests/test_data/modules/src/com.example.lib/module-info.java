@@ -0,0 +1,4 @@
+module com.example.lib {
+    requires transitive com.example.util;
+    exports com.example.lib;
+}
@@ -0,0 +1,4 @@
+package com.example.optional;
+
+public class Api {
+}
@@ -0,0 +1 @@
+module com.example.optional { exports com.example.optional; }
@@ -0,0 +1,4 @@
+package com.example.util;
+
+public class Util {
+}
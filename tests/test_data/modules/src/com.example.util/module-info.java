@@ -0,0 +1,3 @@
+open module com.example.util {
+    exports com.example.util;
+}
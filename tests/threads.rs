@@ -0,0 +1,48 @@
+use rjvm::vm::class_loader::ClassLoader;
+use rjvm::vm::data_type::Value;
+use rjvm::vm::data_type::Value::Int;
+use rjvm::vm::native::Native;
+use rjvm::vm::VirtualMachine;
+
+fn run(vm: &mut VirtualMachine, method: &str) -> rjvm::error::Result<Option<Value>> {
+    let mut class_loader = ClassLoader::new();
+    class_loader.set_paths(vec!["./tests/", "./jre/"]);
+
+    let mut native = Native::new();
+    vm.run(
+        &mut class_loader,
+        &mut native,
+        "test_data/Threads",
+        method,
+        vec![],
+    )
+}
+
+#[test]
+fn test_deadlock() {
+    let mut vm = VirtualMachine::default();
+    let error = run(&mut vm, "deadlock").unwrap_err();
+
+    assert_eq!(error.to_string(), "Deadlock, all threads are waiting.");
+}
+
+#[test]
+fn test_uncaught_exception_terminates_thread() {
+    let mut vm = VirtualMachine::default();
+    let result = run(&mut vm, "uncaught_in_thread").unwrap();
+
+    assert_eq!(result, Some(Int(5)));
+}
+
+#[test]
+fn test_time_slice() {
+    let mut vm = VirtualMachine::default();
+    assert_eq!(run(&mut vm, "switches").unwrap(), Some(Int(1)));
+
+    vm.set_time_slice(20);
+    let switches = run(&mut vm, "switches").unwrap();
+    assert_ne!(switches, Some(Int(1)));
+
+    // The threads are interleaved the same way every run.
+    assert_eq!(run(&mut vm, "switches").unwrap(), switches);
+}
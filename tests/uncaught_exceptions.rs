@@ -0,0 +1,172 @@
+use rjvm::error::ErrorKind::UncaughtException;
+use rjvm::vm::class_loader::ClassLoader;
+use rjvm::vm::data_type::Value::Int;
+use rjvm::vm::exception::StackTraceElement;
+use rjvm::vm::native::Native;
+use rjvm::vm::VirtualMachine;
+
+#[test]
+fn test_uncaught_exception() {
+    let mut class_loader = ClassLoader::new();
+    class_loader.set_paths(vec!["./tests/", "./jre/"]);
+
+    let mut native = Native::new();
+    let mut vm = VirtualMachine::default();
+
+    let error = vm
+        .run(
+            &mut class_loader,
+            &mut native,
+            "test_data/Exceptions",
+            "uncaught",
+            vec![],
+        )
+        .unwrap_err();
+
+    assert!(matches!(error.kind(), UncaughtException(_)));
+
+    let exception = error.exception().unwrap();
+    assert_eq!(exception.class_name, "java/lang/RuntimeException");
+    assert_eq!(exception.message, Some("Failure".to_owned()));
+    assert_eq!(
+        exception.stack_trace,
+        vec![
+            StackTraceElement {
+                class_name: "test_data/Exceptions".to_owned(),
+                method_name: "fail".to_owned(),
+                file_name: Some("Exceptions.java".to_owned()),
+                line_number: Some(10),
+            },
+            StackTraceElement {
+                class_name: "test_data/Exceptions".to_owned(),
+                method_name: "uncaught".to_owned(),
+                file_name: Some("Exceptions.java".to_owned()),
+                line_number: Some(6),
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_uncaught_internal_exception() {
+    let mut class_loader = ClassLoader::new();
+    class_loader.set_paths(vec!["./tests/", "./jre/"]);
+
+    let mut native = Native::new();
+    let mut vm = VirtualMachine::default();
+
+    let error = vm
+        .run(
+            &mut class_loader,
+            &mut native,
+            "test_data/Exceptions",
+            "uncaught_internal",
+            vec![],
+        )
+        .unwrap_err();
+
+    assert_eq!(
+        error.to_string(),
+        "Uncaught exception java.lang.ArrayIndexOutOfBoundsException\n\
+         \tat test_data.Exceptions.uncaught_internal(Exceptions.java:15)"
+    );
+}
+
+#[test]
+fn test_stack_trace_from_creation() {
+    let mut class_loader = ClassLoader::new();
+    class_loader.set_paths(vec!["./tests/", "./jre/"]);
+
+    let mut native = Native::new();
+    let mut vm = VirtualMachine::default();
+
+    let error = vm
+        .run(
+            &mut class_loader,
+            &mut native,
+            "test_data/Exceptions",
+            "rethrown",
+            vec![],
+        )
+        .unwrap_err();
+
+    assert_eq!(
+        error.to_string(),
+        "Uncaught exception java.lang.RuntimeException\n\
+         \tat test_data.Exceptions.create(Exceptions.java:32)\n\
+         \tat test_data.Exceptions.rethrown(Exceptions.java:27)"
+    );
+}
+
+#[test]
+fn test_print_stack_trace() {
+    let mut class_loader = ClassLoader::new();
+    class_loader.set_paths(vec!["./tests/", "./jre/"]);
+
+    let mut native = Native::new();
+    let mut vm = VirtualMachine::default();
+
+    let value = vm
+        .run(
+            &mut class_loader,
+            &mut native,
+            "test_data/Exceptions",
+            "printed",
+            vec![],
+        )
+        .unwrap();
+    assert_eq!(value, Some(Int(1)));
+}
+
+#[test]
+fn test_vm_usable_after_uncaught_exception() {
+    let mut class_loader = ClassLoader::new();
+    class_loader.set_paths(vec!["./tests/", "./jre/"]);
+
+    let mut native = Native::new();
+    let mut vm = VirtualMachine::default();
+
+    let result = vm.run(
+        &mut class_loader,
+        &mut native,
+        "test_data/Exceptions",
+        "uncaught",
+        vec![],
+    );
+    assert!(result.is_err());
+
+    let value = vm
+        .run(
+            &mut class_loader,
+            &mut native,
+            "test_data/Exceptions",
+            "handled",
+            vec![],
+        )
+        .unwrap();
+    assert_eq!(value, Some(Int(1)));
+}
+
+#[test]
+fn test_method_not_found() {
+    let mut class_loader = ClassLoader::new();
+    class_loader.set_paths(vec!["./tests/", "./jre/"]);
+
+    let mut native = Native::new();
+    let mut vm = VirtualMachine::default();
+
+    let error = vm
+        .run(
+            &mut class_loader,
+            &mut native,
+            "test_data/Exceptions",
+            "missing",
+            vec![],
+        )
+        .unwrap_err();
+
+    assert_eq!(
+        error.to_string(),
+        "Public static method missing not found in class test_data/Exceptions."
+    );
+}
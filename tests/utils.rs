@@ -1,21 +1,29 @@
+use rjvm::error::Result;
 use rjvm::vm::class_loader::ClassLoader;
 use rjvm::vm::data_type::Value;
-use rjvm::vm::native::Native;
-use rjvm::vm::stack::Stack;
+use rjvm::vm::heap::Heap;
+use rjvm::vm::native::NativeResult::Return;
+use rjvm::vm::native::{Native, NativeResult};
+use rjvm::vm::thread::Threads;
 use rjvm::vm::VirtualMachine;
 
-fn java_assert_equals(stack: &mut Stack) -> Option<Value> {
-    let frame = stack.current_frame_mut();
+fn java_assert_equals(
+    _: &mut ClassLoader,
+    _: &mut Heap,
+    threads: &mut Threads,
+) -> Result<NativeResult> {
+    let stack = &threads.current().stack;
+    let frame = stack.current_frame();
     let left = &frame.local_variables[0];
     let right = &frame.local_variables[1];
 
     if left == right {
-        return None;
+        return Ok(Return(None));
     }
 
     eprintln!("Stack: \n{}", stack);
 
-    let frame = stack.current_frame_mut();
+    let frame = stack.current_frame();
     eprintln!(
         "Java assertion failed:\n\n\t{:?} == {:?}\n\n",
         frame.local_variables[0], frame.local_variables[1]
@@ -40,6 +48,7 @@ pub fn run_method(class_name: &str, method_name: &str) -> Option<Value> {
         method_name,
         vec![],
     )
+    .unwrap()
 }
 
 #[allow(dead_code)]
@@ -58,4 +67,5 @@ pub fn run_method_args(class_name: &str, method_name: &str, args: Vec<Value>) ->
         method_name,
         args,
     )
+    .unwrap()
 }
@@ -0,0 +1,72 @@
+use rjvm::vm::class_loader::ClassLoader;
+use rjvm::vm::data_type::Value::Int;
+use rjvm::vm::native::Native;
+use rjvm::vm::VirtualMachine;
+
+#[test]
+fn test_verify_error_caught() {
+    let mut class_loader = ClassLoader::new();
+    class_loader.set_paths(vec!["./tests/", "./jre/"]);
+
+    let mut native = Native::new();
+    let mut vm = VirtualMachine::default();
+    let value = vm
+        .run(
+            &mut class_loader,
+            &mut native,
+            "test_data/Verification",
+            "caught",
+            vec![],
+        )
+        .unwrap();
+
+    assert_eq!(value, Some(Int(1)));
+}
+
+#[test]
+fn test_verify_error_message() {
+    let mut class_loader = ClassLoader::new();
+    class_loader.set_paths(vec!["./tests/", "./jre/"]);
+
+    let mut native = Native::new();
+    let mut vm = VirtualMachine::default();
+    let value = vm
+        .run(
+            &mut class_loader,
+            &mut native,
+            "test_data/Verification",
+            "message",
+            vec![],
+        )
+        .unwrap();
+
+    let reference = value.unwrap().expect_reference().unwrap();
+    assert_eq!(
+        vm.heap().get_string(reference).unwrap(),
+        "test_data/Invalid.value()I at pc 1: Bad type on operand stack"
+    );
+}
+
+#[test]
+fn test_verify_error_when_loading_main_class() {
+    let mut class_loader = ClassLoader::new();
+    class_loader.set_paths(vec!["./tests/", "./jre/"]);
+
+    let mut native = Native::new();
+    let mut vm = VirtualMachine::default();
+    let error = vm
+        .run(
+            &mut class_loader,
+            &mut native,
+            "test_data/Invalid",
+            "value",
+            vec![],
+        )
+        .unwrap_err();
+
+    assert_eq!(error.linkage_error_class(), Some("java/lang/VerifyError"));
+    assert_eq!(
+        error.to_string(),
+        "test_data/Invalid.value()I at pc 1: Bad type on operand stack"
+    );
+}